@@ -0,0 +1,89 @@
+//! Ranking 1,000 mixed queries of the bundled `languages.yml`, mostly
+//! extensions as for a directory listing, and finding the nearest xterm colors
+//! of their first results: one at a time, and at once with
+//! `ColorMap::ranked_many` and `nearest_many_in_palette`, which use all cores
+//! with the `rayon` feature.
+//!
+//! `cargo bench --bench query_many` and `cargo bench --bench query_many --features rayon`
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use color_art::Color;
+use linguist_termcolor::{nearest_in_palette, nearest_many_in_palette, Linguist, Metric, Palette};
+
+const ROUNDS: u32 = 20;
+const QUERIES: usize = 1000;
+
+fn time(name: &str, mut run: impl FnMut()) {
+    run();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        run();
+    }
+    let each: Duration = start.elapsed() / ROUNDS;
+    println!("{name:<32} {each:>12.2?} per round");
+}
+
+fn main() {
+    let yml = include_str!("../data/languages.yml");
+    let linguist: Linguist = yml.parse().unwrap();
+    let colors = linguist.colors().unwrap();
+    // every fourth query a name, a typo, or a repeat of a common extension
+    let extensions = linguist.languages().flat_map(|lang| lang.extensions());
+    let extensions = extensions.map(String::as_str).collect::<Vec<_>>();
+    let others = [
+        "rust",
+        "pyton",
+        ".rs",
+        "c++",
+        "*.py",
+        "objective-c",
+        "ts",
+        "md",
+    ];
+    let queries = (0..QUERIES)
+        .map(|i| match i % 4 {
+            0 => others[i / 4 % others.len()],
+            _ => extensions[i * 7 % extensions.len()],
+        })
+        .collect::<Vec<_>>();
+    let found: Vec<Color> = colors
+        .ranked_many(queries.iter().copied())
+        .into_iter()
+        .filter_map(|(_, ranked)| Some(ranked.first()?.color.color()))
+        .collect();
+    let rayon = if cfg!(feature = "rayon") { "on" } else { "off" };
+    println!(
+        "{} queries, {} colors, rayon {rayon}",
+        queries.len(),
+        found.len()
+    );
+
+    time("ranked, one at a time", || {
+        for query in &queries {
+            black_box(colors.ranked(black_box(query)));
+        }
+    });
+    time("ranked_many", || {
+        black_box(colors.ranked_many(black_box(&queries).iter().copied()));
+    });
+
+    for (metric, name) in [
+        (Metric::LinearRgb, "linear-rgb"),
+        (Metric::De2000, "de2000"),
+    ] {
+        time(&format!("nearest, one at a time, {name}"), || {
+            for color in &found {
+                black_box(nearest_in_palette(color, Palette::Xterm256, metric));
+            }
+        });
+        time(&format!("nearest_many, {name}"), || {
+            black_box(nearest_many_in_palette(
+                black_box(&found),
+                Palette::Xterm256,
+                metric,
+            ));
+        });
+    }
+}
@@ -0,0 +1,55 @@
+//! Rendering every language in the bundled `languages.yml`, once allocating a
+//! `String` per color with `TermColor::render`, and once into one reused
+//! buffer with `TermColor::render_to`, as the CLI writes its listings.
+//!
+//! `cargo bench --bench render`
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use linguist_termcolor::{Depth, Linguist, RenderOptions, TermColor};
+
+const ROUNDS: u32 = 20;
+
+fn time(name: &str, mut run: impl FnMut()) {
+    run();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        run();
+    }
+    let each: Duration = start.elapsed() / ROUNDS;
+    println!("{name:<24} {each:>12.2?} per round");
+}
+
+fn main() {
+    let yml = include_str!("../data/languages.yml");
+    let linguist: Linguist = yml.parse().unwrap();
+    let colors: Vec<TermColor> = linguist
+        .languages()
+        .filter_map(|lang| lang.color())
+        .map(TermColor::from)
+        .collect();
+    colored::control::set_override(true);
+
+    for (depth, name) in [
+        (Depth::TrueColor, "truecolor"),
+        (Depth::Xterm256, "xterm256"),
+    ] {
+        let options = RenderOptions::new().depth(depth);
+
+        time(&format!("render, {name}"), || {
+            for color in &colors {
+                black_box(color.render(black_box(&options)));
+            }
+        });
+
+        let mut buffer = String::new();
+        time(&format!("render_to, {name}"), || {
+            buffer.clear();
+            for color in &colors {
+                color.render_to(&mut buffer, black_box(&options)).unwrap();
+            }
+            black_box(&buffer);
+        });
+    }
+}
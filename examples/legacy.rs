@@ -0,0 +1,21 @@
+//! Call sites written against the API before `ColorMap::ranked` and
+//! `RenderOptions`, kept compiling through their deprecated adapters.
+//!
+//! ```text
+//! cargo run --example legacy -- rust
+//! ```
+
+#![allow(deprecated)]
+
+use color_art::ColorSpace;
+use linguist_termcolor::Linguist;
+
+fn main() -> anyhow::Result<()> {
+    let query = std::env::args().nth(1).unwrap_or_else(|| "rust".into());
+    let linguist = Linguist::new()?;
+    let colors = linguist.colors()?;
+    for (name, color) in colors.query(&query) {
+        println!("{} {name}", color.print(ColorSpace::Lab));
+    }
+    Ok(())
+}
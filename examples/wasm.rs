@@ -0,0 +1,39 @@
+//! The library without fetching, as for a web-based terminal built for
+//! `wasm32-unknown-unknown`, which passes `languages.yml` in itself:
+//!
+//! ```text
+//! cargo build --example wasm --target wasm32-unknown-unknown --no-default-features
+//! wasm-bindgen --target web --out-dir pkg \
+//!     target/wasm32-unknown-unknown/debug/examples/wasm.wasm
+//! ```
+//!
+//! ```text
+//! import init, { Colors } from "./pkg/wasm.js";
+//! await init();
+//! const colors = new Colors(await (await fetch("languages.yml")).text());
+//! colors.lookup("rust"); // "#dea584 180 Rust"
+//! ```
+
+use linguist_termcolor::{ColorMap, Linguist};
+use wasm_bindgen::prelude::*;
+
+/// The index of a `languages.yml`.
+#[wasm_bindgen]
+pub struct Colors(ColorMap<'static>);
+
+#[wasm_bindgen]
+impl Colors {
+    #[wasm_bindgen(constructor)]
+    pub fn new(languages_yml: &str) -> Result<Colors, JsError> {
+        let linguist = languages_yml.parse::<Linguist>()?;
+        Ok(Colors(linguist.into_colors()?))
+    }
+
+    /// The best match for `query` as its hex code, nearest xterm color, and
+    /// name, e.g. `#dea584 180 Rust`.
+    pub fn lookup(&self, query: &str) -> Option<String> {
+        let best = self.0.ranked(query).into_iter().next()?;
+        let xterm = best.color.nearest_xterm(color_art::ColorSpace::RGB).index;
+        Some(format!("{} {xterm} {}", best.color.hex(), best.name))
+    }
+}
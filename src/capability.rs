@@ -0,0 +1,361 @@
+//! How many colors the terminal running this program can show, guessed from
+//! its environment.
+//!
+//! Terminals do not report this, so [`ColorSupport::detect`] goes by what
+//! they and their users set, in order of precedence:
+//!
+//! 1. `COLORTERM=truecolor` or `24bit`: 24-bit colors
+//! 2. `TERM=dumb`: no colors, while without `TERM`, as when output is
+//!    captured with `--color always`, nothing is known, so 24-bit colors
+//! 3. the `colors` capability of `TERM` in the terminfo database, see
+//!    [`terminfo_max_colors`]
+//! 4. `TERM` naming a `direct`, `truecolor`, or `256color` terminal, such as
+//!    `xterm-256color`, for entries the database does not have
+//! 5. 16 colors otherwise
+
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{Depth, RenderOptions};
+
+/// The colors a terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSupport {
+    /// 24-bit colors, which show Linguist colors exactly.
+    TrueColor,
+    /// The 256 xterm colors.
+    Xterm256,
+    /// The 16 ANSI colors, whose look depends on the terminal theme.
+    Ansi16,
+    /// No colors at all.
+    None,
+}
+
+impl ColorSupport {
+    /// Guess from environment variables, looked up with `env`, and the
+    /// `colors` capability of `TERM` in the terminfo database, if known, such
+    /// as from [`terminfo_max_colors`]. See the [module docs](self) for the
+    /// order in which these are considered.
+    pub fn detect<F>(env: F, max_colors: Option<i32>) -> Self
+    where
+        F: Fn(&str) -> Option<OsString>,
+    {
+        if env("COLORTERM").is_some_and(|v| v == "truecolor" || v == "24bit") {
+            return ColorSupport::TrueColor;
+        }
+        let Some(term) = env("TERM").filter(|term| !term.is_empty()) else {
+            return ColorSupport::TrueColor;
+        };
+        let term = term.to_string_lossy();
+        if term == "dumb" {
+            return ColorSupport::None;
+        }
+        if let Some(colors) = max_colors {
+            return Self::of_max_colors(colors);
+        }
+        if term.ends_with("-direct") || term.contains("truecolor") || term.contains("24bit") {
+            ColorSupport::TrueColor
+        } else if term.contains("256color") {
+            ColorSupport::Xterm256
+        } else {
+            ColorSupport::Ansi16
+        }
+    }
+
+    /// What a terminfo entry with `colors#max_colors` can show; 8 colors count
+    /// as 16, since the bright ones are then usually shown bold.
+    pub fn of_max_colors(max_colors: i32) -> Self {
+        match max_colors {
+            // 2^24, as in xterm-direct
+            0x100_0000.. => ColorSupport::TrueColor,
+            256.. => ColorSupport::Xterm256,
+            8.. => ColorSupport::Ansi16,
+            _ => ColorSupport::None,
+        }
+    }
+
+    /// `options` for rendering colors with this support: the depth of escape
+    /// sequences, and with 16 colors, [`RenderOptions::ansi16_escapes`]. The
+    /// palette, whose colors are printed, stays as it is. `None` if colors
+    /// should be turned off.
+    pub fn rendering(self, options: RenderOptions) -> Option<RenderOptions> {
+        match self {
+            ColorSupport::TrueColor => Some(options.depth(Depth::TrueColor)),
+            ColorSupport::Xterm256 => Some(options.depth(Depth::Xterm256)),
+            ColorSupport::Ansi16 => Some(options.depth(Depth::Xterm256).ansi16_escapes(true)),
+            ColorSupport::None => None,
+        }
+    }
+}
+
+/// The `colors` capability of the terminfo entry for `term`, looked up in the
+/// directories where ncurses looks, with `env` for `TERMINFO`,
+/// `TERMINFO_DIRS`, and `HOME`. `None` if there is no such entry, or it has
+/// no `colors`, or is not in the compiled terminfo format.
+pub fn terminfo_max_colors<F>(term: &str, env: F) -> Option<i32>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    if term.is_empty() || term.contains(['/', '\\']) {
+        return None;
+    }
+    let first = term.chars().next()?;
+    terminfo_dirs(env).into_iter().find_map(|dir| {
+        // Linux uses the first letter, macOS its code in hex
+        [first.to_string(), format!("{:02x}", first as u32)]
+            .iter()
+            .find_map(|sub| fs::read(dir.join(sub).join(term)).ok())
+            .and_then(|entry| parse_max_colors(&entry))
+    })
+}
+
+/// Where ncurses looks for terminfo entries, in order.
+fn terminfo_dirs<F>(env: F) -> Vec<PathBuf>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    const DEFAULT: &str = "/usr/share/terminfo";
+
+    let mut dirs = vec![];
+    if let Some(dir) = env("TERMINFO").filter(|dir| !dir.is_empty()) {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(home) = env("HOME").filter(|home| !home.is_empty()) {
+        dirs.push(Path::new(&home).join(".terminfo"));
+    }
+    if let Some(list) = env("TERMINFO_DIRS") {
+        // an empty entry stands for the default
+        for dir in list.to_string_lossy().split(':') {
+            dirs.push(PathBuf::from(if dir.is_empty() { DEFAULT } else { dir }));
+        }
+    }
+    for dir in [
+        "/etc/terminfo",
+        "/lib/terminfo",
+        DEFAULT,
+        "/usr/lib/terminfo",
+    ] {
+        dirs.push(PathBuf::from(dir));
+    }
+    dirs
+}
+
+/// The `colors` number of a compiled terminfo entry, see term(5): a header of
+/// six 16-bit numbers, the names, the booleans, padding to an even offset,
+/// then the numbers, of which `colors` is the 14th. Numbers are 16 bits wide,
+/// or 32 in the extended format of ncurses 6.1.
+fn parse_max_colors(entry: &[u8]) -> Option<i32> {
+    const MAX_COLORS: usize = 13;
+
+    let short = |at: usize| Some(i16::from_le_bytes([*entry.get(at)?, *entry.get(at + 1)?]));
+    let width = match short(0)? {
+        0o432 => 2,
+        0o1036 => 4,
+        _ => return None,
+    };
+    let names = usize::try_from(short(2)?).ok()?;
+    let booleans = usize::try_from(short(4)?).ok()?;
+    let numbers = usize::try_from(short(6)?).ok()?;
+    if MAX_COLORS >= numbers {
+        return None;
+    }
+    let start = 12 + names + booleans;
+    let at = start + start % 2 + MAX_COLORS * width;
+    let colors = match width {
+        2 => i32::from(short(at)?),
+        _ => i32::from_le_bytes(entry.get(at..at + 4)?.try_into().ok()?),
+    };
+    // -1 for absent, -2 for cancelled
+    (colors >= 0).then_some(colors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Layer, Palette, TermColor};
+
+    /// An environment of `vars`, as `NAME=value`.
+    fn env<'a>(vars: &'a [&'a str]) -> impl Fn(&str) -> Option<OsString> + 'a {
+        move |key| {
+            vars.iter()
+                .filter_map(|var| var.split_once('='))
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| value.into())
+        }
+    }
+
+    #[test]
+    fn detected() {
+        for (vars, max_colors, expected) in [
+            (
+                &["COLORTERM=truecolor", "TERM=dumb"][..],
+                None,
+                ColorSupport::TrueColor,
+            ),
+            (
+                &["COLORTERM=24bit", "TERM=xterm"],
+                Some(8),
+                ColorSupport::TrueColor,
+            ),
+            (&[], None, ColorSupport::TrueColor),
+            (&["TERM="], None, ColorSupport::TrueColor),
+            (&["TERM=dumb"], Some(256), ColorSupport::None),
+            (&["COLORTERM=yes", "TERM=dumb"], None, ColorSupport::None),
+            (&["TERM=xterm-256color"], Some(8), ColorSupport::Ansi16),
+            (&["TERM=xterm"], Some(256), ColorSupport::Xterm256),
+            (&["TERM=xterm"], Some(0x100_0000), ColorSupport::TrueColor),
+            (&["TERM=vt100"], Some(-1), ColorSupport::None),
+            (&["TERM=xterm-direct"], None, ColorSupport::TrueColor),
+            (&["TERM=foot-truecolor"], None, ColorSupport::TrueColor),
+            (&["TERM=screen-256color"], None, ColorSupport::Xterm256),
+            (&["TERM=xterm"], None, ColorSupport::Ansi16),
+        ] {
+            let detected = ColorSupport::detect(env(vars), max_colors);
+            assert_eq!(detected, expected, "{vars:?} {max_colors:?}");
+        }
+    }
+
+    #[test]
+    fn max_colors() {
+        for (max_colors, expected) in [
+            (0x100_0000, ColorSupport::TrueColor),
+            (0xff_ffff, ColorSupport::Xterm256),
+            (256, ColorSupport::Xterm256),
+            (88, ColorSupport::Ansi16),
+            (16, ColorSupport::Ansi16),
+            (8, ColorSupport::Ansi16),
+            (2, ColorSupport::None),
+            (0, ColorSupport::None),
+        ] {
+            assert_eq!(
+                ColorSupport::of_max_colors(max_colors),
+                expected,
+                "{max_colors}"
+            );
+        }
+    }
+
+    /// Rendering with fewer colors shows them differently, but prints the
+    /// same xterm color, from the same palette.
+    #[test]
+    fn rendering_keeps_the_palette() {
+        let color = "#3572a5".parse::<TermColor>().unwrap();
+        let options = RenderOptions::new().palette(Palette::Xterm256);
+        assert!(ColorSupport::None.rendering(options.clone()).is_none());
+
+        colored::control::set_override(true);
+        let render = |support: ColorSupport, options: &RenderOptions| {
+            color.render(&support.rendering(options.clone()).unwrap())
+        };
+        let truecolor = render(ColorSupport::TrueColor, &options);
+        assert!(
+            truecolor.starts_with("\x1b[1;38;2;53;114;165m"),
+            "{truecolor:?}"
+        );
+        let xterm = render(ColorSupport::Xterm256, &options);
+        assert!(xterm.starts_with("\x1b[1;38;5;61m"), "{xterm:?}");
+        let ansi = render(ColorSupport::Ansi16, &options);
+        assert!(ansi.starts_with("\x1b[1;36m"), "{ansi:?}");
+        for rendered in [&truecolor, &xterm, &ansi] {
+            assert!(rendered.contains("xterm 61"), "{rendered:?}");
+        }
+
+        // the standard colors of a theme are shown as they are
+        let ansi16 = options.clone().palette(Palette::Ansi16);
+        assert_eq!(
+            render(ColorSupport::Ansi16, &ansi16),
+            render(ColorSupport::Xterm256, &ansi16)
+        );
+        let background = options.layer(Layer::Background);
+        let ansi = render(ColorSupport::Ansi16, &background);
+        assert!(ansi.starts_with("\x1b[1;46;97m"), "{ansi:?}");
+    }
+
+    /// A compiled entry with `colors`, in the legacy format with 16-bit
+    /// numbers or the extended one with 32-bit numbers.
+    fn entry(colors: i32, wide: bool) -> Vec<u8> {
+        let names = b"test|a test terminal\0";
+        let booleans = 3;
+        let numbers = 15;
+        let mut entry = Vec::new();
+        let magic: i16 = if wide { 0o1036 } else { 0o432 };
+        for short in [magic, names.len() as i16, booleans, numbers, 0, 0] {
+            entry.extend(short.to_le_bytes());
+        }
+        entry.extend(names);
+        entry.extend([0; 3]);
+        if entry.len() % 2 == 1 {
+            entry.push(0);
+        }
+        for index in 0..numbers {
+            let number = if index == 13 { colors } else { -1 };
+            match wide {
+                true => entry.extend(number.to_le_bytes()),
+                false => entry.extend((number as i16).to_le_bytes()),
+            }
+        }
+        entry
+    }
+
+    #[test]
+    fn parsed_max_colors() {
+        assert_eq!(parse_max_colors(&entry(256, false)), Some(256));
+        assert_eq!(parse_max_colors(&entry(8, false)), Some(8));
+        assert_eq!(parse_max_colors(&entry(0x100_0000, true)), Some(0x100_0000));
+        assert_eq!(parse_max_colors(&entry(-1, false)), None);
+        assert_eq!(parse_max_colors(&entry(-2, true)), None);
+        assert_eq!(parse_max_colors(b""), None);
+        assert_eq!(parse_max_colors(b"not an entry"), None);
+        // cut off in the middle of `colors`
+        let short = entry(256, false);
+        assert_eq!(parse_max_colors(&short[..short.len() - 3]), None);
+    }
+
+    #[test]
+    fn terminfo_lookup() {
+        let dir = std::env::temp_dir().join(format!(
+            "linguist-termcolor-terminfo-{}",
+            std::process::id()
+        ));
+        // Linux and macOS layouts
+        fs::create_dir_all(dir.join("t")).unwrap();
+        fs::create_dir_all(dir.join("76")).unwrap();
+        fs::write(dir.join("t/test-256"), entry(256, false)).unwrap();
+        fs::write(dir.join("76/vtest"), entry(8, false)).unwrap();
+        let terminfo = format!("TERMINFO={}", dir.display());
+        let vars = [terminfo.as_str()];
+        assert_eq!(terminfo_max_colors("test-256", env(&vars)), Some(256));
+        assert_eq!(terminfo_max_colors("vtest", env(&vars)), Some(8));
+        assert_eq!(terminfo_max_colors("test-none", env(&vars)), None);
+        assert_eq!(terminfo_max_colors("../t/test-256", env(&vars)), None);
+        assert_eq!(terminfo_max_colors("", env(&vars)), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn terminfo_order() {
+        let dirs = terminfo_dirs(env(&[
+            "TERMINFO=/a",
+            "HOME=/home/me",
+            "TERMINFO_DIRS=/b::/c",
+        ]));
+        let dirs = dirs
+            .iter()
+            .map(|dir| dir.to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            dirs[..5],
+            [
+                "/a",
+                "/home/me/.terminfo",
+                "/b",
+                "/usr/share/terminfo",
+                "/c"
+            ]
+        );
+        assert_eq!(terminfo_dirs(env(&[]))[0], Path::new("/etc/terminfo"));
+    }
+}
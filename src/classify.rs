@@ -0,0 +1,189 @@
+//! Recognize queries that are not language names, such as file globs and
+//! questions pasted from shell history, and reduce them to the words that are.
+//!
+//! This only applies to queries with no exact name or alias match: a
+//! [`ColorMap`](crate::ColorMap) query is classified right before it is split
+//! into words.
+
+use std::collections::BTreeSet;
+
+/// What a query looks like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryShape {
+    /// Used as is.
+    Plain,
+    /// File paths or glob patterns, such as `src/**/*.rs`, reduced to their
+    /// extensions.
+    Glob,
+    /// A question, such as `what color is rust?`, with [`STOPWORDS`] removed.
+    Sentence,
+}
+
+/// Words removed from questions.
+pub const STOPWORDS: &[&str] = &[
+    "a",
+    "an",
+    "about",
+    "color",
+    "colors",
+    "colour",
+    "colours",
+    "do",
+    "does",
+    "for",
+    "get",
+    "give",
+    "how",
+    "in",
+    "is",
+    "language",
+    "languages",
+    "me",
+    "of",
+    "please",
+    "show",
+    "tell",
+    "the",
+    "what",
+    "whats",
+    "what's",
+    "which",
+    "with",
+];
+
+/// Words that start a question.
+const QUESTION_WORDS: &[&str] = &[
+    "what", "whats", "what's", "which", "how", "is", "does", "show", "give", "tell",
+];
+
+/// Classify `query`, returning its shape and the text to tokenize in its place.
+///
+/// ```text
+/// src/**/*.rs            -> Glob, "rs"
+/// src/main.rs lib/a.py   -> Glob, "py rs"
+/// what color is rust?    -> Sentence, "rust"
+/// rust python            -> Plain, "rust python"
+/// ```
+pub fn classify(query: &str) -> (QueryShape, String) {
+    if let Some(extensions) = glob_extensions(query) {
+        return (
+            QueryShape::Glob,
+            extensions.into_iter().collect::<Vec<_>>().join(" "),
+        );
+    }
+    if let Some(words) = question_words(query) {
+        return (QueryShape::Sentence, words.join(" "));
+    }
+    (QueryShape::Plain, query.to_string())
+}
+
+/// The last extension of every word, if every word is a path or a pattern
+/// ending in an extension.
+fn glob_extensions(query: &str) -> Option<BTreeSet<&str>> {
+    let mut extensions = BTreeSet::new();
+    for word in query.split_whitespace() {
+        if !word.contains(['/', '\\', '*', '?']) {
+            return None;
+        }
+        let file = word.rsplit(['/', '\\']).next()?;
+        let (_, extension) = file.rsplit_once('.')?;
+        if extension.is_empty() || extension.contains(['*', '?', '[', '{']) {
+            return None;
+        }
+        extensions.insert(extension);
+    }
+    match extensions.is_empty() {
+        true => None,
+        false => Some(extensions),
+    }
+}
+
+/// The words of `query` besides [`STOPWORDS`], if it reads like a question:
+/// it ends with `?`, or starts with a question word and has more than two words.
+fn question_words(query: &str) -> Option<Vec<&str>> {
+    let words = query
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c: char| matches!(c, '?' | ',' | '!' | '"')))
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
+    let first = words.first()?.to_lowercase();
+    let question = query.trim_end().ends_with('?')
+        || (words.len() > 2 && QUESTION_WORDS.contains(&first.as_str()));
+    if !question {
+        return None;
+    }
+    let words = words
+        .into_iter()
+        .filter(|word| !STOPWORDS.contains(&word.to_lowercase().as_str()))
+        .collect::<Vec<_>>();
+    match words.is_empty() {
+        true => None,
+        false => Some(words),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Linguist;
+
+    #[test]
+    fn shapes() {
+        for (query, shape, text) in [
+            ("src/**/*.rs", QueryShape::Glob, "rs"),
+            ("*.py", QueryShape::Glob, "py"),
+            ("src/main.rs lib/a.py", QueryShape::Glob, "py rs"),
+            ("src\\main.rs", QueryShape::Glob, "rs"),
+            ("a/b.rs c/d.rs", QueryShape::Glob, "rs"),
+            ("what color is rust?", QueryShape::Sentence, "rust"),
+            ("What's the colour of Go", QueryShape::Sentence, "Go"),
+            (
+                "which color does python have?",
+                QueryShape::Sentence,
+                "python have",
+            ),
+            ("rust?", QueryShape::Sentence, "rust"),
+            (
+                "show me the color of \"c++\", please",
+                QueryShape::Sentence,
+                "c++",
+            ),
+            ("rust python", QueryShape::Plain, "rust python"),
+            ("what rust", QueryShape::Plain, "what rust"),
+            // a mix of patterns and words, or patterns without an extension
+            ("src/*.rs rust", QueryShape::Plain, "src/*.rs rust"),
+            ("src/**", QueryShape::Plain, "src/**"),
+            ("*.{rs,py}", QueryShape::Plain, "*.{rs,py}"),
+            ("Makefile", QueryShape::Plain, "Makefile"),
+            // only a question left once the stopwords are gone
+            (
+                "what is the color?",
+                QueryShape::Plain,
+                "what is the color?",
+            ),
+            ("", QueryShape::Plain, ""),
+        ] {
+            assert_eq!(classify(query), (shape, text.to_string()), "{query:?}");
+        }
+    }
+
+    /// Each style of input finds the language it is about first.
+    #[test]
+    fn finds_the_language() {
+        let linguist: Linguist = include_str!("../data/languages.yml").parse().unwrap();
+        let colors = linguist.colors().unwrap();
+        for (query, language) in [
+            ("src/**/*.rs", "Rust"),
+            ("./crates/*/src/lib.rs", "Rust"),
+            ("what color is rust?", "Rust"),
+            ("what's the color of python", "Python"),
+            ("which color does golang have?", "Go"),
+            ("tell me the colour of typescript", "TypeScript"),
+            ("rust", "Rust"),
+        ] {
+            let ranked = colors.ranked(query);
+            let first = ranked.first().map(|ranked| ranked.name.as_ref());
+            assert_eq!(first, Some(language), "{query:?}");
+        }
+    }
+}
@@ -0,0 +1,840 @@
+//! Color conversions and the arithmetic built on them.
+//!
+//! Everything here works on plain `[f64; 3]` triples; [`Color`] only appears at
+//! the boundaries ([`srgb`], [`from_srgb`], and the convenience functions taking
+//! `&Color`), so that every feature doing color arithmetic agrees on the math.
+//!
+//! - sRGB and linear sRGB components are in `0.0..=1.0`
+//! - XYZ is relative to D65 with `Y` in `0.0..=1.0`
+//! - [CIELAB] is relative to D50, matching CSS `lab()`
+//! - [OKLab] follows Björn Ottosson's definition, matching CSS `oklab()`
+//! - HSL is `[hue in degrees, saturation in 0..=1, lightness in 0..=1]`
+//!
+//! [CIELAB]: https://www.w3.org/TR/css-color-4/#lab-colors
+//! [OKLab]: https://bottosson.github.io/posts/oklab/
+
+use color_art::Color;
+
+type Matrix = [[f64; 3]; 3];
+
+fn multiply(m: &Matrix, v: [f64; 3]) -> [f64; 3] {
+    [
+        m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+        m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+        m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+    ]
+}
+
+/// The sRGB components of `color`.
+pub fn srgb(color: &Color) -> [f64; 3] {
+    [
+        color.red() as f64 / 255.0,
+        color.green() as f64 / 255.0,
+        color.blue() as f64 / 255.0,
+    ]
+}
+
+/// A [`Color`] from sRGB components, clamped to the gamut.
+pub fn from_srgb(rgb: [f64; 3]) -> Color {
+    let [r, g, b] = rgb.map(|c| (c.clamp(0.0, 1.0) * 255.0).round());
+    Color::new(r, g, b, 1.0)
+}
+
+/// Whether sRGB components are within the gamut, allowing for rounding errors.
+pub fn in_gamut(rgb: [f64; 3]) -> bool {
+    rgb.iter().all(|c| (-1e-6..=1.0 + 1e-6).contains(c))
+}
+
+/// The sRGB transfer function (EOTF), decoding a gamma-encoded component.
+pub fn srgb_to_linear(c: f64) -> f64 {
+    if c.abs() <= 0.04045 {
+        c / 12.92
+    } else {
+        c.signum() * ((c.abs() + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// The inverse of [`srgb_to_linear`].
+pub fn linear_to_srgb(c: f64) -> f64 {
+    if c.abs() <= 0.0031308 {
+        c * 12.92
+    } else {
+        c.signum() * (1.055 * c.abs().powf(1.0 / 2.4) - 0.055)
+    }
+}
+
+const LINEAR_SRGB_TO_XYZ: Matrix = [
+    [0.41239079926595934, 0.357584339383878, 0.1804807884018343],
+    [0.21263900587151027, 0.715168678767756, 0.07219231536073371],
+    [0.01933081871559182, 0.11919477979462598, 0.9505321522496606],
+];
+
+const XYZ_TO_LINEAR_SRGB: Matrix = [
+    [3.2409699419045226, -1.537383177570094, -0.4986107602930034],
+    [-0.9692436362808796, 1.8759675015077202, 0.04155505740717559],
+    [
+        0.05563007969699366,
+        -0.20397695888897652,
+        1.0569715142428786,
+    ],
+];
+
+/// Bradford chromatic adaptation
+const D65_TO_D50: Matrix = [
+    [
+        1.0479297925449969,
+        0.022946870601609652,
+        -0.05019226628920524,
+    ],
+    [
+        0.02962780877005599,
+        0.9904344267538799,
+        -0.017073799063418826,
+    ],
+    [
+        -0.009243040646204504,
+        0.015055191490298152,
+        0.7518742814281371,
+    ],
+];
+
+const D50_TO_D65: Matrix = [
+    [0.955473421488075, -0.02309845494876471, 0.06325924320057072],
+    [
+        -0.0283697093338637,
+        1.0099953980813041,
+        0.021041441191917323,
+    ],
+    [
+        0.012314014864481998,
+        -0.020507649298898964,
+        1.330365926242124,
+    ],
+];
+
+const D50: [f64; 3] = [0.3457 / 0.3585, 1.0, (1.0 - 0.3457 - 0.3585) / 0.3585];
+
+const LAB_EPSILON: f64 = 216.0 / 24389.0;
+const LAB_KAPPA: f64 = 24389.0 / 27.0;
+
+pub fn linear_to_xyz(rgb: [f64; 3]) -> [f64; 3] {
+    multiply(&LINEAR_SRGB_TO_XYZ, rgb)
+}
+
+pub fn xyz_to_linear(xyz: [f64; 3]) -> [f64; 3] {
+    multiply(&XYZ_TO_LINEAR_SRGB, xyz)
+}
+
+pub fn xyz_to_lab(xyz: [f64; 3]) -> [f64; 3] {
+    let xyz = multiply(&D65_TO_D50, xyz);
+    let [fx, fy, fz] = [0, 1, 2].map(|i| {
+        let v = xyz[i] / D50[i];
+        if v > LAB_EPSILON {
+            v.cbrt()
+        } else {
+            (LAB_KAPPA * v + 16.0) / 116.0
+        }
+    });
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+pub fn lab_to_xyz(lab: [f64; 3]) -> [f64; 3] {
+    let [l, a, b] = lab;
+    let fy = (l + 16.0) / 116.0;
+    let fx = a / 500.0 + fy;
+    let fz = fy - b / 200.0;
+    let x = match fx.powi(3) {
+        v if v > LAB_EPSILON => v,
+        _ => (116.0 * fx - 16.0) / LAB_KAPPA,
+    };
+    let y = match l > LAB_KAPPA * LAB_EPSILON {
+        true => fy.powi(3),
+        false => l / LAB_KAPPA,
+    };
+    let z = match fz.powi(3) {
+        v if v > LAB_EPSILON => v,
+        _ => (116.0 * fz - 16.0) / LAB_KAPPA,
+    };
+    multiply(&D50_TO_D65, [x * D50[0], y * D50[1], z * D50[2]])
+}
+
+const LINEAR_SRGB_TO_LMS: Matrix = [
+    [0.4122214708, 0.5363325363, 0.0514459929],
+    [0.2119034982, 0.6806995451, 0.1073969566],
+    [0.0883024619, 0.2817188376, 0.6299787005],
+];
+
+const LMS_TO_OKLAB: Matrix = [
+    [0.2104542553, 0.7936177850, -0.0040720468],
+    [1.9779984951, -2.4285922050, 0.4505937099],
+    [0.0259040371, 0.7827717662, -0.8086757660],
+];
+
+const OKLAB_TO_LMS: Matrix = [
+    [1.0, 0.3963377774, 0.2158037573],
+    [1.0, -0.1055613458, -0.0638541728],
+    [1.0, -0.0894841775, -1.2914855480],
+];
+
+const LMS_TO_LINEAR_SRGB: Matrix = [
+    [4.0767416621, -3.3077115913, 0.2309699292],
+    [-1.2684380046, 2.6097574011, -0.3413193965],
+    [-0.0041960863, -0.7034186147, 1.7076147010],
+];
+
+pub fn linear_to_oklab(rgb: [f64; 3]) -> [f64; 3] {
+    let lms = multiply(&LINEAR_SRGB_TO_LMS, rgb).map(f64::cbrt);
+    multiply(&LMS_TO_OKLAB, lms)
+}
+
+pub fn oklab_to_linear(lab: [f64; 3]) -> [f64; 3] {
+    let lms = multiply(&OKLAB_TO_LMS, lab).map(|c| c.powi(3));
+    multiply(&LMS_TO_LINEAR_SRGB, lms)
+}
+
+/// `[L, a, b]` to `[L, C, h]`, with the hue in degrees. Works for both
+/// CIELAB and OKLab.
+pub fn to_polar(lab: [f64; 3]) -> [f64; 3] {
+    let [l, a, b] = lab;
+    let c = a.hypot(b);
+    let h = b.atan2(a).to_degrees().rem_euclid(360.0);
+    [l, c, h]
+}
+
+/// The inverse of [`to_polar`].
+pub fn from_polar(lch: [f64; 3]) -> [f64; 3] {
+    let [l, c, h] = lch;
+    let (sin, cos) = h.to_radians().sin_cos();
+    [l, c * cos, c * sin]
+}
+
+pub fn rgb_to_hsl(rgb: [f64; 3]) -> [f64; 3] {
+    let [r, g, b] = rgb;
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let d = max - min;
+    if d == 0.0 {
+        return [0.0, 0.0, l];
+    }
+    let s = d / (1.0 - (2.0 * l - 1.0).abs());
+    let h = if max == r {
+        ((g - b) / d).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / d + 2.0
+    } else {
+        (r - g) / d + 4.0
+    };
+    [h * 60.0, s, l]
+}
+
+pub fn hsl_to_rgb(hsl: [f64; 3]) -> [f64; 3] {
+    let [h, s, l] = hsl;
+    let h = h.rem_euclid(360.0);
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = c * (1.0 - ((h / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = l - c / 2.0;
+    let [r, g, b] = match h {
+        h if h < 60.0 => [c, x, 0.0],
+        h if h < 120.0 => [x, c, 0.0],
+        h if h < 180.0 => [0.0, c, x],
+        h if h < 240.0 => [0.0, x, c],
+        h if h < 300.0 => [x, 0.0, c],
+        _ => [c, 0.0, x],
+    };
+    [r + m, g + m, b + m]
+}
+
+/// Linear sRGB components of `color`.
+pub fn linear(color: &Color) -> [f64; 3] {
+    srgb(color).map(srgb_to_linear)
+}
+
+/// A [`Color`] from linear sRGB components, the inverse of [`linear`].
+pub fn from_linear(rgb: [f64; 3]) -> Color {
+    from_srgb(rgb.map(linear_to_srgb))
+}
+
+/// CIELAB coordinates of `color`.
+pub fn lab(color: &Color) -> [f64; 3] {
+    xyz_to_lab(linear_to_xyz(linear(color)))
+}
+
+/// A [`Color`] from CIELAB coordinates, clamped to the sRGB gamut.
+pub fn from_lab(lab: [f64; 3]) -> Color {
+    from_linear(xyz_to_linear(lab_to_xyz(lab)))
+}
+
+/// OKLab coordinates of `color`.
+pub fn oklab(color: &Color) -> [f64; 3] {
+    linear_to_oklab(linear(color))
+}
+
+/// A [`Color`] from OKLab coordinates, clamped to the sRGB gamut.
+pub fn from_oklab(lab: [f64; 3]) -> Color {
+    from_linear(oklab_to_linear(lab))
+}
+
+/// HSL coordinates of `color`.
+pub fn hsl(color: &Color) -> [f64; 3] {
+    rgb_to_hsl(srgb(color))
+}
+
+/// A [`Color`] from HSL coordinates.
+pub fn from_hsl(hsl: [f64; 3]) -> Color {
+    from_srgb(hsl_to_rgb(hsl))
+}
+
+/// Euclidean distance between two triples, e.g. ΔE*76 for CIELAB, or ΔEOK for OKLab.
+pub fn euclidean(a: [f64; 3], b: [f64; 3]) -> f64 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+/// The CIEDE2000 color difference between two CIELAB colors.
+///
+/// See Sharma, Wu, and Dalal, "The CIEDE2000 Color-Difference Formula:
+/// Implementation Notes, Supplementary Test Data, and Mathematical Observations" (2005).
+pub fn delta_e2000(lab1: [f64; 3], lab2: [f64; 3]) -> f64 {
+    let [l1, a1, b1] = lab1;
+    let [l2, a2, b2] = lab2;
+
+    let c_bar = (a1.hypot(b1) + a2.hypot(b2)) / 2.0;
+    let c_bar7 = c_bar.powi(7);
+    let g = 0.5 * (1.0 - (c_bar7 / (c_bar7 + 25f64.powi(7))).sqrt());
+
+    let a1p = a1 * (1.0 + g);
+    let a2p = a2 * (1.0 + g);
+    let c1p = a1p.hypot(b1);
+    let c2p = a2p.hypot(b2);
+
+    let hue = |b: f64, a: f64| match (a, b) {
+        (a, b) if a == 0.0 && b == 0.0 => 0.0,
+        _ => b.atan2(a).to_degrees().rem_euclid(360.0),
+    };
+    let h1p = hue(b1, a1p);
+    let h2p = hue(b2, a2p);
+
+    let dlp = l2 - l1;
+    let dcp = c2p - c1p;
+    let dhp = match c1p * c2p {
+        0.0 => 0.0,
+        _ => match h2p - h1p {
+            d if d > 180.0 => d - 360.0,
+            d if d < -180.0 => d + 360.0,
+            d => d,
+        },
+    };
+    let dhp = 2.0 * (c1p * c2p).sqrt() * (dhp / 2.0).to_radians().sin();
+
+    let lp_bar = (l1 + l2) / 2.0;
+    let cp_bar = (c1p + c2p) / 2.0;
+    let hp_bar = match c1p * c2p {
+        0.0 => h1p + h2p,
+        _ => match ((h1p - h2p).abs() > 180.0, h1p + h2p < 360.0) {
+            (false, _) => (h1p + h2p) / 2.0,
+            (true, true) => (h1p + h2p + 360.0) / 2.0,
+            (true, false) => (h1p + h2p - 360.0) / 2.0,
+        },
+    };
+
+    let t = 1.0 - 0.17 * (hp_bar - 30.0).to_radians().cos()
+        + 0.24 * (2.0 * hp_bar).to_radians().cos()
+        + 0.32 * (3.0 * hp_bar + 6.0).to_radians().cos()
+        - 0.20 * (4.0 * hp_bar - 63.0).to_radians().cos();
+
+    let d_theta = 30.0 * (-((hp_bar - 275.0) / 25.0).powi(2)).exp();
+    let cp_bar7 = cp_bar.powi(7);
+    let r_c = 2.0 * (cp_bar7 / (cp_bar7 + 25f64.powi(7))).sqrt();
+    let s_l = 1.0 + (0.015 * (lp_bar - 50.0).powi(2)) / (20.0 + (lp_bar - 50.0).powi(2)).sqrt();
+    let s_c = 1.0 + 0.045 * cp_bar;
+    let s_h = 1.0 + 0.015 * cp_bar * t;
+    let r_t = -(2.0 * d_theta).to_radians().sin() * r_c;
+
+    let l = dlp / s_l;
+    let c = dcp / s_c;
+    let h = dhp / s_h;
+    (l * l + c * c + h * h + r_t * c * h).sqrt()
+}
+
+/// Saturation below which [`weighted_hsl`] ignores hue, which is mostly noise
+/// for nearly gray colors.
+pub const ACHROMATIC_SATURATION: f64 = 0.05;
+
+/// The distance between two HSL colors, with the differences in hue,
+/// saturation, and lightness multiplied by `weights` in that order.
+///
+/// The hue difference is taken the short way around the wheel and divided by
+/// 180°, so that all three are in `0.0..=1.0`. It counts as 0 when either
+/// color is below [`ACHROMATIC_SATURATION`].
+pub fn weighted_hsl(hsl1: [f64; 3], hsl2: [f64; 3], weights: [f64; 3]) -> f64 {
+    let [h1, s1, l1] = hsl1;
+    let [h2, s2, l2] = hsl2;
+    let dh = match s1.min(s2) < ACHROMATIC_SATURATION {
+        true => 0.0,
+        false => {
+            let dh = (h1 - h2).rem_euclid(360.0);
+            dh.min(360.0 - dh) / 180.0
+        }
+    };
+    let [wh, ws, wl] = weights;
+    ((wh * dh).powi(2) + (ws * (s1 - s2)).powi(2) + (wl * (l1 - l2)).powi(2)).sqrt()
+}
+
+/// [Relative luminance] as defined by WCAG 2.
+///
+/// [Relative luminance]: https://www.w3.org/TR/WCAG21/#dfn-relative-luminance
+pub fn relative_luminance(color: &Color) -> f64 {
+    let [r, g, b] = linear(color);
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+/// [Contrast ratio] as defined by WCAG 2, from 1 to 21.
+///
+/// [Contrast ratio]: https://www.w3.org/TR/WCAG21/#dfn-contrast-ratio
+pub fn contrast_ratio(a: &Color, b: &Color) -> f64 {
+    let la = relative_luminance(a);
+    let lb = relative_luminance(b);
+    (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+}
+
+/// A color vision deficiency, see [`simulate_cvd`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cvd {
+    /// No long-wavelength (red) cones.
+    Protanopia,
+    /// No medium-wavelength (green) cones.
+    Deuteranopia,
+    /// No short-wavelength (blue) cones.
+    Tritanopia,
+}
+
+impl Cvd {
+    pub const ALL: [Cvd; 3] = [Cvd::Protanopia, Cvd::Deuteranopia, Cvd::Tritanopia];
+
+    /// The lowercase name, e.g. `protanopia`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Cvd::Protanopia => "protanopia",
+            Cvd::Deuteranopia => "deuteranopia",
+            Cvd::Tritanopia => "tritanopia",
+        }
+    }
+
+    /// The inverse of [`Cvd::name`].
+    pub fn from_name(name: &str) -> Option<Cvd> {
+        Cvd::ALL.into_iter().find(|cvd| cvd.name() == name)
+    }
+
+    /// The simulation matrix in linear sRGB at full severity.
+    fn matrix(self) -> Matrix {
+        match self {
+            Cvd::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            Cvd::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            Cvd::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+}
+
+/// How `color` looks to someone with `kind`, clamped to the sRGB gamut.
+///
+/// See Machado, Oliveira, and Fernandes, "A Physiologically-based Model for
+/// Simulation of Color Vision Deficiency" (2009), whose matrices for severity
+/// 1.0 are applied to linear sRGB.
+pub fn simulate_cvd(color: &Color, kind: Cvd) -> Color {
+    from_linear(multiply(&kind.matrix(), linear(color)))
+}
+
+/// A named range of hues, see [`color_family`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Family {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Cyan,
+    Blue,
+    Purple,
+    Pink,
+    /// Colors with too little chroma to have a meaningful hue, including black and white.
+    Gray,
+}
+
+impl Family {
+    pub const ALL: [Family; 9] = [
+        Family::Red,
+        Family::Orange,
+        Family::Yellow,
+        Family::Green,
+        Family::Cyan,
+        Family::Blue,
+        Family::Purple,
+        Family::Pink,
+        Family::Gray,
+    ];
+
+    /// The lowercase name, e.g. `orange`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Family::Red => "red",
+            Family::Orange => "orange",
+            Family::Yellow => "yellow",
+            Family::Green => "green",
+            Family::Cyan => "cyan",
+            Family::Blue => "blue",
+            Family::Purple => "purple",
+            Family::Pink => "pink",
+            Family::Gray => "gray",
+        }
+    }
+
+    /// The inverse of [`Family::name`].
+    pub fn from_name(name: &str) -> Option<Family> {
+        Family::ALL.into_iter().find(|family| family.name() == name)
+    }
+}
+
+/// Boundaries of the color [`Family`]s, by OKLCh hue and chroma.
+#[derive(Debug, Clone)]
+pub struct FamilyBands {
+    /// Colors with an OKLCh chroma below this are [`Family::Gray`].
+    pub gray_below: f64,
+    /// The hue in degrees at which each family starts, in order around the
+    /// hue circle; each family ends where the next starts.
+    pub starts: Vec<(f64, Family)>,
+}
+
+impl Default for FamilyBands {
+    /// | family | OKLCh hue |
+    /// | ------ | --------- |
+    /// | pink   | 330 – 15  |
+    /// | red    | 15 – 45   |
+    /// | orange | 45 – 80   |
+    /// | yellow | 80 – 120  |
+    /// | green  | 120 – 170 |
+    /// | cyan   | 170 – 230 |
+    /// | blue   | 230 – 285 |
+    /// | purple | 285 – 330 |
+    ///
+    /// Colors with chroma below 0.03 are gray.
+    fn default() -> Self {
+        Self {
+            gray_below: 0.03,
+            starts: vec![
+                (15.0, Family::Red),
+                (45.0, Family::Orange),
+                (80.0, Family::Yellow),
+                (120.0, Family::Green),
+                (170.0, Family::Cyan),
+                (230.0, Family::Blue),
+                (285.0, Family::Purple),
+                (330.0, Family::Pink),
+            ],
+        }
+    }
+}
+
+impl FamilyBands {
+    pub fn classify(&self, color: &Color) -> Family {
+        let [_, c, h] = to_polar(oklab(color));
+        if c < self.gray_below {
+            return Family::Gray;
+        }
+        // hues before the first start belong to the last family, wrapping around
+        self.starts
+            .iter()
+            .rev()
+            .find(|(start, _)| h >= *start)
+            .or(self.starts.last())
+            .map(|(_, family)| *family)
+            .unwrap_or(Family::Gray)
+    }
+}
+
+/// The [`Family`] of `color` with the default [`FamilyBands`].
+pub fn color_family(color: &Color) -> Family {
+    FamilyBands::default().classify(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: [f64; 3], b: [f64; 3], tolerance: f64) -> bool {
+        a.iter().zip(b).all(|(a, b)| (a - b).abs() <= tolerance)
+    }
+
+    fn hex(hex: &str) -> Color {
+        Color::from_hex(hex).unwrap()
+    }
+
+    /// Reference values from the sample code of CSS Color 4, whose `lab()` is
+    /// also relative to D50, and from Björn Ottosson's post for OKLab.
+    #[test]
+    fn reference_values() {
+        let white = linear(&hex("#ffffff"));
+        assert!(close(linear_to_xyz(white), [0.95046, 1.0, 1.08906], 1e-4));
+        for (color, lab, oklab) in [
+            ("#ffffff", [100.0, 0.0, 0.0], [1.0, 0.0, 0.0]),
+            ("#000000", [0.0, 0.0, 0.0], [0.0, 0.0, 0.0]),
+            (
+                "#ff0000",
+                [54.29, 80.80, 69.89],
+                [0.62796, 0.22486, 0.12585],
+            ),
+            (
+                "#00ff00",
+                [87.82, -79.27, 80.99],
+                [0.86644, -0.23389, 0.17950],
+            ),
+            (
+                "#0000ff",
+                [29.57, 68.29, -112.03],
+                [0.45201, -0.03246, -0.31153],
+            ),
+        ] {
+            let color = hex(color);
+            assert!(
+                close(super::lab(&color), lab, 0.05),
+                "CIELAB of {color}: {:?}",
+                super::lab(&color)
+            );
+            assert!(
+                close(super::oklab(&color), oklab, 1e-4),
+                "OKLab of {color}: {:?}",
+                super::oklab(&color)
+            );
+        }
+    }
+
+    /// Six evenly spaced levels of each sRGB channel survive every conversion
+    /// and back.
+    #[test]
+    fn round_trips() {
+        let steps = (0..=255).step_by(51).map(|c| c as f64 / 255.0);
+        for r in steps.clone() {
+            for g in steps.clone() {
+                for b in steps.clone() {
+                    let rgb = [r, g, b];
+                    let linear = rgb.map(srgb_to_linear);
+                    assert!(close(linear.map(linear_to_srgb), rgb, 1e-9), "{rgb:?}");
+                    let xyz = linear_to_xyz(linear);
+                    assert!(close(xyz_to_linear(xyz), linear, 1e-9), "{rgb:?}");
+                    assert!(close(lab_to_xyz(xyz_to_lab(xyz)), xyz, 1e-9), "{rgb:?}");
+                    let oklab = linear_to_oklab(linear);
+                    assert!(close(oklab_to_linear(oklab), linear, 1e-6), "{rgb:?}");
+                    assert!(close(from_polar(to_polar(oklab)), oklab, 1e-9), "{rgb:?}");
+                    assert!(close(hsl_to_rgb(rgb_to_hsl(rgb)), rgb, 1e-9), "{rgb:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hsl_values() {
+        for (color, hsl) in [
+            ("#ff0000", [0.0, 1.0, 0.5]),
+            ("#00ff00", [120.0, 1.0, 0.5]),
+            ("#0000ff", [240.0, 1.0, 0.5]),
+            ("#808080", [0.0, 0.0, 128.0 / 255.0]),
+            ("#dea584", [22.0, 0.5769, 0.6941]),
+        ] {
+            let color = hex(color);
+            assert!(
+                close(super::hsl(&color), hsl, 1e-3),
+                "HSL of {color}: {:?}",
+                super::hsl(&color)
+            );
+        }
+    }
+
+    /// Hues are compared the short way around the wheel, so 359° and 1° are
+    /// 2° apart rather than 358°, and not at all for nearly gray colors.
+    #[test]
+    fn hue_wraparound() {
+        let weights = [2.0, 1.0, 1.0];
+        let hue = |h1: f64, h2: f64| weighted_hsl([h1, 1.0, 0.5], [h2, 1.0, 0.5], weights);
+        for (h1, h2, degrees) in [
+            (359.0, 1.0, 2.0),
+            (1.0, 359.0, 2.0),
+            (350.0, 10.0, 20.0),
+            (0.0, 360.0, 0.0),
+            (720.0, 1.0, 1.0),
+            (-10.0, 10.0, 20.0),
+            (90.0, 270.0, 180.0),
+            (10.0, 200.0, 170.0),
+        ] {
+            let expected = 2.0 * degrees / 180.0;
+            assert!((hue(h1, h2) - expected).abs() < 1e-9, "{h1}° {h2}°");
+        }
+        assert!(hue(359.0, 1.0) < hue(0.0, 3.0));
+        // opposite hues, as far apart as hues go, weigh `weights[0]`
+        assert_eq!(hue(0.0, 180.0), 2.0);
+
+        let gray = [200.0, ACHROMATIC_SATURATION / 2.0, 0.5];
+        assert_eq!(weighted_hsl(gray, [20.0, 1.0, 0.5], [1.0, 0.0, 0.0]), 0.0);
+        assert_eq!(weighted_hsl([20.0, 1.0, 0.5], gray, [1.0, 0.0, 0.0]), 0.0);
+
+        // the same through colors, whose hues come out in 0..360
+        let metric = crate::Metric::WeightedHsl(Default::default());
+        let (a, b) = (from_hsl([359.0, 1.0, 0.5]), from_hsl([1.0, 1.0, 0.5]));
+        assert!(
+            metric.distance(&a, &b) < 0.03,
+            "{}",
+            metric.distance(&a, &b)
+        );
+        assert_eq!(metric.distance(&a, &b), metric.distance(&b, &a));
+    }
+
+    /// Pairs 1, 7, 17, 25, and 34 of the test data of Sharma, Wu, and Dalal.
+    #[test]
+    fn ciede2000() {
+        for (lab1, lab2, delta) in [
+            ([50.0, 2.6772, -79.7751], [50.0, 0.0, -82.7485], 2.0425),
+            ([50.0, 0.0, 0.0], [50.0, -1.0, 2.0], 2.3669),
+            ([50.0, 2.5, 0.0], [73.0, 25.0, -18.0], 27.1492),
+            (
+                [60.2574, -34.0099, 36.2677],
+                [60.4626, -34.1751, 39.4387],
+                1.2644,
+            ),
+            (
+                [2.0776, 0.0795, -1.1350],
+                [0.9033, -0.0636, -0.5514],
+                0.9082,
+            ),
+        ] {
+            assert!(
+                (delta_e2000(lab1, lab2) - delta).abs() < 1e-4,
+                "{lab1:?} {lab2:?}"
+            );
+            assert!(
+                (delta_e2000(lab2, lab1) - delta).abs() < 1e-4,
+                "{lab2:?} {lab1:?}"
+            );
+        }
+        assert_eq!(delta_e2000([50.0, 10.0, 10.0], [50.0, 10.0, 10.0]), 0.0);
+    }
+
+    /// Values from the WCAG 2 definitions.
+    #[test]
+    fn contrast() {
+        assert_eq!(relative_luminance(&hex("#ffffff")), 1.0);
+        assert_eq!(relative_luminance(&hex("#000000")), 0.0);
+        assert_eq!(contrast_ratio(&hex("#000000"), &hex("#ffffff")), 21.0);
+        assert_eq!(contrast_ratio(&hex("#dea584"), &hex("#dea584")), 1.0);
+        // the gray that just misses AA for normal text on white
+        let gray = contrast_ratio(&hex("#777777"), &hex("#ffffff"));
+        assert!((gray - 4.48).abs() < 0.01, "{gray}");
+        assert_eq!(
+            contrast_ratio(&hex("#777777"), &hex("#ffffff")),
+            contrast_ratio(&hex("#ffffff"), &hex("#777777"))
+        );
+    }
+
+    /// Simulations keep grays gray, and each kind loses its own cone's hues.
+    #[test]
+    fn cvd() {
+        for kind in [Cvd::Protanopia, Cvd::Deuteranopia, Cvd::Tritanopia] {
+            assert_eq!(Cvd::from_name(kind.name()), Some(kind));
+            for gray in ["#000000", "#808080", "#ffffff"] {
+                let seen = simulate_cvd(&hex(gray), kind);
+                assert!(
+                    close(srgb(&seen), srgb(&hex(gray)), 2.0 / 255.0),
+                    "{gray} with {kind:?}"
+                );
+            }
+        }
+        let red = simulate_cvd(&hex("#ff0000"), Cvd::Protanopia);
+        let [r, g, _] = srgb(&red);
+        assert!(r - g < 0.5, "red with protanopia: {red}");
+    }
+
+    /// The matrices of Machado, Oliveira, and Fernandes for severity 1.0,
+    /// from the table of their paper, turn each primary into the column of
+    /// it, up to 8-bit rounding, and with negative components clamped to 0.
+    /// The colors below are what the matrices give on linear sRGB, worked out
+    /// apart from this code.
+    #[test]
+    fn cvd_reference_values() {
+        let published: [(Cvd, Matrix); 3] = [
+            (
+                Cvd::Protanopia,
+                [
+                    [0.152286, 1.052583, -0.204868],
+                    [0.114503, 0.786281, 0.099216],
+                    [-0.003882, -0.048116, 1.051998],
+                ],
+            ),
+            (
+                Cvd::Deuteranopia,
+                [
+                    [0.367322, 0.860646, -0.227968],
+                    [0.280085, 0.672501, 0.047413],
+                    [-0.011820, 0.042940, 0.968881],
+                ],
+            ),
+            (
+                Cvd::Tritanopia,
+                [
+                    [1.255528, -0.076749, -0.178779],
+                    [-0.078411, 0.930809, 0.147602],
+                    [0.004733, 0.691367, 0.303900],
+                ],
+            ),
+        ];
+        for (kind, matrix) in published {
+            for (column, primary) in ["#ff0000", "#00ff00", "#0000ff"].into_iter().enumerate() {
+                let seen = linear(&simulate_cvd(&hex(primary), kind));
+                let expected = matrix.map(|row| row[column].clamp(0.0, 1.0));
+                assert!(
+                    close(seen, expected, 0.005),
+                    "{primary} with {kind:?}: {seen:?}"
+                );
+            }
+        }
+        for (color, protanopia, deuteranopia, tritanopia) in [
+            ("#ff0000", "#6d5f00", "#a39000", "#ff000f"),
+            ("#00ff00", "#ffe500", "#efd63a", "#00f7d9"),
+            ("#0000ff", "#0059ff", "#003dfb", "#006b96"),
+            ("#dea584", "#b5aa82", "#c2b684", "#ed9b9c"),
+            ("#3572a5", "#5b74a7", "#4c69a4", "#007e84"),
+        ] {
+            for (kind, expected) in [
+                (Cvd::Protanopia, protanopia),
+                (Cvd::Deuteranopia, deuteranopia),
+                (Cvd::Tritanopia, tritanopia),
+            ] {
+                let seen = simulate_cvd(&hex(color), kind);
+                assert_eq!(srgb(&seen), srgb(&hex(expected)), "{color} with {kind:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn families() {
+        for (color, family) in [
+            ("#ff0000", Family::Red),
+            ("#dea584", Family::Orange),
+            ("#ffff00", Family::Yellow),
+            ("#00ff00", Family::Green),
+            ("#00add8", Family::Cyan),
+            ("#0000ff", Family::Blue),
+            ("#808080", Family::Gray),
+            ("#ffffff", Family::Gray),
+        ] {
+            assert_eq!(color_family(&hex(color)), family, "for {color}");
+        }
+    }
+}
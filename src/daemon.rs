@@ -0,0 +1,384 @@
+//! A Unix socket server that keeps the data loaded between queries, for prompt
+//! and editor integrations that query many times a minute.
+//!
+//! Every message, in either direction, is a 4-byte big-endian length followed
+//! by that many bytes of JSON. A client may send any number of requests over
+//! one connection, and gets one response for each:
+//!
+//! ```text
+//! {"op":"query","q":"rust","space":"lab"}  -> [{"language":"rust",...}]
+//! {"op":"stop"}                            -> {"stopped":true}
+//! ```
+//!
+//! The result of a query is up to the handler given to [`Server::serve`]. A
+//! request that fails is answered with `{"error":"..."}`.
+
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    os::unix::{
+        fs::PermissionsExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::Error;
+
+/// The largest message accepted, in bytes.
+pub const MAX_MESSAGE: u32 = 1024 * 1024;
+
+/// How often the server checks for idleness and [`Request::Stop`].
+const POLL: Duration = Duration::from_millis(50);
+
+/// How long a connection may go without a request before it is closed.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A request to the daemon.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Request {
+    Query(Query),
+    /// Shut the daemon down once the connections in progress are done.
+    Stop,
+}
+
+/// The arguments of [`Request::Query`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Query {
+    /// The query, as typed after `for`.
+    pub q: String,
+    /// The query split into arguments as the shell did, if it matters, such as
+    /// for `each`. Default: `q` split on whitespace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub args: Option<Vec<String>>,
+    /// Look up every argument on its own, as with `for --each`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub each: bool,
+    /// The color space in which to find the nearest xterm colors, by any name
+    /// that `-c` accepts. Default: the daemon's own `-c`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub space: Option<String>,
+    /// The order of the results, by any name that `--sort` accepts. Default:
+    /// best match first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+}
+
+impl Query {
+    /// [`Query::args`], or `q` split on whitespace.
+    pub fn args(&self) -> Vec<String> {
+        match &self.args {
+            Some(args) => args.clone(),
+            None => self.q.split_whitespace().map(String::from).collect(),
+        }
+    }
+}
+
+/// Where the daemon listens: `$XDG_RUNTIME_DIR/linguist-termcolor.sock`, or a
+/// file named after the user in the temporary directory.
+pub fn socket_path() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        Some(dir) => Path::new(&dir).join("linguist-termcolor.sock"),
+        None => {
+            let user = std::env::var("USER").unwrap_or_default();
+            std::env::temp_dir().join(format!("linguist-termcolor-{user}.sock"))
+        }
+    }
+}
+
+/// Write `message` framed with its length.
+pub fn write_message<W: Write>(w: &mut W, message: &impl Serialize) -> Result<(), Error> {
+    let body = serde_json::to_vec(message)?;
+    let len = u32::try_from(body.len())
+        .ok()
+        .filter(|&len| len <= MAX_MESSAGE)
+        .ok_or_else(|| Error::Daemon(format!("message of {} bytes is too large", body.len())))?;
+    w.write_all(&len.to_be_bytes())?;
+    w.write_all(&body)?;
+    w.flush()?;
+    Ok(())
+}
+
+/// Read a message framed with its length. `None` if the other end closed the
+/// connection between messages.
+pub fn read_message<R: Read, T: DeserializeOwned>(r: &mut R) -> Result<Option<T>, Error> {
+    let mut len = [0; 4];
+    match r.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+    let len = u32::from_be_bytes(len);
+    if len > MAX_MESSAGE {
+        return Err(Error::Daemon(format!(
+            "message of {len} bytes is too large, the limit is {MAX_MESSAGE}"
+        )));
+    }
+    let mut body = vec![0; len as usize];
+    r.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+/// Send `request` to the daemon listening on `path`, if there is one.
+///
+/// `None` if nothing is listening, so that the caller can do the work itself.
+/// An `{"error":"..."}` response is returned as an error.
+pub fn request(path: &Path, request: &Request) -> Result<Option<Value>, Error> {
+    let mut stream = match UnixStream::connect(path) {
+        Ok(stream) => stream,
+        Err(err)
+            if matches!(
+                err.kind(),
+                io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused
+            ) =>
+        {
+            return Ok(None)
+        }
+        Err(err) => return Err(err.into()),
+    };
+    write_message(&mut stream, request)?;
+    let response = read_message::<_, Value>(&mut stream)?.ok_or_else(|| {
+        Error::Daemon("the daemon closed the connection without responding".into())
+    })?;
+    match response.get("error").and_then(Value::as_str) {
+        Some(error) => Err(Error::Daemon(format!("daemon: {error}"))),
+        None => Ok(Some(response)),
+    }
+}
+
+/// A bound socket, removed when dropped.
+#[derive(Debug)]
+pub struct Server {
+    listener: UnixListener,
+    path: PathBuf,
+    idle: Option<Duration>,
+}
+
+impl Server {
+    /// Listen on `path`, replacing a socket left over by a daemon that is no
+    /// longer running. Fails if another daemon is listening there.
+    pub fn bind(path: &Path) -> Result<Self, Error> {
+        if path.exists() {
+            if UnixStream::connect(path).is_ok() {
+                return Err(Error::Daemon(format!(
+                    "a daemon is already listening on {}",
+                    path.display()
+                )));
+            }
+            std::fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            path: path.to_path_buf(),
+            idle: None,
+        })
+    }
+
+    /// Shut down after no client has connected for `idle`. Default: never
+    pub fn idle_timeout(mut self, idle: Option<Duration>) -> Self {
+        self.idle = idle;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Answer queries with `handle`, each connection on its own thread, until
+    /// [`Request::Stop`] or the idle timeout. An error from `handle` is sent
+    /// to the client as its alternate `Display`, which for `anyhow` includes
+    /// the causes.
+    pub fn serve<F, E>(&self, handle: F) -> Result<(), Error>
+    where
+        F: Fn(&Query) -> Result<Value, E> + Sync,
+        E: fmt::Display,
+    {
+        let stop = AtomicBool::new(false);
+        let active = AtomicUsize::new(0);
+        let last_seen = Mutex::new(Instant::now());
+        let idle = || {
+            let since = last_seen.lock().unwrap().elapsed();
+            active.load(Ordering::SeqCst) == 0 && self.idle.is_some_and(|idle| since > idle)
+        };
+        std::thread::scope(|scope| loop {
+            if stop.load(Ordering::SeqCst) || idle() {
+                return Ok(());
+            }
+            let stream = match self.listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(POLL);
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            };
+            stream.set_nonblocking(false)?;
+            stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+            active.fetch_add(1, Ordering::SeqCst);
+            *last_seen.lock().unwrap() = Instant::now();
+            let (handle, stop, active, last_seen) = (&handle, &stop, &active, &last_seen);
+            scope.spawn(move || {
+                // a client that goes away mid-request only affects itself
+                let _ = connection(stream, handle, stop);
+                *last_seen.lock().unwrap() = Instant::now();
+                active.fetch_sub(1, Ordering::SeqCst);
+            });
+        })
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn connection<F, E>(mut stream: UnixStream, handle: &F, stop: &AtomicBool) -> Result<(), Error>
+where
+    F: Fn(&Query) -> Result<Value, E>,
+    E: fmt::Display,
+{
+    loop {
+        let request = match read_message::<_, Request>(&mut stream) {
+            Ok(Some(request)) => request,
+            Ok(None) => return Ok(()),
+            Err(err @ Error::Json(_)) => {
+                write_message(&mut stream, &json!({ "error": err.to_string() }))?;
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        let response = match request {
+            Request::Query(query) => match handle(&query) {
+                Ok(value) => value,
+                Err(err) => json!({ "error": format!("{err:#}") }),
+            },
+            Request::Stop => {
+                stop.store(true, Ordering::SeqCst);
+                json!({ "stopped": true })
+            }
+        };
+        write_message(&mut stream, &response)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn query(q: &str) -> Request {
+        Request::Query(Query {
+            q: q.to_string(),
+            args: None,
+            each: false,
+            space: None,
+            sort: None,
+        })
+    }
+
+    /// A socket path of its own for each test.
+    fn socket(name: &str) -> PathBuf {
+        let id = std::process::id();
+        std::env::temp_dir().join(format!("linguist-termcolor-test-{id}-{name}.sock"))
+    }
+
+    #[test]
+    fn framed() {
+        let mut buf = Vec::new();
+        write_message(&mut buf, &query("rust")).unwrap();
+        write_message(&mut buf, &Request::Stop).unwrap();
+        let body = br#"{"op":"query","q":"rust"}"#;
+        assert_eq!(buf[..4], (body.len() as u32).to_be_bytes());
+        assert_eq!(&buf[4..4 + body.len()], body);
+
+        let mut r = Cursor::new(buf);
+        let Some(Request::Query(first)) = read_message(&mut r).unwrap() else {
+            panic!("not a query")
+        };
+        assert_eq!(first.q, "rust");
+        assert_eq!(first.args(), ["rust"]);
+        assert!(matches!(read_message(&mut r).unwrap(), Some(Request::Stop)));
+        assert!(read_message::<_, Request>(&mut r).unwrap().is_none());
+    }
+
+    #[test]
+    fn defaults() {
+        let json = r#"{"op":"query","q":"c sharp"}"#;
+        let Request::Query(query) = serde_json::from_str(json).unwrap() else {
+            panic!("not a query")
+        };
+        assert_eq!(query.args(), ["c", "sharp"]);
+        assert!(!query.each);
+        assert_eq!((query.space, query.sort), (None, None));
+    }
+
+    #[test]
+    fn too_large() {
+        let mut r = Cursor::new((MAX_MESSAGE + 1).to_be_bytes().to_vec());
+        let err = read_message::<_, Value>(&mut r).unwrap_err();
+        assert!(matches!(err, Error::Daemon(_)), "{err}");
+
+        let message = "x".repeat(MAX_MESSAGE as usize);
+        let err = write_message(&mut Vec::new(), &message).unwrap_err();
+        assert!(matches!(err, Error::Daemon(_)), "{err}");
+    }
+
+    #[test]
+    fn truncated() {
+        let mut buf = Vec::new();
+        write_message(&mut buf, &query("rust")).unwrap();
+        buf.truncate(buf.len() - 1);
+        let err = read_message::<_, Request>(&mut Cursor::new(buf)).unwrap_err();
+        assert!(matches!(err, Error::Io(_)), "{err}");
+    }
+
+    #[test]
+    fn served_until_stopped() {
+        let path = socket("served");
+        let server = Server::bind(&path).unwrap();
+        assert!(Server::bind(&path).is_err());
+        std::thread::scope(|scope| {
+            let serving = scope.spawn(|| {
+                server.serve(|query| match query.q.as_str() {
+                    "fail" => Err("no such language"),
+                    _ => Ok(json!({ "q": query.q, "args": query.args() })),
+                })
+            });
+            let answer = request(&path, &query("rust .rs")).unwrap();
+            assert_eq!(
+                answer,
+                Some(json!({ "q": "rust .rs", "args": ["rust", ".rs"] }))
+            );
+            let err = request(&path, &query("fail")).unwrap_err();
+            assert_eq!(err.to_string(), "daemon: no such language");
+            let stopped = request(&path, &Request::Stop).unwrap();
+            assert_eq!(stopped, Some(json!({ "stopped": true })));
+            serving.join().unwrap().unwrap();
+        });
+        drop(server);
+        assert!(!path.exists());
+        assert_eq!(request(&path, &Request::Stop).unwrap(), None);
+    }
+
+    #[test]
+    fn idle() {
+        let server = Server::bind(&socket("idle"))
+            .unwrap()
+            .idle_timeout(Some(Duration::from_millis(100)));
+        server.serve(|_| Ok::<_, Error>(Value::Null)).unwrap();
+    }
+}
@@ -0,0 +1,498 @@
+//! Classify file paths into Linguist languages.
+//!
+//! A [`Pipeline`] runs its [`Stage`]s in order and stops at the first one that
+//! produces a match. The default precedence is:
+//!
+//! 1. [`Stage::Filename`]: the file name exactly matches one of Linguist's `filenames`
+//! 2. [`Stage::Extension`]: the last extension matches, case-sensitively
+//! 3. [`Stage::ExtensionCaseInsensitive`]: the last extension matches, ignoring case
+//! 4. [`Stage::CompoundExtension`]: a multi-dot extension such as `.rs.in` matches,
+//!    longest first
+//! 5. [`Stage::Shebang`]: the file has no extension, can be read, and starts with
+//!    a `#!` line naming a known interpreter
+//! 6. [`Stage::RcFile`]: the file name looks like `.<name>rc` and `<name>` is a known
+//!    interpreter, language name, or alias
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+};
+
+use crate::{
+    heuristics::{self, Heuristics},
+    Language, Linguist,
+};
+
+/// A rule used to classify a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    Filename,
+    Extension,
+    ExtensionCaseInsensitive,
+    CompoundExtension,
+    Shebang,
+    RcFile,
+}
+
+impl Stage {
+    /// All stages, in their default order.
+    pub const ALL: [Stage; 6] = [
+        Stage::Filename,
+        Stage::Extension,
+        Stage::ExtensionCaseInsensitive,
+        Stage::CompoundExtension,
+        Stage::Shebang,
+        Stage::RcFile,
+    ];
+
+    /// What the stage looks at, for messages, e.g. `shebang`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Filename => "filename",
+            Stage::Extension => "extension",
+            Stage::ExtensionCaseInsensitive => "extension ignoring case",
+            Stage::CompoundExtension => "compound extension",
+            Stage::Shebang => "shebang",
+            Stage::RcFile => "rc file name",
+        }
+    }
+}
+
+/// The outcome of running a [`Pipeline`] on a path.
+#[derive(Debug, Clone)]
+pub struct Detection<'a> {
+    /// The stage that produced the match.
+    pub stage: Stage,
+    /// The keyword that matched, e.g. the extension or the interpreter name.
+    pub matched: String,
+    /// Every language the keyword maps to, sorted by name, or only the one
+    /// picked by content heuristics.
+    pub languages: Vec<Language<'a>>,
+    /// Whether [heuristics](Pipeline::rules) narrowed down `languages` by the
+    /// file's contents.
+    pub heuristic: bool,
+}
+
+/// Lookup tables for path classification, built once from a [`Linguist`].
+pub struct Pipeline<'a> {
+    linguist: &'a Linguist,
+    stages: Vec<Stage>,
+    filenames: HashMap<&'a str, Vec<Language<'a>>>,
+    extensions: HashMap<&'a str, Vec<Language<'a>>>,
+    extensions_folded: HashMap<String, Vec<Language<'a>>>,
+    interpreters: HashMap<&'a str, Vec<Language<'a>>>,
+    heuristics: bool,
+    /// `None` for [`Heuristics::builtin`]
+    rules: Option<Heuristics>,
+}
+
+impl<'a> Pipeline<'a> {
+    /// Build a pipeline running every stage in the default order.
+    pub fn new(linguist: &'a Linguist) -> Self {
+        Self::with_stages(linguist, Stage::ALL)
+    }
+
+    /// Build a pipeline running only the given stages, in the given order.
+    pub fn with_stages<I>(linguist: &'a Linguist, stages: I) -> Self
+    where
+        I: IntoIterator<Item = Stage>,
+    {
+        let mut filenames = HashMap::<_, Vec<_>>::new();
+        let mut extensions = HashMap::<_, Vec<_>>::new();
+        let mut extensions_folded = HashMap::<_, Vec<_>>::new();
+        let mut interpreters = HashMap::<_, Vec<_>>::new();
+
+        for lang in linguist.0.values() {
+            let language = Language::of(lang);
+            for filename in lang.filenames.iter() {
+                filenames
+                    .entry(filename.as_str())
+                    .or_default()
+                    .push(language);
+            }
+            for ext in lang.extensions.iter() {
+                extensions.entry(ext.as_str()).or_default().push(language);
+                extensions_folded
+                    .entry(ext.to_lowercase())
+                    .or_default()
+                    .push(language);
+            }
+            for interpreter in lang.interpreters.iter() {
+                interpreters
+                    .entry(interpreter.as_str())
+                    .or_default()
+                    .push(language);
+            }
+        }
+
+        fn sorted<K>(mut map: HashMap<K, Vec<Language<'_>>>) -> HashMap<K, Vec<Language<'_>>> {
+            map.values_mut()
+                .for_each(|langs| langs.sort_by(|a, b| crate::by_name(a.name, b.name)));
+            map
+        }
+
+        Self {
+            linguist,
+            stages: stages.into_iter().collect(),
+            filenames: sorted(filenames),
+            extensions: sorted(extensions),
+            extensions_folded: sorted(extensions_folded),
+            interpreters: sorted(interpreters),
+            heuristics: true,
+            rules: None,
+        }
+    }
+
+    /// Whether to read files whose extension maps to several languages and
+    /// pick one by their contents, see [`heuristics`]. Default: true
+    pub fn heuristics(mut self, enabled: bool) -> Self {
+        self.heuristics = enabled;
+        self
+    }
+
+    /// The rules to pick by, such as all of Linguist's `heuristics.yml`.
+    /// Default: [`Heuristics::builtin`]
+    pub fn rules(mut self, rules: Heuristics) -> Self {
+        self.rules = Some(rules);
+        self
+    }
+
+    /// The stages this pipeline runs, in order.
+    pub fn stages(&self) -> &[Stage] {
+        &self.stages
+    }
+
+    /// Classify `path`, returning the result of the first stage that matches.
+    ///
+    /// Only [`Stage::Shebang`] and [heuristics](Pipeline::heuristics) touch the
+    /// file system; every other stage looks at the path alone.
+    pub fn detect(&self, path: &Path) -> Option<Detection<'a>> {
+        let filename = path.file_name()?.to_str()?;
+        let mut detection = self
+            .stages
+            .iter()
+            .find_map(|&stage| self.run(stage, path, filename))?;
+        let by_extension = matches!(
+            detection.stage,
+            Stage::Extension | Stage::ExtensionCaseInsensitive | Stage::CompoundExtension
+        );
+        if self.heuristics && by_extension && detection.languages.len() > 1 {
+            let candidates = detection
+                .languages
+                .iter()
+                .map(|l| l.name)
+                .collect::<Vec<_>>();
+            let rules = self.rules.as_ref().unwrap_or(Heuristics::builtin());
+            let picked = read_sample(path)
+                .map(|sample| rules.disambiguate(&detection.matched, &sample, &candidates))
+                .unwrap_or_default();
+            if !picked.is_empty() && picked.len() < candidates.len() {
+                detection.languages.retain(|l| picked.contains(&l.name));
+                detection.heuristic = true;
+            }
+        }
+        Some(detection)
+    }
+
+    /// Classify a bare extension such as `rs` or `.rs`, running only the
+    /// extension stages of this pipeline.
+    pub fn detect_extension(&self, ext: &str) -> Option<Detection<'a>> {
+        let filename = format!("file.{}", ext.trim_start_matches('.'));
+        let path = Path::new(&filename);
+        self.stages
+            .iter()
+            .filter(|stage| {
+                matches!(
+                    stage,
+                    Stage::Extension | Stage::ExtensionCaseInsensitive | Stage::CompoundExtension
+                )
+            })
+            .find_map(|&stage| self.run(stage, path, &filename))
+    }
+
+    fn run(&self, stage: Stage, path: &Path, filename: &str) -> Option<Detection<'a>> {
+        let found = |matched: &str, languages: &Vec<Language<'a>>| Detection {
+            stage,
+            matched: matched.to_string(),
+            languages: languages.clone(),
+            heuristic: false,
+        };
+        match stage {
+            Stage::Filename => self
+                .filenames
+                .get(filename)
+                .map(|langs| found(filename, langs)),
+            Stage::Extension => {
+                let ext = extension(filename)?;
+                self.extensions.get(ext).map(|langs| found(ext, langs))
+            }
+            Stage::ExtensionCaseInsensitive => {
+                let ext = extension(filename)?;
+                self.extensions_folded
+                    .get(&ext.to_lowercase())
+                    .map(|langs| found(ext, langs))
+            }
+            Stage::CompoundExtension => compound_extensions(filename).find_map(|ext| {
+                self.extensions_folded
+                    .get(&ext.to_lowercase())
+                    .map(|langs| found(ext, langs))
+            }),
+            Stage::Shebang => {
+                if extension(filename).is_some() {
+                    return None;
+                }
+                let line = read_first_line(path)?;
+                let interpreter = parse_shebang(&line)?;
+                let detection = interpreter_candidates(interpreter)
+                    .find_map(|name| self.interpreters.get(name).map(|langs| found(name, langs)));
+                detection
+            }
+            Stage::RcFile => {
+                let name = filename.strip_prefix('.')?.strip_suffix("rc")?;
+                if name.is_empty() {
+                    return None;
+                }
+                if let Some(langs) = self.interpreters.get(name) {
+                    return Some(found(name, langs));
+                }
+                let language = self.linguist.get(name)?;
+                Some(found(name, &vec![language]))
+            }
+        }
+    }
+}
+
+/// The last extension in `filename`, including the dot.
+///
+/// A leading dot does not start an extension, so `.gitignore` has none.
+fn extension(filename: &str) -> Option<&str> {
+    match filename.rfind('.') {
+        Some(0) | None => None,
+        Some(idx) => Some(&filename[idx..]),
+    }
+}
+
+/// Extensions spanning more than one dot in `filename`, longest first.
+fn compound_extensions(filename: &str) -> impl Iterator<Item = &str> {
+    let last = filename.rfind('.').unwrap_or(0);
+    filename
+        .match_indices('.')
+        .map(|(idx, _)| idx)
+        .filter(move |&idx| idx != 0 && idx < last)
+        .map(|idx| &filename[idx..])
+}
+
+fn read_sample(path: &Path) -> Option<String> {
+    let file = File::open(path).ok()?;
+    let mut sample = vec![];
+    file.take(heuristics::SAMPLE_BYTES)
+        .read_to_end(&mut sample)
+        .ok()?;
+    Some(String::from_utf8_lossy(&sample).into_owned())
+}
+
+fn read_first_line(path: &Path) -> Option<String> {
+    let file = File::open(path).ok()?;
+    let mut line = String::new();
+    BufReader::new(file.take(1024)).read_line(&mut line).ok()?;
+    Some(line)
+}
+
+/// Extract the interpreter name from a shebang line.
+///
+/// Handles absolute paths (`#!/bin/bash`) as well as `env` indirection
+/// (`#!/usr/bin/env -S python3 -u`), returning `bash` and `python3` respectively.
+pub fn parse_shebang(line: &str) -> Option<&str> {
+    let mut words = line.strip_prefix("#!")?.split_whitespace();
+    let program = basename(words.next()?);
+    if program != "env" {
+        return Some(program);
+    }
+    words
+        .find(|word| !word.starts_with('-') && !word.contains('='))
+        .map(basename)
+}
+
+fn basename(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// `python3.11` yields `python3.11`, `python3`, and `python`.
+fn interpreter_candidates(name: &str) -> impl Iterator<Item = &str> {
+    let mut next = Some(name);
+    std::iter::from_fn(move || {
+        let current = next?;
+        let trimmed = match current.rsplit_once('.') {
+            Some((head, tail)) if tail.chars().all(|c| c.is_ascii_digit()) => head,
+            _ => current.trim_end_matches(|c: char| c.is_ascii_digit()),
+        };
+        next = (!trimmed.is_empty() && trimmed != current).then_some(trimmed);
+        Some(current)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+
+    /// The snapshot of `languages.yml` that the `bundled` feature embeds.
+    fn linguist() -> Linguist {
+        include_str!("../data/languages.yml").parse().unwrap()
+    }
+
+    /// A file named `name` in a directory of its own, with `contents`.
+    fn file(test: &str, name: &str, contents: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("linguist-termcolor-{}", std::process::id()))
+            .join(test);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn extensions() {
+        for (filename, ext) in [
+            ("main.rs", Some(".rs")),
+            ("archive.tar.gz", Some(".gz")),
+            ("MAIN.RS", Some(".RS")),
+            (".gitignore", None),
+            (".eslintrc.json", Some(".json")),
+            ("Makefile", None),
+            ("trailing.", Some(".")),
+        ] {
+            assert_eq!(extension(filename), ext, "for {filename:?}");
+        }
+    }
+
+    #[test]
+    fn compound() {
+        for (filename, compound) in [
+            ("build.rs.in", &[".rs.in"][..]),
+            ("a.b.c.d", &[".b.c.d", ".c.d"]),
+            ("main.rs", &[]),
+            (".eslintrc.json", &[]),
+            (".config.toml.example", &[".toml.example"]),
+            ("Makefile", &[]),
+        ] {
+            let found = compound_extensions(filename).collect::<Vec<_>>();
+            assert_eq!(found, compound, "for {filename:?}");
+        }
+    }
+
+    #[test]
+    fn shebangs() {
+        for (line, interpreter) in [
+            ("#!/bin/bash", Some("bash")),
+            ("#!/bin/bash -e\n", Some("bash")),
+            ("#!/usr/bin/env python3", Some("python3")),
+            ("#!/usr/bin/env -S python3 -u", Some("python3")),
+            ("#!/usr/bin/env LANG=C perl -w", Some("perl")),
+            ("#! /usr/local/bin/node", Some("node")),
+            ("#!/usr/bin/env", None),
+            ("#!", None),
+            ("# not a shebang", None),
+            ("", None),
+        ] {
+            assert_eq!(parse_shebang(line), interpreter, "for {line:?}");
+        }
+    }
+
+    #[test]
+    fn interpreters() {
+        for (name, candidates) in [
+            ("python3.11", &["python3.11", "python3", "python"][..]),
+            ("python3", &["python3", "python"]),
+            ("python", &["python"]),
+            ("lua5.1", &["lua5.1", "lua5", "lua"]),
+            ("2to3", &["2to3", "2to"]),
+            ("42", &["42"]),
+        ] {
+            let found = interpreter_candidates(name).collect::<Vec<_>>();
+            assert_eq!(found, candidates, "for {name:?}");
+        }
+    }
+
+    /// One fixture per stage, then conflicts where an earlier stage wins; the
+    /// files of the shebang cases are written to disk, the others are not.
+    #[test]
+    fn stages() {
+        let linguist = linguist();
+        let pipeline = Pipeline::new(&linguist).heuristics(false);
+        let script = file("stages", "script", "#!/usr/bin/env -S python3 -u\n");
+        let versioned = file("stages", "tool", "#!/usr/bin/python3.11\n");
+        let python_shebang = file("stages", "install.sh", "#!/usr/bin/env python3\n");
+        let unknown = file("stages", "notes", "#!/usr/bin/frobnicate\n");
+        for (path, expected) in [
+            // one per stage
+            (
+                Path::new("Makefile"),
+                Some((Stage::Filename, "Makefile", "Makefile")),
+            ),
+            (
+                Path::new(".gitignore"),
+                Some((Stage::Filename, ".gitignore", "Ignore List")),
+            ),
+            (
+                Path::new("cmd/main.go"),
+                Some((Stage::Extension, ".go", "Go")),
+            ),
+            (
+                Path::new("MAIN.GO"),
+                Some((Stage::ExtensionCaseInsensitive, ".GO", "Go")),
+            ),
+            (
+                Path::new("build.rs.in"),
+                Some((Stage::CompoundExtension, ".rs.in", "Rust")),
+            ),
+            (&script, Some((Stage::Shebang, "python3", "Python"))),
+            (&versioned, Some((Stage::Shebang, "python3", "Python"))),
+            (
+                Path::new(".pythonrc"),
+                Some((Stage::RcFile, "python", "Python")),
+            ),
+            // earlier stages win
+            (
+                Path::new(".bashrc"),
+                Some((Stage::Filename, ".bashrc", "Shell")),
+            ),
+            (
+                Path::new("CMakeLists.txt"),
+                Some((Stage::Filename, "CMakeLists.txt", "CMake")),
+            ),
+            (&python_shebang, Some((Stage::Extension, ".sh", "Shell"))),
+            // no stage matches
+            (&unknown, None),
+            (Path::new("no-such-file"), None),
+            (Path::new(".rc"), None),
+        ] {
+            let detection = pipeline.detect(path);
+            let found = detection.as_ref().map(|d| {
+                let names = d.languages.iter().map(|l| l.name()).collect::<Vec<_>>();
+                (d.stage, d.matched.as_str(), names)
+            });
+            let expected = expected.map(|(stage, matched, name)| (stage, matched, vec![name]));
+            assert_eq!(found, expected, "for {path:?}");
+        }
+        fs::remove_dir_all(script.parent().unwrap()).unwrap();
+    }
+
+    /// Without the filename stage, `.bashrc` falls through to its name.
+    #[test]
+    fn custom_stages() {
+        let linguist = linguist();
+        let pipeline = Pipeline::with_stages(&linguist, [Stage::RcFile]);
+        let detection = pipeline.detect(Path::new(".bashrc")).unwrap();
+        assert_eq!(
+            (detection.stage, detection.matched.as_str()),
+            (Stage::RcFile, "bash")
+        );
+        assert_eq!(detection.languages[0].name(), "Shell");
+        assert!(pipeline.detect(Path::new("main.rs")).is_none());
+    }
+}
@@ -0,0 +1,207 @@
+//! Nudge a set of colors apart so that every pair stays distinguishable.
+//!
+//! Colors that are already far enough from every other color are kept as is.
+//! The rest are moved apart in lightness in [OKLab], keeping their hue and giving
+//! up chroma only where the new lightness would leave the sRGB gamut.
+//!
+//! [OKLab]: https://bottosson.github.io/posts/oklab/
+
+use color_art::Color;
+
+use crate::color_math::{
+    delta_e2000, from_polar, from_srgb, in_gamut, lab, linear_to_srgb, oklab, oklab_to_linear,
+    to_polar,
+};
+
+/// How far any color may move in OKLab lightness, out of `0.0..=1.0`.
+const MAX_SHIFT: f64 = 0.3;
+
+/// How far colors move per iteration.
+const STEP: f64 = 0.005;
+
+const MAX_ITERATIONS: usize = 1_000;
+
+/// A color returned by [`distinct`].
+#[derive(Debug, Clone, Copy)]
+pub struct Nudged {
+    pub color: Color,
+    /// The CIEDE2000 difference from the original color, `0.0` if unchanged.
+    pub moved: f64,
+}
+
+/// Adjust `colors` until every pair is at least `min_delta` apart in CIEDE2000,
+/// returning the colors in the same order.
+///
+/// Each color moves at most a bounded amount, so a set that is too crowded may
+/// still contain pairs closer than `min_delta`; see [`min_pairwise_delta`].
+pub fn distinct(colors: &[Color], min_delta: f64) -> Vec<Nudged> {
+    let original = colors
+        .iter()
+        .map(|c| to_polar(oklab(c)))
+        .collect::<Vec<_>>();
+    let mut lch = original.clone();
+    let mut current = colors.to_vec();
+
+    for _ in 0..MAX_ITERATIONS {
+        let mut moved = false;
+        for i in 0..lch.len() {
+            for j in i + 1..lch.len() {
+                if difference(&current[i], &current[j]) >= min_delta {
+                    continue;
+                }
+                // the lighter color goes up, the darker one goes down
+                let up = match lch[i][0].total_cmp(&lch[j][0]) {
+                    std::cmp::Ordering::Equal => i < j,
+                    ordering => ordering.is_gt(),
+                };
+                let sign = if up { 1.0 } else { -1.0 };
+                moved |= nudge(&mut lch[i], &original[i], sign * STEP);
+                moved |= nudge(&mut lch[j], &original[j], -sign * STEP);
+            }
+        }
+        if !moved {
+            break;
+        }
+        for (idx, color) in current.iter_mut().enumerate() {
+            *color = match lch[idx] == original[idx] {
+                true => colors[idx],
+                false => to_color(lch[idx]),
+            };
+        }
+    }
+
+    colors
+        .iter()
+        .zip(current)
+        .map(|(original, color)| Nudged {
+            color,
+            moved: difference(original, &color),
+        })
+        .collect()
+}
+
+/// The smallest CIEDE2000 difference between any two of `colors`, or
+/// [`f64::INFINITY`] if there are fewer than two.
+pub fn min_pairwise_delta(colors: &[Color]) -> f64 {
+    let mut min = f64::INFINITY;
+    for (i, a) in colors.iter().enumerate() {
+        for b in colors[i + 1..].iter() {
+            min = min.min(difference(a, b));
+        }
+    }
+    min
+}
+
+fn difference(a: &Color, b: &Color) -> f64 {
+    delta_e2000(lab(a), lab(b))
+}
+
+/// Move `lch` in lightness by `step`, within [`MAX_SHIFT`] of `original`.
+/// Returns whether it moved.
+fn nudge(lch: &mut [f64; 3], original: &[f64; 3], step: f64) -> bool {
+    let lightness = (lch[0] + step)
+        .clamp(original[0] - MAX_SHIFT, original[0] + MAX_SHIFT)
+        .clamp(0.0, 1.0);
+    let moved = lightness != lch[0];
+    lch[0] = lightness;
+    moved
+}
+
+/// An sRGB color from OKLCh, reducing chroma until it fits in the gamut.
+fn to_color(lch: [f64; 3]) -> Color {
+    let rgb = |c: f64| oklab_to_linear(from_polar([lch[0], c, lch[2]])).map(linear_to_srgb);
+    if in_gamut(rgb(lch[1])) {
+        return from_srgb(rgb(lch[1]));
+    }
+    let (mut lo, mut hi) = (0.0, lch[1]);
+    for _ in 0..32 {
+        let mid = (lo + hi) / 2.0;
+        match in_gamut(rgb(mid)) {
+            true => lo = mid,
+            false => hi = mid,
+        }
+    }
+    from_srgb(rgb(lo))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_hex_color;
+
+    fn colors(hex: &[&str]) -> Vec<Color> {
+        hex.iter()
+            .map(|hex| parse_hex_color(hex).unwrap())
+            .collect()
+    }
+
+    fn nudged(nudged: &[Nudged]) -> Vec<Color> {
+        nudged.iter().map(|nudged| nudged.color).collect()
+    }
+
+    /// Sets that can be separated end up with every pair at least the
+    /// minimum apart.
+    #[test]
+    fn meets_the_minimum() {
+        for (hex, min_delta) in [
+            (&["#b07219", "#b8761c", "#a96f14", "#dea584"][..], 15.0),
+            (&["#3572a5", "#3572a6"], 10.0),
+            (&["#555555", "#555555", "#555555"], 5.0),
+            // at the ends of lightness, where only one of them can move
+            (&["#000000", "#0a0a0a"], 10.0),
+            (&["#ffffff", "#f5f5f5"], 10.0),
+            // saturated, so that moving in lightness leaves the gamut
+            (&["#0000ff", "#0a00f5"], 10.0),
+            (&["#00ff00", "#10ff10", "#20ee20"], 8.0),
+        ] {
+            let colors = colors(hex);
+            assert!(min_pairwise_delta(&colors) < min_delta, "{hex:?}");
+            let nudged = nudged(&distinct(&colors, min_delta));
+            let min = min_pairwise_delta(&nudged);
+            assert!(min >= min_delta, "{hex:?}: {min} < {min_delta}");
+        }
+    }
+
+    /// Colors far from every other one are returned as they are, and those
+    /// that move keep their hue.
+    #[test]
+    fn keeps_what_does_not_collide() {
+        let colors = colors(&["#3572a5", "#3573a5", "#f1e05a", "#00add8"]);
+        let result = distinct(&colors, 10.0);
+        for (original, nudged) in colors.iter().zip(&result).skip(2) {
+            assert_eq!(nudged.color, *original);
+            assert_eq!(nudged.moved, 0.0);
+        }
+        for (original, nudged) in colors.iter().zip(&result).take(2) {
+            assert!(nudged.moved > 0.0);
+            let (before, after) = (to_polar(oklab(original)), to_polar(oklab(&nudged.color)));
+            // in degrees
+            assert!((before[2] - after[2]).abs() < 0.5, "{before:?} {after:?}");
+        }
+    }
+
+    /// A set too crowded to separate stops moving, with each color at most
+    /// [`MAX_SHIFT`] from where it was, rather than running on or leaving
+    /// the gamut.
+    #[test]
+    fn movement_is_capped() {
+        let colors = colors(&["#808080"; 12]);
+        let result = distinct(&colors, 40.0);
+        assert_eq!(result.len(), colors.len());
+        for (original, nudged) in colors.iter().zip(&result) {
+            let shift = oklab(&nudged.color)[0] - oklab(original)[0];
+            // and a little more from rounding to 8 bits per channel
+            assert!(shift.abs() <= MAX_SHIFT + 0.005, "{shift}");
+        }
+        let min = min_pairwise_delta(&nudged(&result));
+        assert!(min < 40.0, "{min}");
+    }
+
+    #[test]
+    fn fewer_than_two() {
+        assert_eq!(min_pairwise_delta(&[]), f64::INFINITY);
+        let one = colors(&["#dea584"]);
+        assert_eq!(min_pairwise_delta(&one), f64::INFINITY);
+        assert_eq!(distinct(&one, 100.0)[0].color, one[0]);
+    }
+}
@@ -0,0 +1,192 @@
+//! The error type of this library.
+//!
+//! [`Error`] tells failures apart for callers that need to, such as a wrong
+//! [`Linguist::new_with_ref`] from a network outage. Its `Display` is a
+//! message fit for users, including that of the underlying error, which the
+//! variant holds where there is one.
+//!
+//! [`Linguist::new_with_ref`]: crate::Linguist::new_with_ref
+
+use std::{fmt, io, path::PathBuf};
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Fetching `languages.yml` failed, including with an HTTP error status.
+    #[cfg(feature = "fetch")]
+    Fetch(reqwest::Error),
+    /// Fetching failed on each of `attempts`, the last time with `source`, see
+    /// [`LinguistSource::retries`](crate::source::LinguistSource::retries).
+    #[cfg(feature = "fetch")]
+    FetchAttempts {
+        attempts: u32,
+        source: reqwest::Error,
+    },
+    /// Upstream has no Linguist branch, tag, or commit by this name.
+    RefNotFound(String),
+    /// Reading failed, or what was read is not UTF-8.
+    Io(io::Error),
+    /// Reading or parsing the file at `path` failed.
+    File { path: PathBuf, source: Box<Error> },
+    /// The data is not YAML, or not shaped like `languages.yml`.
+    Parse(serde_yaml::Error),
+    /// The data exceeds [`Limits::max_bytes`](crate::limits::Limits::max_bytes).
+    /// `len` is the size, if it was announced ahead of time.
+    TooLarge { len: Option<u64>, limit: u64 },
+    /// The data exceeds [`Limits::max_languages`](crate::limits::Limits::max_languages).
+    TooManyLanguages { count: usize, limit: usize },
+    /// A language exceeds [`Limits::max_list_len`](crate::limits::Limits::max_list_len)
+    /// in `field`, such as `extensions`.
+    TooManyEntries {
+        language: String,
+        field: &'static str,
+        count: usize,
+        limit: usize,
+    },
+    /// A language's `color` is not a hex color, see
+    /// [`parse_hex_color`](crate::parse_hex_color). An error in overrides,
+    /// and a warning in [`Linguist::skipped_colors`](crate::Linguist::skipped_colors).
+    InvalidColor { language: String, value: String },
+    /// Not a hex color, see [`parse_hex_color`](crate::parse_hex_color).
+    HexColor(String),
+    /// A color that is not in any notation [`TermColor`](crate::TermColor)
+    /// parses, or has a component out of range.
+    ColorNotation(String),
+    /// A palette file that is not shaped like one, see
+    /// [`NamedPalette::from_reader`](crate::themes::NamedPalette::from_reader).
+    /// The message tells which entry is wrong, if one is.
+    Palette(String),
+    /// No language matches the query.
+    NoMatch { query: String },
+    /// A color space that is known but not supported; `closest` is the
+    /// supported space resembling it most.
+    UnsupportedColorSpace { name: String, closest: &'static str },
+    /// A color space name not in [`spaces::ALIASES`](crate::spaces::ALIASES);
+    /// `suggestion` is the most similar one that is.
+    UnknownColorSpace {
+        name: String,
+        suggestion: &'static str,
+    },
+    /// A daemon message or saved index that is not JSON, or not shaped like
+    /// one.
+    Json(serde_json::Error),
+    /// A saved index written by another version of this crate, see
+    /// [`ColorMap::load`](crate::ColorMap::load).
+    IndexVersion { found: u32, expected: u32 },
+    /// The daemon protocol was not followed, or the daemon responded with
+    /// this error.
+    Daemon(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // reqwest's message for a timeout is only `error sending request`
+            #[cfg(feature = "fetch")]
+            Error::Fetch(err) if err.is_timeout() => write!(f, "{err}: timed out"),
+            #[cfg(feature = "fetch")]
+            Error::Fetch(err) => write!(f, "{err}"),
+            #[cfg(feature = "fetch")]
+            Error::FetchAttempts { attempts, source } if source.is_timeout() => {
+                write!(f, "{source}: timed out (after {attempts} attempts)")
+            }
+            #[cfg(feature = "fetch")]
+            Error::FetchAttempts { attempts, source } => {
+                write!(f, "{source} (after {attempts} attempts)")
+            }
+            Error::RefNotFound(git_ref) => write!(f, "ref '{git_ref}' not found upstream"),
+            Error::Io(err) => write!(f, "{err}"),
+            Error::File { path, source } => match **source {
+                Error::Io(_) | Error::TooLarge { .. } => {
+                    write!(f, "failed to read {}: {source}", path.display())
+                }
+                _ => write!(f, "failed to parse {}: {source}", path.display()),
+            },
+            Error::Parse(err) => write!(f, "{err}"),
+            Error::TooLarge {
+                len: Some(len),
+                limit,
+            } => write!(
+                f,
+                "languages.yml is {len} bytes, exceeding the size limit of {limit} bytes"
+            ),
+            Error::TooLarge { len: None, limit } => {
+                write!(f, "languages.yml exceeds the size limit of {limit} bytes")
+            }
+            Error::TooManyLanguages { count, limit } => write!(
+                f,
+                "languages.yml has {count} languages, exceeding the limit of {limit}"
+            ),
+            Error::TooManyEntries {
+                language,
+                field,
+                count,
+                limit,
+            } => write!(
+                f,
+                "{language} has {count} {field}, exceeding the limit of {limit} per language"
+            ),
+            Error::InvalidColor { language, value } => {
+                write!(
+                    f,
+                    "{language} has color {value:?}, expected #rrggbb or #rgb"
+                )
+            }
+            Error::HexColor(value) => {
+                write!(f, "{value:?} is not a hex color, expected #rrggbb or #rgb")
+            }
+            Error::ColorNotation(value) => write!(
+                f,
+                "unrecognized color {value:?}, expected hex (#dea584 or #abc), \
+                rgb() (rgb(222, 165, 132) or rgb(87% 65% 52%)), \
+                hsl() (hsl(22deg 58% 69%)), or a CSS color name (rebeccapurple)"
+            ),
+            Error::Palette(message) => write!(f, "{message}"),
+            Error::NoMatch { query } => write!(f, "no colors found for {query:?}"),
+            Error::UnsupportedColorSpace { name, closest } => {
+                write!(
+                    f,
+                    "{name} is not supported (yet), closest supported: {closest}"
+                )
+            }
+            Error::UnknownColorSpace { name, suggestion } => {
+                write!(
+                    f,
+                    "unknown color space {name:?}, did you mean {suggestion}?"
+                )
+            }
+            Error::Json(err) => write!(f, "{err}"),
+            Error::IndexVersion { found, expected } => {
+                write!(f, "index is of version {found}, expected {expected}")
+            }
+            Error::Daemon(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(feature = "fetch")]
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Error::Fetch(err)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<serde_yaml::Error> for Error {
+    fn from(err: serde_yaml::Error) -> Self {
+        Error::Parse(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
@@ -0,0 +1,167 @@
+//! Languages arranged by Linguist's `group` field.
+//!
+//! Dialects and variants name their parent language in `group`, e.g. JSX is
+//! grouped under JavaScript. Following these links yields a forest of small trees.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use color_art::Color;
+
+use crate::{Language, Linguist};
+
+/// A language and the languages grouped under it.
+#[derive(Debug, Clone)]
+pub struct Group<'a> {
+    language: Language<'a>,
+    children: Vec<Group<'a>>,
+}
+
+impl<'a> Group<'a> {
+    pub fn language(&self) -> Language<'a> {
+        self.language
+    }
+
+    /// Direct children, sorted by name.
+    pub fn children(&self) -> &[Group<'a>] {
+        &self.children
+    }
+}
+
+/// Every language, either in a group tree or standing alone.
+#[derive(Debug, Clone)]
+pub struct Groups<'a> {
+    /// Trees with at least one child, sorted by the name of the root.
+    pub trees: Vec<Group<'a>>,
+    /// Languages that neither have a group nor are a group, sorted by name.
+    pub ungrouped: Vec<Language<'a>>,
+}
+
+impl Linguist {
+    /// Arrange all languages into group trees.
+    ///
+    /// A `group` naming an unknown language is ignored. If groups form a cycle,
+    /// the link from the alphabetically first language in the cycle is ignored,
+    /// making that language a root.
+    pub fn groups(&self) -> Groups<'_> {
+        let mut parents = self
+            .0
+            .iter()
+            .filter_map(|(name, lang)| {
+                let group = lang.group.as_deref()?.to_ascii_lowercase();
+                let (group, _) = self.0.get_key_value(&group)?;
+                (group != name).then_some((name.as_str(), group.as_str()))
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        for name in self.0.keys() {
+            let mut seen = BTreeSet::new();
+            let mut current = name.as_str();
+            while let Some(&parent) = parents.get(current) {
+                if !seen.insert(current) {
+                    let mut cycle = vec![current];
+                    let mut next = parent;
+                    while next != current {
+                        cycle.push(next);
+                        next = parents[next];
+                    }
+                    let first = cycle.into_iter().min().unwrap();
+                    parents.remove(first);
+                    break;
+                }
+                current = parent;
+            }
+        }
+
+        let mut children = BTreeMap::<_, Vec<_>>::new();
+        for (&child, &parent) in parents.iter() {
+            children.entry(parent).or_default().push(child);
+        }
+
+        fn build<'a>(
+            linguist: &'a Linguist,
+            children: &BTreeMap<&'a str, Vec<&'a str>>,
+            name: &'a str,
+        ) -> Group<'a> {
+            let (name, lang) = linguist.0.get_key_value(name).unwrap();
+            Group {
+                language: Language::of(lang),
+                children: children
+                    .get(name.as_str())
+                    .into_iter()
+                    .flatten()
+                    .map(|child| build(linguist, children, child))
+                    .collect(),
+            }
+        }
+
+        let mut trees = vec![];
+        let mut ungrouped = vec![];
+
+        for (name, lang) in self.0.iter() {
+            if parents.contains_key(name.as_str()) {
+                continue;
+            }
+            if children.contains_key(name.as_str()) {
+                trees.push(build(self, &children, name));
+            } else {
+                ungrouped.push(Language::of(lang));
+            }
+        }
+
+        Groups { trees, ungrouped }
+    }
+
+    /// The group tree containing the language `name`, looked up like [`Linguist::get`].
+    pub fn family(&self, name: &str) -> Option<Group<'_>> {
+        let name = self.get(name)?.name();
+
+        fn contains(group: &Group<'_>, name: &str) -> bool {
+            group.language.name == name || group.children.iter().any(|c| contains(c, name))
+        }
+
+        let Groups { trees, ungrouped } = self.groups();
+        trees
+            .into_iter()
+            .find(|tree| contains(tree, name))
+            .or_else(|| {
+                ungrouped
+                    .into_iter()
+                    .find(|lang| lang.name == name)
+                    .map(|language| Group {
+                        language,
+                        children: vec![],
+                    })
+            })
+    }
+}
+
+impl Group<'_> {
+    /// Visit every language in this tree depth first, along with its color,
+    /// which is inherited from the closest ancestor with a color if it has none.
+    ///
+    /// `visit` receives the group, whether each node on the path from the root
+    /// (excluding the root itself) is the last of its siblings, and the color
+    /// with whether it was inherited.
+    pub fn walk<F>(&self, mut visit: F)
+    where
+        F: FnMut(&Group<'_>, &[bool], Option<(Color, bool)>),
+    {
+        fn walk<F>(group: &Group<'_>, visit: &mut F, path: &mut Vec<bool>, inherited: Option<Color>)
+        where
+            F: FnMut(&Group<'_>, &[bool], Option<(Color, bool)>),
+        {
+            let color = match group.language.color() {
+                Some(color) => Some((color, false)),
+                None => inherited.map(|color| (color, true)),
+            };
+            visit(group, path, color);
+            let inherited = color.map(|(color, _)| color);
+            for (idx, child) in group.children.iter().enumerate() {
+                path.push(idx + 1 == group.children.len());
+                walk(child, visit, path, inherited);
+                path.pop();
+            }
+        }
+        walk(self, &mut visit, &mut vec![], None)
+    }
+}
@@ -0,0 +1,633 @@
+//! Tell apart languages sharing an extension by looking at file contents.
+//!
+//! [`DISAMBIGUATIONS`] is a small subset of Linguist's own
+//! [`heuristics.yml`][heuristics], for the extensions most often met in
+//! practice. For each extension, rules are tried in order, and the first rule
+//! with a matching pattern picks the language; a rule without patterns always
+//! matches.
+//!
+//! [`Heuristics`] reads all of `heuristics.yml` instead, fetched with
+//! [`HEURISTICS_YML`] or from a file:
+//!
+//! ```text
+//! let rules = Heuristics::from_path("heuristics.yml")?;
+//! let pipeline = Pipeline::new(&linguist).rules(rules);
+//! ```
+//!
+//! [heuristics]: https://github.com/github-linguist/linguist/blob/master/lib/linguist/heuristics.yml
+
+use std::{collections::HashMap, fs::File, io, path::Path, str::FromStr};
+
+use once_cell::sync::{Lazy, OnceCell};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{limits::Limits, Error, Stopwatch};
+
+/// Where to fetch Linguist's `heuristics.yml` for [`Heuristics`].
+pub const HEURISTICS_YML: &str =
+    "https://raw.githubusercontent.com/github/linguist/master/lib/linguist/heuristics.yml";
+
+/// Where to fetch `heuristics.yml` as of a Linguist branch, tag, or commit,
+/// like [`languages_yml_at`](crate::languages_yml_at).
+pub fn heuristics_yml_at(git_ref: &str) -> String {
+    format!(
+        "https://raw.githubusercontent.com/github/linguist/{git_ref}/lib/linguist/heuristics.yml"
+    )
+}
+
+/// Rules for files ending in one of `extensions`.
+#[derive(Debug, Clone, Copy)]
+pub struct Disambiguation {
+    /// Lowercase, including the dot.
+    pub extensions: &'static [&'static str],
+    pub rules: &'static [Rule],
+}
+
+/// Pick `language` if any of `patterns` matches, or unconditionally if there
+/// are none. Patterns are regular expressions matched in multi-line mode.
+#[derive(Debug, Clone, Copy)]
+pub struct Rule {
+    /// The language name, lowercased; it is matched regardless of case.
+    pub language: &'static str,
+    pub patterns: &'static [&'static str],
+}
+
+const OBJECTIVE_C: &str = r#"^\s*(@(interface|class|protocol|property|end|synchronised|selector|implementation)\b|#import\s+.+\.h[">])"#;
+
+/// The rules used by [`disambiguate`].
+pub const DISAMBIGUATIONS: &[Disambiguation] = &[
+    Disambiguation {
+        extensions: &[".h"],
+        rules: &[
+            Rule {
+                language: "objective-c",
+                patterns: &[OBJECTIVE_C],
+            },
+            Rule {
+                language: "c++",
+                patterns: &[
+                    r"^\s*#\s*include <(cstdint|string|vector|map|list|array|bitset|queue|stack|forward_list|unordered_map|unordered_set|(i|o|io)stream)>",
+                    r"^\s*template\s*<",
+                    r"^[ \t]*(try|constexpr)",
+                    r"^[ \t]*catch\s*\(",
+                    r"^[ \t]*(class|(using[ \t]+)?namespace)\s+\w+",
+                    r"^[ \t]*(private|public|protected):$",
+                    r"std::\w+",
+                ],
+            },
+            Rule {
+                language: "c",
+                patterns: &[],
+            },
+        ],
+    },
+    Disambiguation {
+        extensions: &[".m"],
+        rules: &[
+            Rule {
+                language: "objective-c",
+                patterns: &[OBJECTIVE_C],
+            },
+            Rule {
+                language: "mercury",
+                patterns: &[r":- module"],
+            },
+            Rule {
+                language: "matlab",
+                patterns: &[r"^\s*%", r"^\s*classdef\b", r"^\s*function\b.*="],
+            },
+        ],
+    },
+    Disambiguation {
+        extensions: &[".rs"],
+        rules: &[
+            Rule {
+                language: "rust",
+                patterns: &[r"^(use |fn |mod |pub |macro_rules|impl|#!?\[)"],
+            },
+            Rule {
+                language: "renderscript",
+                patterns: &[r"#include|#pragma\s+(rs|version)|__attribute__"],
+            },
+            Rule {
+                language: "xml",
+                patterns: &[r"^\s*<\?xml"],
+            },
+        ],
+    },
+    Disambiguation {
+        extensions: &[".pl"],
+        rules: &[
+            Rule {
+                language: "prolog",
+                patterns: &[r"^[^#]*:-"],
+            },
+            Rule {
+                language: "raku",
+                patterns: &[r"^\s*use\s+v6\b", r"^\s*(my\s+)?(class|grammar|role)\s+\w+"],
+            },
+            Rule {
+                language: "perl",
+                patterns: &[],
+            },
+        ],
+    },
+    Disambiguation {
+        extensions: &[".ts"],
+        rules: &[
+            Rule {
+                language: "xml",
+                patterns: &[r"<TS\b"],
+            },
+            Rule {
+                language: "typescript",
+                patterns: &[],
+            },
+        ],
+    },
+    Disambiguation {
+        extensions: &[".md"],
+        rules: &[
+            Rule {
+                language: "gcc machine description",
+                patterns: &[r"^(;;|\(define_)"],
+            },
+            Rule {
+                language: "markdown",
+                patterns: &[],
+            },
+        ],
+    },
+    Disambiguation {
+        extensions: &[".v"],
+        rules: &[
+            Rule {
+                language: "rocq prover",
+                patterns: &[
+                    r"(?:^|\s)(?:Proof|Qed)\.(?:$|\s)|(?:^|\s)Require[ \t]+(Import|Export)\s",
+                ],
+            },
+            Rule {
+                language: "verilog",
+                patterns: &[
+                    r"^[ \t]*module\s+[^\s()]+\s*\#?\(|^[ \t]*`(?:define|ifdef|ifndef|include|timescale|pragma)|^[ \t]*always[ \t]*@|^[ \t]*initial[ \t]*(begin|@)",
+                ],
+            },
+            Rule {
+                language: "v",
+                patterns: &[r"\$(?:if|else)[ \t]|^[ \t]*fn\s+[^\s()]+\(.*?\).*?\{|^[ \t]*for\s*\{"],
+            },
+        ],
+    },
+];
+
+/// How much of a file the patterns see.
+pub const SAMPLE_BYTES: u64 = 8 * 1024;
+
+static BUILTIN: Lazy<Heuristics> = Lazy::new(|| {
+    let mut heuristics = Heuristics::default();
+    for d in DISAMBIGUATIONS {
+        let rules = d.rules.iter().map(|rule| RawRule {
+            languages: vec![rule.language.to_string()],
+            condition: match rule.patterns {
+                [] => Condition::Always,
+                patterns => Condition::Any(patterns.iter().map(|p| p.to_string()).collect()),
+            },
+        });
+        heuristics.insert(d.extensions.iter().copied(), rules.collect());
+    }
+    heuristics
+});
+
+/// The language, among `candidates`, that the rules for `extension` pick for
+/// a file starting with `sample`. `None` if there are no rules for the
+/// extension or none of them matches a candidate.
+pub fn disambiguate<'a>(extension: &str, sample: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    BUILTIN
+        .disambiguate(extension, sample, candidates)
+        .first()
+        .copied()
+}
+
+/// Rules for telling apart languages by file contents, as parsed from
+/// Linguist's `heuristics.yml`, or [`Heuristics::builtin`].
+///
+/// Patterns are Ruby regular expressions, which the [`regex`] crate mostly
+/// but not entirely understands. A rule with a pattern it rejects, such as
+/// one with a lookbehind, is skipped with a debug message rather than failing,
+/// so that the other rules for the extension still apply. Patterns are
+/// compiled the first time a file with one of their extensions is looked at.
+#[derive(Debug, Clone, Default)]
+pub struct Heuristics {
+    /// Lowercase extensions, including the dot, to indices into `blocks`.
+    extensions: HashMap<String, usize>,
+    blocks: Vec<Block>,
+}
+
+/// The rules for some extensions.
+#[derive(Debug, Clone)]
+struct Block {
+    rules: Vec<RawRule>,
+    compiled: OnceCell<Vec<CompiledRule>>,
+}
+
+/// A rule with its patterns in `P`: as written, or compiled.
+#[derive(Debug, Clone)]
+struct LanguageRule<P> {
+    /// As written, matched regardless of case.
+    languages: Vec<String>,
+    condition: Condition<P>,
+}
+
+type RawRule = LanguageRule<String>;
+
+type CompiledRule = LanguageRule<Regex>;
+
+#[derive(Debug, Clone)]
+enum Condition<P> {
+    Always,
+    /// Any of the patterns matches.
+    Any(Vec<P>),
+    /// None of the patterns matches, as `negative_pattern`.
+    None(Vec<P>),
+    /// Every condition holds, as `and`.
+    All(Vec<Condition<P>>),
+}
+
+impl Condition<String> {
+    fn compile(&self) -> Result<Condition<Regex>, String> {
+        let compile_all = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|p| compile(p).map_err(|err| regex_error(&err)))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(match self {
+            Condition::Always => Condition::Always,
+            Condition::Any(patterns) => Condition::Any(compile_all(patterns)?),
+            Condition::None(patterns) => Condition::None(compile_all(patterns)?),
+            Condition::All(all) => {
+                Condition::All(all.iter().map(Self::compile).collect::<Result<_, _>>()?)
+            }
+        })
+    }
+}
+
+impl Condition<Regex> {
+    fn matches(&self, sample: &str) -> bool {
+        match self {
+            Condition::Always => true,
+            Condition::Any(patterns) => patterns.iter().any(|p| p.is_match(sample)),
+            Condition::None(patterns) => !patterns.iter().any(|p| p.is_match(sample)),
+            Condition::All(conditions) => conditions.iter().all(|c| c.matches(sample)),
+        }
+    }
+}
+
+/// `pattern` as Linguist matches it, where `^` and `$` match at every line.
+fn compile(pattern: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!("(?m){pattern}"))
+}
+
+/// The gist of `err`, without the pattern it quotes, which can be long.
+fn regex_error(err: &regex::Error) -> String {
+    let message = err.to_string();
+    let last = message.lines().last().unwrap_or_default();
+    last.strip_prefix("error: ").unwrap_or(last).to_string()
+}
+
+impl Block {
+    fn compiled(&self) -> &[CompiledRule] {
+        self.compiled.get_or_init(|| {
+            let compile = |rule: &RawRule| match rule.condition.compile() {
+                Ok(condition) => Some(LanguageRule {
+                    languages: rule.languages.clone(),
+                    condition,
+                }),
+                Err(err) => {
+                    let languages = rule.languages.join(", ");
+                    log::debug!("skipped a heuristic for {languages}: {err}");
+                    None
+                }
+            };
+            self.rules.iter().filter_map(compile).collect()
+        })
+    }
+}
+
+/// `heuristics.yml`, of which only what [`Heuristics`] uses is read.
+#[derive(Deserialize)]
+struct HeuristicsYml {
+    #[serde(default)]
+    disambiguations: Vec<DisambiguationYml>,
+    #[serde(default)]
+    named_patterns: HashMap<String, OneOrMany>,
+}
+
+#[derive(Deserialize)]
+struct DisambiguationYml {
+    extensions: Vec<String>,
+    rules: Vec<RuleYml>,
+}
+
+/// A rule, or within `and`, a condition without `language`.
+#[derive(Deserialize)]
+struct RuleYml {
+    #[serde(default)]
+    language: Option<OneOrMany>,
+    #[serde(default)]
+    pattern: Option<OneOrMany>,
+    #[serde(default)]
+    negative_pattern: Option<OneOrMany>,
+    #[serde(default)]
+    named_pattern: Option<String>,
+    #[serde(default)]
+    and: Option<Vec<RuleYml>>,
+}
+
+impl RuleYml {
+    /// The condition of this rule, taking `and`, then `pattern`, then
+    /// `negative_pattern`, then `named_pattern`, like Linguist does.
+    fn condition(&self, named: &HashMap<String, OneOrMany>) -> Result<Condition<String>, String> {
+        if let Some(all) = &self.and {
+            let all = all.iter().map(|rule| rule.condition(named));
+            return Ok(Condition::All(all.collect::<Result<_, _>>()?));
+        }
+        if let Some(patterns) = &self.pattern {
+            return Ok(Condition::Any(patterns.to_vec()));
+        }
+        if let Some(patterns) = &self.negative_pattern {
+            return Ok(Condition::None(patterns.to_vec()));
+        }
+        if let Some(name) = &self.named_pattern {
+            let Some(patterns) = named.get(name) else {
+                return Err(format!("no named pattern {name:?}"));
+            };
+            return Ok(Condition::Any(patterns.to_vec()));
+        }
+        Ok(Condition::Always)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn to_vec(&self) -> Vec<String> {
+        match self {
+            OneOrMany::One(one) => vec![one.clone()],
+            OneOrMany::Many(many) => many.clone(),
+        }
+    }
+}
+
+impl Heuristics {
+    /// The rules of [`DISAMBIGUATIONS`], which [`disambiguate`] uses.
+    pub fn builtin() -> &'static Heuristics {
+        &BUILTIN
+    }
+
+    /// Fetch `heuristics.yml` from `url`, such as [`HEURISTICS_YML`] or one of
+    /// [`heuristics_yml_at`], within `limits`.
+    #[cfg(feature = "fetch")]
+    pub fn from_url_with_client(
+        url: &str,
+        client: &reqwest::blocking::Client,
+        limits: &Limits,
+    ) -> Result<Self, Error> {
+        log::info!("Fetching {url}");
+        let res = client.get(url).send()?.error_for_status()?;
+        if let Some(len) = res.content_length() {
+            limits.check_len(len)?;
+        }
+        limits.read(res)?.parse()
+    }
+
+    /// Read `heuristics.yml` from `reader`, within the default [`Limits`].
+    pub fn from_reader<R: io::Read>(reader: R) -> Result<Self, Error> {
+        Limits::default().read(reader)?.parse()
+    }
+
+    /// Read `heuristics.yml` from a local file. Errors name the path.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let in_file = |err| Error::File {
+            path: path.to_path_buf(),
+            source: Box::new(err),
+        };
+        let file = File::open(path).map_err(|err| in_file(err.into()))?;
+        Self::from_reader(file).map_err(in_file)
+    }
+
+    /// The languages, among `candidates`, that the rules for `extension` pick
+    /// for a file starting with `sample`: those of the first matching rule
+    /// that names any candidate, usually one. Empty if there are no rules for
+    /// the extension or none of them matches.
+    pub fn disambiguate<'a>(
+        &self,
+        extension: &str,
+        sample: &str,
+        candidates: &[&'a str],
+    ) -> Vec<&'a str> {
+        let Some(&idx) = self.extensions.get(&extension.to_lowercase()) else {
+            return vec![];
+        };
+        let named = |rule: &CompiledRule| {
+            candidates
+                .iter()
+                .copied()
+                .filter(|name| rule.languages.iter().any(|l| name.eq_ignore_ascii_case(l)))
+                .collect::<Vec<_>>()
+        };
+        self.blocks[idx]
+            .compiled()
+            .iter()
+            .map(|rule| (named(rule), rule))
+            .find(|(named, rule)| !named.is_empty() && rule.condition.matches(sample))
+            .map(|(named, _)| named)
+            .unwrap_or_default()
+    }
+
+    /// The extensions that have rules, lowercase and including the dot.
+    pub fn extensions(&self) -> impl Iterator<Item = &str> {
+        self.extensions.keys().map(String::as_str)
+    }
+
+    /// Add rules for `extensions`, replacing any earlier ones.
+    fn insert<'e>(&mut self, extensions: impl IntoIterator<Item = &'e str>, rules: Vec<RawRule>) {
+        let idx = self.blocks.len();
+        self.blocks.push(Block {
+            rules,
+            compiled: OnceCell::new(),
+        });
+        for ext in extensions {
+            self.extensions.insert(ext.to_lowercase(), idx);
+        }
+    }
+}
+
+/// Parse `heuristics.yml` that is already in memory. Patterns are compiled
+/// later, see [`Heuristics`].
+impl FromStr for Heuristics {
+    type Err = Error;
+
+    fn from_str(yaml: &str) -> Result<Self, Error> {
+        let started = Stopwatch::start();
+        let yml = serde_yaml::from_str::<HeuristicsYml>(yaml)?;
+        let mut heuristics = Heuristics::default();
+        for d in yml.disambiguations {
+            let mut rules = vec![];
+            for rule in d.rules {
+                let Some(languages) = &rule.language else {
+                    continue;
+                };
+                match rule.condition(&yml.named_patterns) {
+                    Ok(condition) => rules.push(LanguageRule {
+                        languages: languages.to_vec(),
+                        condition,
+                    }),
+                    Err(err) => {
+                        let extensions = d.extensions.join(", ");
+                        log::debug!("skipped a heuristic for {extensions}: {err}");
+                    }
+                }
+            }
+            heuristics.insert(d.extensions.iter().map(String::as_str), rules);
+        }
+        log::debug!(
+            "parsed heuristics for {} extensions in {:?}",
+            heuristics.extensions.len(),
+            started.elapsed(),
+        );
+        Ok(heuristics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const H: &[&str] = &["C", "C++", "Objective-C"];
+    const M: &[&str] = &["MATLAB", "Mercury", "Objective-C", "Limbo", "M"];
+    const PL: &[&str] = &["Perl", "Prolog", "Raku"];
+
+    /// Samples under `tests/fixtures/samples`, with their extension, the
+    /// languages sharing it, and the language they are in.
+    const SAMPLES: &[(&str, &str, &[&str], &str)] = &[
+        (
+            include_str!("../tests/fixtures/samples/objc.h"),
+            ".h",
+            H,
+            "Objective-C",
+        ),
+        (
+            include_str!("../tests/fixtures/samples/cpp.h"),
+            ".h",
+            H,
+            "C++",
+        ),
+        (include_str!("../tests/fixtures/samples/c.h"), ".h", H, "C"),
+        (
+            include_str!("../tests/fixtures/samples/objc.m"),
+            ".m",
+            M,
+            "Objective-C",
+        ),
+        (
+            include_str!("../tests/fixtures/samples/mercury.m"),
+            ".m",
+            M,
+            "Mercury",
+        ),
+        (
+            include_str!("../tests/fixtures/samples/matlab.m"),
+            ".M",
+            M,
+            "MATLAB",
+        ),
+        (
+            include_str!("../tests/fixtures/samples/perl.pl"),
+            ".pl",
+            PL,
+            "Perl",
+        ),
+        (
+            include_str!("../tests/fixtures/samples/prolog.pl"),
+            ".pl",
+            PL,
+            "Prolog",
+        ),
+        (
+            include_str!("../tests/fixtures/samples/raku.pl"),
+            ".pl",
+            PL,
+            "Raku",
+        ),
+    ];
+
+    #[test]
+    fn builtin() {
+        for &(sample, extension, candidates, language) in SAMPLES {
+            let found = disambiguate(extension, sample, candidates);
+            assert_eq!(found, Some(language), "{language}{extension}");
+        }
+    }
+
+    /// The rules of Linguist's own `heuristics.yml` for the same extensions,
+    /// with `named_pattern`, `and`, and `negative_pattern`, pick the same.
+    #[test]
+    fn heuristics_yml() {
+        let yml = include_str!("../tests/fixtures/heuristics.yml");
+        let heuristics = yml.parse::<Heuristics>().unwrap();
+        let mut extensions = heuristics.extensions().collect::<Vec<_>>();
+        extensions.sort();
+        assert_eq!(extensions, [".h", ".m", ".pl"]);
+        for &(sample, extension, candidates, language) in SAMPLES {
+            let found = heuristics.disambiguate(extension, sample, candidates);
+            assert_eq!(found, [language], "{language}{extension}");
+        }
+        // none of them is a candidate, or there are no rules
+        assert!(heuristics.disambiguate(".h", "", &["Rust"]).is_empty());
+        assert!(heuristics
+            .disambiguate(".rs", "fn main() {}", &["Rust"])
+            .is_empty());
+    }
+
+    /// Rules that cannot be represented are skipped, and the rest of the
+    /// extension's rules still apply.
+    #[test]
+    fn skipped_rules() {
+        let heuristics = r#"
+disambiguations:
+- extensions: ['.x']
+  rules:
+  - language: Lookbehind
+    pattern: '(?<!#)x'
+  - language: Named
+    named_pattern: missing
+  - language: [Either, Or]
+    pattern: '^either'
+  - language: Fallback
+"#
+        .parse::<Heuristics>()
+        .unwrap();
+        let candidates = ["Lookbehind", "Named", "Either", "Or", "Fallback"];
+        assert_eq!(
+            heuristics.disambiguate(".x", "x", &candidates),
+            ["Fallback"]
+        );
+        assert_eq!(
+            heuristics.disambiguate(".x", "either", &candidates),
+            ["Either", "Or"]
+        );
+        assert!(matches!(
+            "disambiguations: 3".parse::<Heuristics>(),
+            Err(Error::Parse(_))
+        ));
+    }
+}
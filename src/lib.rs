@@ -7,6 +7,9 @@
 //!
 //! You can query with language names or file extensions.
 //!
+//! As a library, [`lookup`] is the shortest way to a color; see [`Linguist`] and
+//! [`ColorMap`] for everything else.
+//!
 //! ## xterm colors and color distances
 //!
 //! For finding the nearest xterm colors, a `--colors`/`-c` option is available for specifying
@@ -18,7 +21,13 @@
 //!
 //! The default is [RGB], which may not actually yield the best result in terms of human perception.
 //! For finding colors that "look" the closest, [CIELAB] is a reasonable choice; use it with `-c lab`.
-//! See [`ColorSpace`] for available choices.
+//! [`supported_color_spaces`] lists the available choices, and [`spaces::ALIASES`] the names
+//! accepted by `-c`. `linguist-termcolor compare python` shows the result in each of them.
+//!
+//! `-c de2000` measures [CIEDE2000] instead, which is no color space but usually matches perception
+//! better still, see [`Metric`].
+//!
+//! [CIEDE2000]: https://en.wikipedia.org/wiki/Color_difference#CIEDE2000
 //!
 //! For example, here are the different results for `"python"` using [RGB], [CMYK], and [CIELAB], respectively.
 //!
@@ -35,42 +44,167 @@
 //! <pre>$ linguist-termcolor -c lab for python
 //! <strong style="color: #3572a5 !important">rgb #3572a5</strong> <strong style="color: #005f87 !important">xterm 24</strong> python</pre>
 
+pub mod capability;
+pub mod classify;
+pub mod color_math;
+#[cfg(unix)]
+pub mod daemon;
+pub mod detect;
+pub mod distinct;
+pub mod error;
+pub mod groups;
+pub mod heuristics;
+pub mod limits;
+#[cfg(feature = "fetch")]
+pub mod lookup;
+pub mod lut;
+pub mod metric;
+pub mod names;
+pub mod numbers;
+pub mod scan;
+#[cfg(feature = "fetch")]
+pub mod source;
+pub mod spaces;
+pub mod tab;
+pub mod themes;
+
+pub use error::Error;
+#[cfg(feature = "fetch")]
+pub use lookup::{global, global_with, lookup, lookup_with, Lookup, LookupOptions};
+pub use metric::{HslWeights, Metric};
+pub use spaces::supported_color_spaces;
+
+#[cfg(all(
+    feature = "fetch",
+    not(any(feature = "native-tls", feature = "rustls"))
+))]
+compile_error!(
+    "either the `native-tls` or the `rustls` feature is required to fetch languages.yml"
+);
+
 use std::{
     borrow::Cow,
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt,
+    fs::File,
+    io,
+    path::Path,
+    str::FromStr,
+    time::{Duration, Instant},
 };
 
-use color_art::{distance_with, Color, ColorSpace};
-use colored::Colorize;
+use classify::{classify, QueryShape};
+use color_art::{Color, ColorSpace};
+use color_math::Cvd;
+use limits::Limits;
+use lut::NearestColorIndex;
+use numbers::Precision;
 use once_cell::sync::Lazy;
 use regex::Regex;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "fetch")]
+use source::LinguistSource;
+use themes::{NamedPalette, PaletteFormat};
 
-/// Find the color among `choices` having the smallest distance to `color`
-/// using [color_art::distance_with].
-///
-/// Returns the index and the color.
-fn find_nearest_color<'a, I>(
+/// The nearest color of a [`Palette`] to some color, see [`nearest_xterm`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct XtermMatch {
+    /// The index in the palette, as in `xterm 180`.
+    pub index: u8,
+    pub color: Color,
+    /// The distance to the color, in the [`Metric`] it was found with.
+    pub distance: f64,
+}
+
+/// The nearest of the 256 xterm colors to `color`, found with `metric`, such
+/// as a [`ColorSpace`]. A large [`XtermMatch::distance`] means a poor
+/// approximation.
+pub fn nearest_xterm(color: &Color, metric: impl Into<Metric>) -> XtermMatch {
+    nearest_match(color, Palette::Xterm256, metric.into())
+}
+
+/// Like [`nearest_xterm`], but among the colors of `palette`, e.g. only the
+/// 6×6×6 cube with [`Palette::xterm_without`]. The index is still that of the
+/// xterm color.
+pub fn nearest_in_palette(
+    color: &Color,
+    palette: Palette,
+    metric: impl Into<Metric>,
+) -> XtermMatch {
+    nearest_match(color, palette, metric.into())
+}
+
+/// [`nearest_in_palette`] for each of `colors`, in the same order, and with
+/// the `rayon` feature, on all cores.
+pub fn nearest_many_in_palette(
+    colors: &[Color],
+    palette: Palette,
+    metric: impl Into<Metric>,
+) -> Vec<XtermMatch> {
+    let index = NearestColorIndex::shared(palette, metric.into());
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        colors
+            .par_iter()
+            .map(|color| index.nearest(color))
+            .collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    colors.iter().map(|color| index.nearest(color)).collect()
+}
+
+/// The `n` xterm colors nearest `color`, found with `metric`, nearest first
+/// and the lower index first among equally near ones. The first is that of
+/// [`nearest_xterm`].
+pub fn nearest_xterm_n(color: &Color, n: usize, metric: impl Into<Metric>) -> Vec<XtermMatch> {
+    nearest_n_in_palette(color, Palette::Xterm256, n, metric)
+}
+
+/// Like [`nearest_xterm_n`], but among the colors of `palette`.
+pub fn nearest_n_in_palette(
     color: &Color,
-    choices: I,
-    colors: ColorSpace,
-) -> Option<(usize, &'a Color)>
-where
-    I: Iterator<Item = &'a Color>,
-{
-    choices
-        .map(|c| (c, distance_with(c, color, colors)))
-        .enumerate()
-        .min_by(|(_, (_, d1)), (_, (_, d2))| d1.partial_cmp(d2).unwrap())
-        .map(|(i, (c, _))| (i, c))
+    palette: Palette,
+    n: usize,
+    metric: impl Into<Metric>,
+) -> Vec<XtermMatch> {
+    NearestColorIndex::shared(palette, metric.into()).nearest_n(color, n)
+}
+
+fn nearest_match(color: &Color, palette: Palette, metric: Metric) -> XtermMatch {
+    NearestColorIndex::shared(palette, metric).nearest(color)
+}
+
+/// Where [`Linguist::new`] fetches the data from.
+pub const LANGUAGES_YML: &str =
+    "https://raw.githubusercontent.com/github/linguist/master/lib/linguist/languages.yml";
+
+/// Where to fetch `languages.yml` as of a Linguist branch, tag, or commit,
+/// e.g. `v7.30.0`. [`LANGUAGES_YML`] is the URL for `master`.
+pub fn languages_yml_at(git_ref: &str) -> String {
+    format!(
+        "https://raw.githubusercontent.com/github/linguist/{git_ref}/lib/linguist/languages.yml"
+    )
 }
 
+/// A snapshot of [`LANGUAGES_YML`], embedded with the `bundled` feature.
+/// Refresh it by downloading the file over `data/languages.yml`.
+#[cfg(feature = "bundled")]
+pub const BUNDLED_LANGUAGES_YML: &str = include_str!("../data/languages.yml");
+
 /// See <https://github.com/github-linguist/linguist>
-pub struct Linguist(HashMap<String, LinguistLang>);
+///
+/// Languages are kept sorted by their lowercased names, so anything built by
+/// iterating over them comes out the same way on every run. Colors that are
+/// not hex colors are left out, see [`Linguist::skipped_colors`].
+pub struct Linguist(BTreeMap<String, LinguistLang>, Vec<Error>);
 
 /// See <https://github.com/github-linguist/linguist/blob/master/lib/linguist/languages.yml>
 #[derive(Debug, Deserialize)]
 struct LinguistLang {
+    /// the name as written, such as `Jupyter Notebook`, set from the key
+    #[serde(skip)]
+    name: String,
     /// color in hex
     #[serde(default)]
     color: Option<String>,
@@ -78,6 +212,250 @@ struct LinguistLang {
     extensions: Vec<String>,
     #[serde(default)]
     aliases: Vec<String>,
+    #[serde(default)]
+    filenames: Vec<String>,
+    #[serde(default)]
+    interpreters: Vec<String>,
+    /// name of the parent language
+    #[serde(default)]
+    group: Option<String>,
+    #[serde(default, rename = "type")]
+    language_type: Option<LanguageType>,
+    #[serde(default)]
+    language_id: Option<u64>,
+    /// TextMate scope, or `none`
+    #[serde(default)]
+    tm_scope: Option<String>,
+    /// anything, for data files that are not upstream's
+    #[serde(default)]
+    metadata: Option<serde_yaml::Value>,
+}
+
+/// Linguist's `type` of a language.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "String")]
+pub enum LanguageType {
+    Programming,
+    Markup,
+    Data,
+    Prose,
+    /// A type Linguist did not have when this was written.
+    Other(String),
+}
+
+impl LanguageType {
+    /// The type as written in `languages.yml`, e.g. `programming`.
+    pub fn name(&self) -> &str {
+        match self {
+            LanguageType::Programming => "programming",
+            LanguageType::Markup => "markup",
+            LanguageType::Data => "data",
+            LanguageType::Prose => "prose",
+            LanguageType::Other(name) => name,
+        }
+    }
+}
+
+impl From<String> for LanguageType {
+    fn from(name: String) -> Self {
+        match name.as_str() {
+            "programming" => LanguageType::Programming,
+            "markup" => LanguageType::Markup,
+            "data" => LanguageType::Data,
+            "prose" => LanguageType::Prose,
+            _ => LanguageType::Other(name),
+        }
+    }
+}
+
+impl LinguistLang {
+    fn color(&self) -> Option<u32> {
+        self.color.as_deref().and_then(parse_hex)
+    }
+}
+
+/// A hex color, `#rrggbb` or `#rgb` with or without the `#`, such as
+/// `#dea584`, `dea584`, or `#abc` for `#aabbcc`. Anything else, including
+/// surrounding whitespace, is [`Error::HexColor`].
+///
+/// This is how colors in `languages.yml` are read, and the hex notation of
+/// [`TermColor`]'s `FromStr`.
+pub fn parse_hex_color(text: &str) -> Result<Color, Error> {
+    parse_hex(text)
+        .map(color_from_rgb)
+        .ok_or_else(|| Error::HexColor(text.to_string()))
+}
+
+/// `#dea584`, `dea584`, `#abc`, or `abc` as `0xdea584` or `0xaabbcc`.
+fn parse_hex(text: &str) -> Option<u32> {
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    // from_str_radix also takes a sign
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let rgb = u32::from_str_radix(hex, 16).ok()?;
+    match hex.len() {
+        6 => Some(rgb),
+        3 => {
+            let [r, g, b] = [8, 4, 0].map(|shift| (rgb >> shift & 0xf) * 0x11);
+            Some(r << 16 | g << 8 | b)
+        }
+        _ => None,
+    }
+}
+
+/// When a step started, for logging how long it took. `wasm32-unknown-unknown`
+/// has no clock, and [`Instant::now`] panics there, so steps take no time.
+pub(crate) struct Stopwatch(Option<Instant>);
+
+impl Stopwatch {
+    pub(crate) fn start() -> Self {
+        let clock = !cfg!(all(target_family = "wasm", target_os = "unknown"));
+        Self(clock.then(Instant::now))
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.0.map_or(Duration::ZERO, |started| started.elapsed())
+    }
+}
+
+/// The color of `rgb`, as in `0xdea584`. Bits above the lowest 24 are ignored.
+fn color_from_rgb(rgb: u32) -> Color {
+    let [_, r, g, b] = rgb.to_be_bytes();
+    Color::new(r, g, b, 1.0)
+}
+
+/// Whether `err` is an HTTP 404 from fetching `languages.yml`.
+#[cfg(feature = "fetch")]
+pub fn is_not_found(err: &Error) -> bool {
+    match err {
+        Error::Fetch(err) => err.status() == Some(reqwest::StatusCode::NOT_FOUND),
+        _ => false,
+    }
+}
+
+/// Parse `languages.yml` that is already in memory, within the default [`Limits`].
+impl FromStr for Linguist {
+    type Err = Error;
+
+    fn from_str(yaml: &str) -> Result<Self, Error> {
+        let limits = Limits::default();
+        limits.check_len(yaml.len() as u64)?;
+        Self::parse(yaml, &limits)
+    }
+}
+
+/// A language known to Linguist.
+#[derive(Debug, Clone, Copy)]
+pub struct Language<'a> {
+    name: &'a str,
+    lang: &'a LinguistLang,
+}
+
+impl<'a> Language<'a> {
+    fn of(lang: &'a LinguistLang) -> Self {
+        let name = &lang.name;
+        Language { name, lang }
+    }
+
+    /// The language name as Linguist writes it, such as `Jupyter Notebook`.
+    /// [`Linguist::get`] and queries find it regardless of case.
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn color(&self) -> Option<Color> {
+        self.lang.color().map(color_from_rgb)
+    }
+
+    pub fn aliases(&self) -> &'a [String] {
+        &self.lang.aliases
+    }
+
+    /// File extensions, in the order Linguist lists them.
+    pub fn extensions(&self) -> &'a [String] {
+        &self.lang.extensions
+    }
+
+    /// File names such as `Makefile`, as Linguist lists them.
+    pub fn filenames(&self) -> &'a [String] {
+        &self.lang.filenames
+    }
+
+    /// Interpreters such as `python3`, as Linguist lists them.
+    pub fn interpreters(&self) -> &'a [String] {
+        &self.lang.interpreters
+    }
+
+    /// The extension Linguist lists first, which it considers the primary one.
+    pub fn primary_extension(&self) -> Option<&'a str> {
+        self.lang.extensions.first().map(String::as_str)
+    }
+
+    /// A stable, URL- and key-safe identifier, see [`slugify`].
+    pub fn slug(&self) -> String {
+        slugify(self.name)
+    }
+
+    /// The parent language, as written in Linguist's `group` field.
+    pub fn group(&self) -> Option<&'a str> {
+        self.lang.group.as_deref()
+    }
+
+    /// Linguist's `type` field, which every upstream language has.
+    pub fn language_type(&self) -> Option<&'a LanguageType> {
+        self.lang.language_type.as_ref()
+    }
+
+    /// Linguist's `language_id`, the number GitHub identifies the language by,
+    /// which stays the same when the language is renamed. Every upstream
+    /// language has one.
+    pub fn language_id(&self) -> Option<u64> {
+        self.lang.language_id
+    }
+
+    /// Linguist's `tm_scope`, the TextMate scope of the language's grammar,
+    /// such as `source.rust`. Upstream writes `none` for languages with no
+    /// grammar, which is returned as written.
+    pub fn tm_scope(&self) -> Option<&'a str> {
+        self.lang.tm_scope.as_deref()
+    }
+
+    /// The `metadata` field, if present, as written. Upstream Linguist has no
+    /// such field; it is for notes in a custom `languages.yml`:
+    ///
+    /// ```text
+    /// Rust:
+    ///   color: "#dea584"
+    ///   metadata:
+    ///     note: use this color for the payments service dashboards
+    /// ```
+    pub fn metadata(&self) -> Option<&'a serde_yaml::Value> {
+        self.lang.metadata.as_ref()
+    }
+}
+
+/// As its name, color, type, group, `language_id`, `tm_scope`, and lists, e.g.
+/// for `info --format json`. Missing fields are `null`.
+/// Requires the `serde` feature.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Language<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut lang = serializer.serialize_struct("Language", 10)?;
+        lang.serialize_field("name", self.name)?;
+        lang.serialize_field("color", &self.color().map(TermColor::from))?;
+        lang.serialize_field("type", &self.language_type().map(LanguageType::name))?;
+        lang.serialize_field("group", &self.group())?;
+        lang.serialize_field("language_id", &self.language_id())?;
+        lang.serialize_field("tm_scope", &self.tm_scope())?;
+        lang.serialize_field("aliases", self.aliases())?;
+        lang.serialize_field("extensions", self.extensions())?;
+        lang.serialize_field("filenames", self.filenames())?;
+        lang.serialize_field("interpreters", self.interpreters())?;
+        lang.end()
+    }
 }
 
 impl<'de> Deserialize<'de> for Linguist {
@@ -88,145 +466,3460 @@ impl<'de> Deserialize<'de> for Linguist {
         let map = HashMap::<String, LinguistLang>::deserialize(deserializer)?;
         Ok(Self(
             map.into_iter()
-                .map(|(k, v)| (k.to_ascii_lowercase(), v))
+                .map(|(k, v)| (k.to_ascii_lowercase(), LinguistLang { name: k, ..v }))
                 .collect(),
+            Vec::new(),
         ))
     }
 }
 
 impl Linguist {
-    pub fn new() -> anyhow::Result<Self> {
-        let url =
-            "https://raw.githubusercontent.com/github/linguist/master/lib/linguist/languages.yml";
-        eprintln!("{}", format!("Fetching {}", url).dimmed());
-        let res = reqwest::blocking::get(url)?.error_for_status()?;
-        let map = serde_yaml::from_str(&res.text()?)?;
+    /// Fetch [`LANGUAGES_YML`] with the defaults of [`Linguist::builder`].
+    #[cfg(feature = "fetch")]
+    pub fn new() -> Result<Self, Error> {
+        Self::builder().build()
+    }
+
+    /// Configure where and how the data is loaded: from a URL, a Linguist ref,
+    /// a file, or a reader, with a timeout, a user agent, and whether to log
+    /// the URL.
+    #[cfg(feature = "fetch")]
+    pub fn builder() -> LinguistSource {
+        LinguistSource::new()
+    }
+
+    /// Parse the snapshot of [`LANGUAGES_YML`] embedded at build time, without
+    /// touching the network. Requires the `bundled` feature.
+    #[cfg(feature = "bundled")]
+    pub fn bundled() -> Self {
+        Self::parse(BUNDLED_LANGUAGES_YML, &Limits::default())
+            .expect("the bundled languages.yml should be valid")
+    }
+
+    /// Fetch `languages.yml` as of a Linguist branch, tag, or commit, for output
+    /// that does not change when upstream does. See [`languages_yml_at`].
+    #[cfg(feature = "fetch")]
+    pub fn new_with_ref(git_ref: &str) -> Result<Self, Error> {
+        Self::builder().git_ref(git_ref).build()
+    }
+
+    /// Fetch `languages.yml` from `url`, within the default [`Limits`].
+    #[cfg(feature = "fetch")]
+    pub fn from_url(url: &str) -> Result<Self, Error> {
+        Self::from_url_with_limits(url, &Limits::default())
+    }
+
+    #[cfg(feature = "fetch")]
+    pub fn from_url_with_limits(url: &str, limits: &Limits) -> Result<Self, Error> {
+        Self::from_url_with_client(url, &reqwest::blocking::Client::new(), limits)
+    }
+
+    /// Like [`Linguist::from_url_with_limits`], but fetch with `client`, e.g. one
+    /// that trusts additional root certificates.
+    #[cfg(feature = "fetch")]
+    pub fn from_url_with_client(
+        url: &str,
+        client: &reqwest::blocking::Client,
+        limits: &Limits,
+    ) -> Result<Self, Error> {
+        Self::fetch(url, client, limits, &source::Policy::ONCE)
+    }
+
+    #[cfg(feature = "fetch")]
+    fn fetch(
+        url: &str,
+        client: &reqwest::blocking::Client,
+        limits: &Limits,
+        policy: &source::Policy,
+    ) -> Result<Self, Error> {
+        policy.announce(url);
+        let started = Instant::now();
+        let res = source::get(client, url, policy)?;
+        if let Some(len) = res.content_length() {
+            limits.check_len(len)?;
+        }
+        let text = limits.read(res)?;
+        log::debug!("fetched {} bytes in {:?}", text.len(), started.elapsed());
+        Self::parse(&text, limits)
+    }
+
+    /// Like [`Linguist::new`], but without blocking the current thread, for use
+    /// inside an async runtime. Requires the `async` feature; parsing is the same.
+    /// For the options of [`Linguist::builder`], see [`LinguistSource::build_async`].
+    #[cfg(feature = "async")]
+    pub async fn new_async() -> Result<Self, Error> {
+        Self::builder().build_async().await
+    }
+
+    /// Like [`Linguist::from_url_with_limits`], but without blocking. Requires the
+    /// `async` feature.
+    #[cfg(feature = "async")]
+    pub async fn from_url_async(url: &str, limits: &Limits) -> Result<Self, Error> {
+        let source = Self::builder().url(url).limits(limits.clone());
+        source.build_async().await
+    }
+
+    /// Like [`Linguist::fetch`], without blocking.
+    #[cfg(feature = "async")]
+    async fn fetch_async(
+        url: &str,
+        client: &reqwest::Client,
+        limits: &Limits,
+        policy: &source::Policy,
+    ) -> Result<Self, Error> {
+        policy.announce(url);
+        let started = Instant::now();
+        let mut res = source::get_async(client, url, policy).await?;
+        if let Some(len) = res.content_length() {
+            limits.check_len(len)?;
+        }
+        let mut body = vec![];
+        while let Some(chunk) = res.chunk().await? {
+            body.extend_from_slice(&chunk);
+            limits.check_len(body.len() as u64)?;
+        }
+        let text = String::from_utf8(body)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        log::debug!("fetched {} bytes in {:?}", text.len(), started.elapsed());
+        Self::parse(&text, limits)
+    }
+
+    /// Read `languages.yml` from `reader`, e.g. stdin, buffering all of it before
+    /// parsing, within the default [`Limits`].
+    pub fn from_reader<R: io::Read>(reader: R) -> Result<Self, Error> {
+        Self::from_reader_with_limits(reader, &Limits::default())
+    }
+
+    pub fn from_reader_with_limits<R: io::Read>(reader: R, limits: &Limits) -> Result<Self, Error> {
+        Self::parse(&limits.read(reader)?, limits)
+    }
+
+    /// Read `languages.yml` from a local file, within the default [`Limits`].
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_path_with_limits(path, &Limits::default())
+    }
+
+    /// Like [`Linguist::from_path`]. Errors name the path, and tell a file that
+    /// could not be read apart from one that could not be parsed.
+    pub fn from_path_with_limits<P: AsRef<Path>>(path: P, limits: &Limits) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let in_file = |err| Error::File {
+            path: path.to_path_buf(),
+            source: Box::new(err),
+        };
+        let file = File::open(path).map_err(|err| in_file(err.into()))?;
+        let text = limits.read(file).map_err(in_file)?;
+        Self::parse(&text, limits).map_err(in_file)
+    }
+
+    /// Merge the overrides in the YAML file at `path` over this data, such as
+    /// an organization's own languages and colors:
+    ///
+    /// ```text
+    /// TypeScript:
+    ///   color: "#0057b8"
+    ///   aliases: [tsx]
+    /// Acme Config:
+    ///   color: "#ff6600"
+    ///   extensions: [".acme"]
+    /// ```
+    ///
+    /// Each key is a language, as in `languages.yml`, matched regardless of
+    /// case. A language that is not in the data is added as written. For one
+    /// that is, each field given replaces or extends that of the language:
+    ///
+    /// - `color`, `type`, `group`, and `metadata` replace it;
+    /// - `aliases`, `extensions`, `filenames`, and `interpreters` are appended
+    ///   to it, skipping any it already lists, so nothing is removed.
+    ///
+    /// Fields not given stay as they are. Every `color` must be a hex color
+    /// as [`parse_hex_color`] reads it, `#rrggbb` or `#rgb` with or without
+    /// the `#`, or [`Error::InvalidColor`] names the language. Errors name the
+    /// path too, as in [`Linguist::from_path`].
+    pub fn with_overrides<P: AsRef<Path>>(mut self, path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let in_file = |err| Error::File {
+            path: path.to_path_buf(),
+            source: Box::new(err),
+        };
+        let file = File::open(path).map_err(|err| in_file(err.into()))?;
+        let text = Limits::default().read(file).map_err(in_file)?;
+        self.merge(&text).map_err(in_file)?;
+        Ok(self)
+    }
+
+    /// See [`Linguist::with_overrides`].
+    fn merge(&mut self, overrides: &str) -> Result<(), Error> {
+        let overrides = serde_yaml::from_str::<Linguist>(overrides)?;
+        for lang in overrides.0.values() {
+            match &lang.color {
+                Some(value) if parse_hex(value).is_none() => {
+                    return Err(Error::InvalidColor {
+                        language: lang.name.clone(),
+                        value: value.clone(),
+                    })
+                }
+                _ => {}
+            }
+        }
+        for (name, with) in overrides.0 {
+            let Some(lang) = self.0.get_mut(&name) else {
+                log::debug!("overrides add {name}");
+                self.0.insert(name, with);
+                continue;
+            };
+            log::debug!("overrides change {name}");
+            if with.color.is_some() {
+                // a color of one's own for one that was skipped
+                (self.1).retain(|skipped| {
+                    !matches!(skipped, Error::InvalidColor { language, .. } if *language == lang.name)
+                });
+            }
+            let extend = |list: &mut Vec<String>, with: Vec<String>| {
+                for item in with {
+                    if !list.contains(&item) {
+                        list.push(item);
+                    }
+                }
+            };
+            extend(&mut lang.aliases, with.aliases);
+            extend(&mut lang.extensions, with.extensions);
+            extend(&mut lang.filenames, with.filenames);
+            extend(&mut lang.interpreters, with.interpreters);
+            lang.color = with.color.or(lang.color.take());
+            lang.group = with.group.or(lang.group.take());
+            lang.language_type = with.language_type.or(lang.language_type.take());
+            lang.language_id = with.language_id.or(lang.language_id.take());
+            lang.tm_scope = with.tm_scope.or(lang.tm_scope.take());
+            lang.metadata = with.metadata.or(lang.metadata.take());
+        }
+        Ok(())
+    }
+
+    fn parse(text: &str, limits: &Limits) -> Result<Self, Error> {
+        let started = Stopwatch::start();
+        let mut map = serde_yaml::from_str::<Self>(text)?;
+        limits.check(&map)?;
+        let Linguist(languages, skipped) = &mut map;
+        for lang in languages.values_mut() {
+            let Some(value) = lang.color.take_if(|value| parse_hex(value).is_none()) else {
+                continue;
+            };
+            log::debug!("skipped the color of {}: {value:?}", lang.name);
+            skipped.push(Error::InvalidColor {
+                language: lang.name.clone(),
+                value,
+            });
+        }
+        log::debug!(
+            "parsed {} languages in {:?}",
+            map.0.len(),
+            started.elapsed()
+        );
         Ok(map)
     }
 
+    /// The colors left out when parsing because they are not hex colors, as
+    /// [`parse_hex_color`] reads them, each an [`Error::InvalidColor`], in
+    /// the order of [`Linguist::languages`]. Their languages are kept without
+    /// a color, so that one bad entry upstream does not fail the whole load,
+    /// and are taken off the list by a color of their own in
+    /// [`Linguist::with_overrides`].
+    pub fn skipped_colors(&self) -> &[Error] {
+        &self.1
+    }
+
+    /// Every language, sorted by name.
+    pub fn languages(&self) -> impl Iterator<Item = Language<'_>> {
+        self.0.values().map(Language::of)
+    }
+
+    /// Look up a language by its name or one of its aliases, case-insensitively.
+    pub fn get(&self, name: &str) -> Option<Language<'_>> {
+        let name = name.to_ascii_lowercase();
+        self.0
+            .get_key_value(&name)
+            .or_else(|| {
+                self.0
+                    .iter()
+                    .find(|(_, lang)| lang.aliases.iter().any(|a| a.eq_ignore_ascii_case(&name)))
+            })
+            .map(|(_, lang)| Language::of(lang))
+    }
+
+    /// Whether `name` is a language name or alias, case-insensitively. Cheaper
+    /// than building a [`ColorMap`] to find out.
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Every language with a color of its own and that color, sorted by name,
+    /// e.g. to dump into a lookup table.
+    pub fn iter_colors(&self) -> impl Iterator<Item = (&str, Color)> {
+        self.languages()
+            .filter_map(|lang| Some((lang.name(), lang.color()?)))
+    }
+
     /// Build a rudimentary search index for the colors.
-    pub fn colors(&self) -> anyhow::Result<ColorMap<'_>> {
+    pub fn colors(&self) -> Result<ColorMap<'_>, Error> {
+        self.colors_with_options(&ColorMapOptions::default())
+    }
+
+    /// Like [`Linguist::colors`], consuming the data for an index that owns
+    /// its names, so that it can be kept without keeping `self`, see
+    /// [`ColorMap::into_owned`].
+    pub fn into_colors(self) -> Result<ColorMap<'static>, Error> {
+        Ok(self.colors()?.into_owned())
+    }
+
+    /// Like [`Linguist::colors`], leaving out parts of the index as configured in `options`.
+    pub fn colors_with_options(&self, options: &ColorMapOptions) -> Result<ColorMap<'_>, Error> {
+        let started = Stopwatch::start();
+        let mut inherited = HashMap::new();
         let colors = self
             .0
-            .values()
-            .map(|lang| {
-                lang.color
-                    .as_ref()
-                    .and_then(|c| u32::from_str_radix(&c[1..], 16).ok())
+            .iter()
+            .map(|(name, lang)| match lang.color() {
+                Some(color) => Some(color),
+                None if options.inherit_group_colors => {
+                    let (group, color) = self.group_color(name)?;
+                    inherited.insert(Cow::from(lang.name.as_str()), Cow::from(group));
+                    Some(color)
+                }
+                None => None,
             })
             .collect::<Vec<_>>();
 
-        let mut map = HashMap::<_, Vec<(Cow<'_, str>, u32)>>::with_capacity(
+        let mut words = HashMap::<_, Vec<(Cow<'_, str>, u32)>>::with_capacity(
             self.0
                 .values()
                 .map(|lang| 1 + lang.aliases.len() + lang.extensions.len())
                 .sum::<usize>(),
         );
 
+        let mut exact = HashMap::<_, Vec<(Cow<'_, str>, u32)>>::new();
+
+        let mut by_extension = HashMap::<_, Vec<(Cow<'_, str>, u32)>>::new();
+
+        // where each language lists each extension, for picking primary ones
+        let mut listed_at = HashMap::<String, Vec<(&str, usize)>>::new();
+
+        let mut interpreters = HashMap::<_, Vec<(Cow<'_, str>, u32)>>::new();
+
+        let mut by_filename = HashMap::<_, Vec<(Cow<'_, str>, u32)>>::new();
+
+        let mut languages = vec![];
+
         self.0.iter().enumerate().for_each(|(idx, (name, lang))| {
             let Some(color) = colors[idx] else { return };
+            if let Some(types) = &options.types {
+                if !lang
+                    .language_type
+                    .as_ref()
+                    .is_some_and(|t| types.contains(t))
+                {
+                    return;
+                }
+            }
+
+            languages.push((Cow::from(lang.name.as_str()), color));
+
+            let aliases = match options.include_aliases {
+                true => lang.aliases.as_slice(),
+                false => &[],
+            };
+            let (extensions, filenames, interpreted) = match options.include_extensions {
+                true => (
+                    &lang.extensions[..],
+                    &lang.filenames[..],
+                    &lang.interpreters[..],
+                ),
+                false => (&[][..], &[][..], &[][..]),
+            };
+
+            std::iter::once(name.as_str())
+                .chain(aliases.iter().map(String::as_str))
+                .chain(filenames.iter().map(String::as_str))
+                .map(normalize)
+                .chain(std::iter::once(slugify(name)))
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .for_each(|keyword| {
+                    let name = Cow::from(lang.name.as_str());
+                    exact
+                        .entry(Cow::from(keyword))
+                        .or_default()
+                        .push((name, color));
+                });
+
+            (filenames.iter().map(|filename| normalize(filename)))
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .for_each(|filename| {
+                    let name = Cow::from(lang.name.as_str());
+                    by_filename
+                        .entry(Cow::from(filename))
+                        .or_default()
+                        .push((name, color));
+                });
+
+            interpreted.iter().for_each(|interpreter| {
+                let name = Cow::from(lang.name.as_str());
+                interpreters
+                    .entry(Cow::from(interpreter.to_lowercase()))
+                    .or_default()
+                    .push((name, color));
+            });
+
+            extensions.iter().enumerate().for_each(|(position, ext)| {
+                let key = ext.trim_start_matches('.').to_lowercase();
+                (listed_at.entry(key.clone()).or_default()).push((&lang.name, position));
+                let name = Cow::from(lang.name.as_str());
+                by_extension
+                    .entry(Cow::from(key))
+                    .or_default()
+                    .push((name, color));
+            });
 
+            if !options.include_words {
+                return;
+            }
             let text = std::iter::once(name.as_str())
-                .chain(lang.aliases.iter().map(String::as_str))
-                .chain(lang.extensions.iter().map(String::as_str));
+                .chain(aliases.iter().map(String::as_str))
+                .chain(extensions.iter().map(String::as_str));
 
             text.for_each(|keyword| {
-                tokenize(keyword).iter().copied().for_each(|word| {
-                    let name = Cow::from(name.as_str());
-                    let word = Cow::from(word);
-                    map.entry(word).or_default().push((name, color));
-                })
+                let words_of = |word| std::iter::once(word).chain(dotted_parts(word));
+                tokenize(keyword)
+                    .into_iter()
+                    .flat_map(words_of)
+                    .for_each(|word| {
+                        let name = Cow::from(lang.name.as_str());
+                        let word = Cow::from(word);
+                        words.entry(word).or_default().push((name, color));
+                    })
             });
         });
 
-        Ok(ColorMap(map))
-    }
-}
+        words
+            .values_mut()
+            .chain(by_extension.values_mut())
+            .chain(interpreters.values_mut())
+            .for_each(|entries| {
+                entries.sort_unstable();
+                entries.dedup();
+            });
+        if let Some(max) = options.max_entries_per_keyword {
+            words.retain(|_, entries| entries.len() <= max);
+        }
 
-pub struct ColorMap<'a>(HashMap<Cow<'a, str>, Vec<(Cow<'a, str>, u32)>>);
+        let primary = listed_at
+            .into_iter()
+            .filter(|(key, _)| {
+                by_extension
+                    .get(key.as_str())
+                    .is_some_and(|found| found.len() > 1)
+            })
+            .filter_map(|(key, listed)| {
+                let primary = primary_language(&key, &listed)?;
+                Some((Cow::from(key), Cow::from(primary)))
+            })
+            .collect::<HashMap<_, _>>();
+        if options.primary_only {
+            for (key, primary) in &primary {
+                if let Some(found) = by_extension.get_mut(key) {
+                    found.retain(|(name, _)| name == primary);
+                }
+            }
+        }
 
-impl ColorMap<'_> {
-    pub fn query(&self, query: &str) -> BTreeMap<Cow<'_, str>, TermColor> {
-        tokenize(query)
+        let parents = self
+            .0
             .iter()
-            .copied()
-            .flat_map(|word| self.0.get(word))
-            .flatten()
-            .cloned()
-            .map(|(name, color)| (name, TermColor::from(Color::from_num(color).unwrap())))
-            .collect::<BTreeMap<_, _>>()
+            .filter_map(|(name, lang)| {
+                let group = lang.group.as_deref()?.to_ascii_lowercase();
+                let (group, parent) = self.0.get_key_value(&group)?;
+                (group != name).then(|| {
+                    (
+                        Cow::from(lang.name.as_str()),
+                        Cow::from(parent.name.as_str()),
+                    )
+                })
+            })
+            .collect();
+
+        log::debug!(
+            "indexed {} languages under {} words, {} names, {} extensions, \
+            and {} interpreters in {:?}",
+            languages.len(),
+            words.len(),
+            exact.len(),
+            by_extension.len(),
+            interpreters.len(),
+            started.elapsed()
+        );
+        Ok(ColorMap {
+            words,
+            exact,
+            extensions: by_extension,
+            interpreters,
+            filenames: by_filename,
+            languages,
+            parents,
+            inherited,
+            primary,
+        })
+    }
+
+    /// The nearest language up the `group` chain of `name` with a color of its
+    /// own, and that color. `None` if the chain ends or loops first.
+    fn group_color(&self, name: &str) -> Option<(&str, u32)> {
+        let mut seen = BTreeSet::from([name]);
+        let mut lang = self.0.get(name)?;
+        while let Some(group) = lang.group.as_deref() {
+            let (group, parent) = self.0.get_key_value(&group.to_ascii_lowercase())?;
+            if !seen.insert(group) {
+                return None;
+            }
+            if let Some(color) = parent.color() {
+                return Some((&parent.name, color));
+            }
+            lang = parent;
+        }
+        None
     }
 }
 
-#[derive(Debug)]
-pub struct TermColor(Color);
+/// Options for [`Linguist::colors_with_options`].
+///
+/// Whole names are always indexed, so that a pruned index still answers
+/// queries for exact language names the same way.
+#[derive(Debug, Clone)]
+pub struct ColorMapOptions {
+    max_entries_per_keyword: Option<usize>,
+    include_aliases: bool,
+    include_extensions: bool,
+    include_words: bool,
+    types: Option<Vec<LanguageType>>,
+    inherit_group_colors: bool,
+    primary_only: bool,
+}
 
-impl From<Color> for TermColor {
-    fn from(value: Color) -> Self {
-        Self(value)
+impl Default for ColorMapOptions {
+    fn default() -> Self {
+        Self {
+            max_entries_per_keyword: None,
+            include_aliases: true,
+            include_extensions: true,
+            include_words: true,
+            types: None,
+            inherit_group_colors: true,
+            primary_only: false,
+        }
     }
 }
 
-impl TermColor {
-    pub fn print(&self, colors: ColorSpace) -> String {
-        let color = self.0;
-        let xterm = find_nearest_color(&self.0, XTERM_COLORS.iter(), colors).unwrap();
+impl ColorMapOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        fn with_color(color: &Color, text: &str) -> colored::ColoredString {
-            text.truecolor(color.red(), color.green(), color.blue())
-        }
+    /// Drop words that match more than `max` languages. Default: no limit
+    pub fn max_entries_per_keyword(mut self, max: Option<usize>) -> Self {
+        self.max_entries_per_keyword = max;
+        self
+    }
 
-        let color_text = with_color(&color, &format!("rgb {}", color.hex_full())).bold();
-        let xterm_text = with_color(xterm.1, &format!("xterm {:<3}", xterm.0)).bold(); // <3
+    /// Index aliases. Default: true
+    pub fn include_aliases(mut self, include: bool) -> Self {
+        self.include_aliases = include;
+        self
+    }
 
-        format!("{} {}", color_text, xterm_text)
+    /// Index extensions, and the filenames and interpreters that also tell
+    /// files apart, such as `Makefile` and `python3`. Default: true
+    pub fn include_extensions(mut self, include: bool) -> Self {
+        self.include_extensions = include;
+        self
     }
-}
 
-/// See:
-///
-/// - <https://gist.github.com/jasonm23/2868981#file-xterm-256color-yaml>
-/// - <https://commons.wikimedia.org/wiki/File:Xterm_256color_chart.svg>
-static XTERM_COLORS: Lazy<Vec<Color>> = Lazy::new(|| {
-    let colors: &[u32; 256] = &[
-        0x000000, 0x800000, 0x008000, 0x808000, 0x000080, 0x800080, 0x008080, 0xc0c0c0, 0x808080,
-        0xff0000, 0x00ff00, 0xffff00, 0x0000ff, 0xff00ff, 0x00ffff, 0xffffff, 0x000000, 0x00005f,
-        0x000087, 0x0000af, 0x0000d7, 0x0000ff, 0x005f00, 0x005f5f, 0x005f87, 0x005faf, 0x005fd7,
-        0x005fff, 0x008700, 0x00875f, 0x008787, 0x0087af, 0x0087d7, 0x0087ff, 0x00af00, 0x00af5f,
-        0x00af87, 0x00afaf, 0x00afd7, 0x00afff, 0x00d700, 0x00d75f, 0x00d787, 0x00d7af, 0x00d7d7,
-        0x00d7ff, 0x00ff00, 0x00ff5f, 0x00ff87, 0x00ffaf, 0x00ffd7, 0x00ffff, 0x5f0000, 0x5f005f,
-        0x5f0087, 0x5f00af, 0x5f00d7, 0x5f00ff, 0x5f5f00, 0x5f5f5f, 0x5f5f87, 0x5f5faf, 0x5f5fd7,
-        0x5f5fff, 0x5f8700, 0x5f875f, 0x5f8787, 0x5f87af, 0x5f87d7, 0x5f87ff, 0x5faf00, 0x5faf5f,
-        0x5faf87, 0x5fafaf, 0x5fafd7, 0x5fafff, 0x5fd700, 0x5fd75f, 0x5fd787, 0x5fd7af, 0x5fd7d7,
-        0x5fd7ff, 0x5fff00, 0x5fff5f, 0x5fff87, 0x5fffaf, 0x5fffd7, 0x5fffff, 0x870000, 0x87005f,
-        0x870087, 0x8700af, 0x8700d7, 0x8700ff, 0x875f00, 0x875f5f, 0x875f87, 0x875faf, 0x875fd7,
-        0x875fff, 0x878700, 0x87875f, 0x878787, 0x8787af, 0x8787d7, 0x8787ff, 0x87af00, 0x87af5f,
-        0x87af87, 0x87afaf, 0x87afd7, 0x87afff, 0x87d700, 0x87d75f, 0x87d787, 0x87d7af, 0x87d7d7,
-        0x87d7ff, 0x87ff00, 0x87ff5f, 0x87ff87, 0x87ffaf, 0x87ffd7, 0x87ffff, 0xaf0000, 0xaf005f,
-        0xaf0087, 0xaf00af, 0xaf00d7, 0xaf00ff, 0xaf5f00, 0xaf5f5f, 0xaf5f87, 0xaf5faf, 0xaf5fd7,
-        0xaf5fff, 0xaf8700, 0xaf875f, 0xaf8787, 0xaf87af, 0xaf87d7, 0xaf87ff, 0xafaf00, 0xafaf5f,
-        0xafaf87, 0xafafaf, 0xafafd7, 0xafafff, 0xafd700, 0xafd75f, 0xafd787, 0xafd7af, 0xafd7d7,
-        0xafd7ff, 0xafff00, 0xafff5f, 0xafff87, 0xafffaf, 0xafffd7, 0xafffff, 0xd70000, 0xd7005f,
-        0xd70087, 0xd700af, 0xd700d7, 0xd700ff, 0xd75f00, 0xd75f5f, 0xd75f87, 0xd75faf, 0xd75fd7,
-        0xd75fff, 0xd78700, 0xd7875f, 0xd78787, 0xd787af, 0xd787d7, 0xd787ff, 0xd7af00, 0xd7af5f,
-        0xd7af87, 0xd7afaf, 0xd7afd7, 0xd7afff, 0xd7d700, 0xd7d75f, 0xd7d787, 0xd7d7af, 0xd7d7d7,
-        0xd7d7ff, 0xd7ff00, 0xd7ff5f, 0xd7ff87, 0xd7ffaf, 0xd7ffd7, 0xd7ffff, 0xff0000, 0xff005f,
-        0xff0087, 0xff00af, 0xff00d7, 0xff00ff, 0xff5f00, 0xff5f5f, 0xff5f87, 0xff5faf, 0xff5fd7,
-        0xff5fff, 0xff8700, 0xff875f, 0xff8787, 0xff87af, 0xff87d7, 0xff87ff, 0xffaf00, 0xffaf5f,
-        0xffaf87, 0xffafaf, 0xffafd7, 0xffafff, 0xffd700, 0xffd75f, 0xffd787, 0xffd7af, 0xffd7d7,
-        0xffd7ff, 0xffff00, 0xffff5f, 0xffff87, 0xffffaf, 0xffffd7, 0xffffff, 0x080808, 0x121212,
-        0x1c1c1c, 0x262626, 0x303030, 0x3a3a3a, 0x444444, 0x4e4e4e, 0x585858, 0x626262, 0x6c6c6c,
-        0x767676, 0x808080, 0x8a8a8a, 0x949494, 0x9e9e9e, 0xa8a8a8, 0xb2b2b2, 0xbcbcbc, 0xc6c6c6,
-        0xd0d0d0, 0xdadada, 0xe4e4e4, 0xeeeeee,
-    ];
-    colors
-        .iter()
-        .map(|c| Color::from_num(*c).unwrap())
-        .collect()
-});
+    /// Index the words of names, aliases, and extensions, for queries that
+    /// are not a whole name or alias. Without them, and without extensions,
+    /// only exact matches are found, so that `c` is only C. Default: true
+    pub fn include_words(mut self, include: bool) -> Self {
+        self.include_words = include;
+        self
+    }
 
-fn tokenize(text: &str) -> Vec<&str> {
-    RE_MATCH_WORDS.find_iter(text).map(|m| m.as_str()).collect()
-}
+    /// Only index languages of these types, so that `yml` with only
+    /// [`LanguageType::Data`] is YAML alone. Default: every language
+    pub fn types(mut self, types: Option<Vec<LanguageType>>) -> Self {
+        self.types = types;
+        self
+    }
 
-static RE_MATCH_WORDS: Lazy<Regex> = Lazy::new(|| Regex::new(r"[\pL\pN+*_#-]+").unwrap());
+    /// Index languages without a color of their own, such as Tcsh, in the
+    /// color of the nearest language up their `group` chain that has one, see
+    /// [`ColorMap::color_source`]. Default: true
+    pub fn inherit_group_colors(mut self, inherit: bool) -> Self {
+        self.inherit_group_colors = inherit;
+        self
+    }
+
+    /// Index an extension shared by several languages only under its primary
+    /// language, see [`ColorMap::primary_language`], so that `.h` finds C
+    /// alone. Default: false
+    pub fn primary_only(mut self, primary_only: bool) -> Self {
+        self.primary_only = primary_only;
+        self
+    }
+}
+
+/// Sizes of a [`ColorMap`], see [`ColorMap::stats`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexStats {
+    /// Distinct languages in the index.
+    pub languages: usize,
+    /// Distinct words.
+    pub words: usize,
+    /// Distinct whole names and aliases.
+    pub exact: usize,
+    /// Distinct extensions, without the leading dot.
+    pub extensions: usize,
+    /// Distinct interpreters.
+    pub interpreters: usize,
+    /// Languages listed under all words, whole names, extensions, and
+    /// interpreters, counting repeats.
+    pub entries: usize,
+    /// An estimate of the memory owned by the index, in bytes, counting the
+    /// allocated capacity of its tables, lists, and strings but not the
+    /// [`Linguist`] it borrows from.
+    pub heap_bytes: usize,
+    /// The words matching the most languages, most first, at most 10.
+    pub largest: Vec<(String, usize)>,
+}
+
+impl IndexStats {
+    /// Entries per word, whole name, extension, or interpreter.
+    pub fn average_entries(&self) -> f64 {
+        let keys = self.words + self.exact + self.extensions + self.interpreters;
+        self.entries as f64 / keys.max(1) as f64
+    }
+}
+
+/// A table of a [`ColorMap`], from keys to the languages they match.
+type Table<'a> = HashMap<Cow<'a, str>, Vec<(Cow<'a, str>, u32)>>;
+
+/// The version of the format of [`ColorMap::save`], bumped whenever the index
+/// changes shape, so that an index saved by another version is rebuilt. 2 has
+/// languages by their names as Linguist writes them, rather than lowercased,
+/// 3 the primary languages of extensions, and 4 filenames.
+const INDEX_VERSION: u32 = 4;
+
+/// What [`ColorMap::save`] writes.
+#[derive(Serialize)]
+struct SavedIndex<'s, 'a> {
+    version: u32,
+    words: &'s Table<'a>,
+    exact: &'s Table<'a>,
+    extensions: &'s Table<'a>,
+    interpreters: &'s Table<'a>,
+    filenames: &'s Table<'a>,
+    languages: &'s [(Cow<'a, str>, u32)],
+    parents: &'s HashMap<Cow<'a, str>, Cow<'a, str>>,
+    inherited: &'s HashMap<Cow<'a, str>, Cow<'a, str>>,
+    primary: &'s HashMap<Cow<'a, str>, Cow<'a, str>>,
+}
+
+/// What [`ColorMap::load`] reads.
+#[derive(Deserialize)]
+struct LoadedIndex {
+    version: u32,
+    words: Table<'static>,
+    exact: Table<'static>,
+    extensions: Table<'static>,
+    interpreters: Table<'static>,
+    filenames: Table<'static>,
+    languages: Vec<(Cow<'static, str>, u32)>,
+    parents: HashMap<Cow<'static, str>, Cow<'static, str>>,
+    inherited: HashMap<Cow<'static, str>, Cow<'static, str>>,
+    primary: HashMap<Cow<'static, str>, Cow<'static, str>>,
+}
+
+pub struct ColorMap<'a> {
+    /// tokenized names, aliases, and extensions
+    words: HashMap<Cow<'a, str>, Vec<(Cow<'a, str>, u32)>>,
+    /// whole names, aliases, and filenames, normalized but not tokenized, so
+    /// that symbol-heavy aliases such as `asp.net` or `f#` and filenames such
+    /// as `CMakeLists.txt` can be matched verbatim
+    exact: HashMap<Cow<'a, str>, Vec<(Cow<'a, str>, u32)>>,
+    /// extensions, lowercased and without the leading dot
+    extensions: HashMap<Cow<'a, str>, Vec<(Cow<'a, str>, u32)>>,
+    /// interpreters, such as `python3`
+    interpreters: HashMap<Cow<'a, str>, Vec<(Cow<'a, str>, u32)>>,
+    /// filenames, normalized as in `exact`, to tell them from aliases there
+    filenames: HashMap<Cow<'a, str>, Vec<(Cow<'a, str>, u32)>>,
+    /// every indexed language and its color, sorted by name
+    languages: Vec<(Cow<'a, str>, u32)>,
+    /// languages to the language named by their `group`
+    parents: HashMap<Cow<'a, str>, Cow<'a, str>>,
+    /// languages without a color to the group whose color they are shown in
+    inherited: HashMap<Cow<'a, str>, Cow<'a, str>>,
+    /// extensions shared by several languages to the primary one, see
+    /// [`ColorMap::primary_language`]
+    primary: HashMap<Cow<'a, str>, Cow<'a, str>>,
+}
+
+impl ColorMap<'static> {
+    /// Read an index written by [`ColorMap::save`]. Fails if it was saved by
+    /// a version of this crate with an index of another shape.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let in_file = |err| Error::File {
+            path: path.to_path_buf(),
+            source: Box::new(err),
+        };
+        let file = io::BufReader::new(File::open(path).map_err(|err| in_file(err.into()))?);
+        let loaded =
+            serde_json::from_reader::<_, LoadedIndex>(file).map_err(|err| in_file(err.into()))?;
+        if loaded.version != INDEX_VERSION {
+            return Err(in_file(Error::IndexVersion {
+                found: loaded.version,
+                expected: INDEX_VERSION,
+            }));
+        }
+        Ok(ColorMap {
+            words: loaded.words,
+            exact: loaded.exact,
+            extensions: loaded.extensions,
+            interpreters: loaded.interpreters,
+            filenames: loaded.filenames,
+            languages: loaded.languages,
+            parents: loaded.parents,
+            inherited: loaded.inherited,
+            primary: loaded.primary,
+        })
+    }
+
+    /// [`ColorMap::load`] the index at `path`, or if it is missing, stale, or
+    /// corrupt, build it with `build` and save it there for next time. Only an
+    /// error from `build` fails; one from saving is ignored.
+    pub fn load_or_build<P, F>(path: P, build: F) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+        F: FnOnce() -> Result<Self, Error>,
+    {
+        let path = path.as_ref();
+        match Self::load(path) {
+            Ok(colors) => {
+                log::debug!("loaded the index from {}", path.display());
+                return Ok(colors);
+            }
+            Err(err) => log::debug!("rebuilding the index at {}: {err}", path.display()),
+        }
+        let colors = build()?;
+        if let Err(err) = colors.save(path) {
+            log::debug!("failed to save the index to {}: {err}", path.display());
+        }
+        Ok(colors)
+    }
+}
+
+impl ColorMap<'_> {
+    /// Copy every name the index borrows from its [`Linguist`], for an index
+    /// that can be stored or sent to another thread on its own, such as in a
+    /// server that builds it once at startup.
+    pub fn into_owned(self) -> ColorMap<'static> {
+        fn text(text: Cow<'_, str>) -> Cow<'static, str> {
+            Cow::Owned(text.into_owned())
+        }
+        fn table(table: Table<'_>) -> Table<'static> {
+            (table.into_iter())
+                .map(|(key, entries)| {
+                    let entries = entries.into_iter().map(|(name, color)| (text(name), color));
+                    (text(key), entries.collect())
+                })
+                .collect()
+        }
+        fn names(
+            names: HashMap<Cow<'_, str>, Cow<'_, str>>,
+        ) -> HashMap<Cow<'static, str>, Cow<'static, str>> {
+            (names.into_iter())
+                .map(|(name, other)| (text(name), text(other)))
+                .collect()
+        }
+
+        ColorMap {
+            words: table(self.words),
+            exact: table(self.exact),
+            extensions: table(self.extensions),
+            interpreters: table(self.interpreters),
+            filenames: table(self.filenames),
+            languages: (self.languages.into_iter())
+                .map(|(name, color)| (text(name), color))
+                .collect(),
+            parents: names(self.parents),
+            inherited: names(self.inherited),
+            primary: names(self.primary),
+        }
+    }
+
+    /// Write the index to `path`, to be read back with [`ColorMap::load`]
+    /// without fetching or parsing `languages.yml` again.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let in_file = |err| Error::File {
+            path: path.to_path_buf(),
+            source: Box::new(err),
+        };
+        let saved = SavedIndex {
+            version: INDEX_VERSION,
+            words: &self.words,
+            exact: &self.exact,
+            extensions: &self.extensions,
+            interpreters: &self.interpreters,
+            filenames: &self.filenames,
+            languages: &self.languages,
+            parents: &self.parents,
+            inherited: &self.inherited,
+            primary: &self.primary,
+        };
+        let file = File::create(path).map_err(|err| in_file(err.into()))?;
+        serde_json::to_writer(io::BufWriter::new(file), &saved).map_err(|err| in_file(err.into()))
+    }
+
+    pub fn stats(&self) -> IndexStats {
+        let owned = |text: &Cow<'_, str>| match text {
+            Cow::Borrowed(_) => 0,
+            Cow::Owned(text) => text.capacity(),
+        };
+
+        // a hashbrown table holds a control byte per bucket besides the entry itself
+        let table = |table: &Table<'_>| -> usize {
+            let entry = std::mem::size_of::<(Cow<'_, str>, Vec<(Cow<'_, str>, u32)>)>();
+            let buckets = table.capacity() * (entry + 1);
+            let contents = table
+                .iter()
+                .map(|(key, entries)| {
+                    let list = entries.capacity() * std::mem::size_of::<(Cow<'_, str>, u32)>();
+                    owned(key) + list + entries.iter().map(|(name, _)| owned(name)).sum::<usize>()
+                })
+                .sum::<usize>();
+            buckets + contents
+        };
+
+        let parents = self.parents.capacity()
+            * (std::mem::size_of::<(Cow<'_, str>, Cow<'_, str>)>() + 1)
+            + self
+                .parents
+                .iter()
+                .map(|(k, v)| owned(k) + owned(v))
+                .sum::<usize>();
+
+        let languages = self
+            .exact
+            .values()
+            .flatten()
+            .map(|(name, _)| name)
+            .collect::<BTreeSet<_>>()
+            .len();
+
+        let mut largest = self
+            .words
+            .iter()
+            .map(|(word, entries)| (word.to_string(), entries.len()))
+            .collect::<Vec<_>>();
+        largest.sort_by(|(a, x), (b, y)| y.cmp(x).then_with(|| a.cmp(b)));
+        largest.truncate(10);
+
+        IndexStats {
+            languages,
+            words: self.words.len(),
+            exact: self.exact.len(),
+            extensions: self.extensions.len(),
+            interpreters: self.interpreters.len(),
+            entries: self
+                .words
+                .values()
+                .chain(self.exact.values())
+                .chain(self.extensions.values())
+                .chain(self.interpreters.values())
+                .map(Vec::len)
+                .sum(),
+            heap_bytes: table(&self.words)
+                + table(&self.exact)
+                + table(&self.extensions)
+                + table(&self.interpreters)
+                + table(&self.filenames)
+                + parents,
+            largest,
+        }
+    }
+
+    /// Every whole name, alias, and filename in the index, with each language
+    /// it names and its color, sorted by keyword, then by language.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, TermColor)> {
+        let mut keywords = self.exact.iter().collect::<Vec<_>>();
+        keywords.sort_unstable_by_key(|(keyword, _)| *keyword);
+        keywords.into_iter().flat_map(|(keyword, entries)| {
+            let mut entries = entries.iter().collect::<Vec<_>>();
+            entries.sort_unstable_by(|(a, _), (b, _)| by_name(a, b));
+            entries.into_iter().map(|(name, color)| {
+                let color = TermColor::from(color_from_rgb(*color));
+                (keyword.as_ref(), name.as_ref(), color)
+            })
+        })
+    }
+
+    /// The language that Linguist defaults to for `extension`, such as C for
+    /// `.h`, if several languages share it, with or without `.` or `*.`.
+    ///
+    /// It is the language that [`heuristics`] pick when no pattern matches, if
+    /// any; otherwise the one listing the extension earliest among its own,
+    /// as its primary extension if possible, then the one its rules try
+    /// first, then by name. `.m` is Objective-C by its rules, over Mercury,
+    /// which also lists `.m` first.
+    pub fn primary_language(&self, extension: &str) -> Option<&str> {
+        let key = extension_key(extension)?.to_lowercase();
+        self.primary.get(key.as_str()).map(|name| name.as_ref())
+    }
+
+    /// The language named by the `group` of the language `name`, if it is known.
+    pub fn parent(&self, name: &str) -> Option<&str> {
+        self.parents.get(name).map(|parent| parent.as_ref())
+    }
+
+    /// The `n` languages whose colors are nearest `color` with `metric`, such
+    /// as a [`ColorSpace`], nearest first, then by name, with their colors and
+    /// distances. Languages with exactly `color` come first, at distance `0.0`.
+    pub fn nearest_languages(
+        &self,
+        color: &Color,
+        n: usize,
+        metric: impl Into<Metric>,
+    ) -> Vec<(&str, Color, f64)> {
+        let metric = metric.into();
+        let mut nearest = self
+            .languages
+            .iter()
+            .map(|(name, other)| {
+                let other = color_from_rgb(*other);
+                let distance = match other.hex() == color.hex() {
+                    true => 0.0,
+                    false => metric.distance(&other, color),
+                };
+                (name.as_ref(), other, distance)
+            })
+            .collect::<Vec<_>>();
+        nearest.sort_by(|(a, _, x), (b, _, y)| x.total_cmp(y).then_with(|| by_name(a, b)));
+        nearest.truncate(n);
+        nearest
+    }
+
+    /// Languages whose name, an alias, or a filename contains `needle`,
+    /// case-insensitively, even in the middle of a word, as `noteb` in
+    /// `jupyter notebook`. Unlike [`ColorMap::ranked`], nothing is tokenized.
+    ///
+    /// Each language is listed once, for the keyword it is best found by.
+    /// Languages with a keyword starting with `needle` come first, then by
+    /// name. An empty `needle` finds nothing.
+    pub fn search(&self, needle: &str) -> Vec<SearchMatch<'_>> {
+        let needle = normalize(needle);
+        if needle.is_empty() {
+            return vec![];
+        }
+        let mut best = HashMap::<&str, SearchMatch<'_>>::new();
+        // names are keywords too, lowercased
+        let keywords = self.exact.iter().flat_map(|(keyword, entries)| {
+            entries
+                .iter()
+                .map(move |(name, color)| (keyword, name, *color))
+        });
+        for (keyword, name, color) in keywords {
+            let Some(at) = keyword.find(&needle) else {
+                continue;
+            };
+            let found = SearchMatch {
+                name: name.as_ref(),
+                color: TermColor::from(color_from_rgb(color)),
+                keyword: keyword.as_ref(),
+                prefix: at == 0,
+            };
+            // a prefix over a substring, then the name itself over an alias,
+            // then the shortest
+            fn rank<'a>(found: &SearchMatch<'a>) -> (bool, bool, usize, &'a str) {
+                let named = found.keyword == normalize(found.name);
+                (!found.prefix, !named, found.keyword.len(), found.keyword)
+            }
+            match best.get(found.name) {
+                Some(other) if rank(other) <= rank(&found) => {}
+                _ => {
+                    best.insert(found.name, found);
+                }
+            }
+        }
+        let mut found = best.into_values().collect::<Vec<_>>();
+        found.sort_by(|a, b| {
+            (!a.prefix)
+                .cmp(&!b.prefix)
+                .then_with(|| by_name(a.name, b.name))
+        });
+        found
+    }
+
+    /// The language whose color the language `name` is shown in, if it has no
+    /// color of its own. See [`ColorMapOptions::inherit_group_colors`].
+    pub fn color_source(&self, name: &str) -> Option<&str> {
+        self.inherited.get(name).map(|group| group.as_ref())
+    }
+
+    /// Find colors for languages matching `query`, sorted by name.
+    ///
+    /// If the whole query is a language name or alias, only that language is returned.
+    /// Otherwise, the query is [classified](classify::classify) and tokenized, and every
+    /// language matching any of the words is returned.
+    #[deprecated(note = "use `ColorMap::ranked`, which returns the same matches, best first")]
+    pub fn query(&self, query: &str) -> BTreeMap<Cow<'_, str>, TermColor> {
+        self.ranked(query)
+            .into_iter()
+            .map(|Ranked { name, color, .. }| (name, color))
+            .collect()
+    }
+
+    /// Find colors for languages matching `query`, best match first.
+    ///
+    /// If the whole query is a language name, alias, or filename such as `Makefile`, only
+    /// that language is returned. If it is an interpreter such as `node`, or an extension,
+    /// as in `rs`, `.rs`, or `*.rs`, every language with that interpreter or extension is
+    /// returned too, after the language it names, if any.
+    /// Otherwise, the query is [classified](classify::classify) and tokenized, and every
+    /// language matching any of the words is returned.
+    ///
+    /// An exact name or alias match outranks any word match. Word matches are
+    /// scored by how many distinct words of the query they share. A single
+    /// letter only matches a language of that name, and a dotted word such as
+    /// `node.js` is split on its dots unless it is a word of its own. Among ties,
+    /// a group's parent language comes right before its members; otherwise ties
+    /// are sorted by name.
+    ///
+    /// At the `trace` log level, every step of [`ColorMap::explain`] is logged.
+    pub fn ranked(&self, query: &str) -> Vec<Ranked<'_>> {
+        if !log::log_enabled!(log::Level::Trace) {
+            return self.rank(query, None);
+        }
+        let (ranked, events) = self.explain(query);
+        for event in events {
+            log::trace!("{query:?}: {event:?}");
+        }
+        ranked
+    }
+
+    /// [`ColorMap::ranked`] for each of `queries`, with its position among
+    /// them, in the order given. Repeated queries, as for the extensions of
+    /// many files, are only ranked once, and with the `rayon` feature, the
+    /// others on all cores.
+    pub fn ranked_many<'q, I>(&self, queries: I) -> Vec<(usize, Vec<Ranked<'_>>)>
+    where
+        I: IntoIterator<Item = &'q str>,
+    {
+        let queries = queries.into_iter().collect::<Vec<_>>();
+        let mut seen = HashSet::new();
+        let unique = (queries.iter().copied())
+            .filter(|query| seen.insert(*query))
+            .collect::<Vec<_>>();
+        #[cfg(feature = "rayon")]
+        let ranked = {
+            use rayon::prelude::*;
+            (unique.into_par_iter())
+                .map(|query| (query, self.ranked(query)))
+                .collect::<HashMap<_, _>>()
+        };
+        #[cfg(not(feature = "rayon"))]
+        let ranked = (unique.into_iter())
+            .map(|query| (query, self.ranked(query)))
+            .collect::<HashMap<_, _>>();
+        (queries.into_iter().enumerate())
+            .map(|(idx, query)| (idx, ranked[query].clone()))
+            .collect()
+    }
+
+    /// Languages whose name, alias, or extension is a few typos away from
+    /// `query` or one of its words, most similar first, then by name, at most
+    /// `limit` of them. Meant for when [`ColorMap::ranked`] finds nothing, as
+    /// for `pyton`.
+    pub fn suggest(&self, query: &str, limit: usize) -> Vec<&str> {
+        let normalized = normalize(query);
+        let words = tokenize(&normalized);
+        // about one typo for every four characters typed
+        let close = |key: &str, typed: &str| {
+            let distance = spaces::edit_distance(key, typed);
+            (distance <= (typed.chars().count() / 4).max(1)).then_some(distance)
+        };
+        let exact = self
+            .exact
+            .iter()
+            .filter_map(|(key, entries)| Some((entries, close(key, &normalized)?)));
+        let words = self.words.iter().filter_map(|(key, entries)| {
+            let distance = words.iter().filter_map(|word| close(key, word)).min()?;
+            Some((entries, distance))
+        });
+        let mut best = HashMap::<&str, usize>::new();
+        for (entries, distance) in exact.chain(words) {
+            for (name, _) in entries {
+                best.entry(name.as_ref())
+                    .and_modify(|best| *best = distance.min(*best))
+                    .or_insert(distance);
+            }
+        }
+        let mut best = best.into_iter().collect::<Vec<_>>();
+        best.sort_by(|(a, x), (b, y)| x.cmp(y).then_with(|| by_name(a, b)));
+        best.truncate(limit);
+        best.into_iter().map(|(name, _)| name).collect()
+    }
+
+    /// Like [`ColorMap::ranked`], also returning every step taken to get there.
+    pub fn explain(&self, query: &str) -> (Vec<Ranked<'_>>, Vec<ExplainEvent<'_>>) {
+        let mut events = vec![];
+        let ranked = self.rank(query, Some(&mut events));
+        (ranked, events)
+    }
+
+    fn rank<'a>(
+        &'a self,
+        query: &str,
+        mut events: Option<&mut Vec<ExplainEvent<'a>>>,
+    ) -> Vec<Ranked<'a>> {
+        let normalized = normalize(query);
+        let extension = extension_key(&normalized)
+            .and_then(|key| self.extensions.get_key_value(key))
+            .map(|(key, found)| (key.as_ref(), found));
+        let key = extension.map_or(normalized.as_str(), |(key, _)| key);
+        let primary = extension.and_then(|(key, _)| self.primary.get(key));
+        let exact = self.exact.get(key);
+        let interpreter = self.interpreters.get(normalized.as_str());
+        if let Some(events) = events.as_deref_mut() {
+            events.push(ExplainEvent::Normalized {
+                query: query.to_string(),
+                normalized: normalized.clone(),
+            });
+            events.push(ExplainEvent::ExactLookup {
+                key: key.to_string(),
+                found: exact
+                    .into_iter()
+                    .flatten()
+                    .map(|(name, _)| name.clone())
+                    .collect(),
+            });
+            if let Some(found) = interpreter {
+                events.push(ExplainEvent::InterpreterLookup {
+                    key: normalized.clone(),
+                    found: found.iter().map(|(name, _)| name.clone()).collect(),
+                });
+            }
+            if let Some((key, found)) = extension {
+                events.push(ExplainEvent::ExtensionLookup {
+                    key: key.to_string(),
+                    found: found.iter().map(|(name, _)| name.clone()).collect(),
+                });
+            }
+        }
+        let whole = |found: Option<&'a Vec<(Cow<'a, str>, u32)>>, kind| {
+            (found.into_iter().flatten()).map(move |(name, color)| (name, *color, kind, 1))
+        };
+        let pattern = name_pattern(&normalized).map(|pattern| {
+            let found = self
+                .exact
+                .iter()
+                .filter(|(key, _)| matches_pattern(pattern, key))
+                .flat_map(|(_, found)| found)
+                .collect::<Vec<_>>();
+            (pattern, found)
+        });
+        if let (Some(events), Some((pattern, found))) = (events.as_deref_mut(), &pattern) {
+            events.push(ExplainEvent::PatternLookup {
+                pattern: pattern.to_string(),
+                found: found.iter().map(|(name, _)| name.clone()).collect(),
+            });
+        }
+        let mut ranked = match (exact, interpreter, extension) {
+            (None, None, None) if pattern.as_ref().is_some_and(|(_, found)| !found.is_empty()) => {
+                let (_, found) = pattern.unwrap();
+                found
+                    .into_iter()
+                    .map(|(name, color)| (name, *color, MatchKind::Pattern, 1))
+                    .collect()
+            }
+            (None, None, None) => {
+                let (shape, text) = classify(query);
+                if let Some(events) = events.as_deref_mut() {
+                    events.push(ExplainEvent::Classified {
+                        shape,
+                        text: text.clone(),
+                    });
+                }
+                let words = self.words_of(&text);
+                if let Some(events) = events.as_deref_mut() {
+                    events.push(ExplainEvent::Tokenized {
+                        words: words.iter().map(|w| w.to_string()).collect(),
+                    });
+                }
+                let mut scores = BTreeMap::<&Cow<'_, str>, (u32, usize)>::new();
+                for word in words {
+                    let mut seen = BTreeSet::new();
+                    for (name, color) in self.word_index(word).get(word).into_iter().flatten() {
+                        if seen.insert(name) {
+                            scores.entry(name).or_insert((*color, 0)).1 += 1;
+                        }
+                    }
+                    if let Some(events) = events.as_deref_mut() {
+                        events.push(ExplainEvent::WordLookup {
+                            word: word.to_string(),
+                            found: seen.into_iter().cloned().collect(),
+                        });
+                    }
+                }
+                scores
+                    .into_iter()
+                    .map(|(name, (color, score))| (name, color, MatchKind::Words, score))
+                    .collect::<Vec<_>>()
+            }
+            (exact, interpreter, extension) => whole(exact, MatchKind::Exact)
+                .chain(whole(interpreter, MatchKind::Interpreter))
+                .chain(whole(
+                    extension.map(|(_, found)| found),
+                    MatchKind::Extension,
+                ))
+                .collect(),
+        };
+        let family = |name: &'a Cow<'a, str>| match self.parents.get(name) {
+            Some(parent) => (parent, true, name),
+            None => (name, false, name),
+        };
+        let by_family = |a, b| {
+            let ((a_parent, a_member, a), (b_parent, b_member, b)) = (family(a), family(b));
+            by_name(a_parent, b_parent)
+                .then(a_member.cmp(&b_member))
+                .then_with(|| by_name(a, b))
+        };
+        // the primary language of a shared extension comes first among equals
+        let is_primary = |name: &Cow<'a, str>| primary == Some(name);
+        ranked.sort_by(|a, b| {
+            let by_kind = a.2.cmp(&b.2);
+            by_kind
+                .then_with(|| b.3.cmp(&a.3))
+                .then_with(|| is_primary(b.0).cmp(&is_primary(a.0)))
+                .then_with(|| by_family(a.0, b.0))
+        });
+        // a language named by the query is also found by its extension
+        let mut seen = BTreeSet::new();
+        ranked.retain(|(name, ..)| seen.insert(*name));
+        if let Some(events) = events {
+            for &(name, _, kind, score) in ranked.iter() {
+                events.push(ExplainEvent::Ranked {
+                    name: name.clone(),
+                    kind,
+                    score,
+                });
+            }
+        }
+        ranked
+            .into_iter()
+            .map(|(name, color, kind, score)| Ranked {
+                name: name.clone(),
+                color: TermColor::from(color_from_rgb(color)),
+                kind,
+                score,
+                primary: primary == Some(name),
+            })
+            .collect()
+    }
+
+    /// The distinct words of a [classified](classify::classify) query, as
+    /// [`ColorMap::ranked`] looks them up.
+    fn words_of<'q>(&self, text: &'q str) -> Vec<&'q str> {
+        // a dotted word that is not itself indexed, such as `node.js`, is
+        // looked up by its pieces
+        let mut words = tokenize(text)
+            .into_iter()
+            .flat_map(|word| match self.words.contains_key(word) {
+                true => vec![word],
+                false => std::iter::once(word).chain(dotted_parts(word)).collect(),
+            })
+            .collect::<Vec<_>>();
+        words.sort_unstable();
+        words.dedup();
+        words
+    }
+
+    /// Where to look up one word of a query.
+    fn word_index(&self, word: &str) -> &Table<'_> {
+        // a single letter such as the `c` of `c language` names C, not every
+        // language with a `c` in it
+        match word.chars().count() == 1 {
+            true => &self.exact,
+            false => &self.words,
+        }
+    }
+
+    /// Like [`ColorMap::ranked`], with how each language matched `query`, as
+    /// [`ColorMap::provenance`] has it: a language found by several keywords
+    /// is listed once for each, such as Python for `py`, which is both its
+    /// alias and its extension.
+    pub fn query_detailed(&self, query: &str) -> Vec<QueryMatch<'_>> {
+        (self.ranked(query).iter())
+            .flat_map(|ranked| self.provenance(query, &ranked.name))
+            .collect()
+    }
+
+    /// How the language `name` matched `query` in [`ColorMap::ranked`]: every
+    /// keyword of the language that is the query, or one of its words, best
+    /// first. Empty if the language is not found for `query`.
+    pub fn provenance(&self, query: &str, name: &str) -> Vec<QueryMatch<'_>> {
+        let normalized = normalize(query);
+        let extension =
+            extension_key(&normalized).and_then(|key| self.extensions.get_key_value(key));
+        let key = extension.map_or(normalized.as_str(), |(key, _)| key);
+        let exact = self.exact.get_key_value(key);
+        let interpreter = self.interpreters.get_key_value(normalized.as_str());
+
+        // every keyword looked up, in the order they rank
+        let mut looked_up = vec![];
+        if exact.is_some() || interpreter.is_some() || extension.is_some() {
+            looked_up.extend(exact.map(|found| (found, MatchKind::Exact)));
+            looked_up.extend(interpreter.map(|found| (found, MatchKind::Interpreter)));
+            looked_up.extend(extension.map(|found| (found, MatchKind::Extension)));
+        } else if let Some(pattern) = name_pattern(&normalized)
+            .filter(|pattern| self.exact.keys().any(|key| matches_pattern(pattern, key)))
+        {
+            let mut found = (self.exact.iter())
+                .filter(|(key, _)| matches_pattern(pattern, key))
+                .map(|found| (found, MatchKind::Pattern))
+                .collect::<Vec<_>>();
+            found.sort_unstable_by_key(|((key, _), _)| *key);
+            looked_up.extend(found);
+        } else {
+            let (_, text) = classify(query);
+            for word in self.words_of(&text) {
+                let found = self.word_index(word).get_key_value(word);
+                looked_up.extend(found.map(|found| (found, MatchKind::Words)));
+            }
+        }
+        (looked_up.into_iter())
+            .filter_map(|((keyword, found), kind)| {
+                let (name, color) = found.iter().find(|(other, _)| other == name)?;
+                let via = match kind {
+                    MatchKind::Exact | MatchKind::Pattern => self.exact_via(keyword, name),
+                    MatchKind::Interpreter => MatchVia::Interpreter,
+                    MatchKind::Extension => MatchVia::Extension,
+                    MatchKind::Words => MatchVia::Word,
+                };
+                Some(QueryMatch {
+                    name: name.clone(),
+                    color: TermColor::from(color_from_rgb(*color)),
+                    kind,
+                    via,
+                    keyword: keyword.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Whether `keyword`, under which `name` is in `exact`, is its name, an
+    /// alias, or a filename.
+    fn exact_via(&self, keyword: &str, name: &str) -> MatchVia {
+        let is_filename = (self.filenames.get(keyword))
+            .is_some_and(|found| found.iter().any(|(other, _)| other == name));
+        match () {
+            _ if keyword == normalize(name) || keyword == slugify(name) => MatchVia::Name,
+            _ if is_filename => MatchVia::Filename,
+            _ => MatchVia::Alias,
+        }
+    }
+}
+
+/// A step taken by [`ColorMap::explain`], in the order they happen.
+#[derive(Debug, Clone)]
+pub enum ExplainEvent<'a> {
+    /// The query was normalized for exact lookup.
+    Normalized { query: String, normalized: String },
+    /// The normalized query was looked up among names and aliases.
+    ExactLookup {
+        key: String,
+        found: Vec<Cow<'a, str>>,
+    },
+    /// The normalized query was a pattern such as `objective-*`, matched
+    /// against whole names, aliases, and filenames.
+    PatternLookup {
+        pattern: String,
+        found: Vec<Cow<'a, str>>,
+    },
+    /// The normalized query was looked up among interpreters.
+    InterpreterLookup {
+        key: String,
+        found: Vec<Cow<'a, str>>,
+    },
+    /// The normalized query was an extension, with or without `.` or `*.`,
+    /// which was looked up among extensions.
+    ExtensionLookup {
+        key: String,
+        found: Vec<Cow<'a, str>>,
+    },
+    /// There was no exact match, so the query was classified and possibly
+    /// reduced to `text`.
+    Classified { shape: QueryShape, text: String },
+    /// The classified query was split into distinct words.
+    Tokenized { words: Vec<String> },
+    /// A word was looked up among tokenized names, aliases, and extensions.
+    WordLookup {
+        word: String,
+        found: Vec<Cow<'a, str>>,
+    },
+    /// A candidate's final rank, emitted once per candidate in ranked order.
+    Ranked {
+        name: Cow<'a, str>,
+        kind: MatchKind,
+        score: usize,
+    },
+}
+
+/// How a language matched a query, best first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum MatchKind {
+    /// The whole query is the language's name, one of its aliases, or one of
+    /// its filenames, such as `Makefile`.
+    Exact,
+    /// The whole query is one of the language's interpreters, such as `node`.
+    Interpreter,
+    /// The whole query is one of the language's extensions, such as `.rs`.
+    Extension,
+    /// The query is a pattern such as `objective-*` matching the language's
+    /// name, one of its aliases, or one of its filenames.
+    Pattern,
+    /// Some words of the query appear in the language's name, aliases, or extensions.
+    Words,
+}
+
+/// Which keyword of a language matched a query, see [`QueryMatch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum MatchVia {
+    /// The language's name, or its slug, such as `c-sharp`.
+    Name,
+    /// One of its aliases.
+    Alias,
+    /// One of its filenames, such as `Makefile`.
+    Filename,
+    /// One of its interpreters, such as `node`.
+    Interpreter,
+    /// One of its extensions, without the leading dot.
+    Extension,
+    /// A word of its name, aliases, or extensions, for [`MatchKind::Words`].
+    Word,
+}
+
+impl MatchVia {
+    /// The lowercase name, e.g. `alias`.
+    pub fn name(self) -> &'static str {
+        match self {
+            MatchVia::Name => "name",
+            MatchVia::Alias => "alias",
+            MatchVia::Filename => "filename",
+            MatchVia::Interpreter => "interpreter",
+            MatchVia::Extension => "extension",
+            MatchVia::Word => "word",
+        }
+    }
+}
+
+/// One way a language matched a query, as returned by
+/// [`ColorMap::query_detailed`] and [`ColorMap::provenance`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct QueryMatch<'a> {
+    pub name: Cow<'a, str>,
+    pub color: TermColor,
+    /// How the language ranks for the query, as in [`Ranked::kind`].
+    pub kind: MatchKind,
+    pub via: MatchVia,
+    /// The keyword that matched, as indexed: lowercased, and for extensions
+    /// without the leading dot, such as `py`.
+    pub keyword: Cow<'a, str>,
+}
+
+/// A language found by [`ColorMap::search`].
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SearchMatch<'a> {
+    pub name: &'a str,
+    pub color: TermColor,
+    /// The name, alias, or filename containing the needle, lowercased.
+    pub keyword: &'a str,
+    /// Whether `keyword` starts with the needle, rather than only containing it.
+    pub prefix: bool,
+}
+
+/// A language matching a query, as returned by [`ColorMap::ranked`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Ranked<'a> {
+    pub name: Cow<'a, str>,
+    pub color: TermColor,
+    pub kind: MatchKind,
+    /// For [`MatchKind::Words`], the number of distinct query words matched.
+    pub score: usize,
+    /// Whether the query is an extension that several languages share, of
+    /// which this is the [primary](ColorMap::primary_language) one, even if
+    /// the language was found by name, such as Rust for `rs`.
+    pub primary: bool,
+}
+
+impl Ranked<'_> {
+    /// The same match with its name copied, so that it can outlive the
+    /// [`ColorMap`] it came from.
+    pub fn into_owned(self) -> Ranked<'static> {
+        Ranked {
+            name: Cow::Owned(self.name.into_owned()),
+            color: self.color,
+            kind: self.kind,
+            score: self.score,
+            primary: self.primary,
+        }
+    }
+
+    /// Whether `self` and `other` are equally good matches.
+    pub fn ties(&self, other: &Ranked<'_>) -> bool {
+        self.kind == other.kind && self.score == other.score
+    }
+}
+
+/// A color as rendered with some [`RenderOptions`], from
+/// [`TermColor::resolve`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Resolved {
+    /// The color itself, such as Linguist's.
+    pub original: Color,
+    /// After [`RenderOptions::adjust`].
+    pub adjusted: Color,
+    /// After [`RenderOptions::ensure_contrast`] too: the color shown.
+    pub shown: Color,
+    /// The nearest color of [`RenderOptions::palette`] to `shown`.
+    pub nearest: XtermMatch,
+    /// With [`RenderOptions::simulate`], `shown` as seen with the deficiency,
+    /// and the nearest color to that.
+    pub simulated: Option<(Cvd, Color, XtermMatch)>,
+}
+
+impl Resolved {
+    /// The CIEDE2000 difference between `shown` and `nearest`, which
+    /// [`RenderOptions::warn_delta`] is compared with.
+    pub fn delta_e(&self) -> f64 {
+        delta_e2000(&self.shown, &self.nearest.color)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TermColor(Color);
+
+impl From<Color> for TermColor {
+    fn from(value: Color) -> Self {
+        Self(value)
+    }
+}
+
+/// Parse, in any case:
+///
+/// - hex: `#dea584`, `dea584`, or `#abc`
+/// - `rgb()` or `rgba()` with channels from 0 to 255 or percentages:
+///   `rgb(222, 165, 132)` or `rgb(87% 65% 52%)`
+/// - `hsl()` or `hsla()` with the hue in degrees: `hsl(22deg 58% 69%)`
+/// - a CSS color name: `rebeccapurple`
+///
+/// Both the comma-separated and the space-separated CSS syntax work. An
+/// alpha, as in `rgba(222, 165, 132, 0.5)` or `rgb(222 165 132 / 50%)`, must
+/// be valid but is ignored, since terminal colors are opaque.
+impl FromStr for TermColor {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Error> {
+        let unrecognized = || Error::ColorNotation(text.to_string());
+        let text = text.trim().to_ascii_lowercase();
+
+        if let Ok(color) = parse_hex_color(&text) {
+            return Ok(TermColor(color));
+        }
+        if let Some((name, args)) = color_function(&text) {
+            let color = match (name, args) {
+                ("rgb" | "rgba", [r, g, b]) => {
+                    let channel = |c: &str| match c.strip_suffix('%') {
+                        Some(c) => percent(c).map(|c| c * 255.0),
+                        None => c.parse::<f64>().ok().filter(|c| (0.0..=255.0).contains(c)),
+                    };
+                    let (Some(r), Some(g), Some(b)) = (channel(r), channel(g), channel(b)) else {
+                        return Err(unrecognized());
+                    };
+                    let [r, g, b] = [r, g, b].map(|c| c.round() as u8);
+                    Color::new(r, g, b, 1.0)
+                }
+                ("hsl" | "hsla", [h, s, l]) => {
+                    let h = h.strip_suffix("deg").unwrap_or(h).parse::<f64>().ok();
+                    let [s, l] = [s, l].map(|c| c.strip_suffix('%').and_then(percent));
+                    let (Some(h), Some(s), Some(l)) = (h.filter(|h| h.is_finite()), s, l) else {
+                        return Err(unrecognized());
+                    };
+                    color_math::from_hsl([h, s, l])
+                }
+                _ => return Err(unrecognized()),
+            };
+            return Ok(TermColor(color));
+        }
+        // color_art also knows Chinese color names, which are not CSS
+        if text.bytes().all(|c| c.is_ascii_lowercase()) {
+            if let Ok(color) = Color::from_name(&text) {
+                return Ok(TermColor(color));
+            }
+        }
+        Err(unrecognized())
+    }
+}
+
+/// The name and the three main arguments of a CSS color function such as
+/// `rgb(222, 165, 132)` or `rgb(222 165 132 / 50%)`, checking and dropping
+/// the alpha.
+fn color_function(text: &str) -> Option<(&str, [&str; 3])> {
+    let (name, args) = text.strip_suffix(')')?.split_once('(')?;
+    let (args, alpha) = match args.contains(',') {
+        true => {
+            let mut args = args.split(',').map(str::trim).collect::<Vec<_>>();
+            let alpha = (args.len() == 4).then(|| args.pop()).flatten();
+            (args, alpha)
+        }
+        false => {
+            let (args, alpha) = match args.split_once('/') {
+                Some((args, alpha)) => (args, Some(alpha.trim())),
+                None => (args, None),
+            };
+            (args.split_whitespace().collect(), alpha)
+        }
+    };
+    if let Some(alpha) = alpha {
+        let valid = match alpha.strip_suffix('%') {
+            Some(alpha) => percent(alpha).is_some(),
+            None => alpha.parse::<f64>().is_ok_and(|a| (0.0..=1.0).contains(&a)),
+        };
+        if !valid {
+            return None;
+        }
+    }
+    Some((name.trim(), <[&str; 3]>::try_from(args).ok()?))
+}
+
+/// A percentage without its `%`, from 0 to 1.
+fn percent(text: &str) -> Option<f64> {
+    let percent = text.parse::<f64>().ok()?;
+    (0.0..=100.0).contains(&percent).then_some(percent / 100.0)
+}
+
+/// The plain `rgb #dea584 xterm 180` form, never colorized, with the nearest
+/// xterm color found in RGB. See [`TermColor::render`] for anything else.
+impl fmt::Display for TermColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let xterm = self.xterm_index(ColorSpace::RGB);
+        write!(f, "rgb {} xterm {xterm}", self.hex())
+    }
+}
+
+/// As `{"hex": "#dea584", "rgb": [222, 165, 132]}`. Requires the `serde` feature.
+#[cfg(feature = "serde")]
+impl serde::Serialize for TermColor {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let (r, g, b) = self.rgb();
+        let mut color = serializer.serialize_struct("TermColor", 2)?;
+        color.serialize_field("hex", &self.hex())?;
+        color.serialize_field("rgb", &[r, g, b])?;
+        color.end()
+    }
+}
+
+/// From a hex string such as `"#dea584"`, or from what [`TermColor`]
+/// serializes to. Requires the `serde` feature.
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for TermColor {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Hex(String),
+            Object { hex: String },
+        }
+
+        let (Repr::Hex(hex) | Repr::Object { hex }) = Repr::deserialize(deserializer)?;
+        match parse_hex_color(&hex) {
+            Ok(color) => Ok(TermColor(color)),
+            Err(err) => Err(serde::de::Error::custom(err)),
+        }
+    }
+}
+
+impl TermColor {
+    /// Equivalent to [`TermColor::render`] with default options in the given color space.
+    #[deprecated(note = "use `TermColor::render` with `RenderOptions::new().color_space(colors)`")]
+    pub fn print(&self, colors: ColorSpace) -> String {
+        self.render(&RenderOptions::new().color_space(colors))
+    }
+
+    pub fn render(&self, options: &RenderOptions) -> String {
+        let mut text = String::new();
+        self.render_to(&mut text, options).unwrap();
+        text
+    }
+
+    /// Like [`TermColor::render`], but write into `w` instead of allocating a [`String`].
+    pub fn render_to<W: fmt::Write>(&self, w: &mut W, options: &RenderOptions) -> fmt::Result {
+        let resolved = self.resolve(options);
+        let (color, xterm) = (resolved.shown, resolved.nearest);
+
+        let with_color = |w: &mut dyn fmt::Write,
+                          (color, xterm): (&Color, &XtermMatch),
+                          text: fmt::Arguments<'_>|
+         -> fmt::Result {
+            let colorize = colored::control::SHOULD_COLORIZE.should_colorize();
+            if colorize {
+                write_sgr(w, (xterm.index.into(), color), &xterm.color, options)?;
+            }
+            if options.swatch {
+                match options.layer {
+                    Layer::Foreground => w.write_str("██ ")?,
+                    Layer::Background => w.write_str("   ")?,
+                }
+            }
+            w.write_fmt(text)?;
+            if colorize {
+                w.write_str("\x1b[0m")?;
+            }
+            Ok(())
+        };
+
+        let hex = |w: &mut dyn fmt::Write, color: &Color, xterm: &XtermMatch| {
+            let (r, g, b) = (color.red(), color.green(), color.blue());
+            let shown = (color, xterm);
+            match options.hex_format {
+                HexFormat::Lower => {
+                    with_color(w, shown, format_args!("rgb #{r:02x}{g:02x}{b:02x}"))
+                }
+                HexFormat::Upper => {
+                    with_color(w, shown, format_args!("rgb #{r:02X}{g:02X}{b:02X}"))
+                }
+            }
+        };
+        let checked_hex = |w: &mut dyn fmt::Write, color: &Color, xterm: &XtermMatch| {
+            if options.assert_consistency {
+                let mut segment = String::new();
+                hex(&mut segment, color, xterm)?;
+                assert_consistent(&segment, options);
+                w.write_str(&segment)
+            } else {
+                hex(w, color, xterm)
+            }
+        };
+        // the color as in Linguist, as shifted, then as adjusted for contrast
+        let adjusted = resolved.adjusted;
+        if options.show_unadjusted && adjusted != self.0 {
+            let original = nearest_match(&self.0, options.palette, options.color_space);
+            checked_hex(w, &self.0, &original)?;
+            w.write_str(" → ")?;
+        }
+        if color != adjusted {
+            let original = nearest_match(&adjusted, options.palette, options.color_space);
+            checked_hex(w, &adjusted, &original)?;
+            w.write_str(" → ")?;
+        }
+        let with_label = |w: &mut dyn fmt::Write, color: &Color, xterm: &XtermMatch| {
+            checked_hex(w, color, xterm)?;
+            w.write_char(' ')?;
+            let label = Label(options.palette, xterm.index);
+            match options.palette {
+                Palette::Named(_) => {
+                    let theme = &xterm.color;
+                    let (r, g, b) = (theme.red(), theme.green(), theme.blue());
+                    let code = match options.hex_format {
+                        HexFormat::Lower => format!("#{r:02x}{g:02x}{b:02x}"),
+                        HexFormat::Upper => format!("#{r:02X}{g:02X}{b:02X}"),
+                    };
+                    with_color(w, (theme, xterm), format_args!("{label} ({code})"))
+                }
+                _ => with_color(w, (&xterm.color, xterm), format_args!("{label}")), // <3
+            }
+        };
+        with_label(w, &color, &xterm)?;
+
+        if options.show_distance {
+            match colored::control::SHOULD_COLORIZE.should_colorize() {
+                true => write!(
+                    w,
+                    " \x1b[2mΔ{}\x1b[0m",
+                    Precision::Distance.fixed(xterm.distance)
+                )?,
+                false => write!(w, " Δ{}", Precision::Distance.fixed(xterm.distance))?,
+            }
+        }
+
+        if let Some(threshold) = options.warn_delta {
+            let delta = resolved.delta_e();
+            if delta > threshold {
+                let delta = Precision::DeltaE.fixed(delta);
+                match colored::control::SHOULD_COLORIZE.should_colorize() {
+                    true => write!(w, " \x1b[33m⚠ Δ{delta}\x1b[0m")?,
+                    false => write!(w, " ⚠ Δ{delta}")?,
+                }
+            }
+        }
+
+        if let Some((kind, simulated, xterm)) = resolved.simulated {
+            write!(w, " → {} ", kind.name())?;
+            with_label(w, &simulated, &xterm)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write `text` in this color, or as is if colors are disabled.
+    ///
+    /// Only [`RenderOptions::color_space`], [`RenderOptions::depth`], and
+    /// [`RenderOptions::layer`] apply.
+    pub fn paint_to<W: fmt::Write>(
+        &self,
+        w: &mut W,
+        text: &str,
+        options: &RenderOptions,
+    ) -> fmt::Result {
+        if !colored::control::SHOULD_COLORIZE.should_colorize() {
+            return w.write_str(text);
+        }
+        w.write_str(&self.sgr(options))?;
+        w.write_str(text)?;
+        w.write_str("\x1b[0m")
+    }
+
+    /// The escape sequence that turns on this color, whether or not colors are
+    /// enabled, for embedding elsewhere. Reset with `\x1b[0m`.
+    pub fn sgr(&self, options: &RenderOptions) -> String {
+        let color = options.readable(self).0;
+        let xterm = nearest_match(&color, options.palette, options.color_space);
+        let mut sgr = String::new();
+        write_sgr(
+            &mut sgr,
+            (xterm.index.into(), &color),
+            &xterm.color,
+            options,
+        )
+        .unwrap();
+        sgr
+    }
+
+    /// The escape sequence setting the text color to the nearest xterm color
+    /// with `colors`, a [`Metric`] or [`ColorSpace`], e.g. `\x1b[38;5;61m`.
+    /// Unlike [`TermColor::sgr`], it sets nothing else, such as bold.
+    pub fn ansi_fg_256(&self, colors: impl Into<Metric>) -> String {
+        format!("\x1b[38;5;{}m", self.xterm_index(colors))
+    }
+
+    /// The escape sequence setting the text color to this color, e.g.
+    /// `\x1b[38;2;53;114;165m`.
+    pub fn ansi_fg_truecolor(&self) -> String {
+        let (r, g, b) = self.rgb();
+        format!("\x1b[38;2;{r};{g};{b}m")
+    }
+
+    /// Like [`TermColor::ansi_fg_256`], for the background: `\x1b[48;5;61m`.
+    pub fn ansi_bg_256(&self, colors: impl Into<Metric>) -> String {
+        format!("\x1b[48;5;{}m", self.xterm_index(colors))
+    }
+
+    /// Like [`TermColor::ansi_fg_truecolor`], for the background:
+    /// `\x1b[48;2;53;114;165m`.
+    pub fn ansi_bg_truecolor(&self) -> String {
+        let (r, g, b) = self.rgb();
+        format!("\x1b[48;2;{r};{g};{b}m")
+    }
+
+    /// The CIEDE2000 difference between the color, as rendered with `options`,
+    /// and its nearest xterm color, which is found with the metric set there.
+    pub fn approximation_delta(&self, options: &RenderOptions) -> f64 {
+        self.resolve(options).delta_e()
+    }
+
+    /// The colors that [`TermColor::render_to`] shows for this one with
+    /// `options`, for output in other formats that should agree with it.
+    pub fn resolve(&self, options: &RenderOptions) -> Resolved {
+        let adjusted = options.adjustment.apply(self).0;
+        let shown = options.readable(self).0;
+        let nearest = nearest_match(&shown, options.palette, options.color_space);
+        let simulated = options.simulate.map(|kind| {
+            let simulated = color_math::simulate_cvd(&shown, kind);
+            let nearest = nearest_match(&simulated, options.palette, options.color_space);
+            (kind, simulated, nearest)
+        });
+        Resolved {
+            original: self.0,
+            adjusted,
+            shown,
+            nearest,
+            simulated,
+        }
+    }
+
+    /// Whether [`TermColor::approximation_delta`] exceeds the threshold set with
+    /// [`RenderOptions::warn_delta`]. Always `false` if no threshold is set.
+    pub fn is_poor_approximation(&self, options: &RenderOptions) -> bool {
+        options
+            .warn_delta
+            .is_some_and(|threshold| self.approximation_delta(options) > threshold)
+    }
+
+    pub fn color(&self) -> Color {
+        self.0
+    }
+
+    /// The color in lowercase hex notation, e.g. `#dea584`.
+    pub fn hex(&self) -> String {
+        let (r, g, b) = (self.0.red(), self.0.green(), self.0.blue());
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        (self.0.red(), self.0.green(), self.0.blue())
+    }
+
+    /// Hue in degrees, then saturation and lightness in `0.0..=1.0`.
+    pub fn hsl(&self) -> [f64; 3] {
+        color_math::hsl(&self.0)
+    }
+
+    /// The [WCAG contrast ratio] between this color and `other`, from 1 for
+    /// the same luminance to 21 for black and white.
+    ///
+    /// [WCAG contrast ratio]: https://www.w3.org/TR/WCAG21/#dfn-contrast-ratio
+    pub fn contrast_ratio(&self, other: &Color) -> f64 {
+        color_math::contrast_ratio(&self.0, other)
+    }
+
+    /// Black or white, whichever text is easier to read on this color as the
+    /// background: black if its relative luminance is above about 0.18, where
+    /// both have the same contrast ratio with it. This is the text color of
+    /// [`Layer::Background`].
+    pub fn best_text_on(&self) -> Color {
+        *readable_on(&self.0, Palette::Xterm256).1
+    }
+
+    /// This color if its [`TermColor::contrast_ratio`] with `background` is
+    /// at least `min_ratio`, such as 4.5 for WCAG AA; otherwise the color of
+    /// the same hue and saturation that is, with the least change in
+    /// lightness. If no lightness is, black or white, whichever contrasts more.
+    pub fn ensure_contrast(&self, background: &Color, min_ratio: f64) -> TermColor {
+        let contrast = |color: &Color| color_math::contrast_ratio(color, background);
+        if contrast(&self.0) >= min_ratio {
+            return *self;
+        }
+        let [h, s, l] = self.hsl();
+        let at = |l: f64| color_math::from_hsl([h, s, l]);
+        // every channel grows with lightness, so going one way, the contrast
+        // may first drop to 1 at the luminance of the background, but once
+        // it reaches `min_ratio` it stays there
+        let toward = |end: f64| -> Option<f64> {
+            if contrast(&at(end)) < min_ratio {
+                return None;
+            }
+            let (mut near, mut far) = (l, end);
+            for _ in 0..24 {
+                let mid = (near + far) / 2.0;
+                match contrast(&at(mid)) >= min_ratio {
+                    true => far = mid,
+                    false => near = mid,
+                }
+            }
+            Some(far)
+        };
+        let lightness = match (toward(0.0), toward(1.0)) {
+            (Some(darker), Some(lighter)) => match l - darker <= lighter - l {
+                true => darker,
+                false => lighter,
+            },
+            (Some(darker), None) => darker,
+            (None, Some(lighter)) => lighter,
+            (None, None) => match contrast(&at(0.0)) >= contrast(&at(1.0)) {
+                true => 0.0,
+                false => 1.0,
+            },
+        };
+        TermColor(at(lightness))
+    }
+
+    /// The index and color of the nearest color in `palette`, found with
+    /// `colors`, a [`Metric`] or [`ColorSpace`].
+    pub fn nearest_in(&self, palette: Palette, colors: impl Into<Metric>) -> (usize, TermColor) {
+        let nearest = nearest_match(&self.0, palette, colors.into());
+        (nearest.index.into(), TermColor(nearest.color))
+    }
+
+    /// This color with its HSL lightness raised by `percent` percentage
+    /// points, like Sass's `lighten`, up to white. Negative `percent` darkens.
+    pub fn lighten(&self, percent: f64) -> TermColor {
+        self.shift_hsl(1, percent)
+    }
+
+    /// This color with its HSL lightness lowered by `percent` percentage
+    /// points, down to black, the inverse of [`TermColor::lighten`].
+    pub fn darken(&self, percent: f64) -> TermColor {
+        self.shift_hsl(1, -percent)
+    }
+
+    /// This color with its HSL saturation raised by `percent` percentage
+    /// points, up to full saturation. Negative `percent` desaturates.
+    pub fn saturate(&self, percent: f64) -> TermColor {
+        self.shift_hsl(0, percent)
+    }
+
+    /// Add `percent` percentage points to saturation or lightness, at
+    /// `channel` 0 or 1 of those, clamped to the gamut.
+    fn shift_hsl(&self, channel: usize, percent: f64) -> TermColor {
+        if percent == 0.0 {
+            // converting back and forth may round
+            return *self;
+        }
+        let mut hsl = self.hsl();
+        hsl[channel + 1] = (hsl[channel + 1] + percent / 100.0).clamp(0.0, 1.0);
+        TermColor(color_math::from_hsl(hsl))
+    }
+
+    /// How this color looks with `kind`, see [`color_math::simulate_cvd`].
+    pub fn simulate_cvd(&self, kind: Cvd) -> TermColor {
+        TermColor(color_math::simulate_cvd(&self.0, kind))
+    }
+
+    /// The nearest xterm color with `metric`, such as a [`ColorSpace`], with
+    /// its index and distance, as shown by [`TermColor::render`].
+    pub fn nearest_xterm(&self, metric: impl Into<Metric>) -> XtermMatch {
+        nearest_xterm(&self.0, metric)
+    }
+
+    /// The index of the nearest xterm color with `colors`, a [`Metric`] or
+    /// [`ColorSpace`].
+    pub fn xterm_index(&self, colors: impl Into<Metric>) -> usize {
+        self.nearest_xterm(colors).index.into()
+    }
+
+    /// The nearest xterm color itself, as found by [`TermColor::xterm_index`].
+    pub fn xterm_color(&self, colors: impl Into<Metric>) -> TermColor {
+        TermColor(self.nearest_xterm(colors).color)
+    }
+
+    /// A [`fmt::Display`] of this color, rendered with `options`.
+    pub fn display<'a>(&'a self, options: &'a RenderOptions) -> Rendered<'a> {
+        Rendered {
+            color: self,
+            options,
+        }
+    }
+}
+
+/// See [`TermColor::display`].
+pub struct Rendered<'a> {
+    color: &'a TermColor,
+    options: &'a RenderOptions,
+}
+
+impl fmt::Display for Rendered<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.color.render_to(f, self.options)
+    }
+}
+
+/// How many colors the terminal can display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Depth {
+    /// 24-bit colors, the exact Linguist color is shown.
+    #[default]
+    TrueColor,
+    /// 256 colors, everything is shown in the nearest xterm color.
+    Xterm256,
+}
+
+/// The colors to find the nearest of, for [`Depth::Xterm256`] and the `xterm`
+/// part of rendered colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Palette {
+    /// All 256 xterm colors, rendered as `xterm 180`.
+    #[default]
+    Xterm256,
+    /// The 16 standard ANSI colors, which are the first 16 xterm colors,
+    /// rendered as `ansi 3`. For terminals and log viewers without 256 colors.
+    Ansi16,
+    /// The xterm colors from `first` to `last`, which is not less than
+    /// `first`, rendered as `xterm 180`. See [`Palette::xterm_without`].
+    XtermRange { first: u8, last: u8 },
+    /// The colors of a terminal theme, such as
+    /// [`NamedPalette::solarized_dark`], rendered by their names in the
+    /// theme and their hex codes, such as `theme yellow (#b58900)`. Not empty.
+    Named(&'static NamedPalette),
+}
+
+impl Palette {
+    /// The xterm colors without the 16 system colors, which terminal themes
+    /// redefine, if `system` is set, and without the grayscale ramp, 232 to
+    /// 255, if `grayscale` is set.
+    pub fn xterm_without(system: bool, grayscale: bool) -> Palette {
+        match (system, grayscale) {
+            (false, false) => Palette::Xterm256,
+            (system, grayscale) => Palette::XtermRange {
+                first: if system { 16 } else { 0 },
+                last: if grayscale { 231 } else { 255 },
+            },
+        }
+    }
+
+    /// Whether these are the 256 xterm colors, or a range of them.
+    pub fn is_xterm(self) -> bool {
+        matches!(self, Palette::Xterm256 | Palette::XtermRange { .. })
+    }
+
+    /// The colors, indexed as in escape sequences, except in
+    /// [`Palette::XtermRange`], where they start at `first`, and in
+    /// [`Palette::Named`], where they are in the order of
+    /// [`NamedPalette::indices`].
+    pub fn colors(self) -> &'static [Color] {
+        match self {
+            Palette::Xterm256 => &XTERM_COLORS,
+            Palette::Ansi16 => &XTERM_COLORS[..16],
+            Palette::XtermRange { first, last } => &XTERM_COLORS[first.into()..=last.into()],
+            Palette::Named(palette) => palette.colors(),
+        }
+    }
+
+    /// The colors with their indices in escape sequences.
+    pub fn indexed(self) -> impl Iterator<Item = (usize, &'static Color)> {
+        let (first, indices) = match self {
+            Palette::XtermRange { first, .. } => (first.into(), &[][..]),
+            Palette::Named(palette) => (0, palette.indices()),
+            _ => (0, &[][..]),
+        };
+        let index = move |i: usize| indices.get(i).map_or(first + i, |&index| index.into());
+        self.colors()
+            .iter()
+            .enumerate()
+            .map(move |(i, c)| (index(i), c))
+    }
+
+    /// A [`Palette::Named`] read from a palette file, see
+    /// [`NamedPalette::from_reader`]. The palette is leaked, to live as long
+    /// as the built-in ones, so this is for loading it once.
+    pub fn from_reader(reader: impl io::Read, format: PaletteFormat) -> Result<Palette, Error> {
+        let palette = NamedPalette::from_reader(reader, format)?;
+        Ok(Palette::Named(Box::leak(Box::new(palette))))
+    }
+
+    /// The name that `--palette` takes: `xterm256`, also for a
+    /// [`Palette::XtermRange`], `ansi16`, or [`NamedPalette::name`].
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Xterm256 | Palette::XtermRange { .. } => "xterm256",
+            Palette::Ansi16 => "ansi16",
+            Palette::Named(palette) => palette.name(),
+        }
+    }
+
+    /// How the color at `index` is rendered: `xterm 180`, `ansi 3`, or its
+    /// name in a [`Palette::Named`], such as `theme base0A`.
+    pub fn label(self, index: u8) -> String {
+        Label(self, index).to_string()
+    }
+}
+
+/// [`Palette::label`], padded to the same width for every color.
+struct Label(Palette, u8);
+
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Label(palette, index) = *self;
+        match palette {
+            Palette::Xterm256 | Palette::XtermRange { .. } => write!(f, "xterm {index:<3}"),
+            Palette::Ansi16 => write!(f, "ansi {index:<2}"),
+            Palette::Named(palette) => {
+                let label = palette.label(index).unwrap_or_default();
+                write!(f, "theme {label:<width$}", width = palette.label_width())
+            }
+        }
+    }
+}
+
+/// Where to put the color when rendering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layer {
+    /// Colored text.
+    #[default]
+    Foreground,
+    /// Colored background, with black or white text, whichever contrasts more.
+    Background,
+}
+
+/// Letter case of hex notations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HexFormat {
+    #[default]
+    Lower,
+    Upper,
+}
+
+/// Options for [`TermColor::render`].
+///
+/// ```text
+/// let options = RenderOptions::new()
+///     .depth(Depth::Xterm256)
+///     .swatch(true)
+///     .show_distance(true)
+///     .hex_format(HexFormat::Upper);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    color_space: Metric,
+    depth: Depth,
+    swatch: bool,
+    show_distance: bool,
+    hex_format: HexFormat,
+    warn_delta: Option<f64>,
+    layer: Layer,
+    palette: Palette,
+    ansi16_escapes: bool,
+    assert_consistency: bool,
+    contrast: Option<(Color, f64)>,
+    simulate: Option<Cvd>,
+    adjustment: Adjustment,
+    show_unadjusted: bool,
+}
+
+/// Shifts of colors in HSL, in percentage points, applied by
+/// [`Adjustment::apply`] in this order.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Adjustment {
+    /// See [`TermColor::lighten`].
+    pub lighten: f64,
+    /// See [`TermColor::darken`].
+    pub darken: f64,
+    /// See [`TermColor::saturate`].
+    pub saturate: f64,
+}
+
+impl Adjustment {
+    pub fn apply(&self, color: &TermColor) -> TermColor {
+        color
+            .lighten(self.lighten)
+            .darken(self.darken)
+            .saturate(self.saturate)
+    }
+}
+
+impl RenderOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The color space in which to find the nearest xterm color, or another
+    /// [`Metric`]. Default: RGB
+    pub fn color_space(mut self, color_space: impl Into<Metric>) -> Self {
+        self.color_space = color_space.into();
+        self
+    }
+
+    pub fn depth(mut self, depth: Depth) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// With [`Depth::Xterm256`], show colors in escape sequences as the nearest
+    /// of the 16 ANSI colors, for terminals that show no more, while the
+    /// `xterm` part still names the nearest of the xterm palette. Themes and
+    /// [`Palette::Ansi16`] are shown as they are. Default: false
+    pub fn ansi16_escapes(mut self, ansi16_escapes: bool) -> Self {
+        self.ansi16_escapes = ansi16_escapes;
+        self
+    }
+
+    pub fn layer(mut self, layer: Layer) -> Self {
+        self.layer = layer;
+        self
+    }
+
+    /// The colors in which to find the nearest one. Default: [`Palette::Xterm256`]
+    pub fn palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// The palette set by [`RenderOptions::palette`].
+    pub fn palette_in_use(&self) -> Palette {
+        self.palette
+    }
+
+    /// Prefix colors with a block of the color.
+    pub fn swatch(mut self, swatch: bool) -> Self {
+        self.swatch = swatch;
+        self
+    }
+
+    /// Append the distance between the color and its nearest xterm color.
+    pub fn show_distance(mut self, show_distance: bool) -> Self {
+        self.show_distance = show_distance;
+        self
+    }
+
+    pub fn hex_format(mut self, hex_format: HexFormat) -> Self {
+        self.hex_format = hex_format;
+        self
+    }
+
+    /// Mark colors whose nearest xterm color is more than `threshold` away,
+    /// measured in CIEDE2000 regardless of [`RenderOptions::color_space`].
+    pub fn warn_delta(mut self, threshold: Option<f64>) -> Self {
+        self.warn_delta = threshold;
+        self
+    }
+
+    /// Show text colors as [`TermColor::ensure_contrast`] adjusts them for
+    /// `background` and `min_ratio`, after the color itself if they differ:
+    /// `rgb #555555 → rgb #8b8b8b xterm 245`. Nothing changes if `background`
+    /// is `None`, or with [`Layer::Background`], whose text is already black or
+    /// white.
+    pub fn ensure_contrast(mut self, background: Option<Color>, min_ratio: f64) -> Self {
+        self.contrast = background.map(|background| (background, min_ratio));
+        self
+    }
+
+    /// Shift colors before anything else, including finding the nearest xterm
+    /// color and [`RenderOptions::ensure_contrast`].
+    pub fn adjust(mut self, adjustment: Adjustment) -> Self {
+        self.adjustment = adjustment;
+        self
+    }
+
+    /// Show colors as in Linguist before those shifted by
+    /// [`RenderOptions::adjust`]: `rgb #555555 → rgb #6f6f6f xterm 242`.
+    pub fn show_unadjusted(mut self, show_unadjusted: bool) -> Self {
+        self.show_unadjusted = show_unadjusted;
+        self
+    }
+
+    /// Show colors as seen with a color vision deficiency as well, after the
+    /// color itself: `rgb #dea584 xterm 180 → protanopia rgb #b5aa82 xterm 144`.
+    pub fn simulate(mut self, simulate: Option<Cvd>) -> Self {
+        self.simulate = simulate;
+        self
+    }
+
+    /// Whether colors resolve as with the defaults but for the color space:
+    /// with no shifts, contrast, or simulation, among all 256 xterm colors.
+    pub fn is_plain(&self) -> bool {
+        self.adjustment == Adjustment::default()
+            && self.contrast.is_none()
+            && self.simulate.is_none()
+            && self.palette == Palette::Xterm256
+    }
+
+    /// `color` as it is rendered with these options, which is itself unless
+    /// [`RenderOptions::adjust`] or [`RenderOptions::ensure_contrast`]
+    /// changes it.
+    pub fn readable(&self, color: &TermColor) -> TermColor {
+        let color = &self.adjustment.apply(color);
+        match (self.contrast, self.layer) {
+            (Some((background, min_ratio)), Layer::Foreground) => {
+                color.ensure_contrast(&background, min_ratio)
+            }
+            _ => *color,
+        }
+    }
+
+    /// Panic if the escape sequence of a rendered color does not show exactly
+    /// the hex code printed next to it, e.g. in the tests of a program that
+    /// renders colors. Off by default.
+    ///
+    /// Only truecolor output is checked; with [`Depth::Xterm256`] the nearest
+    /// xterm color is shown on purpose.
+    pub fn assert_consistency(mut self, assert_consistency: bool) -> Self {
+        self.assert_consistency = assert_consistency;
+        self
+    }
+}
+
+/// See:
+///
+/// - <https://gist.github.com/jasonm23/2868981#file-xterm-256color-yaml>
+/// - <https://commons.wikimedia.org/wiki/File:Xterm_256color_chart.svg>
+static XTERM_COLORS: Lazy<Vec<Color>> = Lazy::new(|| {
+    let colors: &[u32; 256] = &[
+        0x000000, 0x800000, 0x008000, 0x808000, 0x000080, 0x800080, 0x008080, 0xc0c0c0, 0x808080,
+        0xff0000, 0x00ff00, 0xffff00, 0x0000ff, 0xff00ff, 0x00ffff, 0xffffff, 0x000000, 0x00005f,
+        0x000087, 0x0000af, 0x0000d7, 0x0000ff, 0x005f00, 0x005f5f, 0x005f87, 0x005faf, 0x005fd7,
+        0x005fff, 0x008700, 0x00875f, 0x008787, 0x0087af, 0x0087d7, 0x0087ff, 0x00af00, 0x00af5f,
+        0x00af87, 0x00afaf, 0x00afd7, 0x00afff, 0x00d700, 0x00d75f, 0x00d787, 0x00d7af, 0x00d7d7,
+        0x00d7ff, 0x00ff00, 0x00ff5f, 0x00ff87, 0x00ffaf, 0x00ffd7, 0x00ffff, 0x5f0000, 0x5f005f,
+        0x5f0087, 0x5f00af, 0x5f00d7, 0x5f00ff, 0x5f5f00, 0x5f5f5f, 0x5f5f87, 0x5f5faf, 0x5f5fd7,
+        0x5f5fff, 0x5f8700, 0x5f875f, 0x5f8787, 0x5f87af, 0x5f87d7, 0x5f87ff, 0x5faf00, 0x5faf5f,
+        0x5faf87, 0x5fafaf, 0x5fafd7, 0x5fafff, 0x5fd700, 0x5fd75f, 0x5fd787, 0x5fd7af, 0x5fd7d7,
+        0x5fd7ff, 0x5fff00, 0x5fff5f, 0x5fff87, 0x5fffaf, 0x5fffd7, 0x5fffff, 0x870000, 0x87005f,
+        0x870087, 0x8700af, 0x8700d7, 0x8700ff, 0x875f00, 0x875f5f, 0x875f87, 0x875faf, 0x875fd7,
+        0x875fff, 0x878700, 0x87875f, 0x878787, 0x8787af, 0x8787d7, 0x8787ff, 0x87af00, 0x87af5f,
+        0x87af87, 0x87afaf, 0x87afd7, 0x87afff, 0x87d700, 0x87d75f, 0x87d787, 0x87d7af, 0x87d7d7,
+        0x87d7ff, 0x87ff00, 0x87ff5f, 0x87ff87, 0x87ffaf, 0x87ffd7, 0x87ffff, 0xaf0000, 0xaf005f,
+        0xaf0087, 0xaf00af, 0xaf00d7, 0xaf00ff, 0xaf5f00, 0xaf5f5f, 0xaf5f87, 0xaf5faf, 0xaf5fd7,
+        0xaf5fff, 0xaf8700, 0xaf875f, 0xaf8787, 0xaf87af, 0xaf87d7, 0xaf87ff, 0xafaf00, 0xafaf5f,
+        0xafaf87, 0xafafaf, 0xafafd7, 0xafafff, 0xafd700, 0xafd75f, 0xafd787, 0xafd7af, 0xafd7d7,
+        0xafd7ff, 0xafff00, 0xafff5f, 0xafff87, 0xafffaf, 0xafffd7, 0xafffff, 0xd70000, 0xd7005f,
+        0xd70087, 0xd700af, 0xd700d7, 0xd700ff, 0xd75f00, 0xd75f5f, 0xd75f87, 0xd75faf, 0xd75fd7,
+        0xd75fff, 0xd78700, 0xd7875f, 0xd78787, 0xd787af, 0xd787d7, 0xd787ff, 0xd7af00, 0xd7af5f,
+        0xd7af87, 0xd7afaf, 0xd7afd7, 0xd7afff, 0xd7d700, 0xd7d75f, 0xd7d787, 0xd7d7af, 0xd7d7d7,
+        0xd7d7ff, 0xd7ff00, 0xd7ff5f, 0xd7ff87, 0xd7ffaf, 0xd7ffd7, 0xd7ffff, 0xff0000, 0xff005f,
+        0xff0087, 0xff00af, 0xff00d7, 0xff00ff, 0xff5f00, 0xff5f5f, 0xff5f87, 0xff5faf, 0xff5fd7,
+        0xff5fff, 0xff8700, 0xff875f, 0xff8787, 0xff87af, 0xff87d7, 0xff87ff, 0xffaf00, 0xffaf5f,
+        0xffaf87, 0xffafaf, 0xffafd7, 0xffafff, 0xffd700, 0xffd75f, 0xffd787, 0xffd7af, 0xffd7d7,
+        0xffd7ff, 0xffff00, 0xffff5f, 0xffff87, 0xffffaf, 0xffffd7, 0xffffff, 0x080808, 0x121212,
+        0x1c1c1c, 0x262626, 0x303030, 0x3a3a3a, 0x444444, 0x4e4e4e, 0x585858, 0x626262, 0x6c6c6c,
+        0x767676, 0x808080, 0x8a8a8a, 0x949494, 0x9e9e9e, 0xa8a8a8, 0xb2b2b2, 0xbcbcbc, 0xc6c6c6,
+        0xd0d0d0, 0xdadada, 0xe4e4e4, 0xeeeeee,
+    ];
+    colors.iter().map(|c| color_from_rgb(*c)).collect()
+});
+
+/// Write the escape sequence showing `color` as configured in `options`, which
+/// is xterm color `idx`, or `nearest` if the depth is limited to 256 colors.
+fn write_sgr<W: fmt::Write + ?Sized>(
+    w: &mut W,
+    (idx, color): (usize, &Color),
+    nearest: &Color,
+    options: &RenderOptions,
+) -> fmt::Result {
+    // the standard colors stand in for those of the palette, see
+    // RenderOptions::ansi16_escapes
+    let palette = match options.ansi16_escapes && options.palette.is_xterm() {
+        true => Palette::Ansi16,
+        false => options.palette,
+    };
+    let ansi;
+    let shown = match options.depth {
+        Depth::TrueColor => (idx, color),
+        Depth::Xterm256 if palette != options.palette => {
+            ansi = nearest_match(color, palette, options.color_space);
+            (ansi.index.into(), &ansi.color)
+        }
+        Depth::Xterm256 => (idx, nearest),
+    };
+    let sgr = |w: &mut W, layer: u8, (idx, color): (usize, &Color)| match options.depth {
+        Depth::TrueColor => {
+            let (r, g, b) = (color.red(), color.green(), color.blue());
+            write!(w, ";{layer};2;{r};{g};{b}")
+        }
+        Depth::Xterm256 => match palette {
+            Palette::Xterm256 | Palette::XtermRange { .. } => write!(w, ";{layer};5;{idx}"),
+            Palette::Named(_) if idx >= 16 => write!(w, ";{layer};5;{idx}"),
+            // 30-37 and 90-97 for the text, 40-47 and 100-107 for the background
+            Palette::Ansi16 | Palette::Named(_) => match idx {
+                0..=7 => write!(w, ";{}", layer as usize - 8 + idx),
+                _ => write!(w, ";{}", layer as usize + 52 + idx - 8),
+            },
+        },
+    };
+    w.write_str("\x1b[1")?;
+    match options.layer {
+        Layer::Foreground => sgr(w, 38, shown)?,
+        Layer::Background => {
+            sgr(w, 48, shown)?;
+            sgr(w, 38, readable_on(shown.1, palette))?;
+        }
+    }
+    w.write_char('m')
+}
+
+/// Check that the truecolor parameters in `segment`, a rendered `rgb #xxxxxx`,
+/// are the RGB of its hex code. See [`RenderOptions::assert_consistency`].
+fn assert_consistent(segment: &str, options: &RenderOptions) {
+    let Some(hex) = segment.split("rgb #").nth(1).and_then(|rest| rest.get(..6)) else {
+        panic!("no hex code in rendered color {segment:?}")
+    };
+    let Ok(printed) = u32::from_str_radix(hex, 16) else {
+        panic!("malformed hex code in rendered color {segment:?}")
+    };
+    let printed = [printed >> 16, (printed >> 8) & 0xff, printed & 0xff];
+
+    if options.depth != Depth::TrueColor || !segment.starts_with("\x1b[") {
+        return;
+    }
+    let params = segment[2..segment.find('m').unwrap_or(2)]
+        .split(';')
+        .map(|param| param.parse::<u32>().ok())
+        .collect::<Vec<_>>();
+    let layer = match options.layer {
+        Layer::Foreground => 38,
+        Layer::Background => 48,
+    };
+    let shown = params
+        .windows(5)
+        .find(|sgr| sgr[0] == Some(layer) && sgr[1] == Some(2))
+        .map(|sgr| [sgr[2], sgr[3], sgr[4]]);
+    assert_eq!(
+        shown,
+        Some(printed.map(Some)),
+        "rendered color {segment:?} does not show its hex code"
+    );
+}
+
+/// Black or white from `palette`, whichever has the higher contrast ratio
+/// against `background`, along with its index.
+fn readable_on(background: &Color, palette: Palette) -> (usize, &'static Color) {
+    let contrast = |(_, color): (usize, &Color)| color_math::contrast_ratio(background, color);
+    let (black, white) = match palette {
+        Palette::Xterm256 | Palette::XtermRange { .. } => (16, 231),
+        Palette::Ansi16 => (0, 15),
+        // the darkest and the lightest color of the theme
+        Palette::Named(_) => {
+            let luminance = |(_, color): &(usize, &Color)| color_math::relative_luminance(color);
+            let by = |a: &_, b: &_| luminance(a).total_cmp(&luminance(b));
+            let darkest = palette
+                .indexed()
+                .min_by(by)
+                .expect("palettes are not empty");
+            let lightest = palette
+                .indexed()
+                .max_by(by)
+                .expect("palettes are not empty");
+            return match contrast(darkest) >= contrast(lightest) {
+                true => darkest,
+                false => lightest,
+            };
+        }
+    };
+    let (black, white) = ((black, &XTERM_COLORS[black]), (white, &XTERM_COLORS[white]));
+    match contrast(black) >= contrast(white) {
+        true => black,
+        false => white,
+    }
+}
+
+fn delta_e2000(a: &Color, b: &Color) -> f64 {
+    color_math::delta_e2000(color_math::lab(a), color_math::lab(b))
+}
+
+/// Turn a language name into a kebab-case identifier.
+///
+/// `#` and `+` are spelled out so that they stay distinct, and every other run
+/// of characters besides ASCII letters and digits becomes a single `-`:
+///
+/// ```text
+/// C++          -> c-plus-plus
+/// C#           -> c-sharp
+/// Objective-C  -> objective-c
+/// Ren'Py       -> ren-py
+/// ```
+pub fn slugify(name: &str) -> String {
+    let mut words = vec![];
+    let mut word = String::new();
+    for c in name.chars() {
+        let spelled = match c {
+            '#' => Some("sharp"),
+            '+' => Some("plus"),
+            '*' => Some("star"),
+            _ => None,
+        };
+        if c.is_ascii_alphanumeric() {
+            word.push(c.to_ascii_lowercase());
+            continue;
+        }
+        if !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        if let Some(spelled) = spelled {
+            words.push(spelled.to_string());
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words.join("-")
+}
+
+/// `query` with a leading `*.` or `.` removed, as in `*.rs`, if it could be an
+/// extension: a single word with no path separators or other wildcards.
+fn extension_key(query: &str) -> Option<&str> {
+    if query.contains([' ', '/', '\\']) {
+        return None;
+    }
+    let query = (query.strip_prefix("*.").or_else(|| query.strip_prefix('.'))).unwrap_or(query);
+    (!query.is_empty() && !query.contains(['*', '?'])).then_some(query)
+}
+
+/// The primary language of the extension `key`, among the languages `listed`
+/// with where they list it, see [`ColorMap::primary_language`].
+fn primary_language<'a>(key: &str, listed: &[(&'a str, usize)]) -> Option<&'a str> {
+    let dotted = format!(".{key}");
+    let rules = heuristics::DISAMBIGUATIONS
+        .iter()
+        .find(|rules| rules.extensions.contains(&dotted.as_str()))
+        .map_or(&[][..], |rules| rules.rules);
+    let rank = |&(name, position): &(&'a str, usize)| {
+        let rule = rules
+            .iter()
+            .position(|rule| rule.language.eq_ignore_ascii_case(name));
+        let fallback = rule.is_some_and(|rule| rules[rule].patterns.is_empty());
+        (!fallback, position, rule.unwrap_or(usize::MAX))
+    };
+    let best = listed
+        .iter()
+        .min_by(|a, b| rank(a).cmp(&rank(b)).then_with(|| by_name(a.0, b.0)))?;
+    Some(best.0)
+}
+
+/// `query` if it is a pattern over whole names, such as `objective-*` or
+/// `*script`: a single word with `*` or `?` and no path separators.
+fn name_pattern(query: &str) -> Option<&str> {
+    (query.contains(['*', '?']) && !query.contains([' ', '/', '\\'])).then_some(query)
+}
+
+/// Whether `text` matches `pattern`, in which `*` stands for any run of
+/// characters and `?` for any one character.
+fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    let (mut p, mut t) = (0, 0);
+    // where to resume after the last `*`, if it has to swallow one more character
+    let mut resume = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                resume = Some((p, t));
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match resume {
+                Some((after_star, swallowed)) => {
+                    p = after_star;
+                    t = swallowed + 1;
+                    resume = Some((after_star, t));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Lowercase and collapse whitespace, keeping punctuation intact.
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Language names in the order of their lowercased forms, as [`Linguist`]
+/// keeps them, so that `mIRC Script` sorts among the other m's.
+pub(crate) fn by_name(a: &str, b: &str) -> std::cmp::Ordering {
+    fn lower(name: &str) -> impl Iterator<Item = u8> + '_ {
+        name.bytes().map(|b| b.to_ascii_lowercase())
+    }
+    lower(a).cmp(lower(b)).then_with(|| a.cmp(b))
+}
+
+/// Words made of letters, digits, and `+*_#-`, so that `c++`, `f#`, and
+/// `objective-c` stay whole, as do dotted names such as `asp.net`.
+fn tokenize(text: &str) -> Vec<&str> {
+    RE_MATCH_WORDS.find_iter(text).map(|m| m.as_str()).collect()
+}
+
+/// The pieces of a dotted word such as `node.js` between the dots, if it has
+/// any, so that it can also be found by `node` or `js`.
+fn dotted_parts(word: &str) -> impl Iterator<Item = &str> {
+    let parts = word.contains('.').then(|| word.split('.'));
+    parts.into_iter().flatten().filter(|part| !part.is_empty())
+}
+
+static RE_MATCH_WORDS: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[\pL\pN+*_#-]+(\.[\pL\pN+*_#-]+)*").unwrap());
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The snapshot of `languages.yml` that the `bundled` feature embeds.
+    fn linguist() -> Linguist {
+        include_str!("../data/languages.yml").parse().unwrap()
+    }
+
+    /// The bundled data is parsed like any other, aliases and extensions
+    /// included.
+    #[cfg(feature = "bundled")]
+    #[test]
+    fn bundled() {
+        let linguist = Linguist::bundled();
+        assert!(linguist.0.len() > 500);
+        let colors = linguist.colors().unwrap();
+        for query in ["rust", "Rust", "rs", ".rs"] {
+            let ranked = colors.ranked(query);
+            assert_eq!(ranked[0].name, "Rust", "{query}");
+            assert_eq!(ranked[0].color.hex(), "#dea584", "{query}");
+        }
+    }
+
+    #[test]
+    fn hex_colors() {
+        for (text, hex) in [
+            ("#dea584", "#dea584"),
+            ("dea584", "#dea584"),
+            ("#DEA584", "#dea584"),
+            ("#abc", "#aabbcc"),
+            ("abc", "#aabbcc"),
+            ("#000", "#000000"),
+            ("#ffffff", "#ffffff"),
+        ] {
+            let color = parse_hex_color(text).unwrap_or_else(|err| panic!("{text:?}: {err}"));
+            assert_eq!(TermColor::from(color).hex(), hex, "{text:?}");
+        }
+        for text in [
+            "", "#", "##abc", "#ab", "#abcd", "#abcde", "#dea5840", "#dea58g", "xyz", " #dea584",
+            "#dea584 ", "+dea584", "#-ea584", "é12", "#ééé", "0xdea584",
+        ] {
+            let err = parse_hex_color(text).unwrap_err();
+            assert!(
+                matches!(&err, Error::HexColor(rejected) if rejected == text),
+                "{text:?}: {err}"
+            );
+        }
+    }
+
+    /// Six and three hex digits, with or without `#`, and nothing else.
+    #[test]
+    fn hex_notations() {
+        for (text, rgb) in [
+            ("#dea584", 0xdea584),
+            ("dea584", 0xdea584),
+            ("#DeA584", 0xdea584),
+            ("#abc", 0xaabbcc),
+            ("ABC", 0xaabbcc),
+            ("#000", 0x000000),
+            ("fff", 0xffffff),
+            ("#0f0", 0x00ff00),
+        ] {
+            assert_eq!(parse_hex(text), Some(rgb), "{text:?}");
+        }
+        for text in [
+            "", "#", "#ab", "abcd", "#dea58", "#dea5840", "+dea584", "#+ea584", "+ab", "-ab",
+            "#ééé", "é12", "0xabc", "#abc ",
+        ] {
+            assert_eq!(parse_hex(text), None, "{text:?}");
+        }
+    }
+
+    /// Colors in hex, `rgb()`, `hsl()`, or by CSS name, whatever the case and
+    /// the spaces around them, and malformed ones named in the error.
+    #[test]
+    fn color_notations() {
+        for (text, hex) in [
+            ("#dea584", "#dea584"),
+            (" #DEA584 ", "#dea584"),
+            ("abc", "#aabbcc"),
+            ("rgb(53, 114, 165)", "#3572a5"),
+            ("RGB(53,114,165)", "#3572a5"),
+            ("rgb(100 100 240)", "#6464f0"),
+            ("rgb(100%, 50%, 0%)", "#ff8000"),
+            ("rgba(53, 114, 165, 0.5)", "#3572a5"),
+            ("rgb(53 114 165 / 50%)", "#3572a5"),
+            ("hsl(21, 58%, 69%)", "#dea282"),
+            ("hsl(21deg 58% 69%)", "#dea282"),
+            // hues wrap around
+            ("hsl(381deg, 58%, 69%)", "#dea282"),
+            ("rebeccapurple", "#663399"),
+            ("RebeccaPurple", "#663399"),
+        ] {
+            let color = text.parse::<TermColor>();
+            let color = color.unwrap_or_else(|err| panic!("{text:?}: {err}"));
+            assert_eq!(color.hex(), hex, "{text:?}");
+        }
+        for text in [
+            "rgb(53, 114, 165",
+            "rgb 53, 114, 165)",
+            "rgb(256, 0, 0)",
+            "rgb(-1, 0, 0)",
+            "rgb(101%, 0%, 0%)",
+            "rgb(1, 2)",
+            "rgba(1, 2, 3, 2)",
+            "hsl(21, 58, 69)",
+            "hsl(21, 58%, 101%)",
+            "#dea58",
+            "notacolor",
+            "",
+        ] {
+            let err = text.parse::<TermColor>().unwrap_err();
+            assert!(
+                matches!(&err, Error::ColorNotation(rejected) if rejected == text),
+                "{text:?}: {err}"
+            );
+        }
+    }
+
+    /// Overrides take the same forms of colors as `languages.yml`.
+    #[test]
+    fn override_colors() {
+        let overrides = |yaml: &str| {
+            let path = std::env::temp_dir().join(format!(
+                "linguist-termcolor-overrides-{}-{}.yml",
+                std::process::id(),
+                yaml.len()
+            ));
+            std::fs::write(&path, yaml).unwrap();
+            let result = linguist().with_overrides(&path);
+            let _ = std::fs::remove_file(&path);
+            result
+        };
+        let linguist = overrides("Rust:\n  color: abc\n").unwrap();
+        let color = linguist.get("Rust").unwrap().color().unwrap();
+        assert_eq!(TermColor::from(color).hex(), "#aabbcc");
+        let Err(Error::File { source, .. }) = overrides("Rust:\n  color: '#abcd'\n") else {
+            panic!("not an error in the file")
+        };
+        let Error::InvalidColor { language, .. } = *source else {
+            panic!("not an invalid color: {source}")
+        };
+        assert_eq!(language, "Rust");
+    }
+
+    /// A color that is not a hex color leaves its language without one and
+    /// is listed by its name as written, and the rest of the data loads.
+    #[test]
+    fn skipped_colors() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/bad-color.yml");
+        let loaded = Linguist::from_path(path).unwrap();
+        let [Error::InvalidColor { language, value }] = loaded.skipped_colors() else {
+            panic!("{:?}", loaded.skipped_colors())
+        };
+        assert_eq!((language.as_str(), value.as_str()), ("Bad Lang", "#zzzzzz"));
+        let bad = loaded.get("bad lang").unwrap();
+        assert_eq!(bad.color(), None);
+        assert_eq!(bad.extensions(), [".bad"]);
+        let colors = loaded.colors().unwrap();
+        assert!(colors.ranked("bad lang").is_empty());
+        assert_eq!(colors.ranked("rust")[0].color.hex(), "#dea584");
+        assert!(linguist().skipped_colors().is_empty());
+
+        // a color of its own in overrides fixes it
+        let overrides = std::env::temp_dir().join(format!(
+            "linguist-termcolor-skipped-{}.yml",
+            std::process::id()
+        ));
+        std::fs::write(&overrides, "bad lang:\n  color: '#123456'\n").unwrap();
+        let fixed = Linguist::from_path(path)
+            .unwrap()
+            .with_overrides(&overrides);
+        let _ = std::fs::remove_file(&overrides);
+        let fixed = fixed.unwrap();
+        assert!(fixed.skipped_colors().is_empty());
+        let color = fixed.get("Bad Lang").unwrap().color().unwrap();
+        assert_eq!(TermColor::from(color).hex(), "#123456");
+    }
+
+    /// Overrides add languages as written, replace only the fields they give,
+    /// and extend lists without repeating what they already have.
+    #[test]
+    fn overrides() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/overrides.yml");
+        let before = linguist();
+        let after = linguist().with_overrides(path).unwrap();
+        assert_eq!(after.languages().count(), before.languages().count() + 1);
+        let hex = |lang: Language<'_>| TermColor::from(lang.color().unwrap()).hex();
+
+        // a new language
+        assert!(before.get("acme config").is_none());
+        let acme = after.get("acme").unwrap();
+        assert_eq!(acme.name(), "Acme Config");
+        assert_eq!(hex(acme), "#ff6600");
+        assert_eq!(acme.extensions(), [".acme"]);
+        assert_eq!(acme.language_type(), Some(&LanguageType::Data));
+        let colors = after.colors().unwrap();
+        assert_eq!(colors.ranked("acme")[0].name, "Acme Config");
+        assert_eq!(colors.ranked(".acme")[0].name, "Acme Config");
+
+        // a new color, and nothing else changed
+        let (old, new) = (before.get("typescript").unwrap(), after.get("ts").unwrap());
+        assert_eq!((hex(old), hex(new)), ("#3178c6".into(), "#0057b8".into()));
+        assert_eq!(new.aliases(), old.aliases());
+        assert_eq!(new.extensions(), old.extensions());
+        assert_eq!(new.language_type(), old.language_type());
+        assert_eq!(colors.ranked(".ts")[0].color.hex(), "#0057b8");
+
+        // aliases and extensions appended, once each
+        let rust = after.get("Rust").unwrap();
+        assert_eq!(rust.name(), "Rust");
+        assert_eq!(hex(rust), "#dea584");
+        assert_eq!(rust.aliases(), ["rs", "rustlang"]);
+        assert_eq!(rust.extensions(), [".rs", ".rs.in", ".rlib"]);
+        assert_eq!(colors.ranked("rustlang")[0].name, "Rust");
+        assert_eq!(colors.ranked(".rlib")[0].name, "Rust");
+    }
+
+    /// Names and aliases with characters other than letters, digits, and
+    /// spaces, such as `c++`, `f#`, and `asp.net`, each find their own
+    /// language first.
+    #[test]
+    fn symbol_heavy_aliases() {
+        let linguist = linguist();
+        let colors = linguist.colors().unwrap();
+        let mut checked = 0;
+        for lang in linguist.languages().filter(|lang| lang.color().is_some()) {
+            let keywords =
+                std::iter::once(lang.name()).chain(lang.aliases().iter().map(String::as_str));
+            for keyword in keywords.filter(|k| k.chars().any(|c| !c.is_alphanumeric() && c != ' '))
+            {
+                let ranked = colors.ranked(keyword);
+                let first = ranked.first().map(|found| &*found.name);
+                assert_eq!(first, Some(lang.name()), "for {keyword:?}");
+                assert_eq!(ranked[0].kind, MatchKind::Exact, "for {keyword:?}");
+                checked += 1;
+            }
+        }
+        assert!(checked > 20, "only {checked} symbol-heavy keywords");
+        for (query, name) in [("c++", "C++"), ("f#", "F#"), ("asp.net", "ASP.NET")] {
+            assert_eq!(colors.ranked(query)[0].name, name);
+        }
+    }
+
+    #[test]
+    fn extensions_in_authored_order() {
+        let fixture = include_str!("../tests/fixtures/primary-extensions.yml");
+        let linguist = fixture.parse::<Linguist>().unwrap();
+        let alpha = linguist.get("alpha").unwrap();
+        assert_eq!(alpha.extensions(), [".zz", ".aa"]);
+        assert_eq!(alpha.primary_extension(), Some(".zz"));
+        let colors = linguist.colors().unwrap();
+        assert_eq!(colors.primary_language(".aa"), Some("Zeta"));
+        assert_eq!(colors.primary_language("zz"), None);
+        let ranked = colors.ranked(".aa");
+        let names = ranked.iter().map(|found| &*found.name).collect::<Vec<_>>();
+        assert_eq!(names, ["Zeta", "Alpha"]);
+        assert!(ranked[0].primary);
+    }
+
+    /// Many queries and colors at once come back in the order given, as one
+    /// at a time, with or without `rayon`.
+    #[test]
+    fn many_at_once() {
+        let linguist = linguist();
+        let colors = linguist.colors().unwrap();
+        let extensions = linguist.languages().flat_map(|lang| lang.extensions());
+        let queries = (extensions.map(String::as_str))
+            .chain(["rust", "c++", "rs", "rust", "zzqx", "py"])
+            .collect::<Vec<_>>()
+            .repeat(2);
+        let names = |ranked: &[Ranked<'_>]| {
+            let names = ranked.iter().map(|found| found.name.to_string());
+            names.collect::<Vec<_>>()
+        };
+        let many = colors.ranked_many(queries.iter().copied());
+        assert_eq!(many.len(), queries.len());
+        for ((idx, ranked), query) in many.iter().zip(&queries) {
+            assert_eq!(names(ranked), names(&colors.ranked(query)), "{idx} {query}");
+        }
+        assert_eq!(many.iter().map(|(idx, _)| *idx).collect::<Vec<_>>(), {
+            (0..queries.len()).collect::<Vec<_>>()
+        });
+
+        let found = many.iter().flat_map(|(_, ranked)| ranked.first());
+        let found = found.map(|found| found.color.color()).collect::<Vec<_>>();
+        for metric in [Metric::from(ColorSpace::RGB), Metric::De2000] {
+            let nearest = nearest_many_in_palette(&found, Palette::Xterm256, metric);
+            let one_by_one = found
+                .iter()
+                .map(|c| nearest_in_palette(c, Palette::Xterm256, metric));
+            assert_eq!(nearest, one_by_one.collect::<Vec<_>>(), "{metric:?}");
+        }
+    }
+
+    /// A saved index answers queries as the one it was saved from, without
+    /// `languages.yml`, and one of another version or cut off is rebuilt.
+    #[test]
+    fn saved_index() {
+        let path = std::env::temp_dir().join(format!(
+            "linguist-termcolor-index-{}.json",
+            std::process::id()
+        ));
+        let linguist = linguist();
+        let built = linguist.colors().unwrap();
+        built.save(&path).unwrap();
+        let dump = |colors: &ColorMap<'_>| {
+            let entries = colors
+                .iter()
+                .map(|(keyword, name, color)| format!("{keyword} {name} {}", color.hex()));
+            entries.collect::<Vec<_>>()
+        };
+        // from the file alone
+        let loaded = ColorMap::load_or_build(&path, || panic!("rebuilt")).unwrap();
+        assert_eq!(dump(&loaded), dump(&built));
+        for query in ["rust", "rs", "c++", "Makefile", "objective-*", "node"] {
+            let names = |colors: &ColorMap<'_>| {
+                let ranked = colors.ranked(query).into_iter();
+                ranked
+                    .map(|found| found.name.into_owned())
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(names(&loaded), names(&built), "{query}");
+        }
+        assert_eq!(loaded.primary_language("h"), built.primary_language("h"));
+
+        let saved = std::fs::read_to_string(&path).unwrap();
+        let stale = saved.replacen(
+            &format!("\"version\":{INDEX_VERSION}"),
+            &format!("\"version\":{}", INDEX_VERSION - 1),
+            1,
+        );
+        let broken = [stale, saved[..saved.len() / 2].to_string(), String::new()];
+        for (i, contents) in broken.into_iter().enumerate() {
+            std::fs::write(&path, contents).unwrap();
+            let Err(Error::File { source, .. }) = ColorMap::load(&path) else {
+                panic!("loaded a broken index");
+            };
+            if i == 0 {
+                assert!(matches!(*source, Error::IndexVersion { .. }), "{source}");
+            }
+            let mut rebuilt = false;
+            let colors = ColorMap::load_or_build(&path, || {
+                rebuilt = true;
+                linguist.colors().map(ColorMap::into_owned)
+            });
+            assert!(rebuilt);
+            assert_eq!(dump(&colors.unwrap()), dump(&built));
+            // saved again for next time
+            assert_eq!(dump(&ColorMap::load(&path).unwrap()), dump(&built));
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+
+    /// A [`TermColor`] serializes as its hex and RGB and comes back from
+    /// that or from its hex alone.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_term_color() {
+        let rust = TermColor::from(parse_hex_color("#dea584").unwrap());
+        let json = serde_json::to_value(rust).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"hex": "#dea584", "rgb": [222, 165, 132]})
+        );
+        assert_eq!(serde_json::from_value::<TermColor>(json).unwrap(), rust);
+        for hex in ["\"#dea584\"", "\"#DEA584\"", "\"dea584\""] {
+            assert_eq!(
+                serde_json::from_str::<TermColor>(hex).unwrap(),
+                rust,
+                "{hex}"
+            );
+        }
+        for json in ["\"#dea58\"", "\"\"", "{\"rgb\": [222, 165, 132]}", "222"] {
+            assert!(serde_json::from_str::<TermColor>(json).is_err(), "{json}");
+        }
+    }
+
+    /// Results serialize with their colors as [`TermColor`] does, so that
+    /// their colors come back.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_results() {
+        let linguist = linguist();
+        let colors = linguist.colors().unwrap();
+        let matched = serde_json::to_value(colors.query_detailed("py")).unwrap();
+        assert_eq!(matched[0]["name"], "Python");
+        assert_eq!(matched[0]["kind"], "exact");
+        assert_eq!(matched[0]["via"], "alias");
+        assert_eq!(matched[0]["keyword"], "py");
+        let color = serde_json::from_value::<TermColor>(matched[0]["color"].clone()).unwrap();
+        assert_eq!(color.hex(), "#3572a5");
+
+        let ranked = serde_json::to_value(colors.ranked("rs")).unwrap();
+        assert_eq!(ranked[0]["name"], "Rust");
+        assert_eq!(ranked[0]["kind"], "exact");
+        assert_eq!(ranked[0]["primary"], true);
+    }
+
+    /// Languages serialize with what Linguist has on them, and `null` for what
+    /// it does not.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_languages() {
+        let linguist = linguist();
+        let rust = serde_json::to_value(linguist.get("rust").unwrap()).unwrap();
+        assert_eq!(rust["name"], "Rust");
+        assert_eq!(rust["color"]["hex"], "#dea584");
+        assert_eq!(rust["type"], "programming");
+        assert_eq!(rust["language_id"], 327);
+        assert_eq!(rust["tm_scope"], "source.rust");
+        assert_eq!(rust["group"], serde_json::Value::Null);
+        assert_eq!(rust["extensions"][0], ".rs");
+        let fixture = include_str!("../tests/fixtures/fork.yml");
+        let fork = fixture.parse::<Linguist>().unwrap();
+        let config = serde_json::to_value(fork.get("ferrite config").unwrap()).unwrap();
+        assert_eq!(config["color"], serde_json::Value::Null);
+        assert_eq!(config["tm_scope"], serde_json::Value::Null);
+        assert_eq!(config["group"], "Ferrite");
+        assert_eq!(config["filenames"], serde_json::json!(["Ferritefile"]));
+    }
+
+    /// Names of one letter and names with symbols or dots find only their own
+    /// language, rather than every language with a word like theirs.
+    #[test]
+    fn symbolic_names() {
+        let linguist = linguist();
+        let colors = linguist.colors().unwrap();
+        for (query, name) in [
+            ("c", "C"),
+            ("c++", "C++"),
+            ("c#", "C#"),
+            ("objective-c", "Objective-C"),
+            ("objective-c++", "Objective-C++"),
+            ("asp.net", "ASP.NET"),
+            ("vb.net", "Visual Basic .NET"),
+        ] {
+            let ranked = colors.ranked(query);
+            let names = ranked.iter().map(|found| &*found.name).collect::<Vec<_>>();
+            assert_eq!(names, [name], "{query}");
+        }
+        // not a name, so by its words, Node's first
+        let ranked = colors.ranked("node.js");
+        assert_eq!(ranked[0].name, "JavaScript");
+        assert!(ranked.iter().all(|found| found.kind == MatchKind::Words));
+    }
+
+    /// Linguist's filenames are matched whole and whatever their case, before
+    /// their extensions and words.
+    #[test]
+    fn filenames() {
+        let linguist = linguist();
+        let colors = linguist.colors().unwrap();
+        for (query, name) in [
+            ("Makefile", "Makefile"),
+            ("makefile", "Makefile"),
+            ("GNUmakefile", "Makefile"),
+            ("Gemfile", "Ruby"),
+            ("GEMFILE", "Ruby"),
+            (".vimrc", "Vim Script"),
+            ("CMakeLists.txt", "CMake"),
+            ("Dockerfile", "Dockerfile"),
+        ] {
+            let ranked = colors.ranked(query);
+            assert_eq!(
+                ranked[0].name.to_lowercase(),
+                name.to_lowercase(),
+                "{query}"
+            );
+            // a name that is also a filename matches as the name
+            let via = match query.eq_ignore_ascii_case(name) {
+                true => MatchVia::Name,
+                false => MatchVia::Filename,
+            };
+            let matched = colors.provenance(query, &ranked[0].name);
+            assert_eq!(matched[0].via, via, "{query}: {matched:?}");
+        }
+        assert!(colors.ranked("CMakeLists").is_empty());
+    }
+
+    /// A language without a color is shown in that of the nearest language up
+    /// its `group` chain, and a chain that loops or ends gives it no color
+    /// rather than recursing.
+    #[test]
+    fn group_chains() {
+        let fixture = include_str!("../tests/fixtures/groups.yml");
+        let linguist = fixture.parse::<Linguist>().unwrap();
+        assert_eq!(
+            linguist.group_color("jsx template"),
+            Some(("JavaScript", 0xf1e05a))
+        );
+        assert_eq!(linguist.group_color("jsx"), Some(("JavaScript", 0xf1e05a)));
+        for name in ["loop a", "loop b", "self loop", "orphan"] {
+            assert_eq!(linguist.group_color(name), None, "{name}");
+        }
+
+        let colors = linguist.colors().unwrap();
+        for query in ["jsx template", ".jsxt"] {
+            let ranked = colors.ranked(query);
+            assert_eq!(ranked[0].name, "JSX Template", "{query}");
+            assert_eq!(ranked[0].color.hex(), "#f1e05a", "{query}");
+        }
+        assert_eq!(colors.color_source("JSX Template"), Some("JavaScript"));
+        assert_eq!(colors.color_source("JavaScript"), None);
+        for query in ["loop a", ".loopa", "loop b", "self loop", "orphan"] {
+            assert!(colors.ranked(query).is_empty(), "{query}");
+        }
+
+        let options = ColorMapOptions::default().inherit_group_colors(false);
+        let colors = linguist.colors_with_options(&options).unwrap();
+        assert!(colors.ranked("jsx template").is_empty());
+    }
+
+    /// An extension finds the same results with or without its dot, and as a
+    /// glob, also one shared by a group and one that is also an alias.
+    #[test]
+    fn extension_forms() {
+        let fixture = include_str!("../tests/fixtures/extensions.yml");
+        let linguist = fixture.parse::<Linguist>().unwrap();
+        let colors = linguist.colors().unwrap();
+        let found = |query: &str| {
+            let ranked = colors.ranked(query).into_iter();
+            let ranked = ranked.map(|found| (found.name.to_string(), found.kind, found.primary));
+            ranked.collect::<Vec<_>>()
+        };
+        for (extension, first) in [("rs", "Rust"), ("tsx", "TSX"), ("d", "D")] {
+            let bare = found(extension);
+            assert_eq!(bare.first().map(|found| &*found.0), Some(first));
+            for query in [format!(".{extension}"), format!("*.{extension}")] {
+                assert_eq!(found(&query), bare, "{query}");
+            }
+        }
+        assert_eq!(found(".tsx").len(), 2, "both TSX and TypeScript have .tsx");
+        assert_eq!(found(".rs.in"), found("rs.in"));
+    }
+
+    /// Two indexes built from separately parsed data iterate the same way, in
+    /// the canonical order: by lowercased name, then by name.
+    #[test]
+    fn deterministic_order() {
+        let dump = || {
+            let linguist = linguist();
+            let names = linguist.languages().map(|lang| lang.name().to_string());
+            let names = names.collect::<Vec<_>>();
+            let colors = linguist.colors().unwrap();
+            let entries = colors
+                .iter()
+                .map(|(keyword, name, color)| format!("{keyword} {name} {}", color.hex()));
+            (names, entries.collect::<Vec<_>>())
+        };
+        let (names, entries) = dump();
+        assert_eq!((names.clone(), entries), dump());
+        assert!(names
+            .windows(2)
+            .all(|pair| by_name(&pair[0], &pair[1]).is_lt()));
+        let mirc = names.iter().position(|name| name == "mIRC Script").unwrap();
+        let (before, after) = (
+            names[mirc - 1].to_lowercase(),
+            names[mirc + 1].to_lowercase(),
+        );
+        assert!(before.as_str() < "mirc script" && "mirc script" < after.as_str());
+    }
+
+    /// Every color of every language renders in truecolor with escape
+    /// sequences that show its printed hex, whatever adjusts it.
+    #[test]
+    fn rendered_truecolor_matches_hex() {
+        colored::control::set_override(true);
+        let black = Color::from_hex("#000000").unwrap();
+        let options = [
+            RenderOptions::new(),
+            RenderOptions::new().layer(Layer::Background),
+            RenderOptions::new()
+                .swatch(true)
+                .hex_format(HexFormat::Upper),
+            RenderOptions::new().palette(Palette::Ansi16),
+            RenderOptions::new()
+                .show_unadjusted(true)
+                .adjust(Adjustment {
+                    lighten: 20.0,
+                    darken: 0.0,
+                    saturate: 10.0,
+                }),
+            RenderOptions::new()
+                .show_unadjusted(true)
+                .ensure_contrast(Some(black), 7.0),
+            RenderOptions::new().simulate(Some(Cvd::Deuteranopia)),
+        ];
+        let linguist = linguist();
+        for (_, color) in linguist.iter_colors() {
+            for options in &options {
+                let options = options.clone().assert_consistency(true);
+                let rendered = TermColor::from(color).render(&options);
+                assert!(rendered.contains("\x1b[1;"), "{rendered:?} is not colored");
+            }
+        }
+    }
+
+    /// The ANSI colors of well-known languages, found with the color distance
+    /// asked for, as Go shows, and rendered as `ansi N`.
+    #[test]
+    fn ansi16_mapping() {
+        let linguist = linguist();
+        let colors = linguist.colors().unwrap();
+        for (language, rgb, lab) in [
+            ("Rust", 7, 7),
+            ("Python", 6, 6),
+            ("Go", 14, 6),
+            ("JavaScript", 11, 11),
+            ("Java", 3, 3),
+            ("C", 8, 8),
+            ("Ruby", 1, 1),
+            ("HTML", 9, 9),
+        ] {
+            let color = colors.ranked(language)[0].color.color();
+            for (space, index) in [(ColorSpace::RGB, rgb), (ColorSpace::Lab, lab)] {
+                let nearest = nearest_in_palette(&color, Palette::Ansi16, space);
+                assert_eq!(nearest.index, index, "{language} in {space:?}");
+            }
+            let options = RenderOptions::new().palette(Palette::Ansi16);
+            let rendered = TermColor::from(color).render(&options);
+            assert!(rendered.contains(&format!("ansi {rgb}")), "{rendered:?}");
+        }
+    }
+
+    /// Typos find the languages meant through names, aliases, and
+    /// extensions, closest first, and nothing close finds nothing.
+    #[test]
+    fn suggestions() {
+        let linguist = linguist();
+        let colors = linguist.colors().unwrap();
+        for (typo, suggested) in [
+            ("pyton", "Python"),
+            ("russt", "Rust"),
+            ("javascrpt", "JavaScript"),
+            ("golan", "Go"),
+            ("typescrpt", "TypeScript"),
+            ("pyw3", "Python"),
+        ] {
+            assert!(colors.ranked(typo).is_empty(), "{typo} finds something");
+            let suggestions = colors.suggest(typo, 3);
+            assert_eq!(
+                suggestions.first(),
+                Some(&suggested),
+                "{typo}: {suggestions:?}"
+            );
+            assert!(suggestions.len() <= 3, "{typo}: {suggestions:?}");
+        }
+        assert_eq!(colors.suggest("pyton", 1), ["Python"]);
+        assert!(colors.suggest("zzzzzzzzzz", 3).is_empty());
+    }
+
+    /// Without the grayscale ramp, grays are found in the cube, and without
+    /// the system colors, their colors in the cube too, still by their xterm
+    /// indices rather than their places in the range.
+    #[test]
+    fn skipped_ranges() {
+        let nearest = |hex: &str, system, grayscale| {
+            let color = Color::from_hex(hex).unwrap();
+            let palette = Palette::xterm_without(system, grayscale);
+            let found = nearest_in_palette(&color, palette, ColorSpace::RGB);
+            (found.index, TermColor::from(found.color).hex())
+        };
+        assert_eq!(Palette::xterm_without(false, false), Palette::Xterm256);
+        assert_eq!(nearest("#8a8a8a", false, false), (245, "#8a8a8a".into()));
+        assert_eq!(nearest("#8a8a8a", false, true), (102, "#878787".into()));
+        // xterm 8 is that gray, unless the system colors are left out too
+        assert_eq!(nearest("#808080", false, true), (8, "#808080".into()));
+        assert_eq!(nearest("#808080", true, true), (102, "#878787".into()));
+        assert_eq!(nearest("#767676", true, false), (243, "#767676".into()));
+        assert_eq!(nearest("#767676", true, true), (102, "#878787".into()));
+        assert_eq!(nearest("#121212", true, true), (16, "#000000".into()));
+        assert_eq!(nearest("#800000", false, false), (1, "#800000".into()));
+        assert_eq!(nearest("#800000", true, false), (88, "#870000".into()));
+        // the ends of the range are in it
+        assert_eq!(nearest("#000000", true, true).0, 16);
+        assert_eq!(nearest("#ffffff", true, true).0, 231);
+        assert_eq!(nearest("#eeeeee", true, false).0, 255);
+
+        let xterm = |hex: &str, system, grayscale| {
+            let color = TermColor::from(Color::from_hex(hex).unwrap());
+            let options = RenderOptions {
+                palette: Palette::xterm_without(system, grayscale),
+                ..Default::default()
+            };
+            color.resolve(&options).nearest.index
+        };
+        assert_eq!(xterm("#8a8a8a", false, false), 245);
+        assert_eq!(xterm("#8a8a8a", true, true), 102);
+    }
+
+    /// Black text on colors above the luminance where black and white have
+    /// the same contrast, about 0.179, between the grays `#757575` and
+    /// `#767676`, and white text below it.
+    #[test]
+    fn text_on_backgrounds() {
+        let text_on = |hex: &str| {
+            let color = TermColor::from(Color::from_hex(hex).unwrap());
+            TermColor::from(color.best_text_on()).hex()
+        };
+        for (background, text) in [
+            ("#757575", "#ffffff"),
+            ("#767676", "#000000"),
+            ("#000000", "#ffffff"),
+            ("#ffffff", "#000000"),
+            ("#dea584", "#000000"),
+            ("#3572a5", "#ffffff"),
+            // pure red and blue, either side despite nearly the same lightness
+            ("#ff0000", "#000000"),
+            ("#0000ff", "#ffffff"),
+            // green is lighter than its HSL lightness suggests
+            ("#008a00", "#000000"),
+            ("#008900", "#ffffff"),
+        ] {
+            assert_eq!(text_on(background), text, "on {background}");
+            let contrast = |text: &str| {
+                let text = Color::from_hex(text).unwrap();
+                color_math::contrast_ratio(&Color::from_hex(background).unwrap(), &text)
+            };
+            let other = if text == "#000000" {
+                "#ffffff"
+            } else {
+                "#000000"
+            };
+            assert!(contrast(text) >= contrast(other), "on {background}");
+        }
+    }
+
+    /// Adjustments are in HSL percentage points, stop at the ends of the
+    /// range, and change nothing at 0.
+    #[test]
+    fn adjustments() {
+        let term = |hex: &str| TermColor::from(Color::from_hex(hex).unwrap());
+        let rust = term("#dea584");
+        let [h, s, l] = rust.hsl();
+        // as near as 8-bit channels come
+        let close = |[h1, s1, l1]: [f64; 3], [h2, s2, l2]: [f64; 3]| {
+            (h1 - h2).abs() < 1.0 && (s1 - s2).abs() < 0.01 && (l1 - l2).abs() < 0.01
+        };
+        assert!(close(rust.lighten(10.0).hsl(), [h, s, l + 0.1]));
+        assert!(close(rust.darken(10.0).hsl(), [h, s, l - 0.1]));
+        assert!(close(rust.saturate(-20.0).hsl(), [h, s - 0.2, l]));
+        assert!(close(rust.lighten(10.0).darken(10.0).hsl(), rust.hsl()));
+
+        // at or past 100% lightness, white; at or past 0%, black
+        for amount in [100.0, 31.0, 500.0] {
+            assert_eq!(rust.lighten(amount).hex(), "#ffffff", "{amount}");
+            assert_eq!(rust.darken(amount + l * 100.0).hex(), "#000000", "{amount}");
+        }
+        assert_eq!(term("#ffffff").lighten(50.0).hex(), "#ffffff");
+        assert_eq!(term("#000000").darken(50.0).hex(), "#000000");
+        assert_eq!(rust.lighten(-500.0).hex(), "#000000");
+        // saturation stops at the ends too, gray at 0%
+        let gray = rust.saturate(-200.0).hsl();
+        assert!(gray[1] == 0.0 && (gray[2] - l).abs() < 0.005, "{gray:?}");
+        assert_eq!(rust.saturate(200.0).hsl()[1], rust.saturate(100.0).hsl()[1]);
+        assert!((rust.saturate(100.0).hsl()[1] - 1.0).abs() < 0.005);
+
+        // 0 leaves every color as it is, without rounding through HSL
+        for hex in ["#dea584", "#3572a5", "#000000", "#ffffff", "#010203"] {
+            let color = term(hex);
+            for adjusted in [color.lighten(0.0), color.darken(0.0), color.saturate(0.0)] {
+                assert_eq!(adjusted, color, "{hex}");
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! Bounds on the `languages.yml` input, so that a wrong URL or a hostile mirror
+//! fails quickly instead of exhausting memory.
+//!
+//! Pathological YAML such as deeply nested or exponentially expanding anchors
+//! is already rejected by `serde_yaml`'s own recursion and repetition limits.
+
+use std::io::Read;
+
+use crate::{Error, Linguist};
+
+/// Limits applied when loading [`Linguist`] data.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    max_bytes: u64,
+    max_languages: usize,
+    max_list_len: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_bytes: 10 * 1024 * 1024,
+            max_languages: 5_000,
+            max_list_len: 1_000,
+        }
+    }
+}
+
+impl Limits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The largest input accepted, in bytes. Default: 10 MiB
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// The most languages accepted. Default: 5000
+    pub fn max_languages(mut self, max_languages: usize) -> Self {
+        self.max_languages = max_languages;
+        self
+    }
+
+    /// The most extensions, aliases, filenames, or interpreters accepted for
+    /// a single language. Default: 1000
+    pub fn max_list_len(mut self, max_list_len: usize) -> Self {
+        self.max_list_len = max_list_len;
+        self
+    }
+
+    /// Read all of `reader`, failing as soon as more than `max_bytes` arrive.
+    pub(crate) fn read<R: Read>(&self, reader: R) -> Result<String, Error> {
+        let mut text = String::new();
+        reader.take(self.max_bytes + 1).read_to_string(&mut text)?;
+        if text.len() as u64 > self.max_bytes {
+            return Err(Error::TooLarge {
+                len: None,
+                limit: self.max_bytes,
+            });
+        }
+        Ok(text)
+    }
+
+    /// Check an input size announced ahead of time, e.g. by `Content-Length`.
+    pub(crate) fn check_len(&self, len: u64) -> Result<(), Error> {
+        if len > self.max_bytes {
+            return Err(Error::TooLarge {
+                len: Some(len),
+                limit: self.max_bytes,
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check(&self, linguist: &Linguist) -> Result<(), Error> {
+        if linguist.0.len() > self.max_languages {
+            return Err(Error::TooManyLanguages {
+                count: linguist.0.len(),
+                limit: self.max_languages,
+            });
+        }
+        for lang in linguist.0.values() {
+            let lists = [
+                ("extensions", lang.extensions.len()),
+                ("aliases", lang.aliases.len()),
+                ("filenames", lang.filenames.len()),
+                ("interpreters", lang.interpreters.len()),
+            ];
+            for (field, len) in lists {
+                if len > self.max_list_len {
+                    return Err(Error::TooManyEntries {
+                        language: lang.name.clone(),
+                        field,
+                        count: len,
+                        limit: self.max_list_len,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[test]
+    fn yaml_bomb() {
+        let started = Instant::now();
+        let bomb = include_str!("../tests/fixtures/yaml-bomb.yml");
+        let Err(Error::Parse(err)) = bomb.parse::<Linguist>() else {
+            panic!("the bomb was parsed");
+        };
+        assert!(
+            err.to_string().contains("repetition limit exceeded"),
+            "{err}"
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn too_large() {
+        let yaml = "Rust:\n  color: \"#dea584\"\n";
+        let limits = Limits::new().max_bytes(8);
+        let Err(Error::TooLarge { len: None, limit }) =
+            Linguist::from_reader_with_limits(yaml.as_bytes(), &limits)
+        else {
+            panic!("more than 8 bytes were read");
+        };
+        assert_eq!(limit, 8);
+        assert!(limits.check_len(8).is_ok());
+        assert!(matches!(
+            limits.check_len(9),
+            Err(Error::TooLarge {
+                len: Some(9),
+                limit: 8
+            })
+        ));
+    }
+
+    #[test]
+    fn too_many() {
+        let yaml = "Rust:\n  extensions: [.rs, .rs.in]\nGo:\n  extensions: [.go]\n";
+        let read = |limits: &Limits| Linguist::from_reader_with_limits(yaml.as_bytes(), limits);
+        assert!(read(&Limits::new().max_languages(2).max_list_len(2)).is_ok());
+
+        let Err(Error::TooManyLanguages { count, limit }) = read(&Limits::new().max_languages(1))
+        else {
+            panic!("more than 1 language was accepted");
+        };
+        assert_eq!((count, limit), (2, 1));
+
+        let Err(Error::TooManyEntries {
+            language,
+            field,
+            count,
+            limit,
+        }) = read(&Limits::new().max_list_len(1))
+        else {
+            panic!("more than 1 extension was accepted");
+        };
+        assert_eq!(
+            (&*language, field, count, limit),
+            ("Rust", "extensions", 2, 1)
+        );
+    }
+}
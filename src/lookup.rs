@@ -0,0 +1,154 @@
+//! One call from a language name to its color, for scripts that do not need
+//! anything else from this crate.
+//!
+//! ```text
+//! let rust = linguist_termcolor::lookup("rust")?;
+//! assert_eq!(rust.hex, "#dea584");
+//! assert_eq!(rust.rgb, (0xde, 0xa5, 0x84));
+//! assert_eq!(rust.xterm_index, 180);
+//! ```
+//!
+//! Both functions block: the first [`lookup`] in a process downloads
+//! `languages.yml` from GitHub, and every [`lookup_with`] fetches from its
+//! configured source.
+//!
+//! For more than the best match, [`global`] hands out the [`ColorMap`] that
+//! [`lookup`] uses, e.g. to every worker thread of a program:
+//!
+//! ```text
+//! let colors = linguist_termcolor::global()?;
+//! let ranked = colors.ranked("rust");
+//! ```
+
+use std::sync::Arc;
+
+use color_art::ColorSpace;
+use once_cell::sync::OnceCell;
+
+use crate::{
+    limits::Limits, metric::Metric, source::LinguistSource, ColorMap, ColorMapOptions, Error,
+    Linguist, LANGUAGES_YML,
+};
+
+/// The best match for a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lookup {
+    /// The language name as Linguist writes it, such as `Rust`.
+    pub name: String,
+    /// The color in lowercase hex notation, e.g. `#dea584`.
+    pub hex: String,
+    pub rgb: (u8, u8, u8),
+    /// The nearest xterm color.
+    pub xterm_index: usize,
+}
+
+/// Options for [`lookup_with`].
+#[derive(Debug, Clone)]
+pub struct LookupOptions {
+    source: String,
+    limits: Limits,
+    color_space: Metric,
+    index: ColorMapOptions,
+}
+
+impl Default for LookupOptions {
+    fn default() -> Self {
+        Self {
+            source: LANGUAGES_YML.to_string(),
+            limits: Limits::default(),
+            color_space: Metric::Space(ColorSpace::RGB),
+            index: ColorMapOptions::default(),
+        }
+    }
+}
+
+impl LookupOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Where to fetch `languages.yml` from. Default: [`LANGUAGES_YML`]
+    pub fn source(mut self, url: impl Into<String>) -> Self {
+        self.source = url.into();
+        self
+    }
+
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The color space in which to find the nearest xterm color, or another
+    /// [`Metric`]. Default: RGB
+    pub fn color_space(mut self, color_space: impl Into<Metric>) -> Self {
+        self.color_space = color_space.into();
+        self
+    }
+
+    pub fn index(mut self, index: ColorMapOptions) -> Self {
+        self.index = index;
+        self
+    }
+}
+
+static COLORS: OnceCell<Arc<ColorMap<'static>>> = OnceCell::new();
+
+/// The [`ColorMap`] shared by the whole process, fetched from
+/// [`LANGUAGES_YML`] and indexed on the first call. Later calls, from any
+/// thread, get the same map; cloning the [`Arc`] is cheap.
+///
+/// Threads calling this while the first call is still fetching wait for it
+/// instead of fetching again. If fetching fails, that call returns the error,
+/// and the next call, possibly one that was waiting, tries again.
+pub fn global() -> Result<Arc<ColorMap<'static>>, Error> {
+    global_with(Linguist::builder())
+}
+
+/// Like [`global`], but loading the data from `source` if it is not loaded
+/// yet, e.g. a pinned ref or a local file.
+///
+/// Only the call that loads the data uses its `source`: once any call to
+/// [`global`], [`global_with`], or [`lookup`] has succeeded, every later call
+/// gets that map, and `source` is dropped unused. To control where the data
+/// comes from, call this before anything else in the process does.
+pub fn global_with(source: LinguistSource) -> Result<Arc<ColorMap<'static>>, Error> {
+    let colors = COLORS.get_or_try_init(|| source.build()?.into_colors().map(Arc::new))?;
+    Ok(Arc::clone(colors))
+}
+
+/// The best match for `query` with default options, as ranked by
+/// [`ColorMap::ranked`]. Fails if nothing matches.
+///
+/// The data is that of [`global`]: fetched and indexed on the first call and
+/// shared by every later call in the process, from any thread. If fetching
+/// fails, the next call tries again.
+pub fn lookup(query: &str) -> Result<Lookup, Error> {
+    let colors = global()?;
+    best(&colors, query, Metric::Space(ColorSpace::RGB))
+}
+
+/// Like [`lookup`], but with `options`.
+///
+/// Nothing is shared between calls: each one fetches and indexes the data
+/// again. To look up many queries, build a [`ColorMap`] once instead.
+pub fn lookup_with(query: &str, options: &LookupOptions) -> Result<Lookup, Error> {
+    let linguist = Linguist::from_url_with_limits(&options.source, &options.limits)?;
+    let colors = linguist.colors_with_options(&options.index)?;
+    best(&colors, query, options.color_space)
+}
+
+fn best(colors: &ColorMap<'_>, query: &str, color_space: Metric) -> Result<Lookup, Error> {
+    let best = colors
+        .ranked(query)
+        .into_iter()
+        .next()
+        .ok_or_else(|| Error::NoMatch {
+            query: query.to_string(),
+        })?;
+    Ok(Lookup {
+        name: best.name.into_owned(),
+        hex: best.color.hex(),
+        rgb: best.color.rgb(),
+        xterm_index: best.color.xterm_index(color_space),
+    })
+}
@@ -0,0 +1,474 @@
+//! Faster nearest xterm colors, for callers that map very many colors with
+//! the same [`Metric`].
+//!
+//! A [`XtermLut`] converts all 256 xterm colors into the color space once.
+//!
+//! In RGB, [`XtermLut::lookup`] needs no table: the nearest of the 6×6×6 cube
+//! is found channel by channel, the nearest of the grayscale ramp from the
+//! mean, and only those two and the 16 standard colors are compared.
+//!
+//! With other metrics, it divides RGB into 32×32×32 cells of 8 values per
+//! channel. When all eight corners of a cell have the same nearest xterm
+//! color, it answers with that color for the whole cell; otherwise it falls
+//! back to [`XtermLut::exact`]. Cells are filled on first use. The answer is
+//! exact for colors whose channels are multiples of 8, and for nearly all
+//! others, but a color close to the boundary between two xterm colors can get
+//! its neighbor.
+//!
+//! Rendering and [`TermColor::xterm_index`](crate::TermColor::xterm_index) use
+//! [`XtermLut::exact`], so their answers stay those of a full scan.
+//!
+//! For any [`Palette`], a [`NearestColorIndex`] converts its colors once, and
+//! answers exactly as a full scan does, with the shortcut above in RGB. Every
+//! nearest color this crate finds, e.g. for rendering and
+//! [`nearest_xterm`](crate::nearest_xterm), goes through one shared per
+//! palette and metric, so converting is left to the first call.
+
+use std::{ops::RangeInclusive, sync::Mutex};
+
+use color_art::{Color, ColorSpace};
+use once_cell::sync::{Lazy, OnceCell};
+
+use crate::{metric::Metric, Palette, XtermMatch};
+
+/// Channel values per cell.
+const STEP: usize = 8;
+
+/// Cells per channel.
+const CELLS: usize = 256 / STEP;
+
+/// Corners per channel, the last one at 255.
+const CORNERS: usize = CELLS + 1;
+
+/// The channel values of the 6×6×6 cube, xterm 16 to 231.
+const CUBE: [f64; 6] = [0.0, 95.0, 135.0, 175.0, 215.0, 255.0];
+
+/// See the [module docs](self).
+#[derive(Debug)]
+pub struct XtermLut {
+    metric: Metric,
+    /// the xterm colors, in the space of `metric`
+    palette: Vec<Vec<f64>>,
+    /// allocated on first lookup, and never in RGB
+    table: OnceCell<Table>,
+}
+
+#[derive(Debug)]
+struct Table {
+    /// the nearest xterm color of each corner
+    corners: Vec<OnceCell<u8>>,
+    /// the nearest xterm color of each cell, if all of its corners agree
+    cells: Vec<OnceCell<Option<u8>>>,
+}
+
+impl XtermLut {
+    /// A table for finding nearest colors with `metric`, such as a
+    /// [`ColorSpace`].
+    pub fn new(metric: impl Into<Metric>) -> Self {
+        let metric = metric.into();
+        let palette = Palette::Xterm256.colors();
+        Self {
+            metric,
+            palette: palette.iter().map(|c| metric.coordinates(c)).collect(),
+            table: OnceCell::new(),
+        }
+    }
+
+    /// The table for `metric` shared by the whole process, e.g. by rendering.
+    pub(crate) fn shared(metric: Metric) -> &'static XtermLut {
+        static SHARED: Lazy<Mutex<Vec<&'static XtermLut>>> = Lazy::new(Default::default);
+        let mut shared = SHARED.lock().unwrap_or_else(|err| err.into_inner());
+        match shared.iter().find(|lut| lut.metric == metric) {
+            Some(lut) => lut,
+            None => {
+                let lut = &*Box::leak(Box::new(XtermLut::new(metric)));
+                shared.push(lut);
+                lut
+            }
+        }
+    }
+
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    fn is_rgb(&self) -> bool {
+        matches!(
+            self.metric,
+            Metric::Space(ColorSpace::RGB | ColorSpace::HEX)
+        )
+    }
+
+    /// The index of the xterm color nearest `color`, see the
+    /// [module docs](self) for how exact it is.
+    pub fn lookup(&self, color: &Color) -> u8 {
+        if self.is_rgb() {
+            return self.exact(color);
+        }
+        let table = self.table.get_or_init(|| Table {
+            corners: (0..CORNERS.pow(3)).map(|_| OnceCell::new()).collect(),
+            cells: (0..CELLS.pow(3)).map(|_| OnceCell::new()).collect(),
+        });
+        let [r, g, b] = [color.red(), color.green(), color.blue()].map(|c| c as usize / STEP);
+        let cell = table.cells[(r * CELLS + g) * CELLS + b].get_or_init(|| {
+            let nearest = self.corner(table, [r, g, b]);
+            let corners = (1..8).map(|i| [r + (i & 1), g + (i >> 1 & 1), b + (i >> 2)]);
+            corners
+                .map(|corner| self.corner(table, corner))
+                .all(|other| other == nearest)
+                .then_some(nearest)
+        });
+        match cell {
+            Some(nearest) => *nearest,
+            None => self.exact(color),
+        }
+    }
+
+    /// The index of the xterm color nearest `color`, the same as that of
+    /// [`nearest_xterm`](crate::nearest_xterm), including which of two equally
+    /// near colors wins.
+    pub fn exact(&self, color: &Color) -> u8 {
+        self.exact_of(&self.metric.coordinates(color))
+    }
+
+    /// The index of the nearest of the xterm colors `indices`, as a full scan
+    /// of them finds it.
+    pub fn exact_in(&self, color: &Color, indices: RangeInclusive<u8>) -> u8 {
+        let color = self.metric.coordinates(color);
+        self.nearest_of(&color, indices.map(usize::from))
+    }
+
+    /// [`XtermLut::exact`] of a color already converted with the metric.
+    fn exact_of(&self, color: &[f64]) -> u8 {
+        match self.is_rgb() {
+            true => self.nearest_rgb(color),
+            false => self.nearest_of(color, 0..256),
+        }
+    }
+
+    /// The nearest of the xterm colors `candidates`, in ascending order, the
+    /// first one winning a tie as in a full scan.
+    fn nearest_of(&self, color: &[f64], candidates: impl IntoIterator<Item = usize>) -> u8 {
+        let mut nearest = (0, f64::INFINITY);
+        for index in candidates {
+            let other = &self.palette[index];
+            let distance = self.metric.distance_of(other, color);
+            if distance < nearest.1 {
+                nearest = (index, distance);
+            }
+        }
+        nearest.0 as u8
+    }
+
+    fn nearest_rgb(&self, color: &[f64]) -> u8 {
+        // the nearest value for each channel is also the nearest in the cube,
+        // the lower one of two equally near values having the lower index
+        let cube = color.iter().fold(0, |index, &c| {
+            let level = (0..6)
+                .min_by(|&i, &j| (CUBE[i] - c).abs().total_cmp(&(CUBE[j] - c).abs()))
+                .unwrap_or_default();
+            index * 6 + level
+        });
+        // the ramp is 8, 18, ..., 238, and the gray nearest a color is that
+        // nearest its mean
+        let mean = color.iter().sum::<f64>() / 3.0;
+        let gray = ((mean - 8.0) / 10.0).round().clamp(0.0, 23.0) as usize;
+        let grays = [gray.saturating_sub(1), gray, (gray + 1).min(23)];
+        let candidates = (0..16)
+            .chain([16 + cube])
+            .chain(grays.into_iter().map(|gray| 232 + gray));
+        let mut candidates: Vec<_> = candidates.collect();
+        candidates.dedup();
+        self.nearest_of(color, candidates)
+    }
+
+    fn corner(&self, table: &Table, [r, g, b]: [usize; 3]) -> u8 {
+        *table.corners[(r * CORNERS + g) * CORNERS + b].get_or_init(|| {
+            let [r, g, b] = [r, g, b].map(|c| (c * STEP).min(255) as u8);
+            self.exact(&Color::new(r, g, b, 1.0))
+        })
+    }
+}
+
+/// The colors of a [`Palette`] converted for a [`Metric`] once, for finding
+/// the nearest of them to many colors, see the [module docs](self).
+#[derive(Debug)]
+pub struct NearestColorIndex {
+    palette: Palette,
+    metric: Metric,
+    /// the index in escape sequences, the color, and its coordinates in the
+    /// space of `metric`, in the order of [`Palette::indexed`]
+    colors: Vec<(u8, Color, Vec<f64>)>,
+    /// for the shortcut in RGB, with [`Palette::Xterm256`]
+    lut: Option<&'static XtermLut>,
+}
+
+impl NearestColorIndex {
+    /// An index of the colors of `palette` for `metric`, such as a
+    /// [`ColorSpace`].
+    pub fn new(palette: Palette, metric: impl Into<Metric>) -> Self {
+        let metric = metric.into();
+        let colors = palette
+            .indexed()
+            .map(|(index, color)| (index as u8, *color, metric.coordinates(color)))
+            .collect();
+        let lut = (palette == Palette::Xterm256).then(|| XtermLut::shared(metric));
+        Self {
+            palette,
+            metric,
+            colors,
+            lut,
+        }
+    }
+
+    /// The index for `palette` and `metric` shared by the whole process, e.g.
+    /// by rendering.
+    pub(crate) fn shared(palette: Palette, metric: Metric) -> &'static NearestColorIndex {
+        static SHARED: Lazy<Mutex<Vec<&'static NearestColorIndex>>> = Lazy::new(Default::default);
+        let mut shared = SHARED.lock().unwrap_or_else(|err| err.into_inner());
+        let found = shared
+            .iter()
+            .find(|index| index.palette == palette && index.metric == metric);
+        match found {
+            Some(index) => index,
+            None => {
+                let index = &*Box::leak(Box::new(NearestColorIndex::new(palette, metric)));
+                shared.push(index);
+                index
+            }
+        }
+    }
+
+    pub fn palette(&self) -> Palette {
+        self.palette
+    }
+
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// The color of the palette nearest `color`, the first in the palette
+    /// among equally near ones, as [`nearest_in_palette`](crate::nearest_in_palette)
+    /// finds it.
+    pub fn nearest(&self, color: &Color) -> XtermMatch {
+        let color = self.metric.coordinates(color);
+        let at = match self.lut {
+            Some(lut) => lut.exact_of(&color) as usize,
+            None => {
+                let mut nearest = (0, f64::INFINITY);
+                for (at, (_, _, other)) in self.colors.iter().enumerate() {
+                    let distance = self.metric.distance_of(other, &color);
+                    if distance < nearest.1 {
+                        nearest = (at, distance);
+                    }
+                }
+                nearest.0
+            }
+        };
+        self.matched(at, &color)
+    }
+
+    /// The `n` colors of the palette nearest `color`, nearest first and the
+    /// lower index first among equally near ones, as
+    /// [`nearest_n_in_palette`](crate::nearest_n_in_palette) finds them.
+    pub fn nearest_n(&self, color: &Color, n: usize) -> Vec<XtermMatch> {
+        if n == 0 {
+            return Vec::new();
+        }
+        let color = self.metric.coordinates(color);
+        let mut matches = (0..self.colors.len())
+            .map(|at| self.matched(at, &color))
+            .collect::<Vec<_>>();
+        let order = |a: &XtermMatch, b: &XtermMatch| {
+            (a.distance.total_cmp(&b.distance)).then(a.index.cmp(&b.index))
+        };
+        // only the nearest `n` are sorted
+        if n < matches.len() {
+            matches.select_nth_unstable_by(n - 1, order);
+            matches.truncate(n);
+        }
+        matches.sort_unstable_by(order);
+        matches
+    }
+
+    fn matched(&self, at: usize, color: &[f64]) -> XtermMatch {
+        let (index, nearest, coordinates) = &self.colors[at];
+        XtermMatch {
+            index: *index,
+            color: *nearest,
+            distance: self.metric.distance_of(coordinates, color),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{metric::HslWeights, NamedPalette};
+
+    const METRICS: [Metric; 6] = [
+        Metric::Space(ColorSpace::RGB),
+        Metric::Space(ColorSpace::Lab),
+        Metric::Space(ColorSpace::HSL),
+        Metric::De2000,
+        Metric::LinearRgb,
+        Metric::WeightedHsl(HslWeights {
+            hue: 2.0,
+            saturation: 1.0,
+            lightness: 1.0,
+        }),
+    ];
+
+    /// Colors on a grid of `step`, with the channel values halfway between
+    /// two levels of the cube or the ramp, where ties are.
+    fn colors(step: usize) -> Vec<Color> {
+        let mut values = (0..=255).step_by(step).collect::<Vec<u8>>();
+        values.extend([47, 48, 115, 155, 195, 235, 13, 3]);
+        let mut colors = Vec::new();
+        for &r in &values {
+            for &g in &values {
+                for &b in &values {
+                    colors.push(Color::new(r, g, b, 1.0));
+                }
+            }
+        }
+        colors
+    }
+
+    /// The index of the nearest of `palette` and its distance, the first of
+    /// equally near ones, by comparing with every color.
+    fn scan(palette: Palette, metric: Metric) -> impl Fn(&Color) -> (u8, f64) {
+        let palette = palette
+            .indexed()
+            .map(|(index, other)| (index as u8, metric.coordinates(other)))
+            .collect::<Vec<_>>();
+        move |color| {
+            let color = metric.coordinates(color);
+            let mut nearest = (0, f64::INFINITY);
+            for (index, other) in &palette {
+                let distance = metric.distance_of(other, &color);
+                if distance < nearest.1 {
+                    nearest = (*index, distance);
+                }
+            }
+            nearest
+        }
+    }
+
+    #[test]
+    fn exact_is_a_full_scan() {
+        for metric in METRICS {
+            let lut = XtermLut::new(metric);
+            let scan = scan(Palette::Xterm256, metric);
+            // the shortcut in RGB is checked more closely
+            let step = if lut.is_rgb() { 15 } else { 51 };
+            for color in colors(step) {
+                let expected = scan(&color).0;
+                assert_eq!(lut.exact(&color), expected, "{metric:?} {}", color.hex());
+            }
+        }
+    }
+
+    #[test]
+    fn exact_in_scans_the_range() {
+        let lut = XtermLut::new(ColorSpace::Lab);
+        let palette = Palette::XtermRange {
+            first: 16,
+            last: 231,
+        };
+        let scan = scan(palette, lut.metric());
+        for color in colors(15) {
+            let expected = scan(&color).0;
+            assert_eq!(lut.exact_in(&color, 16..=231), expected, "{}", color.hex());
+        }
+    }
+
+    /// The table is exact on the corners of its cells, and off for only few
+    /// colors elsewhere.
+    #[test]
+    fn lookup_is_nearly_exact() {
+        for metric in [Metric::Space(ColorSpace::Lab), Metric::De2000] {
+            let lut = XtermLut::new(metric);
+            for color in colors(4 * STEP) {
+                let corner = [color.red(), color.green(), color.blue()];
+                if corner.iter().all(|&c| (c as usize).is_multiple_of(STEP)) {
+                    assert_eq!(lut.lookup(&color), lut.exact(&color), "{}", color.hex());
+                }
+            }
+            let colors = colors(17);
+            let off = colors
+                .iter()
+                .filter(|color| lut.lookup(color) != lut.exact(color))
+                .count();
+            assert!(
+                off * 100 < colors.len(),
+                "{metric:?}: {off} of {}",
+                colors.len()
+            );
+        }
+    }
+
+    #[test]
+    fn lookup_in_rgb_is_exact() {
+        let lut = XtermLut::new(ColorSpace::RGB);
+        for color in colors(15) {
+            assert_eq!(lut.lookup(&color), lut.exact(&color), "{}", color.hex());
+        }
+        assert!(lut.table.get().is_none());
+    }
+
+    #[test]
+    fn index_is_a_full_scan() {
+        let palettes = [
+            Palette::Xterm256,
+            Palette::Ansi16,
+            Palette::XtermRange {
+                first: 100,
+                last: 140,
+            },
+            Palette::Named(NamedPalette::solarized_dark()),
+        ];
+        for palette in palettes {
+            for metric in METRICS {
+                let index = NearestColorIndex::new(palette, metric);
+                let scan = scan(palette, metric);
+                for color in colors(51) {
+                    let (expected, distance) = scan(&color);
+                    let nearest = index.nearest(&color);
+                    let context = format!("{palette:?} {metric:?} {}", color.hex());
+                    assert_eq!(nearest.index, expected, "{context}");
+                    assert_eq!(nearest.distance, distance, "{context}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn nearest_n_is_sorted() {
+        let index = NearestColorIndex::new(Palette::Ansi16, ColorSpace::Lab);
+        let color = Color::new(53, 114, 165, 1.0);
+        assert!(index.nearest_n(&color, 0).is_empty());
+        let all = index.nearest_n(&color, 100);
+        assert_eq!(all.len(), 16);
+        assert!(all.windows(2).all(|w| w[0].distance <= w[1].distance));
+        assert_eq!(all[0].index, index.nearest(&color).index);
+        let first = index.nearest_n(&color, 3);
+        let indices = |matches: &[XtermMatch]| matches.iter().map(|m| m.index).collect::<Vec<_>>();
+        assert_eq!(indices(&first), indices(&all[..3]));
+    }
+
+    #[test]
+    fn shared_once() {
+        let metric = Metric::Space(ColorSpace::Lab);
+        assert!(std::ptr::eq(
+            XtermLut::shared(metric),
+            XtermLut::shared(metric)
+        ));
+        let index = NearestColorIndex::shared(Palette::Ansi16, metric);
+        assert!(std::ptr::eq(
+            index,
+            NearestColorIndex::shared(Palette::Ansi16, metric)
+        ));
+        assert_eq!((index.palette(), index.metric()), (Palette::Ansi16, metric));
+    }
+}
@@ -1,64 +1,6025 @@
-use clap::{Parser, Subcommand};
-use color_art::{Color, ColorSpace};
-use linguist_termcolor::{Linguist, TermColor};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    ffi::OsString,
+    io::{self, BufWriter, IsTerminal, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use clap::{
+    builder::TypedValueParser, parser::ValueSource, ArgMatches, CommandFactory, FromArgMatches,
+    Parser, Subcommand, ValueEnum,
+};
+use color_art::Color;
+use colored::Colorize;
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    terminal::{self, ClearType},
+};
+#[cfg(unix)]
+use linguist_termcolor::daemon;
+use linguist_termcolor::{
+    capability::{terminfo_max_colors, ColorSupport},
+    classify::QueryShape,
+    color_math::{color_family, oklab, relative_luminance, to_polar, Cvd, Family},
+    detect::Pipeline,
+    groups::{Group, Groups},
+    heuristics::{heuristics_yml_at, Heuristics, HEURISTICS_YML},
+    limits::Limits,
+    names::DisplayNames,
+    nearest_n_in_palette,
+    numbers::{Precision, Rounding},
+    scan::{ScanOptions, Tally},
+    slugify,
+    source::LinguistSource,
+    spaces::{metric_aliases, parse_metric, supported_metrics, UNSUPPORTED},
+    tab::Terminal,
+    themes::{NamedPalette, PaletteFormat, BASE16_SCHEMES},
+    Adjustment, ColorMap, ColorMapOptions, Depth, Error, ExplainEvent, HslWeights, Language,
+    LanguageType, Layer, Linguist, MatchKind, MatchVia, Metric, Palette, Ranked, RenderOptions,
+    Resolved, TermColor, XtermMatch,
+};
+use serde::{Deserialize, Deserializer, Serialize};
 
 fn main() -> anyhow::Result<()> {
     let Main {
         command,
         color_space,
-    } = Main::parse();
-    match command {
-        Commands::Xterm { colors } => xterm(colors, color_space),
-        Commands::Linguist { query } => linguist(query, color_space),
+        hue_weight,
+        saturation_weight,
+        lightness_weight,
+        color,
+        no_color,
+        color_depth,
+        names,
+        warn_delta,
+        no_warn,
+        source,
+        layer,
+        bg,
+        max_size,
+        cacert,
+        timeout,
+        proxy,
+        retries,
+        offline,
+        languages_file,
+        overrides,
+        no_heuristics,
+        heuristics_file,
+        fetch_heuristics,
+        linguist_ref,
+        format,
+        raw_numbers,
+        palette,
+        palette_file,
+        skip_system,
+        skip_grayscale,
+        swatch,
+        contrast_bg,
+        min_contrast,
+        lighten,
+        darken,
+        saturate,
+        simulate,
+        quiet,
+        verbose,
+        no_config: _,
+    } = Main::with_config()?;
+    log::set_max_level(match (quiet, verbose) {
+        (true, _) => log::LevelFilter::Warn,
+        (false, 0) => log::LevelFilter::Info,
+        (false, 1) => log::LevelFilter::Debug,
+        (false, _) => log::LevelFilter::Trace,
+    });
+    log::set_logger(&Logger).expect("no other logger should be set");
+    let color_space = match color_space {
+        Metric::WeightedHsl(weights) => Metric::WeightedHsl(HslWeights {
+            hue: hue_weight.unwrap_or(weights.hue),
+            saturation: saturation_weight.unwrap_or(weights.saturation),
+            lightness: lightness_weight.unwrap_or(weights.lightness),
+        }),
+        _ if hue_weight
+            .or(saturation_weight)
+            .or(lightness_weight)
+            .is_some() =>
+        {
+            anyhow::bail!(
+                "--hue-weight, --saturation-weight, and --lightness-weight need -c hsl-weighted"
+            )
+        }
+        color_space => color_space,
+    };
+    let data = Dataset {
+        source: source.as_deref(),
+        names: names.as_deref(),
+        limits: Limits::new().max_bytes(max_size),
+        cacert: cacert.as_deref(),
+        timeout: timeout.map(Duration::from_secs),
+        proxy,
+        retries,
+        offline,
+        languages_file: languages_file.as_deref(),
+        overrides: overrides.as_deref(),
+        git_ref: linguist_ref.as_deref(),
+        heuristics_file: heuristics_file.as_deref(),
+        fetch_heuristics,
+    };
+    let layer = match (layer, bg) {
+        (LayerChoice::Bg, _) | (_, true) => Layer::Background,
+        (LayerChoice::Fg, false) => Layer::Foreground,
+    };
+    let palette = match palette_file.unwrap_or(palette) {
+        Palette::Xterm256 => Palette::xterm_without(skip_system, skip_grayscale),
+        Palette::Ansi16 if skip_system => {
+            anyhow::bail!("--skip-system leaves no colors of --palette ansi16")
+        }
+        Palette::Named(_) if skip_system || skip_grayscale => {
+            anyhow::bail!("--skip-system and --skip-grayscale only apply to xterm and ANSI colors")
+        }
+        palette => palette,
+    };
+    let color = match no_color {
+        true => ColorChoice::Never,
+        false => color,
+    };
+    let env = |key: &str| std::env::var_os(key);
+    let colorize = color.resolve(env, io::stdout().is_terminal());
+    #[cfg(windows)]
+    let console = colorize.then(windows_console).flatten();
+    #[cfg(not(windows))]
+    let console = None;
+    let support = match (color_depth.support(), console) {
+        (Some(support), _) => support,
+        // colors asked for whatever the output is, e.g. when it is captured,
+        // are shown in full rather than as the terminal would
+        _ if color.forces(env) => ColorSupport::TrueColor,
+        (None, Some(support)) => support,
+        (None, None) => detect_color_support(),
+    };
+    let options = RenderOptions::new()
+        .color_space(color_space)
+        .warn_delta(match (warn_delta, no_warn) {
+            (_, true) => None,
+            (Some(threshold), false) => Some(threshold),
+            (None, false) => (verbose > 1 && palette.is_xterm()).then_some(WARN_DELTA),
+        })
+        .layer(layer)
+        .palette(palette)
+        .swatch(swatch)
+        .ensure_contrast(contrast_bg.map(|bg| bg.color()), min_contrast)
+        .adjust(Adjustment {
+            lighten: lighten.unwrap_or_default(),
+            darken: darken.unwrap_or_default(),
+            saturate: saturate.unwrap_or_default(),
+        })
+        .show_unadjusted(verbose > 0)
+        .simulate(simulate)
+        .assert_consistency(cfg!(debug_assertions));
+    let rendering = colorize
+        .then(|| support.rendering(options.clone()))
+        .flatten();
+    colored::control::set_override(rendering.is_some());
+    let options = rendering.unwrap_or(options);
+    let numbers = match raw_numbers {
+        true => Rounding::Full,
+        false => Rounding::Places,
+    };
+    let mut out = BufWriter::new(io::stdout().lock());
+    let result = match command {
+        Commands::Xterm {
+            colors,
+            stdin,
+            skip_missing,
+            languages,
+            candidates,
+        } => {
+            let flags = XtermFlags {
+                languages,
+                batch: stdin.then_some(Batch { skip_missing }),
+                format,
+                color_space,
+                layer,
+                candidates: candidates.map(|n| Candidates {
+                    n,
+                    palette,
+                    color_space,
+                    layer,
+                    numbers,
+                }),
+                numbers,
+            };
+            xterm(&mut out, colors, flags, &data, &options)
+        }
+        _ if !format.is_supported_by(&command) => Err(anyhow::anyhow!(
+            "--format {format} is only supported by `for` and `xterm`"
+        )),
+        Commands::Linguist {
+            dir: Some(dir),
+            top,
+            ..
+        } => match format {
+            OutputFormat::Text => {
+                let flags = ScanFlags {
+                    per_root: false,
+                    heuristics: !no_heuristics,
+                    respect_ignore: true,
+                    top,
+                };
+                scan(&mut out, vec![dir], flags, &data, &options)
+            }
+            _ => Err(anyhow::anyhow!(
+                "--dir cannot be combined with --format {format}"
+            )),
+        },
+        Commands::Linguist {
+            query,
+            stdin,
+            skip_missing,
+            each,
+            joined,
+            fail_on_ambiguous,
+            explain,
+            all,
+            max_results_per_keyword,
+            set_tab_color,
+            terminal,
+            dry_run,
+            via_daemon,
+            only,
+            single,
+            exact,
+            primary_only,
+            why,
+            types,
+            file,
+            limit,
+            sort,
+            candidates,
+            dir: None,
+            top: _,
+        } => linguist(
+            &mut out,
+            query,
+            QueryFlags {
+                each,
+                joined,
+                fail_on_ambiguous,
+                explain,
+                all,
+                max_results_per_keyword,
+                tab_color: set_tab_color.then_some(TabColor { terminal, dry_run }),
+                batch: stdin.then_some(Batch { skip_missing }),
+                via_daemon,
+                heuristics: !no_heuristics,
+                format,
+                only,
+                single,
+                color_space,
+                layer,
+                exact,
+                primary_only,
+                why,
+                types,
+                file,
+                limit,
+                sort,
+                candidates: candidates.map(|n| Candidates {
+                    n,
+                    palette,
+                    color_space,
+                    layer,
+                    numbers,
+                }),
+                numbers,
+            },
+            &data,
+            &options,
+        ),
+        Commands::Tree { name, ascii } => tree(&mut out, name, ascii, &data, &options),
+        Commands::Compare { args } => compare(&mut out, args, &data, layer),
+        Commands::Snippet {
+            format,
+            query,
+            text,
+            truecolor,
+            bg,
+        } => {
+            let snippet = Snippet {
+                format,
+                text,
+                bg,
+                depth: match truecolor {
+                    true => Depth::TrueColor,
+                    false => Depth::Xterm256,
+                },
+                layer,
+                color_space,
+            };
+            snippet.print(&mut out, &query.join(" "), &data)
+        }
+        Commands::Nearest { color, n } => {
+            nearest(&mut out, &color, n, &data, color_space, &options)
+        }
+        Commands::Export {
+            format,
+            preview_cmd,
+            mut langs,
+            only,
+            formats,
+            out: out_dir,
+            output,
+            check,
+            strict,
+            truecolor,
+            extensions,
+            phf,
+        } => {
+            langs.extend(only);
+            let flags = ExportFlags {
+                preview_cmd,
+                langs,
+                formats,
+                out_dir,
+                output,
+                check,
+                strict,
+                depth: match truecolor {
+                    true => Depth::TrueColor,
+                    false => Depth::Xterm256,
+                },
+                extensions,
+                phf,
+                numbers,
+            };
+            export(&mut out, format, flags, &data, color_space)
+        }
+        Commands::Paint { pairs, format } => paint(&mut out, pairs, format, &data, &options),
+        Commands::Distinct { langs, min_delta } => {
+            distinct(&mut out, langs, min_delta, &data, &options)
+        }
+        Commands::Coverage { ext_file } => coverage(&mut out, &ext_file, &data, &options),
+        Commands::List {
+            family,
+            prefix,
+            extensions,
+            no_color_only,
+            names_only,
+        } => {
+            let flags = ListFlags {
+                format,
+                family,
+                prefix,
+                extensions,
+                no_color_only,
+                names_only,
+            };
+            list(&mut out, flags, &data, &options)
+        }
+        Commands::Search { needle } => search(&mut out, &needle, &data, &options),
+        Commands::Info { language, index } => match language {
+            Some(language) => info_language(&mut out, &language, format, &data, &options),
+            None => info(&mut out, index, &data),
+        },
+        Commands::Exists { name } => match data.load() {
+            // nothing to print; the exit code is the answer
+            Ok((linguist, _)) if linguist.contains(&name) => Ok(()),
+            Ok(_) => std::process::exit(NotFound::EXIT_CODE),
+            // with the exit codes of other errors, so that 2 only means unknown
+            Err(err) => Err(err),
+        },
+        Commands::Scan {
+            paths,
+            per_root,
+            top,
+            no_ignore,
+        } => {
+            let flags = ScanFlags {
+                per_root,
+                heuristics: !no_heuristics,
+                respect_ignore: !no_ignore,
+                top,
+            };
+            scan(&mut out, paths, flags, &data, &options)
+        }
+        #[cfg(unix)]
+        Commands::Daemon {
+            action,
+            idle_timeout,
+        } => daemon(&mut out, action, idle_timeout, &data, color_space),
+        Commands::Completions { shell } => completions(&mut out, shell),
+        Commands::Capabilities => capabilities(&mut out, format),
+        Commands::Config {
+            action: ConfigAction::Path,
+        } => match config_path() {
+            Some(path) => Ok(writeln!(out, "{}", path.display())?),
+            None => Err(anyhow::anyhow!(
+                "no home directory to look up the config file in"
+            )),
+        },
+        Commands::PickXterm => pick_xterm(&mut out, &data, color_space),
+        Commands::Palette { hex, range, used } => {
+            chart(&mut out, hex, range, used, &data, color_space)
+        }
+    }
+    .and_then(|_| Ok(out.flush()?));
+    match result {
+        // the reader went away, e.g. `linguist-termcolor ... | head`
+        Err(err) if is_broken_pipe(&err) => Ok(()),
+        Err(err) if err.is::<Ambiguous>() => {
+            eprintln!("Error: {err}");
+            std::process::exit(Ambiguous::EXIT_CODE)
+        }
+        Err(err) if err.is::<Uncovered>() => {
+            let code = err.downcast_ref::<Uncovered>().unwrap().exit_code();
+            eprintln!("Error: {err}");
+            std::process::exit(code)
+        }
+        Err(err) => {
+            eprintln!("Error: {err}");
+            std::process::exit(exit_code(&err))
+        }
+        result => result,
+    }
+}
+
+/// The exit code of errors other than [`Ambiguous`] and [`Uncovered`], see
+/// [`EXIT_CODES`].
+fn exit_code(err: &anyhow::Error) -> i32 {
+    fn of(err: &Error) -> i32 {
+        match err {
+            // a data file that is missing or unreadable is as good as malformed
+            Error::File { source, .. } => match of(source) {
+                EXIT_OTHER => EXIT_DATA,
+                code => code,
+            },
+            Error::NoMatch { .. } => NotFound::EXIT_CODE,
+            Error::Fetch(_) | Error::FetchAttempts { .. } => EXIT_FETCH,
+            Error::Parse(_)
+            | Error::TooLarge { .. }
+            | Error::TooManyLanguages { .. }
+            | Error::TooManyEntries { .. }
+            | Error::InvalidColor { .. } => EXIT_DATA,
+            _ => EXIT_OTHER,
+        }
+    }
+    match err.downcast_ref::<Error>() {
+        Some(err) => of(err),
+        None if err.is::<NotFound>() => NotFound::EXIT_CODE,
+        None => EXIT_OTHER,
+    }
+}
+
+/// A usage error, which clap would exit with 2, taken here by [`NotFound`].
+const EXIT_USAGE: i32 = 1;
+
+/// Print a usage error, or `--help` or `--version`, as clap does, but exit
+/// with [`EXIT_USAGE`] rather than clap's 2.
+fn usage_error(err: clap::Error) -> ! {
+    if !err.use_stderr() {
+        err.exit()
+    }
+    let _ = err.print();
+    std::process::exit(EXIT_USAGE)
+}
+
+/// Any error without a code of its own, as for usage errors.
+const EXIT_OTHER: i32 = 1;
+
+/// Fetching `languages.yml` failed, e.g. with GitHub unreachable, and may not
+/// on retry.
+const EXIT_FETCH: i32 = 3;
+
+/// `languages.yml` or another data file could not be read, is malformed, or
+/// exceeds the limits.
+const EXIT_DATA: i32 = 4;
+
+const EXIT_CODES: &str = "\
+Exit codes:
+  0  success
+  1  usage error, or any other error
+  2  no language matches, or it has no color
+  3  fetching languages.yml failed, e.g. with GitHub unreachable
+  4  languages.yml or another data file is missing, unreadable, malformed, or exceeds the limits
+  5  --fail-on-ambiguous: more than one language ties for the best match
+  6  coverage: some extensions have no language with a color
+  7  coverage: no extension has a language with a color";
+
+/// No language matches a query, or none with a color; apart from other errors
+/// so that scripts can fall back to a default color.
+#[derive(Debug)]
+struct NotFound(String);
+
+impl NotFound {
+    /// Apart from 1 for usage and other errors.
+    const EXIT_CODE: i32 = 2;
+}
+
+impl std::fmt::Display for NotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for NotFound {}
+
+/// More than one language tied for the best match under `--fail-on-ambiguous`.
+#[derive(Debug)]
+struct Ambiguous {
+    query: String,
+    candidates: Vec<String>,
+}
+
+impl Ambiguous {
+    /// After the codes for failing to find or load data.
+    const EXIT_CODE: i32 = 5;
+
+    /// Group members tied with their parent language count as the parent
+    /// unless `all` is set.
+    fn check(colors: &ColorMap<'_>, query: &str, all: bool) -> Result<(), Ambiguous> {
+        let ranked = colors.ranked(query);
+        let Some(best) = ranked.first() else {
+            return Ok(());
+        };
+        let tied = ranked
+            .iter()
+            .take_while(|other| other.ties(best))
+            .map(|other| other.name.as_ref())
+            .collect::<Vec<_>>();
+        let candidates = tied
+            .iter()
+            .filter(|name| all || !colors.parent(name).is_some_and(|p| tied.contains(&p)))
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        match candidates.len() {
+            1 => Ok(()),
+            _ => Err(Ambiguous {
+                query: query.to_string(),
+                candidates,
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for Ambiguous {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} is ambiguous between: {}",
+            self.query,
+            self.candidates.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for Ambiguous {}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+/// `--color-depth`
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ColorDepth {
+    /// Guess from COLORTERM, TERM, and terminfo
+    Auto,
+    /// 24-bit colors
+    #[value(name = "24bit")]
+    TrueColor,
+    /// The 256 xterm colors
+    #[value(name = "8bit")]
+    Xterm256,
+    /// The 16 ANSI colors; the xterm colors printed stay the same
+    #[value(name = "4bit")]
+    Ansi16,
+    /// No colors, like --color never
+    None,
+}
+
+impl ColorDepth {
+    /// `None` for `auto`.
+    fn support(self) -> Option<ColorSupport> {
+        match self {
+            ColorDepth::Auto => None,
+            ColorDepth::TrueColor => Some(ColorSupport::TrueColor),
+            ColorDepth::Xterm256 => Some(ColorSupport::Xterm256),
+            ColorDepth::Ansi16 => Some(ColorSupport::Ansi16),
+            ColorDepth::None => Some(ColorSupport::None),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum TerminalChoice {
+    /// iTerm2
+    Iterm2,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum PaintFormat {
+    /// Print the text in color
+    Styled,
+    /// Print a `$'...'` string with the escape sequences, for bash and zsh scripts
+    Sh,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ExportFormat {
+    /// Every format below, each to its own file with --out
+    All,
+    /// `name<TAB>#hex<TAB>xterm` lines for `fzf --ansi --delimiter '\t'`
+    Fzf,
+    /// `--color-rust: #dea584;` custom properties in a `:root` rule
+    Css,
+    /// `$lang-rust: #dea584;` variables
+    Scss,
+    /// An array like the one `for --format json` prints
+    Json,
+    /// A standalone page with a swatch, the hex code, and the xterm color of each language
+    Html,
+    /// `*.rs=38;5;180:...` entries for LS_COLORS, from the extensions of each language
+    LsColors,
+    /// `highlight LinguistRust guifg=#dea584 ctermfg=180` commands
+    Vim,
+    /// A Lua table like `{ rust = { fg = "#dea584", ctermfg = 180 } }`, for Neovim
+    Lua,
+    /// A Rust array like `pub static LANGUAGE_COLORS: &[(&str, u32)]`, to embed in a program
+    Rust,
+    /// A Python dict like `LANGUAGE_COLORS = {"Rust": "#dea584"}`, to embed in a program
+    Python,
+    /// A JSON object like `{"Rust": "#dea584"}`
+    JsonMap,
+}
+
+impl ExportFormat {
+    /// Every format that `all` expands to.
+    const EVERY: [ExportFormat; 11] = [
+        ExportFormat::Fzf,
+        ExportFormat::Css,
+        ExportFormat::Scss,
+        ExportFormat::Json,
+        ExportFormat::Html,
+        ExportFormat::LsColors,
+        ExportFormat::Vim,
+        ExportFormat::Lua,
+        ExportFormat::Rust,
+        ExportFormat::Python,
+        ExportFormat::JsonMap,
+    ];
+
+    /// The file this format is written to with --out.
+    fn file_name(self) -> &'static str {
+        match self {
+            ExportFormat::All => unreachable!("expanded before writing"),
+            ExportFormat::Fzf => "linguist-colors.fzf.tsv",
+            ExportFormat::Css => "linguist-colors.css",
+            ExportFormat::Scss => "_linguist-colors.scss",
+            ExportFormat::Json => "linguist-colors.json",
+            ExportFormat::Html => "linguist-colors.html",
+            ExportFormat::LsColors => "linguist-colors.ls-colors",
+            ExportFormat::Vim => "linguist-colors.vim",
+            ExportFormat::Lua => "linguist-colors.lua",
+            ExportFormat::Rust => "linguist_colors.rs",
+            ExportFormat::Python => "linguist_colors.py",
+            ExportFormat::JsonMap => "linguist-colors.map.json",
+        }
+    }
+
+    /// Whether this format is a table from names to colors, which --extensions
+    /// turns into one from extensions.
+    fn is_table(self) -> bool {
+        matches!(
+            self,
+            ExportFormat::Rust | ExportFormat::Python | ExportFormat::JsonMap
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SnippetFormat {
+    /// A custom module for starship.toml, shown where there are files of the language
+    Starship,
+    /// The text in the color for a bash PS1, with the escapes inside `\[` and `\]`
+    Ps1,
+    /// The text in the color for a tmux status line, between `#[fg=colour180]` and `#[default]`
+    Tmux,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Colored text for people
+    Text,
+    /// JSON for scripts, without escape sequences
+    Json,
+    /// Only the 24-bit escape sequence of each color, e.g. for shell prompts
+    Escape,
+    /// Only the escape sequence of the nearest xterm color of each color
+    #[value(name = "escape-256")]
+    Escape256,
+    /// A GitHub-flavored Markdown table, e.g. for READMEs
+    Markdown,
+}
+
+impl OutputFormat {
+    /// Whether `command`, other than `xterm`, which checks for itself, can
+    /// print this format.
+    fn is_supported_by(self, command: &Commands) -> bool {
+        matches!(
+            (self, command),
+            (OutputFormat::Text, _)
+                | (_, Commands::Linguist { .. } | Commands::Exists { .. })
+                | (OutputFormat::Json, Commands::Capabilities)
+                | (
+                    OutputFormat::Json,
+                    Commands::Info {
+                        language: Some(_),
+                        ..
+                    }
+                )
+                | (OutputFormat::Markdown, Commands::List { .. })
+        )
+    }
+
+    /// The color depth of the escape sequences printed, if any.
+    fn depth(self) -> Option<Depth> {
+        match self {
+            OutputFormat::Text | OutputFormat::Json | OutputFormat::Markdown => None,
+            OutputFormat::Escape => Some(Depth::TrueColor),
+            OutputFormat::Escape256 => Some(Depth::Xterm256),
+        }
+    }
+}
+
+/// The order of results of `for`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SortOrder {
+    /// Best match first
+    Score,
+    /// By language name
+    Name,
+    /// By the hue of the color, e.g. for building palettes
+    Hue,
+    /// Darkest first
+    Luminance,
+}
+
+impl SortOrder {
+    /// Sort `found`, which is best match first, in this order.
+    fn sort(self, found: &mut [Ranked<'_>]) {
+        let luminance = |lang: &Ranked<'_>| relative_luminance(&lang.color.color());
+        match self {
+            SortOrder::Score => {}
+            SortOrder::Name => found.sort_by_cached_key(|lang| lang.name.to_lowercase()),
+            SortOrder::Hue => {
+                found.sort_by(|a, b| a.color.hsl()[0].total_cmp(&b.color.hsl()[0]));
+            }
+            SortOrder::Luminance => found.sort_by(|a, b| luminance(a).total_cmp(&luminance(b))),
+        }
+    }
+}
+
+/// The value that `for --only` prints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OnlyValue {
+    /// The hex code, e.g. `#dea584`
+    Hex,
+    /// The index of the nearest xterm color, or ANSI color with `--palette ansi16`, e.g. `180`
+    Xterm,
+    /// The red, green, and blue components, e.g. `222 165 132`
+    Rgb,
+}
+
+impl OnlyValue {
+    /// The value of a color as the text output shows it.
+    fn value(self, resolved: &Resolved) -> String {
+        let shown = TermColor::from(resolved.shown);
+        match self {
+            OnlyValue::Hex => shown.hex(),
+            OnlyValue::Xterm => resolved.nearest.index.to_string(),
+            OnlyValue::Rgb => {
+                let (r, g, b) = shown.rgb();
+                format!("{r} {g} {b}")
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+/// A color in `--format json`, e.g.
+/// `{"language": "python", "hex": "#3572a5", "palette": "xterm256", "index": 61, "rgb": [53, 114, 165]}`.
+#[derive(Serialize)]
+struct JsonColor<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<&'a str>,
+    /// For `for`, the place among the results, from 0 for the best match,
+    /// or in the order of `--sort`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rank: Option<usize>,
+    hex: String,
+    /// The color shown instead, if `--lighten`, `--min-contrast`, or the
+    /// like change it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shown_hex: Option<String>,
+    /// The `--palette` that `index` is in, e.g. `ansi16`
+    palette: &'static str,
+    /// The index in escape sequences of the nearest color of `palette` to the
+    /// color shown
+    index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    xterm_hex: Option<String>,
+    /// The CIEDE2000 difference between the color shown and `index`, as for
+    /// `--warn-delta`
+    xterm_delta: f64,
+    /// Whether `xterm_delta` is past `--warn-delta`, so that the text output
+    /// marks the color
+    approximation_poor: bool,
+    rgb: (u8, u8, u8),
+    /// With `--candidates`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    candidates: Option<Vec<JsonCandidate>>,
+    /// With `--simulate`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    simulated: Option<JsonSimulated>,
+    /// Found by an extension shared by several languages, as the primary one
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    primary: bool,
+    /// How the query matched the language
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    matched: Vec<JsonMatched<'a>>,
+    /// For languages
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    about: Option<JsonAbout<'a>>,
+}
+
+/// What Linguist has on a language besides its color and lists, in
+/// `--format json`, with `null` for what it does not have, e.g.
+/// `{"type": "programming", "group": null, "language_id": 327, "tm_scope": "source.rust"}`.
+#[derive(Serialize)]
+struct JsonAbout<'a> {
+    #[serde(rename = "type")]
+    language_type: Option<&'a str>,
+    group: Option<&'a str>,
+    language_id: Option<u64>,
+    tm_scope: Option<&'a str>,
+}
+
+impl<'a> JsonAbout<'a> {
+    fn of(lang: Language<'a>) -> Self {
+        Self {
+            language_type: lang.language_type().map(LanguageType::name),
+            group: lang.group(),
+            language_id: lang.language_id(),
+            tm_scope: lang.tm_scope(),
+        }
+    }
+}
+
+/// `info LANGUAGE` in `--format json`.
+#[derive(Serialize)]
+struct JsonInfo<'a> {
+    name: &'a str,
+    hex: Option<String>,
+    #[serde(flatten)]
+    about: JsonAbout<'a>,
+    aliases: &'a [String],
+    extensions: &'a [String],
+    filenames: &'a [String],
+    interpreters: &'a [String],
+}
+
+/// One way a query matched a language in `--format json`, as in
+/// [`ColorMap::provenance`], e.g. `{"via": "extension", "keyword": "py"}`.
+#[derive(Serialize)]
+struct JsonMatched<'a> {
+    via: &'static str,
+    keyword: Cow<'a, str>,
+}
+
+impl<'a> JsonMatched<'a> {
+    /// How `name` matched each of `queries`.
+    fn of(colors: &'a ColorMap<'_>, queries: &[String], name: &str) -> Vec<Self> {
+        (queries.iter())
+            .flat_map(|query| colors.provenance(query, name))
+            .map(|matched| Self {
+                via: matched.via.name(),
+                keyword: matched.keyword,
+            })
+            .collect()
+    }
+}
+
+/// A color as seen with `--simulate` in `--format json`, e.g.
+/// `{"cvd": "protanopia", "hex": "#b5aa82", "index": 144, "rgb": [181, 170, 130]}`,
+/// with `index` in the same palette as that of the color.
+#[derive(Serialize)]
+struct JsonSimulated {
+    cvd: &'static str,
+    hex: String,
+    index: usize,
+    rgb: (u8, u8, u8),
+}
+
+impl JsonSimulated {
+    fn of((kind, simulated, nearest): (Cvd, Color, XtermMatch)) -> Self {
+        let simulated = TermColor::from(simulated);
+        Self {
+            cvd: kind.name(),
+            hex: simulated.hex(),
+            index: nearest.index.into(),
+            rgb: simulated.rgb(),
+        }
+    }
+}
+
+/// One of `--candidates` in `--format json`, e.g.
+/// `{"index": 180, "hex": "#dfaf87", "distance": 12.25}`, with `index` in the
+/// same palette as that of the color.
+#[derive(Serialize)]
+struct JsonCandidate {
+    index: usize,
+    hex: String,
+    distance: f64,
+}
+
+/// `--candidates`: the xterm colors nearest a color, which may look better
+/// than the nearest one.
+#[derive(Clone, Copy)]
+struct Candidates {
+    n: usize,
+    palette: Palette,
+    color_space: Metric,
+    layer: Layer,
+    numbers: Rounding,
+}
+
+impl Candidates {
+    fn of(&self, color: &TermColor) -> Vec<XtermMatch> {
+        nearest_n_in_palette(&color.color(), self.palette, self.n, self.color_space)
+    }
+
+    /// One indented line per candidate: `██ xterm 180 #dfaf87 Δ12.25`.
+    fn write(&self, out: &mut impl Write, color: &TermColor) -> io::Result<()> {
+        for nearest in self.of(color) {
+            let hex = TermColor::from(nearest.color).hex();
+            let swatch = palette_swatch(self.palette, nearest.index.into(), &hex, self.layer);
+            let distance = format!("Δ{}", Precision::Distance.fixed(nearest.distance));
+            writeln!(out, "    {swatch} {}", distance.dimmed())?;
+        }
+        Ok(())
+    }
+
+    fn to_json(self, color: &TermColor) -> Vec<JsonCandidate> {
+        let json = |nearest: XtermMatch| JsonCandidate {
+            index: nearest.index.into(),
+            hex: TermColor::from(nearest.color).hex(),
+            distance: Precision::Distance.rounded(nearest.distance, self.numbers),
+        };
+        self.of(color).into_iter().map(json).collect()
+    }
+}
+
+impl<'a> JsonColor<'a> {
+    /// `color` as the text output shows it with `options`, with numbers
+    /// rounded as it does unless `numbers` says otherwise.
+    fn new(
+        language: Option<&'a str>,
+        color: &TermColor,
+        options: &RenderOptions,
+        numbers: Rounding,
+    ) -> Self {
+        let resolved = color.resolve(options);
+        let shown = (resolved.shown != resolved.original).then_some(resolved.shown);
+        Self {
+            language,
+            rank: None,
+            hex: color.hex(),
+            shown_hex: shown.map(|shown| TermColor::from(shown).hex()),
+            palette: options.palette_in_use().name(),
+            index: resolved.nearest.index.into(),
+            xterm_hex: None,
+            xterm_delta: Precision::DeltaE.rounded(resolved.delta_e(), numbers),
+            approximation_poor: color.is_poor_approximation(options),
+            rgb: color.rgb(),
+            candidates: None,
+            simulated: resolved.simulated.map(JsonSimulated::of),
+            primary: false,
+            matched: vec![],
+            about: None,
+        }
+    }
+}
+
+/// The results for one query of `for --each` in `--format json`.
+#[derive(Serialize)]
+struct JsonQuery<'a> {
+    query: &'a str,
+    results: serde_json::Value,
+}
+
+/// The escape sequence that `--format escape` prints for a color as the text
+/// output shows it, and for the background, that of
+/// [`TermColor::best_text_on`] after it.
+fn escape(resolved: &Resolved, layer: Layer, depth: Depth) -> String {
+    let color = TermColor::from(resolved.shown);
+    let index = resolved.nearest.index;
+    let text = color.best_text_on();
+    let (r, g, b) = (text.red(), text.green(), text.blue());
+    // 16 and 231 rather than 0 and 15, which terminal themes redefine
+    let xterm_text = if text.red() == 0 { 16 } else { 231 };
+    match (layer, depth) {
+        (Layer::Foreground, Depth::TrueColor) => color.ansi_fg_truecolor(),
+        (Layer::Foreground, Depth::Xterm256) => format!("\x1b[38;5;{index}m"),
+        (Layer::Background, Depth::TrueColor) => {
+            format!("{}\x1b[38;2;{r};{g};{b}m", color.ansi_bg_truecolor())
+        }
+        (Layer::Background, Depth::Xterm256) => {
+            format!("\x1b[48;5;{index}m\x1b[38;5;{xterm_text}m")
+        }
+    }
+}
+
+/// Write `value` as one line of JSON.
+fn write_json(out: &mut impl Write, value: &impl Serialize) -> anyhow::Result<()> {
+    serde_json::to_writer(&mut *out, value)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// The results of `for` in `--format json` or `escape`.
+enum Found<'a> {
+    /// Results for the whole query.
+    All(Vec<Ranked<'a>>),
+    /// Results for each argument, unless `--joined` and the arguments agree,
+    /// as in `rust .rs` and unlike `rust .py`.
+    Each(Vec<(&'a str, Vec<Ranked<'a>>)>),
+}
+
+impl<'a> Found<'a> {
+    fn new(colors: &'a ColorMap<'_>, query: &'a [String], each: bool) -> Self {
+        match resolve_mixed(colors, query) {
+            Some(Mixed::Agree { found, .. }) if !each => Found::All(found),
+            Some(Mixed::Disagree) => Found::each(colors, query),
+            _ if each => Found::each(colors, query),
+            _ => Found::All(colors.ranked(&query.join(" "))),
+        }
+    }
+
+    /// [`arrange`] every list of results.
+    fn arrange(&mut self, sort: SortOrder, limit: Option<usize>) {
+        match self {
+            Found::All(found) => arrange(found, sort, limit),
+            Found::Each(found) => {
+                for (_, found) in found {
+                    arrange(found, sort, limit);
+                }
+            }
+        }
+    }
+
+    fn each(colors: &'a ColorMap<'_>, query: &'a [String]) -> Self {
+        let found = query
+            .iter()
+            .map(|query| (query.as_str(), colors.ranked(query)));
+        Found::Each(found.collect())
+    }
+
+    /// What `--format json` prints: an array of [`JsonColor`], or of
+    /// [`JsonQuery`] for [`Found::Each`].
+    fn to_json(
+        &self,
+        (linguist, colors): (&Linguist, &ColorMap<'_>),
+        query: &[String],
+        options: &RenderOptions,
+        candidates: Option<Candidates>,
+        numbers: Rounding,
+    ) -> serde_json::Result<serde_json::Value> {
+        let results = |query: &[String], found: &[Ranked<'_>]| {
+            let found = found.iter().enumerate().map(|(rank, ranked)| JsonColor {
+                rank: Some(rank),
+                candidates: candidates
+                    .map(|candidates| candidates.to_json(&options.readable(&ranked.color))),
+                primary: ranked.primary,
+                matched: JsonMatched::of(colors, query, &ranked.name),
+                about: linguist.get(&ranked.name).map(JsonAbout::of),
+                ..JsonColor::new(Some(&ranked.name), &ranked.color, options, numbers)
+            });
+            serde_json::to_value(found.collect::<Vec<_>>())
+        };
+        match self {
+            // results that the arguments agreed on, as in `rust .rs`, are
+            // matched by each of them
+            Found::All(found) => match resolve_mixed(colors, query) {
+                Some(Mixed::Agree { .. }) => results(query, found),
+                _ => results(&[query.join(" ")], found),
+            },
+            Found::Each(found) => {
+                let found = found
+                    .iter()
+                    .map(|(query, found)| {
+                        Ok(JsonQuery {
+                            query,
+                            results: results(&[query.to_string()], found)?,
+                        })
+                    })
+                    .collect::<serde_json::Result<Vec<_>>>()?;
+                serde_json::to_value(found)
+            }
+        }
+    }
+
+    /// The escape sequences that `--format escape` prints, one per color, as
+    /// the text output shows the colors with `options`.
+    fn to_escapes(&self, layer: Layer, depth: Depth, options: &RenderOptions) -> Vec<String> {
+        self.flatten()
+            .into_iter()
+            .map(|Ranked { color, .. }| escape(&color.resolve(options), layer, depth))
+            .collect()
+    }
+
+    /// The results of every query, in order.
+    fn flatten(&self) -> Vec<&Ranked<'a>> {
+        match self {
+            Found::All(found) => found.iter().collect(),
+            Found::Each(found) => found.iter().flat_map(|(_, found)| found).collect(),
+        }
+    }
+
+    /// Fail as the text output of `for` would if nothing was found.
+    fn check_missing(&self) -> anyhow::Result<()> {
+        match self {
+            Found::All(found) if found.is_empty() => {
+                Err(NotFound("no colors found for this language".to_string()))?
+            }
+            Found::All(_) => Ok(()),
+            Found::Each(found) => {
+                let missing = found
+                    .iter()
+                    .filter(|(_, found)| found.is_empty())
+                    .map(|(query, _)| *query)
+                    .collect::<Vec<_>>();
+                if !missing.is_empty() {
+                    return Err(NotFound(format!(
+                        "no colors found for {} of {} queries: {}",
+                        missing.len(),
+                        found.len(),
+                        missing.join(", ")
+                    ))
+                    .into());
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn daemon(
+    out: &mut impl Write,
+    action: Option<DaemonAction>,
+    idle_timeout: u64,
+    data: &Dataset<'_>,
+    color_space: Metric,
+) -> anyhow::Result<()> {
+    let path = daemon::socket_path();
+    if let Some(DaemonAction::Stop) = action {
+        match daemon::request(&path, &daemon::Request::Stop)? {
+            Some(_) => writeln!(out, "stopped the daemon on {}", path.display())?,
+            None => anyhow::bail!("no daemon is listening on {}", path.display()),
+        }
+        return Ok(());
+    }
+    let server = daemon::Server::bind(&path)?
+        .idle_timeout((idle_timeout > 0).then(|| Duration::from_secs(idle_timeout)));
+    let (linguist, _) = data.load()?;
+    let colors = linguist.colors()?;
+    eprintln!("{}", format!("Listening on {}", path.display()).dimmed());
+    Ok(server.serve(|query| -> anyhow::Result<_> {
+        let color_space = match &query.space {
+            Some(name) => parse_metric(name)?,
+            None => color_space,
+        };
+        let sort = match &query.sort {
+            Some(name) => SortOrder::from_str(name, true).map_err(anyhow::Error::msg)?,
+            None => SortOrder::Score,
+        };
+        let args = query.args();
+        let mut found = Found::new(&colors, &args, query.each);
+        // the client applies `--limit`, so that it can tell what it left out
+        found.arrange(sort, None);
+        let options = RenderOptions::new().color_space(color_space);
+        Ok(found.to_json(
+            (&linguist, &colors),
+            &args,
+            &options,
+            None,
+            Rounding::Places,
+        )?)
+    })?)
+}
+
+/// Results from a running `daemon`, or `None` if there is none.
+#[cfg(unix)]
+fn query_daemon(
+    query: &[String],
+    each: bool,
+    color_space: Metric,
+    sort: SortOrder,
+) -> anyhow::Result<Option<serde_json::Value>> {
+    // the daemon is told metrics by name, which has no weights
+    if matches!(color_space, Metric::WeightedHsl(weights) if weights != HslWeights::default()) {
+        return Ok(None);
+    }
+    let request = daemon::Request::Query(daemon::Query {
+        q: query.join(" "),
+        args: Some(query.to_vec()),
+        each,
+        space: Some(color_space.name().to_string()),
+        sort: sort
+            .to_possible_value()
+            .map(|sort| sort.get_name().to_string()),
+    });
+    Ok(daemon::request(&daemon::socket_path(), &request)?)
+}
+
+#[cfg(not(unix))]
+fn query_daemon(
+    _: &[String],
+    _: bool,
+    _: Metric,
+    _: SortOrder,
+) -> anyhow::Result<Option<serde_json::Value>> {
+    Ok(None)
+}
+
+/// [`arrange`] for results from the daemon: keep the first `limit` of every
+/// query's results.
+fn limit_json(results: &mut serde_json::Value, each: bool, limit: Option<usize>) {
+    let Some(limit) = limit else {
+        return;
+    };
+    let Some(results) = results.as_array_mut() else {
+        return;
+    };
+    let truncate = |found: &mut Vec<serde_json::Value>| {
+        note_limited(found.len().saturating_sub(limit));
+        found.truncate(limit);
+    };
+    match each {
+        true => results
+            .iter_mut()
+            .filter_map(|query| query["results"].as_array_mut())
+            .for_each(truncate),
+        false => truncate(results),
+    }
+}
+
+/// [`Found::check_missing`] for results from the daemon.
+fn json_missing(results: &serde_json::Value) -> anyhow::Result<()> {
+    let results = results.as_array().map(Vec::as_slice).unwrap_or_default();
+    let missing = results
+        .iter()
+        .filter(|query| query["results"].as_array().is_some_and(Vec::is_empty))
+        .filter_map(|query| query["query"].as_str())
+        .collect::<Vec<_>>();
+    if results.is_empty() {
+        return Err(NotFound("no colors found for this language".to_string()).into());
+    }
+    if !missing.is_empty() {
+        return Err(NotFound(format!(
+            "no colors found for {} of {} queries: {}",
+            missing.len(),
+            results.len(),
+            missing.join(", ")
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum LayerChoice {
+    /// Colored text
+    Fg,
+    /// Colored background with contrasting text
+    Bg,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum TypeChoice {
+    Programming,
+    Markup,
+    Data,
+    Prose,
+}
+
+impl From<TypeChoice> for LanguageType {
+    fn from(choice: TypeChoice) -> Self {
+        match choice {
+            TypeChoice::Programming => LanguageType::Programming,
+            TypeChoice::Markup => LanguageType::Markup,
+            TypeChoice::Data => LanguageType::Data,
+            TypeChoice::Prose => LanguageType::Prose,
+        }
+    }
+}
+
+/// `--color-depth auto`: what the terminal supports, from `COLORTERM`, `TERM`,
+/// and terminfo.
+fn detect_color_support() -> ColorSupport {
+    let env = |key: &str| std::env::var_os(key);
+    let term = env("TERM").unwrap_or_default();
+    ColorSupport::detect(env, terminfo_max_colors(&term.to_string_lossy(), env))
+}
+
+/// Turn on the processing of escape sequences in the Windows console, which
+/// conhost leaves off, and return the colors it can then show: 256 before
+/// Windows 10 build 14931, which added 24-bit colors, and none if it cannot
+/// process escape sequences at all, before Windows 10, so that colors are left
+/// out rather than printed as `←[38;2;222;165;132m`. `None` if stdout is not a
+/// console.
+#[cfg(windows)]
+fn windows_console() -> Option<ColorSupport> {
+    use windows_sys::{
+        Wdk::System::SystemServices::RtlGetVersion,
+        Win32::System::{
+            Console::{
+                GetConsoleMode, GetStdHandle, SetConsoleMode, ENABLE_VIRTUAL_TERMINAL_PROCESSING,
+                STD_OUTPUT_HANDLE,
+            },
+            SystemInformation::OSVERSIONINFOW,
+        },
+    };
+
+    // SAFETY: the handle is only passed back to the console API, which checks
+    // it, and `version` is a zeroed OSVERSIONINFOW with its size set
+    unsafe {
+        let handle = GetStdHandle(STD_OUTPUT_HANDLE);
+        let mut mode: u32 = 0;
+        if GetConsoleMode(handle, &mut mode) == 0 {
+            // not a console, e.g. the pty of mintty or Git Bash, which shows
+            // escape sequences as they are
+            return None;
+        }
+        if mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0 {
+            // already on, e.g. in Windows Terminal
+            return Some(ColorSupport::TrueColor);
+        }
+        if SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) == 0 {
+            return Some(ColorSupport::None);
+        }
+        let mut version: OSVERSIONINFOW = std::mem::zeroed();
+        version.dwOSVersionInfoSize = std::mem::size_of::<OSVERSIONINFOW>() as u32;
+        match RtlGetVersion(&mut version) {
+            0 if version.dwBuildNumber < 14931 => Some(ColorSupport::Xterm256),
+            _ => Some(ColorSupport::TrueColor),
+        }
+    }
+}
+
+impl ColorChoice {
+    /// Whether to colorize output, in order of precedence:
+    ///
+    /// 1. `--color always` or `--color never`
+    /// 2. `CLICOLOR_FORCE` set to anything but `0`: on, even when piped
+    /// 3. `NO_COLOR` set and not empty: off
+    /// 4. `CLICOLOR=0`: off
+    /// 5. on if stdout is a terminal
+    ///
+    /// See <https://bixense.com/clicolors/> and <https://no-color.org/>.
+    fn resolve<F>(self, env: F, tty: bool) -> bool
+    where
+        F: Fn(&str) -> Option<OsString>,
+    {
+        match self {
+            ColorChoice::Never => return false,
+            _ if self.forces(&env) => return true,
+            _ => {}
+        }
+        if env("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            return false;
+        }
+        if env("CLICOLOR").is_some_and(|v| v == "0") {
+            return false;
+        }
+        tty
+    }
+
+    /// Whether colors are asked for whatever the output is, with `--color
+    /// always` or `CLICOLOR_FORCE`, so that what the terminal can show is not
+    /// guessed.
+    fn forces<F>(self, env: F) -> bool
+    where
+        F: Fn(&str) -> Option<OsString>,
+    {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => env("CLICOLOR_FORCE").is_some_and(|v| v != "0"),
+        }
+    }
+}
+
+fn is_broken_pipe(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<io::Error>()
+        .is_some_and(|err| err.kind() == io::ErrorKind::BrokenPipe)
+}
+
+/// Where to get the Linguist data from, and how to name languages in output.
+struct Dataset<'a> {
+    /// `-` for stdin, otherwise a URL. Default: GitHub
+    source: Option<&'a str>,
+    names: Option<&'a Path>,
+    limits: Limits,
+    /// A PEM file of extra root certificates to trust.
+    cacert: Option<&'a Path>,
+    timeout: Option<Duration>,
+    proxy: Option<reqwest::Url>,
+    /// How many times to try fetching again after a failure that may pass.
+    retries: u32,
+    /// Use the bundled snapshot instead of `source`.
+    offline: bool,
+    /// A local languages.yml to use instead of `source`.
+    languages_file: Option<&'a Path>,
+    /// A YAML file merged over the data, see [`Linguist::with_overrides`].
+    overrides: Option<&'a Path>,
+    /// The Linguist branch, tag, or commit to fetch instead of `master`.
+    git_ref: Option<&'a str>,
+    /// A local heuristics.yml to use instead of the built-in rules.
+    heuristics_file: Option<&'a Path>,
+    /// Fetch heuristics.yml as of `git_ref` instead of using the built-in rules.
+    fetch_heuristics: bool,
+}
+
+impl Dataset<'_> {
+    /// Whether this is the data that a `daemon` started without data flags
+    /// has loaded.
+    fn is_default(&self) -> bool {
+        !self.offline
+            && self.source.is_none()
+            && self.languages_file.is_none()
+            && self.overrides.is_none()
+            && self.names.is_none()
+            && self.git_ref.is_none()
+    }
+
+    fn load(&self) -> anyhow::Result<(Linguist, DisplayNames)> {
+        let source = Linguist::builder().limits(self.limits.clone());
+        let linguist = match (self.source, self.languages_file) {
+            _ if self.offline => bundled()?,
+            (_, Some(path)) => source.path(path).build()?,
+            (Some("-"), _) => source.reader(io::stdin()).build()?,
+            (Some(url), None) => self.fetching(source.url(url))?.build()?,
+            (None, None) => match self.git_ref {
+                Some(git_ref) => self.fetching(source.git_ref(git_ref))?.build()?,
+                None => self.fetching(source)?.build()?,
+            },
+        };
+        let linguist = match self.overrides {
+            Some(path) => linguist.with_overrides(path)?,
+            None => linguist,
+        };
+        let skipped = linguist.skipped_colors();
+        if !skipped.is_empty() {
+            let which = match log::max_level() >= log::LevelFilter::Debug {
+                true => {
+                    let each = skipped.iter().map(|err| err.to_string());
+                    format!(": {}", each.collect::<Vec<_>>().join("; "))
+                }
+                false => ", see -v".to_string(),
+            };
+            eprintln!(
+                "{} skipped the colors of {} language(s), which are not hex colors{which}",
+                "warning:".yellow(),
+                skipped.len()
+            );
+        }
+        let Some(path) = self.names else {
+            return Ok((linguist, DisplayNames::default()));
+        };
+        let (names, unknown) = DisplayNames::load(path, &linguist)?;
+        for key in unknown {
+            eprintln!(
+                "{} {}: no such language: {key}",
+                "warning:".yellow(),
+                path.display()
+            );
+        }
+        Ok((linguist, names))
+    }
+
+    /// A [`Pipeline`] over `linguist`, with the rules of `--heuristics-file` or
+    /// `--fetch-heuristics` if given.
+    fn pipeline<'l>(
+        &self,
+        linguist: &'l Linguist,
+        heuristics: bool,
+    ) -> anyhow::Result<Pipeline<'l>> {
+        let pipeline = Pipeline::new(linguist).heuristics(heuristics);
+        let rules = match (self.heuristics_file, self.fetch_heuristics) {
+            _ if !heuristics => return Ok(pipeline),
+            (Some(path), _) => Heuristics::from_path(path)?,
+            (None, true) => {
+                let url = match self.git_ref {
+                    Some(git_ref) => heuristics_yml_at(git_ref),
+                    None => HEURISTICS_YML.to_string(),
+                };
+                Heuristics::from_url_with_client(&url, &self.client()?, &self.limits)?
+            }
+            (None, false) => return Ok(pipeline),
+        };
+        Ok(pipeline.rules(rules))
+    }
+
+    /// `source` with the network options: timeout, proxy, `--cacert`, and
+    /// retries.
+    fn fetching(&self, mut source: LinguistSource) -> anyhow::Result<LinguistSource> {
+        if let Some(timeout) = self.timeout {
+            source = source.timeout(timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            source = source.proxy(proxy.clone());
+        }
+        for cert in self.root_certificates()? {
+            source = source.root_certificate(cert);
+        }
+        Ok(source.retries(self.retries))
+    }
+
+    /// The client for fetching `heuristics.yml`, with the same network options.
+    fn client(&self) -> anyhow::Result<reqwest::blocking::Client> {
+        let mut client = reqwest::blocking::Client::builder();
+        if let Some(timeout) = self.timeout {
+            client = client.timeout(timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            client = client.proxy(reqwest::Proxy::all(proxy.clone())?);
+        }
+        for cert in self.root_certificates()? {
+            client = client.add_root_certificate(cert);
+        }
+        Ok(client.build()?)
+    }
+
+    /// The certificates of `--cacert`.
+    fn root_certificates(&self) -> anyhow::Result<Vec<reqwest::Certificate>> {
+        let Some(path) = self.cacert else {
+            return Ok(vec![]);
+        };
+        let pem =
+            std::fs::read(path).map_err(|err| anyhow::anyhow!("{}: {err}", path.display()))?;
+        Ok(reqwest::Certificate::from_pem_bundle(&pem)?)
+    }
+}
+
+#[cfg(feature = "bundled")]
+fn bundled() -> anyhow::Result<Linguist> {
+    Ok(Linguist::bundled())
+}
+
+#[cfg(not(feature = "bundled"))]
+fn bundled() -> anyhow::Result<Linguist> {
+    anyhow::bail!("--offline needs bundled data; rebuild with `--features bundled`")
+}
+
+/// The threshold of `--warn-delta` with `-vv`. CIEDE2000 differences are
+/// about 1 for colors that look the same, and 10 or more for colors that look
+/// clearly different; about 1 in 6 Linguist colors are that far from any xterm
+/// color, mostly dark ones shown as grays, such as Prolog. Themes and the 16
+/// ANSI colors are much farther from most colors, so they are not marked.
+const WARN_DELTA: f64 = 10.0;
+
+/// Print how many colors were marked by `--warn-delta`, after everything else.
+fn report_poor(out: &mut impl Write, count: usize) -> io::Result<()> {
+    if count == 0 {
+        return Ok(());
+    }
+    out.flush()?;
+    let colors = if count == 1 { "color" } else { "colors" };
+    eprintln!(
+        "{} {count} {colors} poorly approximated by xterm colors",
+        "warning:".yellow()
+    );
+    Ok(())
+}
+
+/// An argument of `xterm`: an xterm color by its index, or any other color.
+#[derive(Debug, Clone)]
+enum XtermArg {
+    Index(usize),
+    Color(TermColor),
+}
+
+impl XtermArg {
+    /// Digits, with an optional `-`, are an index, never hex.
+    fn parse(arg: &str) -> anyhow::Result<Self> {
+        let digits = arg.trim().strip_prefix('-').unwrap_or(arg.trim());
+        if digits.is_empty() || !digits.bytes().all(|c| c.is_ascii_digit()) {
+            return Ok(XtermArg::Color(arg.parse()?));
+        }
+        match arg.trim().parse::<u8>() {
+            Ok(index) => Ok(XtermArg::Index(index.into())),
+            Err(_) => anyhow::bail!("xterm colors are numbered 0 to 255, found {}", arg.trim()),
+        }
+    }
+
+    /// The color itself, rather than its nearest xterm color.
+    fn color(&self) -> TermColor {
+        match self {
+            XtermArg::Index(index) => TermColor::from(Palette::Xterm256.colors()[*index]),
+            XtermArg::Color(color) => *color,
+        }
+    }
+
+    /// The index and the color shown for this argument, found in the palette
+    /// of `options` as `for` finds it for a language.
+    fn xterm(&self, options: &RenderOptions) -> (usize, TermColor) {
+        match self {
+            XtermArg::Index(index) => {
+                let color = Palette::Xterm256.colors()[*index];
+                (*index, TermColor::from(color))
+            }
+            XtermArg::Color(color) => {
+                let nearest = color.resolve(options).nearest;
+                (nearest.index.into(), TermColor::from(nearest.color))
+            }
+        }
+    }
+}
+
+/// Options of the `xterm` command.
+struct XtermFlags {
+    languages: bool,
+    /// Read the colors from stdin.
+    batch: Option<Batch>,
+    format: OutputFormat,
+    color_space: Metric,
+    layer: Layer,
+    candidates: Option<Candidates>,
+    numbers: Rounding,
+}
+
+fn xterm(
+    out: &mut impl Write,
+    colors: Vec<String>,
+    flags: XtermFlags,
+    data: &Dataset<'_>,
+    options: &RenderOptions,
+) -> anyhow::Result<()> {
+    let XtermFlags {
+        languages,
+        batch,
+        format,
+        color_space,
+        layer,
+        candidates,
+        numbers,
+    } = flags;
+    if let Some(batch) = batch {
+        if format != OutputFormat::Text {
+            anyhow::bail!("--stdin cannot be combined with --format {format}")
+        }
+        let inputs = Batch::read(data, true)?;
+        let loaded = languages.then(|| data.load()).transpose()?;
+        let (mut found, mut poor) = (0, 0);
+        for input in &inputs {
+            let result = XtermArg::parse(input).ok().map(|color| {
+                let mut result = Vec::new();
+                poor += write_xterm(
+                    &mut result,
+                    &color,
+                    loaded.as_ref(),
+                    color_space,
+                    layer,
+                    options,
+                )? as usize;
+                io::Result::Ok(String::from_utf8_lossy(&result).trim_end().to_string())
+            });
+            let result = result.transpose()?;
+            found += result.is_some() as usize;
+            batch.print(out, input, result, "unrecognized color")?;
+        }
+        report_poor(out, poor)?;
+        return Batch::check(inputs.len(), found, "colors recognized");
+    }
+    let colors = colors
+        .iter()
+        .map(|color| XtermArg::parse(color))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let loaded = languages.then(|| data.load()).transpose()?;
+    let language = |index, xterm: &TermColor| {
+        xterm_language(loaded.as_ref(), index, xterm, color_space, options)
+    };
+
+    if format == OutputFormat::Markdown {
+        anyhow::bail!("--format {format} is only supported by `for` and `list`")
+    }
+    if let Some(depth) = format.depth() {
+        if candidates.is_some() {
+            anyhow::bail!("--candidates cannot be combined with --format {format}")
+        }
+        for color in &colors {
+            let sequence = match (color, depth) {
+                (XtermArg::Color(color), _) => escape(&color.resolve(options), layer, depth),
+                (XtermArg::Index(index), Depth::Xterm256) => match layer {
+                    Layer::Foreground => format!("\x1b[38;5;{index}m"),
+                    Layer::Background => format!("\x1b[48;5;{index}m"),
+                },
+                (XtermArg::Index(_), Depth::TrueColor) => {
+                    let plain = RenderOptions::new().color_space(color_space);
+                    escape(&color.color().resolve(&plain), layer, depth)
+                }
+            };
+            writeln!(out, "{sequence}")?;
+        }
+        return Ok(());
+    }
+    if format == OutputFormat::Json {
+        let found = colors
+            .iter()
+            .map(|color| {
+                let (index, xterm) = color.xterm(options);
+                (color, index, xterm, language(index, &xterm))
+            })
+            .collect::<Vec<_>>();
+        let colors = found
+            .iter()
+            .map(|(color, index, xterm, language)| {
+                let language = language.as_deref();
+                match color {
+                    // the index as given, even for a color that also has a lower one
+                    XtermArg::Index(_) => JsonColor {
+                        language,
+                        index: *index,
+                        xterm_hex: Some(xterm.hex()),
+                        candidates: candidates.map(|candidates| candidates.to_json(xterm)),
+                        ..JsonColor::new(None, xterm, &RenderOptions::new(), numbers)
+                    },
+                    XtermArg::Color(color) => {
+                        let nearest = color.resolve(options).nearest;
+                        JsonColor {
+                            language,
+                            xterm_hex: Some(TermColor::from(nearest.color).hex()),
+                            candidates: candidates
+                                .map(|candidates| candidates.to_json(&options.readable(color))),
+                            ..JsonColor::new(None, color, options, numbers)
+                        }
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+        return write_json(out, &colors);
+    }
+    let mut poor = 0;
+    for color in &colors {
+        poor += write_xterm(out, color, loaded.as_ref(), color_space, layer, options)? as usize;
+        if let Some(candidates) = candidates {
+            candidates.write(out, &color.color())?;
+        }
+    }
+    report_poor(out, poor)?;
+    Ok(())
+}
+
+/// One line of `xterm`, returning whether the xterm color is a poor
+/// approximation.
+fn write_xterm(
+    out: &mut impl Write,
+    color: &XtermArg,
+    languages: Option<&(Linguist, DisplayNames)>,
+    color_space: Metric,
+    layer: Layer,
+    options: &RenderOptions,
+) -> io::Result<bool> {
+    let (index, xterm) = color.xterm(options);
+    let poor = match color {
+        XtermArg::Color(color) => {
+            write!(out, "{}", color.display(options))?;
+            color.is_poor_approximation(options)
+        }
+        XtermArg::Index(index) => {
+            // the index as given, even for a color that also has a lower one
+            write!(out, "{}", xterm_swatch(*index, &xterm.hex(), layer))?;
+            false
+        }
+    };
+    match xterm_language(languages, index, &xterm, color_space, options) {
+        Some(language) => writeln!(out, " {language}")?,
+        None => writeln!(out)?,
+    }
+    Ok(poor)
+}
+
+/// `xterm 61  #5f5faf` after a swatch, in the xterm color `index` itself.
+fn xterm_swatch(index: usize, hex: &str, layer: Layer) -> String {
+    palette_swatch(Palette::Xterm256, index, hex, layer)
+}
+
+/// Like [`xterm_swatch`], labeled as in `palette`, e.g. `base0A #f7ca88`.
+fn palette_swatch(palette: Palette, index: usize, hex: &str, layer: Layer) -> String {
+    let (swatch, sgr) = match layer {
+        Layer::Foreground => ("██ ", 38),
+        Layer::Background => ("   ", 48),
+    };
+    let text = format!("{swatch}{} {hex}", palette.label(index as u8));
+    match colored::control::SHOULD_COLORIZE.should_colorize() {
+        true => format!("\x1b[1;{sgr};5;{index}m{text}\x1b[0m"),
+        false => text,
+    }
+}
+
+fn chart(
+    out: &mut impl Write,
+    hex: bool,
+    range: Range<usize>,
+    used: bool,
+    data: &Dataset<'_>,
+    color_space: Metric,
+) -> anyhow::Result<()> {
+    let used = match used {
+        true => {
+            let (linguist, _) = data.load()?;
+            let used = linguist
+                .iter_colors()
+                .map(|(_, color)| TermColor::from(color).xterm_index(color_space));
+            Some(used.collect::<BTreeSet<_>>())
+        }
+        false => None,
+    };
+    let colors = Palette::Xterm256.colors();
+    let label = |index: usize| {
+        let mark = match &used {
+            Some(used) if used.contains(&index) => "*",
+            Some(_) => " ",
+            None => "",
+        };
+        let color = TermColor::from(colors[index]);
+        let text = match hex {
+            true => format!("{index:>4}{mark} {} ", color.hex()),
+            false => format!("{index:>4}{mark} "),
+        };
+        text
+    };
+    let cell = |index: usize| {
+        let text = label(index);
+        if !colored::control::SHOULD_COLORIZE.should_colorize() {
+            return text;
+        }
+        // black on light colors and white on dark ones, whichever contrasts more
+        let fg = match relative_luminance(&colors[index]) > 0.18 {
+            true => 30,
+            false => 97,
+        };
+        format!("\x1b[{fg};48;5;{index}m{text}\x1b[0m")
+    };
+    let width = label(0).chars().count();
+    let columns = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok());
+    let columns = columns.unwrap_or(80usize);
+    for (i, section) in chart_rows(&range, width, columns).into_iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        for row in section {
+            writeln!(out, "{}", row.map(cell).collect::<String>())?;
+        }
+    }
+    Ok(())
+}
+
+/// The rows of the xterm colors in `range` that `palette` and `pick-xterm`
+/// lay out, in sections for the 16 system colors, the cube, and the grays,
+/// with as many cells `width` wide as fit in `columns`.
+fn chart_rows(range: &Range<usize>, width: usize, columns: usize) -> Vec<Vec<Range<usize>>> {
+    // rows as in the usual chart, or halves and thirds of those to fit
+    let sections: [(Range<usize>, &[usize]); 3] = [
+        (0..16, &[8]),
+        (16..232, &[36, 18, 12, 6]),
+        (232..256, &[24, 12, 6]),
+    ];
+    let mut chart = vec![];
+    for (section, rows) in sections {
+        let shown = section.start.max(range.start)..section.end.min(range.end);
+        if shown.is_empty() {
+            continue;
+        }
+        let per_row = (rows.iter().copied())
+            .find(|row| row * width <= columns)
+            .unwrap_or((columns / width).max(1));
+        // rows start where they would in the full chart
+        let mut index = shown.start;
+        let mut section_rows = vec![];
+        while index < shown.end {
+            let row_end =
+                (section.start + ((index - section.start) / per_row + 1) * per_row).min(shown.end);
+            section_rows.push(index..row_end);
+            index = row_end;
+        }
+        chart.push(section_rows);
+    }
+    chart
+}
+
+/// Raw mode and the alternate screen on stderr for `pick-xterm`, until
+/// dropped, or a panic, whose message then lands on the usual screen.
+struct RawScreen;
+
+impl RawScreen {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        // dropped from here on, so that a failure below still restores
+        let screen = RawScreen;
+        crossterm::execute!(io::stderr(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            RawScreen::restore();
+            hook(info)
+        }));
+        Ok(screen)
+    }
+
+    fn restore() {
+        let _ = crossterm::execute!(io::stderr(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+impl Drop for RawScreen {
+    fn drop(&mut self) {
+        Self::restore();
+        // the hook cannot be changed while panicking
+        if !std::thread::panicking() {
+            drop(std::panic::take_hook());
+        }
+    }
+}
+
+/// What `pick-xterm` shows: the chart, the highlighted color, and the lines
+/// below it.
+struct Picker {
+    sections: Vec<Vec<Range<usize>>>,
+    selected: usize,
+    /// The hex typed after `/`, until Enter or Esc.
+    input: Option<String>,
+    message: String,
+}
+
+/// The width of a cell of `pick-xterm`, as `palette` labels them.
+const PICKER_CELL: usize = 5;
+
+impl Picker {
+    fn new(columns: usize) -> Self {
+        Self {
+            sections: chart_rows(&(0..256), PICKER_CELL, columns),
+            selected: 0,
+            input: None,
+            message: String::new(),
+        }
+    }
+
+    /// The color `rows` rows below the highlighted one, or above for fewer
+    /// than 0, in the same column, or the last of a shorter row.
+    fn vertical(&self, rows: isize) -> usize {
+        let rows_of = self.sections.iter().flatten().collect::<Vec<_>>();
+        let Some(at) = rows_of.iter().position(|row| row.contains(&self.selected)) else {
+            return self.selected;
+        };
+        let column = self.selected - rows_of[at].start;
+        let to = at.saturating_add_signed(rows).min(rows_of.len() - 1);
+        (rows_of[to].start + column).min(rows_of[to].end - 1)
+    }
+
+    fn draw(&self, w: &mut impl Write) -> io::Result<()> {
+        crossterm::queue!(w, cursor::MoveTo(0, 0), terminal::Clear(ClearType::All))?;
+        let colors = Palette::Xterm256.colors();
+        for (i, section) in self.sections.iter().enumerate() {
+            if i > 0 {
+                write!(w, "\r\n")?;
+            }
+            for row in section {
+                for index in row.clone() {
+                    let fg = match relative_luminance(&colors[index]) > 0.18 {
+                        true => 30,
+                        false => 97,
+                    };
+                    match index == self.selected {
+                        true => write!(w, "\x1b[1;4;{fg};48;5;{index}m{:>4} ", format!(">{index}")),
+                        false => write!(w, "\x1b[{fg};48;5;{index}m{index:>4} "),
+                    }?;
+                    write!(w, "\x1b[0m")?;
+                }
+                write!(w, "\r\n")?;
+            }
+        }
+        let hex = TermColor::from(colors[self.selected]).hex();
+        let index = self.selected;
+        write!(
+            w,
+            "\r\n\x1b[48;5;{index}m    \x1b[0m xterm {index} {hex}\r\n"
+        )?;
+        match &self.input {
+            Some(input) => write!(w, "/{input}")?,
+            None => {
+                for line in self.message.lines() {
+                    write!(w, "{line}\r\n")?;
+                }
+                write!(
+                    w,
+                    "\x1b[2m←↓↑→: move  enter: print  /: go to a hex  l: languages  q: quit\x1b[0m"
+                )?;
+            }
+        }
+        if self.input.is_some() {
+            crossterm::queue!(w, cursor::Show)?;
+        } else {
+            crossterm::queue!(w, cursor::Hide)?;
+        }
+        w.flush()
+    }
+}
+
+/// `pick-xterm`: browse the chart of `palette` with the keyboard, and print
+/// the number and hex of the color picked with Enter.
+fn pick_xterm(out: &mut impl Write, data: &Dataset<'_>, color_space: Metric) -> anyhow::Result<()> {
+    if !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+        anyhow::bail!(
+            "pick-xterm needs a terminal to read keys from and draw on; \
+            for a chart of the xterm colors, run `{} palette`",
+            env!("CARGO_BIN_NAME")
+        );
+    }
+    // computed on the first `l`, for each xterm color
+    let mut languages: Option<BTreeMap<usize, Vec<String>>> = None;
+    let picked = {
+        let _screen = RawScreen::enter()?;
+        let mut screen = BufWriter::new(io::stderr().lock());
+        let (columns, _) = terminal::size()?;
+        let mut picker = Picker::new(columns.into());
+        loop {
+            picker.draw(&mut screen)?;
+            let key = match event::read()? {
+                Event::Resize(columns, _) => {
+                    picker.sections = chart_rows(&(0..256), PICKER_CELL, columns.into());
+                    continue;
+                }
+                Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                _ => continue,
+            };
+            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                break None;
+            }
+            if let Some(input) = &mut picker.input {
+                match key.code {
+                    KeyCode::Char(c) => input.push(c),
+                    KeyCode::Backspace => drop(input.pop()),
+                    KeyCode::Esc => picker.input = None,
+                    KeyCode::Enter => {
+                        picker.message = match input.trim().parse::<TermColor>() {
+                            Ok(color) => {
+                                picker.selected = color.xterm_index(color_space);
+                                format!("nearest to {}", color.hex())
+                            }
+                            Err(err) => format!("{input}: {err}"),
+                        };
+                        picker.input = None;
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            picker.message.clear();
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => break None,
+                KeyCode::Enter => break Some(picker.selected),
+                KeyCode::Left => picker.selected = picker.selected.saturating_sub(1),
+                KeyCode::Right => picker.selected = (picker.selected + 1).min(255),
+                KeyCode::Up => picker.selected = picker.vertical(-1),
+                KeyCode::Down => picker.selected = picker.vertical(1),
+                KeyCode::Char('/') => picker.input = Some(String::new()),
+                KeyCode::Char('l') => {
+                    if languages.is_none() {
+                        languages = match data.load() {
+                            Ok((linguist, names)) => {
+                                let mut by_xterm = BTreeMap::<usize, Vec<String>>::new();
+                                for (name, color) in linguist.iter_colors() {
+                                    let index = TermColor::from(color).xterm_index(color_space);
+                                    by_xterm
+                                        .entry(index)
+                                        .or_default()
+                                        .push(names.get(name).to_string());
+                                }
+                                Some(by_xterm)
+                            }
+                            Err(err) => {
+                                picker.message = format!("Error: {err}");
+                                continue;
+                            }
+                        };
+                    }
+                    let found = languages.as_ref().and_then(|by| by.get(&picker.selected));
+                    picker.message = match found {
+                        Some(found) => found.join(", "),
+                        None => "no language's color is nearest to this one".into(),
+                    };
+                }
+                _ => {}
+            }
+        }
+    };
+    if let Some(index) = picked {
+        let hex = TermColor::from(Palette::Xterm256.colors()[index]).hex();
+        writeln!(out, "{index} {hex}")?;
+    }
+    Ok(())
+}
+
+/// A weight of `--hue-weight` and the like, which is finite and not negative.
+fn parse_weight(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(weight) if weight.is_finite() && weight >= 0.0 => Ok(weight),
+        _ => Err(format!("expected a number of 0 or more, found {text:?}")),
+    }
+}
+
+/// A WCAG contrast ratio, which is from 1 to 21.
+fn parse_contrast(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(ratio) if (1.0..=21.0).contains(&ratio) => Ok(ratio),
+        _ => Err(format!(
+            "expected a contrast ratio from 1 to 21, found {text:?}"
+        )),
+    }
+}
+
+fn parse_percent(text: &str) -> Result<f64, String> {
+    match text.trim_end_matches('%').parse::<f64>() {
+        Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(percent),
+        _ => Err(format!(
+            "expected a percentage from 0 to 100, found {text:?}"
+        )),
+    }
+}
+
+fn parse_xterm_arg(arg: &str) -> Result<XtermArg, String> {
+    XtermArg::parse(arg).map_err(|err| err.to_string())
+}
+
+/// A range of xterm colors such as `16..232` or `232..=255`.
+fn parse_xterm_range(text: &str) -> Result<Range<usize>, String> {
+    let invalid = || format!("expected a range of xterm colors such as 16..232, found {text:?}");
+    let (start, end) = text.split_once("..").ok_or_else(invalid)?;
+    let (end, inclusive) = match end.strip_prefix('=') {
+        Some(end) => (end, true),
+        None => (end, false),
+    };
+    let start = match start {
+        "" => 0,
+        start => start.parse::<usize>().map_err(|_| invalid())?,
+    };
+    let end = match end {
+        "" => 256,
+        end => end.parse::<usize>().map_err(|_| invalid())? + inclusive as usize,
+    };
+    match start < end && end <= 256 {
+        true => Ok(start..end),
+        false => Err("xterm colors are numbered 0 to 255, so ranges are within 0..256".into()),
+    }
+}
+
+/// Options of the `snippet` command.
+struct Snippet {
+    format: SnippetFormat,
+    text: Option<String>,
+    /// The background under the language color, with `--bg`.
+    bg: Option<XtermArg>,
+    depth: Depth,
+    layer: Layer,
+    color_space: Metric,
+}
+
+impl Snippet {
+    fn print(&self, out: &mut impl Write, query: &str, data: &Dataset<'_>) -> anyhow::Result<()> {
+        if self.bg.is_some() && matches!(self.layer, Layer::Background) {
+            anyhow::bail!("--bg cannot be combined with --as bg, which already sets the background")
+        }
+        let (linguist, names) = data.load()?;
+        let colors = linguist.colors()?;
+        let Some(found) = colors.ranked(query).into_iter().next() else {
+            Err(NotFound(format!(
+                "no colors found for {query:?}{}",
+                did_you_mean(&colors, query)
+            )))?
+        };
+        let name = names.get(&found.name);
+        let text = self.text.as_deref().unwrap_or(name);
+        let snippet = match self.format {
+            SnippetFormat::Starship => {
+                let language = linguist.get(&found.name);
+                self.starship(&found.name, language, &found.color, text)
+            }
+            SnippetFormat::Ps1 => self.ps1(&found.color, text),
+            SnippetFormat::Tmux => self.tmux(&found.color, text),
+        };
+        writeln!(out, "{snippet}")?;
+        Ok(())
+    }
+
+    /// A `[custom]` module, see https://starship.rs/config/#custom-commands.
+    fn starship(
+        &self,
+        name: &str,
+        language: Option<Language<'_>>,
+        color: &TermColor,
+        text: &str,
+    ) -> String {
+        // JSON strings are also TOML basic strings
+        let toml = |text: &str| serde_json::to_string(text).unwrap();
+        let style = self.styles(color, |layer, color| {
+            let color = match self.depth {
+                Depth::TrueColor => color.hex(),
+                Depth::Xterm256 => color.xterm_index(self.color_space).to_string(),
+            };
+            match layer {
+                Layer::Foreground => format!("fg:{color}"),
+                Layer::Background => format!("bg:{color}"),
+            }
+        });
+        let style = style.join(" ");
+        // characters with a meaning in format strings
+        let mut literal = String::new();
+        for c in text.chars() {
+            if matches!(c, '[' | ']' | '(' | ')' | '$' | '\\') {
+                literal.push('\\');
+            }
+            literal.push(c);
+        }
+        let detect = |values: &[String], strip: &str| {
+            let values = values
+                .iter()
+                .map(|value| toml(value.trim_start_matches(strip)));
+            format!("[{}]", values.collect::<Vec<_>>().join(", "))
+        };
+        let mut lines = vec![format!("[custom.{}]", slugify(name))];
+        let (extensions, filenames) = match language {
+            Some(language) => (language.extensions(), language.filenames()),
+            None => (&[][..], &[][..]),
+        };
+        if !extensions.is_empty() {
+            lines.push(format!("detect_extensions = {}", detect(extensions, ".")));
+        }
+        if !filenames.is_empty() {
+            lines.push(format!("detect_files = {}", detect(filenames, "")));
+        }
+        // with nothing to detect, the module is shown everywhere
+        if extensions.is_empty() && filenames.is_empty() {
+            lines.push("when = true".to_string());
+        }
+        lines.push(format!("style = {}", toml(&style)));
+        lines.push(format!(
+            "format = {}",
+            toml(&format!("[{literal}]($style) "))
+        ));
+        lines.join("\n")
+    }
+
+    fn ps1(&self, color: &TermColor, text: &str) -> String {
+        let escape = self.styles(color, |layer, color| {
+            let sgr = match layer {
+                Layer::Foreground => 38,
+                Layer::Background => 48,
+            };
+            match self.depth {
+                Depth::TrueColor => {
+                    let (r, g, b) = color.rgb();
+                    format!("{sgr};2;{r};{g};{b}")
+                }
+                Depth::Xterm256 => format!("{sgr};5;{}", color.xterm_index(self.color_space)),
+            }
+        });
+        let escape = escape.join(";");
+        // characters bash expands in a prompt; quotes are left to the caller
+        let mut literal = String::new();
+        for c in text.chars() {
+            if matches!(c, '\\' | '$' | '`') {
+                literal.push('\\');
+            }
+            literal.push(c);
+        }
+        format!("\\[\\e[{escape}m\\]{literal}\\[\\e[0m\\]")
+    }
+
+    /// See https://man.openbsd.org/tmux#STYLES.
+    fn tmux(&self, color: &TermColor, text: &str) -> String {
+        let style = self.styles(color, |layer, color| {
+            let color = match self.depth {
+                Depth::TrueColor => color.hex(),
+                Depth::Xterm256 => format!("colour{}", color.xterm_index(self.color_space)),
+            };
+            match layer {
+                Layer::Foreground => format!("fg={color}"),
+                Layer::Background => format!("bg={color}"),
+            }
+        });
+        // formats start with #, so a # of the text is written ##
+        let literal = text.replace('#', "##");
+        format!("#[{}]{literal}#[default]", style.join(","))
+    }
+
+    /// `color` on its layer and the color of `--bg`, if any, each as written
+    /// by `style`.
+    fn styles(
+        &self,
+        color: &TermColor,
+        style: impl Fn(Layer, &TermColor) -> String,
+    ) -> Vec<String> {
+        let bg = (self.bg.iter()).map(|bg| (Layer::Background, bg.color()));
+        std::iter::once((self.layer, *color))
+            .chain(bg)
+            .map(|(layer, color)| style(layer, &color))
+            .collect()
+    }
+}
+
+/// For `xterm --languages`, the language nearest the xterm color `index`
+/// among those whose colors it stands for, in the palette of `options`.
+fn xterm_language(
+    languages: Option<&(Linguist, DisplayNames)>,
+    index: usize,
+    xterm: &TermColor,
+    color_space: Metric,
+    options: &RenderOptions,
+) -> Option<String> {
+    let (linguist, names) = languages?;
+    let shown = |color| TermColor::from(color).resolve(options).nearest.index;
+    let mapped = linguist
+        .iter_colors()
+        .filter(|(_, color)| usize::from(shown(*color)) == index)
+        .map(|(name, color)| (name, color_space.distance(&color, &xterm.color())));
+    let nearest = mapped.min_by(|(_, a), (_, b)| a.total_cmp(b));
+    nearest.map(|(name, _)| names.get(name).to_string())
+}
+
+fn compare(
+    out: &mut impl Write,
+    args: Vec<String>,
+    data: &Dataset<'_>,
+    layer: Layer,
+) -> anyhow::Result<()> {
+    let parsed = args.iter().map(|arg| arg.parse::<TermColor>().ok());
+    let parsed = parsed.collect::<Vec<_>>();
+    // only load the data if some argument is not a color
+    let loaded = parsed
+        .iter()
+        .any(Option::is_none)
+        .then(|| data.load())
+        .transpose()?;
+    let loaded = match &loaded {
+        Some((linguist, names)) => Some((linguist.colors()?, names)),
+        None => None,
+    };
+    // the longest name, such as `hsl-weighted`
+    let width = (supported_metrics().iter())
+        .map(|metric| metric.name().len())
+        .max()
+        .unwrap_or_default();
+    for (idx, (arg, color)) in args.iter().zip(parsed).enumerate() {
+        if idx > 0 {
+            writeln!(out)?;
+        }
+        let (color, title) = match (color, &loaded) {
+            (Some(color), _) => (color, String::new()),
+            (None, Some((colors, names))) => {
+                let found = colors.ranked(arg).into_iter().next().ok_or_else(|| {
+                    NotFound(format!(
+                        "no colors found for {arg:?}{}",
+                        did_you_mean(colors, arg)
+                    ))
+                })?;
+                (found.color, names.get(&found.name).to_string())
+            }
+            (None, None) => unreachable!("the data is loaded for queries"),
+        };
+        let (r, g, b) = color.rgb();
+        let hex = color.hex().truecolor(r, g, b).bold();
+        match title.is_empty() {
+            true => writeln!(out, "{hex}")?,
+            false => writeln!(out, "{hex} {}", title.bold())?,
+        }
+        for metric in supported_metrics() {
+            let nearest = color.nearest_xterm(metric);
+            let name = metric.name();
+            let hex = TermColor::from(nearest.color).hex();
+            let text = xterm_swatch(nearest.index.into(), &hex, layer);
+            let distance = format!("Δ{}", Precision::Distance.fixed(nearest.distance));
+            writeln!(out, "  {name:<width$} {text} {}", distance.dimmed())?;
+        }
+    }
+    Ok(())
+}
+
+fn nearest(
+    out: &mut impl Write,
+    color: &str,
+    n: usize,
+    data: &Dataset<'_>,
+    color_space: Metric,
+    options: &RenderOptions,
+) -> anyhow::Result<()> {
+    let color = color.parse::<TermColor>()?.color();
+    let (linguist, names) = data.load()?;
+    let colors = linguist.colors()?;
+    for (name, found, distance) in colors.nearest_languages(&color, n, color_space) {
+        let found = TermColor::from(found);
+        let distance = match distance == 0.0 {
+            true => "exact".to_string(),
+            false => format!("Δ{}", Precision::Distance.fixed(distance)),
+        };
+        writeln!(
+            out,
+            "{} {} {}",
+            found.display(options),
+            names.get(name),
+            distance.dimmed()
+        )?;
+    }
+    Ok(())
+}
+
+fn search(
+    out: &mut impl Write,
+    needle: &str,
+    data: &Dataset<'_>,
+    options: &RenderOptions,
+) -> anyhow::Result<()> {
+    let (linguist, names) = data.load()?;
+    let colors = linguist.colors()?;
+    let found = colors.search(needle);
+    if found.is_empty() {
+        return Err(NotFound(format!("no languages found containing {:?}", needle.trim())).into());
+    }
+    for found in found {
+        write!(
+            out,
+            "{} {}",
+            found.color.display(options),
+            names.get(found.name)
+        )?;
+        if found.keyword != found.name.to_lowercase() {
+            write!(out, " {}", format!("({})", found.keyword).dimmed())?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Options of the `for` command.
+struct QueryFlags {
+    /// Read the queries from stdin.
+    batch: Option<Batch>,
+    each: bool,
+    /// Join the arguments into one query instead of querying each.
+    joined: bool,
+    fail_on_ambiguous: bool,
+    explain: bool,
+    all: bool,
+    max_results_per_keyword: Option<usize>,
+    tab_color: Option<TabColor>,
+    via_daemon: bool,
+    heuristics: bool,
+    format: OutputFormat,
+    only: Option<OnlyValue>,
+    single: bool,
+    /// Where to find the nearest xterm color in machine-readable formats.
+    color_space: Metric,
+    /// Only match whole names and aliases.
+    exact: bool,
+    /// Only match the primary language of a shared extension.
+    primary_only: bool,
+    why: bool,
+    /// Only match languages of these types, if any.
+    types: Vec<TypeChoice>,
+    /// Every argument is a file to detect the language of.
+    file: bool,
+    limit: Option<usize>,
+    sort: SortOrder,
+    /// The layer to print escape sequences for with `--format escape`.
+    layer: Layer,
+    candidates: Option<Candidates>,
+    numbers: Rounding,
+}
+
+/// `--stdin`
+#[derive(Clone, Copy)]
+struct Batch {
+    skip_missing: bool,
+}
+
+impl Batch {
+    /// The inputs on stdin, separated by whitespace, or by lines if `lines`.
+    fn read(data: &Dataset<'_>, lines: bool) -> anyhow::Result<Vec<String>> {
+        if data.source == Some("-") {
+            anyhow::bail!("--stdin cannot be combined with --source -, which also reads stdin")
+        }
+        let input = io::read_to_string(io::stdin())?;
+        let inputs = match lines {
+            true => input
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect(),
+            false => input.split_whitespace().collect::<Vec<_>>(),
+        };
+        Ok(inputs.into_iter().map(str::to_string).collect())
+    }
+
+    /// Print `input → ` and the result, or a placeholder if there is none,
+    /// unless [`Batch::skip_missing`].
+    fn print(
+        &self,
+        out: &mut impl Write,
+        input: &str,
+        result: Option<String>,
+        missing: &str,
+    ) -> io::Result<()> {
+        match result {
+            Some(result) => writeln!(out, "{input} → {result}"),
+            None if self.skip_missing => Ok(()),
+            None => writeln!(out, "{input} → {}", missing.dimmed()),
+        }
+    }
+
+    /// Fail only if no input had a result.
+    fn check(inputs: usize, found: usize, what: &str) -> anyhow::Result<()> {
+        match (inputs, found) {
+            (0, _) => anyhow::bail!("--stdin: nothing to read"),
+            (_, 0) => Err(NotFound(format!(
+                "--stdin: no {what} for any of {inputs} inputs"
+            )))?,
+            _ => Ok(()),
+        }
+    }
+}
+
+/// `--set-tab-color`
+struct TabColor {
+    terminal: Option<TerminalChoice>,
+    dry_run: bool,
+}
+
+impl TabColor {
+    /// Tint the terminal tab with the top match for `query`. The sequence is only
+    /// written if stdout is a terminal, unless this is a dry run, which prints it
+    /// escaped instead.
+    fn apply(
+        &self,
+        out: &mut impl Write,
+        colors: &ColorMap<'_>,
+        query: &str,
+    ) -> anyhow::Result<()> {
+        let terminal = match self.terminal {
+            Some(TerminalChoice::Iterm2) => Terminal::ITerm2,
+            None => Terminal::detect(|key| std::env::var_os(key)).ok_or_else(|| {
+                anyhow::anyhow!("could not tell which terminal this is, pass --terminal")
+            })?,
+        };
+        let Some(top) = colors.ranked(query).into_iter().next() else {
+            return Ok(());
+        };
+        let sequence = terminal.tab_color(&top.color.color());
+        if self.dry_run {
+            let escaped = sequence.replace('\x1b', "\\e").replace('\x07', "\\a");
+            writeln!(out, "{escaped}")?;
+        } else if io::stdout().is_terminal() {
+            write!(out, "{sequence}")?;
+        }
+        Ok(())
+    }
+}
+
+fn linguist(
+    out: &mut impl Write,
+    query: Vec<String>,
+    flags: QueryFlags,
+    data: &Dataset<'_>,
+    options: &RenderOptions,
+) -> anyhow::Result<()> {
+    let QueryFlags {
+        batch,
+        each,
+        joined,
+        fail_on_ambiguous,
+        explain,
+        all,
+        max_results_per_keyword,
+        tab_color,
+        via_daemon,
+        heuristics,
+        format,
+        only,
+        single,
+        color_space,
+        layer,
+        exact,
+        primary_only,
+        why,
+        types,
+        file,
+        limit,
+        sort,
+        candidates,
+        numbers,
+    } = flags;
+    let simple_index =
+        !exact && !primary_only && types.is_empty() && max_results_per_keyword.is_none();
+    let types = (!types.is_empty()).then(|| types.into_iter().map(TypeChoice::into).collect());
+    let index = ColorMapOptions::new()
+        .max_entries_per_keyword(max_results_per_keyword)
+        .include_extensions(!exact)
+        .include_words(!exact)
+        .primary_only(primary_only)
+        .types(types);
+    let query = match joined || file {
+        true => query,
+        false => split_groups(&query),
+    };
+    // every argument is its own query, shown as a row of a table unless
+    // listed in full
+    let separate = !joined && query.len() > 1;
+    let columns = separate && !each && !explain && only.is_none() && format == OutputFormat::Text;
+    let each = each || separate;
+    if separate && tab_color.is_some() {
+        anyhow::bail!("--set-tab-color takes one query: quote it, or pass --joined")
+    }
+    if let Some(batch) = batch {
+        if format != OutputFormat::Text {
+            anyhow::bail!("--stdin cannot be combined with --format {format}")
+        }
+        let inputs = Batch::read(data, false)?;
+        let (linguist, names) = data.load()?;
+        let colors = linguist.colors_with_options(&index)?;
+        let pipeline = data.pipeline(&linguist, heuristics)?;
+        let (mut found, mut poor) = (0, 0);
+        for input in &inputs {
+            // e.g. from `git ls-files`, where files at the top have no `/`;
+            // a path that cannot be detected is still tried as a query
+            let as_file = file || Path::new(input).is_file();
+            let query = detect_paths(&pipeline, vec![input.clone()], as_file)
+                .ok()
+                .and_then(|query| query.into_iter().next())
+                .unwrap_or_else(|| input.clone());
+            let mut ranked = colors.ranked(&query);
+            sort.sort(&mut ranked);
+            let best = ranked
+                .iter()
+                .find(|lang| !collapsed(&colors, &ranked, all, &lang.name));
+            let result = best.map(|best| {
+                poor += best.color.is_poor_approximation(options) as usize;
+                let mut result =
+                    format!("{} {}", best.color.display(options), names.get(&best.name));
+                if why {
+                    let why = why_matched((&linguist, &colors), best, &query);
+                    result.push_str(&format!(" {}", format!("({why})").dimmed()));
+                }
+                result
+            });
+            found += result.is_some() as usize;
+            batch.print(out, input, result, "no colors found")?;
+        }
+        report_poor(out, poor)?;
+        return Batch::check(inputs.len(), found, "colors found");
+    }
+    if let Some(only) = only {
+        if format != OutputFormat::Text {
+            anyhow::bail!("--only cannot be combined with --format {format}")
+        }
+        let (linguist, _) = data.load()?;
+        let colors = linguist.colors_with_options(&index)?;
+        let query = detect_paths(&data.pipeline(&linguist, heuristics)?, query, file)?;
+        let mut found = Found::new(&colors, &query, each);
+        found.arrange(sort, limit);
+        let groups = match &found {
+            Found::All(found) => vec![(query.join(" "), found)],
+            Found::Each(found) => found
+                .iter()
+                .map(|(query, found)| (query.to_string(), found))
+                .collect(),
+        };
+        for (query, found) in groups {
+            if fail_on_ambiguous {
+                Ambiguous::check(&colors, &query, all)?;
+            }
+            let shown = found
+                .iter()
+                .filter(|lang| !collapsed(&colors, found, all, &lang.name))
+                .collect::<Vec<_>>();
+            if single && shown.len() > 1 {
+                let names = shown.iter().map(|lang| &*lang.name);
+                anyhow::bail!(
+                    "--single: {} languages found for {query:?}: {}",
+                    shown.len(),
+                    names.collect::<Vec<_>>().join(", ")
+                )
+            }
+            for Ranked { color, .. } in shown {
+                writeln!(out, "{}", only.value(&color.resolve(options)))?;
+            }
+        }
+        return found.check_missing();
+    }
+    if format != OutputFormat::Text {
+        if explain || why || tab_color.is_some() {
+            anyhow::bail!(
+                "--format {format} cannot be combined with --explain, --why, or --set-tab-color"
+            )
+        }
+        // the daemon's index is built with the defaults from its own data, and
+        // file paths are relative to this process
+        let simple = !fail_on_ambiguous
+            && simple_index
+            && data.is_default()
+            && candidates.is_none()
+            && options.is_plain()
+            && numbers == Rounding::Places
+            && !query.iter().any(|arg| arg.contains(['/', '\\']));
+        if format == OutputFormat::Json && via_daemon && simple {
+            if let Some(mut results) = query_daemon(&query, each, color_space, sort)? {
+                limit_json(&mut results, each, limit);
+                write_json(out, &results)?;
+                return json_missing(&results);
+            }
+        }
+        let (linguist, names) = data.load()?;
+        let colors = linguist.colors_with_options(&index)?;
+        let query = detect_paths(&data.pipeline(&linguist, heuristics)?, query, file)?;
+        if fail_on_ambiguous {
+            match each {
+                true => query
+                    .iter()
+                    .try_for_each(|query| Ambiguous::check(&colors, query, all))?,
+                false => Ambiguous::check(&colors, &query.join(" "), all)?,
+            }
+        }
+        let mut found = Found::new(&colors, &query, each);
+        found.arrange(sort, limit);
+        if format == OutputFormat::Markdown {
+            if candidates.is_some() {
+                anyhow::bail!("--candidates cannot be combined with --format {format}")
+            }
+            let rows = found
+                .flatten()
+                .into_iter()
+                .map(|Ranked { name, color, .. }| (names.get(name), *color, None));
+            write_markdown(out, rows, false, options)?;
+            return found.check_missing();
+        }
+        match format.depth() {
+            None => {
+                let json =
+                    found.to_json((&linguist, &colors), &query, options, candidates, numbers)?;
+                write_json(out, &json)?
+            }
+            Some(_) if candidates.is_some() => {
+                anyhow::bail!("--candidates cannot be combined with --format {format}")
+            }
+            Some(depth) => {
+                for escape in found.to_escapes(layer, depth, options) {
+                    writeln!(out, "{escape}")?;
+                }
+            }
+        }
+        return found.check_missing();
+    }
+
+    let (linguist, names) = data.load()?;
+    let colors = linguist.colors_with_options(&index)?;
+    if columns {
+        let pipeline = data.pipeline(&linguist, heuristics)?;
+        let mut rows = vec![];
+        for arg in &query {
+            let detected = detect_paths(&pipeline, vec![arg.clone()], file)?;
+            let detected = detected.into_iter().next().unwrap_or_else(|| arg.clone());
+            if fail_on_ambiguous {
+                Ambiguous::check(&colors, &detected, all)?;
+            }
+            let mut ranked = colors.ranked(&detected);
+            sort.sort(&mut ranked);
+            let best = ranked
+                .iter()
+                .find(|lang| !collapsed(&colors, &ranked, all, &lang.name))
+                .cloned();
+            rows.push((arg, detected, best));
+        }
+        let width = |text: &str| text.chars().count();
+        let query_width = rows.iter().map(|(arg, ..)| width(arg)).max();
+        let name_width = rows
+            .iter()
+            .filter_map(|(.., best)| best.as_ref())
+            .map(|best| width(names.get(&best.name)))
+            .max();
+        let (query_width, name_width) = (query_width.unwrap_or(0), name_width.unwrap_or(0));
+        let (mut found, mut poor) = (0, 0);
+        for (arg, query, best) in &rows {
+            let Some(best) = best else {
+                let message = format!("no colors found{}", did_you_mean(&colors, query));
+                writeln!(out, "{arg:query_width$}  {}", message.dimmed())?;
+                continue;
+            };
+            found += 1;
+            poor += best.color.is_poor_approximation(options) as usize;
+            let name = names.get(&best.name);
+            write!(
+                out,
+                "{arg:query_width$}  {name:name_width$}  {}",
+                best.color.display(options)
+            )?;
+            if why {
+                let why = why_matched((&linguist, &colors), best, query);
+                write!(out, " {}", format!("({why})").dimmed())?;
+            }
+            writeln!(out)?;
+            if let Some(candidates) = candidates {
+                candidates.write(out, &options.readable(&best.color))?;
+            }
+        }
+        report_poor(out, poor)?;
+        if found == 0 {
+            Err(NotFound(format!(
+                "no colors found for any of {} queries",
+                rows.len()
+            )))?
+        }
+        return Ok(());
+    }
+    let query = detect_paths(&data.pipeline(&linguist, heuristics)?, query, file)?;
+    let mixed = resolve_mixed(&colors, &query);
+    let each = each || matches!(mixed, Some(Mixed::Disagree));
+
+    if !each {
+        let joined = query.join(" ");
+        if fail_on_ambiguous {
+            Ambiguous::check(&colors, &joined, all)?;
+        }
+        let args = &query[..];
+        let query = joined;
+        let mut poor = 0;
+        let found = match mixed {
+            Some(Mixed::Agree { mut found, unknown }) => {
+                arrange(&mut found, sort, limit);
+                let agreed = (&linguist, args);
+                print_agreed(out, &found, agreed, &names, options, candidates, &mut poor)?;
+                for arg in unknown {
+                    let message =
+                        format!("no colors found for {arg:?}{}", did_you_mean(&colors, arg));
+                    writeln!(out, "{}", message.dimmed())?;
+                }
+                found
+            }
+            _ => {
+                let mut found = colors.ranked(&query);
+                sort.sort(&mut found);
+                let listing = Listing {
+                    all,
+                    why: why.then_some((&linguist, query.as_str())),
+                    limit,
+                    candidates,
+                };
+                print_found(out, &colors, &found, listing, &names, options, &mut poor)?;
+                found
+            }
+        };
+        if explain {
+            print_explain(out, &colors, &query)?;
+        }
+        if let Some(tab_color) = tab_color {
+            tab_color.apply(out, &colors, &query)?;
+        }
+        if found.is_empty() {
+            Err(NotFound(format!(
+                "no colors found for this language{}",
+                did_you_mean(&colors, &query)
+            )))?
+        }
+        report_poor(out, poor)?;
+        return Ok(());
+    }
+
+    if fail_on_ambiguous {
+        for query in query.iter() {
+            Ambiguous::check(&colors, query, all)?;
+        }
+    }
+
+    let groups = query
+        .iter()
+        .map(|query| {
+            let mut found = colors.ranked(query);
+            sort.sort(&mut found);
+            (query, found)
+        })
+        .collect::<Vec<_>>();
+
+    let mut poor = 0;
+    for (idx, (query, found)) in groups.iter().enumerate() {
+        if idx > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "{}", query.bold())?;
+        if found.is_empty() {
+            let message = format!("no colors found{}", did_you_mean(&colors, query));
+            writeln!(out, "{}", message.dimmed())?;
+        }
+        let listing = Listing {
+            all,
+            why: why.then_some((&linguist, query.as_str())),
+            limit,
+            candidates,
+        };
+        print_found(out, &colors, found, listing, &names, options, &mut poor)?;
+        if explain {
+            print_explain(out, &colors, query)?;
+        }
+    }
+    report_poor(out, poor)?;
+
+    let missing = groups
+        .iter()
+        .filter(|(_, found)| found.is_empty())
+        .map(|(query, _)| query.as_str())
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        Err(NotFound(format!(
+            "no colors found for {} of {} queries: {}",
+            missing.len(),
+            groups.len(),
+            missing.join(", ")
+        )))?
+    }
+    Ok(())
+}
+
+/// How [`print_found`] lists results.
+#[derive(Clone, Copy)]
+struct Listing<'a> {
+    /// List group members found alongside their parent language instead of
+    /// collapsing them, as with `--all`.
+    all: bool,
+    /// Show why each result matched the query, as with `--why`.
+    why: Option<(&'a Linguist, &'a str)>,
+    /// Show at most this many results, as with `--limit`.
+    limit: Option<usize>,
+    /// Show the xterm colors nearest each result, as with `--candidates`.
+    candidates: Option<Candidates>,
+}
+
+/// Print query results, with group members collapsed into their parent
+/// language if both were found, unless [`Listing::all`] is set.
+fn print_found(
+    out: &mut impl Write,
+    colors: &ColorMap<'_>,
+    found: &[Ranked<'_>],
+    listing: Listing<'_>,
+    names: &DisplayNames,
+    options: &RenderOptions,
+    poor: &mut usize,
+) -> io::Result<()> {
+    let collapsed = |lang: &str| collapsed(colors, found, listing.all, lang);
+    let shown = found.iter().filter(|lang| !collapsed(&lang.name));
+    let limit = listing.limit.unwrap_or(usize::MAX);
+    let more = shown.clone().count().saturating_sub(limit);
+    for ranked in shown.take(limit) {
+        let Ranked { name, color, .. } = ranked;
+        *poor += color.is_poor_approximation(options) as usize;
+        write!(out, "{} {}", color.display(options), names.get(name))?;
+        let members = found
+            .iter()
+            .filter(|member| colors.parent(&member.name) == Some(name) && collapsed(&member.name))
+            .count();
+        if members > 0 {
+            write!(out, " {}", format!("(+{members} in group)").dimmed())?;
+        }
+        if let Some(source) = colors.color_source(name) {
+            write!(out, " {}", format!("(via {})", names.get(source)).dimmed())?;
+        }
+        if ranked.primary {
+            write!(out, " {}", "(primary)".dimmed())?;
+        }
+        if let Some((linguist, query)) = listing.why {
+            let why = why_matched((linguist, colors), ranked, query);
+            write!(out, " {}", format!("({why})").dimmed())?;
+        }
+        writeln!(out)?;
+        if let Some(candidates) = listing.candidates {
+            candidates.write(out, &options.readable(color))?;
+        }
+    }
+    // after the results, which are buffered
+    out.flush()?;
+    note_limited(more);
+    Ok(())
+}
+
+/// Why `found` matched `query`, for `--why`, from [`ColorMap::provenance`]:
+/// `name`, `alias "py"`, `filename "Makefile"`, `interpreter "python3"`,
+/// `extension ".py"`, or the words of the query it shares, such as
+/// `words "java", "script"`, with every way it matched, as in
+/// `alias "py", extension ".py"`.
+fn why_matched(
+    (linguist, colors): (&Linguist, &ColorMap<'_>),
+    found: &Ranked<'_>,
+    query: &str,
+) -> String {
+    let matched = colors.provenance(query, &found.name);
+    // filenames are indexed lowercased, so take them as Linguist writes them
+    let filename = |keyword: &str| {
+        let lang = linguist.get(&found.name);
+        let filenames = lang.iter().flat_map(|lang| lang.filenames());
+        let filename = filenames.into_iter().find(|f| f.to_lowercase() == keyword);
+        filename.map_or(keyword.to_string(), String::clone)
+    };
+    let mut why = (matched.iter())
+        .filter(|matched| matched.via != MatchVia::Word)
+        .map(|matched| {
+            let keyword = &*matched.keyword;
+            match matched.via {
+                MatchVia::Name if matched.kind == MatchKind::Pattern => format!("name {keyword:?}"),
+                MatchVia::Name => "name".to_string(),
+                MatchVia::Filename => format!("filename {:?}", filename(keyword)),
+                MatchVia::Extension => format!("extension \".{keyword}\""),
+                via => format!("{} {keyword:?}", via.name()),
+            }
+        })
+        .collect::<Vec<_>>();
+    let words = (matched.iter())
+        .filter(|matched| matched.via == MatchVia::Word)
+        .map(|matched| format!("{:?}", matched.keyword))
+        .collect::<Vec<_>>();
+    match words.len() {
+        0 => {}
+        1 => why.push(format!("word {}", words[0])),
+        _ => why.push(format!("words {}", words.join(", "))),
+    }
+    why.join(", ")
+}
+
+/// `, did you mean: a, b?` with languages spelled like `query`, or nothing if
+/// there are none.
+fn did_you_mean(colors: &ColorMap<'_>, query: &str) -> String {
+    match &colors.suggest(query, 3)[..] {
+        [] => String::new(),
+        names => format!(", did you mean: {}?", names.join(", ")),
+    }
+}
+
+/// The queries in `args`: each argument, or each of several separated by
+/// commas, as in `rust,go`.
+fn split_groups(args: &[String]) -> Vec<String> {
+    let queries = args.iter().flat_map(|arg| arg.split(',')).map(str::trim);
+    queries
+        .filter(|query| !query.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Sort `found`, which is best match first, by `sort`, and keep at most `limit`
+/// of them.
+fn arrange(found: &mut Vec<Ranked<'_>>, sort: SortOrder, limit: Option<usize>) {
+    sort.sort(found);
+    if let Some(limit) = limit {
+        note_limited(found.len().saturating_sub(limit));
+        found.truncate(limit);
+    }
+}
+
+/// Tell on stderr that `more` results were left out by `--limit`, if any.
+fn note_limited(more: usize) {
+    if more > 0 {
+        eprintln!("{}", format!("… and {more} more").dimmed());
+    }
+}
+
+/// Whether `lang` is left out of `found` in favor of its parent language,
+/// which is also found, unless `all` is set.
+fn collapsed(colors: &ColorMap<'_>, found: &[Ranked<'_>], all: bool, lang: &str) -> bool {
+    !all && colors
+        .parent(lang)
+        .is_some_and(|parent| found.iter().any(|other| other.name == parent))
+}
+
+/// How the arguments of a query mixing names and extensions, such as
+/// `rust .rs`, resolve one by one.
+enum Mixed<'a> {
+    /// Every argument that matches anything matches these languages best.
+    /// `unknown` are the arguments that match nothing.
+    Agree {
+        found: Vec<Ranked<'a>>,
+        unknown: Vec<&'a str>,
+    },
+    /// The arguments match different languages, so they are shown one by one.
+    Disagree,
+}
+
+/// `None` unless the query has more than one argument, at least one of them an
+/// extension such as `.rs`, and is not a name or alias as a whole.
+fn resolve_mixed<'a>(colors: &'a ColorMap<'_>, query: &'a [String]) -> Option<Mixed<'a>> {
+    let is_extension = |arg: &str| {
+        arg.strip_prefix('.')
+            .is_some_and(|ext| !ext.is_empty() && !ext.contains(['.', '/', '\\']))
+    };
+    if query.len() < 2 || !query.iter().any(|arg| is_extension(arg)) {
+        return None;
+    }
+    let joined = colors.ranked(&query.join(" "));
+    if joined
+        .first()
+        .is_some_and(|best| best.kind == MatchKind::Exact)
+    {
+        return None;
+    }
+    let mut agreed = None::<Vec<Ranked<'a>>>;
+    let mut unknown = vec![];
+    for arg in query {
+        let mut found = colors.ranked(arg);
+        let Some(best) = found.first() else {
+            unknown.push(arg.as_str());
+            continue;
+        };
+        let ties = found.iter().take_while(|other| other.ties(best)).count();
+        found.truncate(ties);
+        agreed = Some(match agreed {
+            None => found,
+            Some(agreed) => agreed
+                .into_iter()
+                .filter(|lang| found.iter().any(|other| other.name == lang.name))
+                .collect(),
+        });
+    }
+    match agreed? {
+        found if found.is_empty() => Some(Mixed::Disagree),
+        found => Some(Mixed::Agree { found, unknown }),
+    }
+}
+
+/// Print the languages every argument agreed on, each with how the arguments
+/// matched it, e.g. `matched: name "rust", ext ".rs"`.
+fn print_agreed(
+    out: &mut impl Write,
+    found: &[Ranked<'_>],
+    (linguist, query): (&Linguist, &[String]),
+    names: &DisplayNames,
+    options: &RenderOptions,
+    candidates: Option<Candidates>,
+    poor: &mut usize,
+) -> io::Result<()> {
+    for Ranked { name, color, .. } in found {
+        *poor += color.is_poor_approximation(options) as usize;
+        write!(out, "{} {}", color.display(options), names.get(name))?;
+        if let Some(lang) = linguist.get(name) {
+            let matched = query
+                .iter()
+                .filter_map(|arg| {
+                    let lower = arg.to_lowercase();
+                    let kind = match () {
+                        _ if lang.name().to_lowercase() == lower => "name",
+                        _ if lang.aliases().contains(&lower) => "alias",
+                        _ if lang
+                            .extensions()
+                            .iter()
+                            .any(|ext| ext.to_lowercase() == lower) =>
+                        {
+                            "ext"
+                        }
+                        _ => return None,
+                    };
+                    Some(format!("{kind} {arg:?}"))
+                })
+                .collect::<Vec<_>>();
+            if !matched.is_empty() {
+                write!(
+                    out,
+                    " {}",
+                    format!("(matched: {})", matched.join(", ")).dimmed()
+                )?;
+            }
+        }
+        writeln!(out)?;
+        if let Some(candidates) = candidates {
+            candidates.write(out, &options.readable(color))?;
+        }
+    }
+    Ok(())
+}
+
+/// Replace arguments that look like paths, such as `src/main.rs` or a glob the
+/// shell has already expanded, with the first language with a color detected
+/// for the file.
+///
+/// Paths that do not exist here are told by their name alone, with `\` taken
+/// as a separator too, so that `C:\src\main.rs` works anywhere.
+///
+/// With `files`, as with `for --file`, every argument is a path, and one that
+/// is not detected is an error naming the stages tried.
+fn detect_paths(
+    pipeline: &Pipeline<'_>,
+    query: Vec<String>,
+    files: bool,
+) -> anyhow::Result<Vec<String>> {
+    let mut detected = vec![];
+    for arg in query {
+        let path = Path::new(&arg);
+        let detection = match files || arg.contains(['/', '\\']) {
+            true if path.is_file() => pipeline.detect(path),
+            true => pipeline.detect(Path::new(&arg.replace('\\', "/"))),
+            false => None,
+        };
+        let language =
+            detection.and_then(|d| d.languages.into_iter().find(|l| l.color().is_some()));
+        let arg = match language {
+            Some(language) => language.name().to_lowercase(),
+            None if files => {
+                let tried = pipeline.stages().iter().map(|stage| stage.name());
+                let tried = tried.collect::<Vec<_>>().join(", ");
+                match path.is_file() {
+                    true => anyhow::bail!("could not tell the language of {arg}, tried: {tried}"),
+                    false => anyhow::bail!(
+                        "could not tell the language of {arg} by its name, as it is not \
+                        a file here, tried: {tried}"
+                    ),
+                }
+            }
+            None => arg,
+        };
+        if !detected.contains(&arg) {
+            detected.push(arg);
+        }
+    }
+    Ok(detected)
+}
+
+/// Narrate how `query` was resolved, from [`ColorMap::explain`].
+fn print_explain(out: &mut impl Write, colors: &ColorMap<'_>, query: &str) -> io::Result<()> {
+    let (_, events) = colors.explain(query);
+    let list = |found: &[std::borrow::Cow<'_, str>]| match found.is_empty() {
+        true => "nothing".to_string(),
+        false => found.join(", "),
+    };
+    let mut rank = 0;
+    for event in events {
+        let line = match event {
+            ExplainEvent::Normalized { query, normalized } => {
+                format!("normalized {query:?} to {normalized:?}")
+            }
+            ExplainEvent::ExactLookup { key, found } => {
+                format!("exact name or alias {key:?}: {}", list(&found))
+            }
+            ExplainEvent::PatternLookup { pattern, found } => {
+                format!("pattern {pattern:?}: {}", list(&found))
+            }
+            ExplainEvent::InterpreterLookup { key, found } => {
+                format!("interpreter {key:?}: {}", list(&found))
+            }
+            ExplainEvent::ExtensionLookup { key, found } => {
+                format!("extension {key:?}: {}", list(&found))
+            }
+            ExplainEvent::Classified { shape, text } => match shape {
+                QueryShape::Plain => "no exact match, using the query as is".to_string(),
+                QueryShape::Glob => format!("no exact match, looks like file paths: {text:?}"),
+                QueryShape::Sentence => format!("no exact match, looks like a question: {text:?}"),
+            },
+            ExplainEvent::Tokenized { words } => {
+                format!("looking up words: {}", words.join(", "))
+            }
+            ExplainEvent::WordLookup { word, found } => {
+                format!("word {word:?}: {}", list(&found))
+            }
+            ExplainEvent::Ranked { name, kind, score } => {
+                rank += 1;
+                match kind {
+                    MatchKind::Exact => format!("#{rank} {name}: exact match"),
+                    MatchKind::Interpreter => format!("#{rank} {name}: interpreter match"),
+                    MatchKind::Extension => format!("#{rank} {name}: extension match"),
+                    MatchKind::Pattern => format!("#{rank} {name}: pattern match"),
+                    MatchKind::Words => format!("#{rank} {name}: {score} word(s) matched"),
+                }
+            }
+        };
+        writeln!(out, "{}", line.dimmed())?;
+    }
+    if rank > 1 {
+        let order = "ordered by match kind, then words matched, then name, \
+            with group members right after their parent";
+        writeln!(out, "{}", order.dimmed())?;
+    }
+    Ok(())
+}
+
+fn tree(
+    out: &mut impl Write,
+    name: Option<String>,
+    ascii: bool,
+    data: &Dataset<'_>,
+    options: &RenderOptions,
+) -> anyhow::Result<()> {
+    let (linguist, names) = data.load()?;
+
+    let [branch, last, pipe, space] = match ascii {
+        true => ["|-- ", "`-- ", "|   ", "    "],
+        false => ["├── ", "└── ", "│   ", "    "],
+    };
+
+    let print_tree = |out: &mut dyn Write, tree: &Group<'_>| -> io::Result<()> {
+        let mut result = Ok(());
+        tree.walk(|group, path, color| {
+            if result.is_err() {
+                return;
+            }
+            let mut prefix = String::new();
+            if let Some((&is_last, ancestors)) = path.split_last() {
+                for &ancestor_is_last in ancestors {
+                    prefix.push_str(if ancestor_is_last { space } else { pipe });
+                }
+                prefix.push_str(if is_last { last } else { branch });
+            }
+            let name = names.get(group.language().name());
+            result = match color {
+                Some((color, inherited)) => {
+                    let color = TermColor::from(color);
+                    match inherited {
+                        true => writeln!(
+                            out,
+                            "{prefix}{name} {} {}",
+                            color.display(options),
+                            "(inherited)".dimmed()
+                        ),
+                        false => writeln!(out, "{prefix}{name} {}", color.display(options)),
+                    }
+                }
+                None => writeln!(out, "{prefix}{name} {}", "no color".dimmed()),
+            };
+        });
+        result
+    };
+
+    if let Some(name) = name {
+        let Some(family) = linguist.family(&name) else {
+            Err(NotFound(format!("no such language: {name}")))?
+        };
+        print_tree(out, &family)?;
+        return Ok(());
+    }
+
+    let Groups { trees, ungrouped } = linguist.groups();
+    for tree in trees.iter() {
+        print_tree(out, tree)?;
+    }
+    writeln!(out)?;
+    writeln!(out, "{}", "Ungrouped".bold())?;
+    for lang in ungrouped {
+        match lang.color() {
+            Some(color) => writeln!(
+                out,
+                "{} {}",
+                names.get(lang.name()),
+                TermColor::from(color).display(options)
+            )?,
+            None => writeln!(out, "{} {}", names.get(lang.name()), "no color".dimmed())?,
+        }
+    }
+    Ok(())
+}
+
+/// `scan`, and `for --dir`
+struct ScanFlags {
+    per_root: bool,
+    heuristics: bool,
+    respect_ignore: bool,
+    /// Show at most this many languages, and the rest as one line.
+    top: Option<usize>,
+}
+
+fn scan(
+    out: &mut impl Write,
+    paths: Vec<PathBuf>,
+    flags: ScanFlags,
+    data: &Dataset<'_>,
+    options: &RenderOptions,
+) -> anyhow::Result<()> {
+    let ScanFlags {
+        per_root,
+        heuristics,
+        respect_ignore,
+        top,
+    } = flags;
+    let (linguist, names) = data.load()?;
+    let pipeline = data.pipeline(&linguist, heuristics)?;
+
+    let paths = match paths.is_empty() {
+        true => vec![PathBuf::from(".")],
+        false => paths,
+    };
+    let scan_options = ScanOptions::new().respect_ignore(respect_ignore);
+    let scan = linguist_termcolor::scan::scan_with_options(&pipeline, &paths, &scan_options)?;
+
+    let print_tally = |out: &mut dyn Write, tally: &Tally<'_>| -> io::Result<()> {
+        let shares = tally.shares();
+        let top = top.unwrap_or(usize::MAX).min(shares.len());
+        for &(lang, _, percent) in &shares[..top] {
+            let percent = Precision::Percent.fixed(percent);
+            let name = names.get(lang.name());
+            match lang.color() {
+                Some(color) => writeln!(
+                    out,
+                    "{} {percent:>5}% {name}",
+                    TermColor::from(color).display(options)
+                )?,
+                None => writeln!(out, "{:<21} {percent:>5}% {name}", "no color".dimmed())?,
+            }
+        }
+        let rest = &shares[top..];
+        if !rest.is_empty() {
+            let percent = Precision::Percent.fixed(rest.iter().map(|(_, _, p)| p).sum());
+            let languages = if rest.len() == 1 {
+                "language"
+            } else {
+                "languages"
+            };
+            let other = format!("other ({} {languages})", rest.len());
+            writeln!(out, "{:<21} {percent:>5}% {}", "", other.dimmed())?;
+        }
+        Ok(())
+    };
+
+    if per_root {
+        for (root, tally) in scan.roots.iter() {
+            writeln!(out, "{}", root.display().to_string().bold())?;
+            if tally.is_empty() {
+                writeln!(out, "{}", "no languages found".dimmed())?;
+            }
+            print_tally(out, tally)?;
+            writeln!(out)?;
+        }
+        writeln!(out, "{}", "Total".bold())?;
+    }
+
+    if scan.total.is_empty() {
+        Err(NotFound("no languages found".to_string()))?
+    }
+    print_tally(out, &scan.total)?;
+    Ok(())
+}
+
+fn paint(
+    out: &mut impl Write,
+    pairs: Vec<String>,
+    format: PaintFormat,
+    data: &Dataset<'_>,
+    options: &RenderOptions,
+) -> anyhow::Result<()> {
+    let chunks = pairs.chunks_exact(2);
+    if !chunks.remainder().is_empty() {
+        Err(anyhow::anyhow!(
+            "expected pairs of LANG TEXT, got an odd number of arguments"
+        ))?
+    }
+    let (linguist, _) = data.load()?;
+    let mut painted = vec![];
+    for pair in chunks {
+        let [lang, text] = pair else { unreachable!() };
+        let Some(color) = linguist.get(lang).and_then(|lang| lang.color()) else {
+            Err(NotFound(format!(
+                "no colors found for this language: {lang}"
+            )))?
+        };
+        painted.push((TermColor::from(color), text));
+    }
+    match format {
+        PaintFormat::Styled => {
+            let mut line = String::new();
+            for (idx, (color, text)) in painted.iter().enumerate() {
+                if idx > 0 {
+                    line.push(' ');
+                }
+                color.paint_to(&mut line, text, options)?;
+            }
+            writeln!(out, "{line}")?;
+        }
+        PaintFormat::Sh => {
+            let mut line = String::from("$'");
+            for (idx, (color, text)) in painted.iter().enumerate() {
+                if idx > 0 {
+                    line.push(' ');
+                }
+                line.push_str(&color.sgr(options).replace('\x1b', "\\e"));
+                line.push_str(&text.replace('\\', "\\\\").replace('\'', "\\'"));
+                line.push_str("\\e[0m");
+            }
+            line.push('\'');
+            writeln!(out, "{line}")?;
+        }
+    }
+    Ok(())
+}
+
+fn distinct(
+    out: &mut impl Write,
+    langs: Vec<String>,
+    min_delta: f64,
+    data: &Dataset<'_>,
+    options: &RenderOptions,
+) -> anyhow::Result<()> {
+    let (linguist, names) = data.load()?;
+    let mut found = vec![];
+    for lang in langs.iter() {
+        let Some(language) = linguist.get(lang) else {
+            Err(NotFound(format!("no such language: {lang}")))?
+        };
+        let Some(color) = language.color() else {
+            Err(NotFound(format!(
+                "no colors found for this language: {lang}"
+            )))?
+        };
+        found.push((language, color));
+    }
+
+    let colors = found.iter().map(|(_, color)| *color).collect::<Vec<_>>();
+    let nudged = linguist_termcolor::distinct::distinct(&colors, min_delta);
+
+    for ((lang, original), nudged) in found.iter().zip(nudged.iter()) {
+        let color = TermColor::from(nudged.color);
+        write!(out, "{} {}", color.display(options), names.get(lang.name()))?;
+        if nudged.moved > 0.0 {
+            let note = format!(
+                "(adjusted from {}, Δ{})",
+                TermColor::from(*original).hex(),
+                Precision::DeltaE.fixed(nudged.moved)
+            );
+            write!(out, " {}", note.dimmed())?;
+        }
+        writeln!(out)?;
+    }
+
+    let colors = nudged.iter().map(|nudged| nudged.color).collect::<Vec<_>>();
+    let min = linguist_termcolor::distinct::min_pairwise_delta(&colors);
+    if min < min_delta {
+        out.flush()?;
+        eprintln!(
+            "{} the closest pair is still only Δ{} apart",
+            "warning:".yellow(),
+            Precision::DeltaE.fixed(min)
+        );
+    }
+    Ok(())
+}
+
+/// Some extensions given to `coverage` have no language with a color.
+#[derive(Debug)]
+struct Uncovered {
+    unresolved: usize,
+    total: usize,
+}
+
+impl Uncovered {
+    /// Exit codes for partial and no coverage, after [`Ambiguous::EXIT_CODE`].
+    const EXIT_PARTIAL: i32 = 6;
+    const EXIT_NONE: i32 = 7;
+
+    fn exit_code(&self) -> i32 {
+        match self.unresolved < self.total {
+            true => Self::EXIT_PARTIAL,
+            false => Self::EXIT_NONE,
+        }
+    }
+}
+
+impl std::fmt::Display for Uncovered {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { unresolved, total } = self;
+        write!(f, "{unresolved} of {total} extensions have no color")
+    }
+}
+
+impl std::error::Error for Uncovered {}
+
+fn coverage(
+    out: &mut impl Write,
+    ext_file: &Path,
+    data: &Dataset<'_>,
+    options: &RenderOptions,
+) -> anyhow::Result<()> {
+    let text = match ext_file.to_str() {
+        Some("-") => io::read_to_string(io::stdin().lock())?,
+        _ => std::fs::read_to_string(ext_file)
+            .map_err(|err| anyhow::anyhow!("{}: {err}", ext_file.display()))?,
+    };
+    let mut extensions = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| format!(".{}", line.trim_start_matches('.')))
+        .collect::<Vec<_>>();
+    extensions.dedup();
+
+    let (linguist, names) = data.load()?;
+    let pipeline = Pipeline::new(&linguist);
+    let width = extensions.iter().map(|ext| ext.len()).max().unwrap_or(0);
+
+    let mut unresolved = vec![];
+    for ext in extensions.iter() {
+        let detected = pipeline
+            .detect_extension(ext)
+            .map(|d| d.languages)
+            .unwrap_or_default();
+        match detected.iter().find_map(|lang| Some((lang, lang.color()?))) {
+            Some((lang, color)) => {
+                let color = TermColor::from(color);
+                let name = names.get(lang.name());
+                writeln!(out, "{ext:<width$} {} {name}", color.display(options))?;
+            }
+            None => {
+                let reason = match detected.is_empty() {
+                    true => "unknown extension".to_string(),
+                    false => {
+                        let langs = detected.iter().map(|lang| names.get(lang.name()));
+                        format!("no color: {}", langs.collect::<Vec<_>>().join(", "))
+                    }
+                };
+                writeln!(out, "{ext:<width$} {}", reason.dimmed())?;
+                unresolved.push(ext.as_str());
+            }
+        }
+    }
+
+    let total = extensions.len();
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{} of {total} extensions resolved",
+        total - unresolved.len()
+    )?;
+    if unresolved.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "unresolved: {}", unresolved.join(", "))?;
+    // exiting with the error skips the final flush
+    out.flush()?;
+    Err(Uncovered {
+        unresolved: unresolved.len(),
+        total,
+    })?
+}
+
+/// Options of the `list` command.
+struct ListFlags {
+    format: OutputFormat,
+    family: Option<Family>,
+    prefix: Option<String>,
+    extensions: bool,
+    no_color_only: bool,
+    names_only: bool,
+}
+
+fn list(
+    out: &mut impl Write,
+    flags: ListFlags,
+    data: &Dataset<'_>,
+    options: &RenderOptions,
+) -> anyhow::Result<()> {
+    let ListFlags {
+        format,
+        family,
+        prefix,
+        extensions,
+        no_color_only,
+        names_only,
+    } = flags;
+    let (linguist, names) = data.load()?;
+    let prefix = prefix.as_deref().unwrap_or_default().to_lowercase();
+    let languages = linguist
+        .languages()
+        .filter(|lang| lang.name().to_lowercase().starts_with(&prefix));
+    let write_extensions = |out: &mut dyn Write, lang: &Language<'_>| match extensions
+        && !lang.extensions().is_empty()
+    {
+        true => write!(out, " {}", lang.extensions().join(" ").dimmed()),
+        false => Ok(()),
+    };
+
+    if format == OutputFormat::Markdown && (no_color_only || names_only) {
+        anyhow::bail!("--format markdown cannot be combined with --no-color-only or --names-only")
+    }
+    if no_color_only {
+        for lang in languages.filter(|lang| lang.color().is_none()) {
+            if names_only {
+                writeln!(out, "{}", lang.name())?;
+                continue;
+            }
+            write!(out, "{}", names.get(lang.name()))?;
+            write_extensions(out, &lang)?;
+            writeln!(out)?;
+        }
+        return Ok(());
+    }
+
+    let mut found = languages
+        .filter_map(|lang| Some((lang, lang.color()?)))
+        .collect::<Vec<_>>();
+    if let Some(family) = family {
+        found.retain(|(_, color)| color_family(color) == family);
+        // most vivid first
+        found.sort_by(|(_, a), (_, b)| chroma(b).total_cmp(&chroma(a)));
+    }
+    if format == OutputFormat::Markdown {
+        let rows = found.iter().map(|(lang, color)| {
+            let lang_extensions = extensions.then(|| lang.extensions());
+            (
+                names.get(lang.name()),
+                TermColor::from(*color),
+                lang_extensions,
+            )
+        });
+        return Ok(write_markdown(out, rows, extensions, options)?);
+    }
+    for (lang, color) in found {
+        if names_only {
+            writeln!(out, "{}", lang.name())?;
+            continue;
+        }
+        let color = TermColor::from(color);
+        write!(out, "{} {}", color.display(options), names.get(lang.name()))?;
+        write_extensions(out, &lang)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn chroma(color: &Color) -> f64 {
+    to_polar(oklab(color))[1]
+}
+
+fn parse_cvd(name: &str) -> Result<Cvd, String> {
+    Cvd::from_name(name).ok_or_else(|| {
+        let names = Cvd::ALL.map(Cvd::name);
+        format!("expected one of: {}", names.join(", "))
+    })
+}
+
+fn parse_family(name: &str) -> Result<Family, String> {
+    Family::from_name(name).ok_or_else(|| {
+        let names = Family::ALL.map(Family::name);
+        format!("expected one of: {}", names.join(", "))
+    })
+}
+
+fn info(out: &mut impl Write, index: bool, data: &Dataset<'_>) -> anyhow::Result<()> {
+    let (linguist, _) = data.load()?;
+    let languages = linguist.languages().count();
+    let colored = linguist.iter_colors().count();
+    let Groups { trees, .. } = linguist.groups();
+    writeln!(out, "{}", "Languages".bold())?;
+    writeln!(out, "total          {languages}")?;
+    writeln!(out, "with colors    {colored}")?;
+    writeln!(out, "groups         {}", trees.len())?;
+
+    if index {
+        let stats = linguist.colors()?.stats();
+        writeln!(out)?;
+        writeln!(out, "{}", "Index".bold())?;
+        writeln!(out, "languages      {}", stats.languages)?;
+        writeln!(out, "words          {}", stats.words)?;
+        writeln!(out, "exact keys     {}", stats.exact)?;
+        writeln!(out, "extension keys {}", stats.extensions)?;
+        writeln!(out, "interpreters   {}", stats.interpreters)?;
+        writeln!(out, "entries        {}", stats.entries)?;
+        let average = Precision::Ratio.fixed(stats.average_entries());
+        writeln!(out, "per key        {average}")?;
+        writeln!(out, "heap (approx)  {} KiB", stats.heap_bytes / 1024)?;
+        writeln!(out)?;
+        writeln!(out, "{}", "Largest words".bold())?;
+        for (word, count) in stats.largest {
+            writeln!(out, "{count:<4} {word}")?;
+        }
+    }
+    Ok(())
+}
+
+fn info_language(
+    out: &mut impl Write,
+    name: &str,
+    format: OutputFormat,
+    data: &Dataset<'_>,
+    options: &RenderOptions,
+) -> anyhow::Result<()> {
+    let (linguist, names) = data.load()?;
+    let Some(lang) = linguist.get(name) else {
+        Err(NotFound(format!("no such language: {name}")))?
+    };
+    if format == OutputFormat::Json {
+        let info = JsonInfo {
+            name: lang.name(),
+            hex: lang.color().map(|color| TermColor::from(color).hex()),
+            about: JsonAbout::of(lang),
+            aliases: lang.aliases(),
+            extensions: lang.extensions(),
+            filenames: lang.filenames(),
+            interpreters: lang.interpreters(),
+        };
+        return write_json(out, &info);
+    }
+    writeln!(out, "{}", names.get(lang.name()).bold())?;
+    match lang.color() {
+        Some(color) => writeln!(
+            out,
+            "color          {}",
+            TermColor::from(color).display(options)
+        )?,
+        None => writeln!(out, "color          {}", "no color".dimmed())?,
+    }
+    if let Some(language_type) = lang.language_type() {
+        writeln!(out, "type           {}", language_type.name())?;
+    }
+    if let Some(group) = lang.group() {
+        writeln!(out, "group          {group}")?;
+    }
+    if let Some(language_id) = lang.language_id() {
+        writeln!(out, "language_id    {language_id}")?;
+    }
+    if let Some(tm_scope) = lang.tm_scope() {
+        writeln!(out, "tm_scope       {tm_scope}")?;
+    }
+    let lists = [
+        ("aliases", lang.aliases()),
+        ("extensions", lang.extensions()),
+        ("filenames", lang.filenames()),
+        ("interpreters", lang.interpreters()),
+    ];
+    for (field, list) in lists {
+        if !list.is_empty() {
+            writeln!(out, "{field:<14} {}", list.join(", "))?;
+        }
+    }
+    Ok(())
+}
+
+/// Options of the `export` command.
+struct ExportFlags {
+    preview_cmd: bool,
+    langs: Vec<String>,
+    formats: Vec<ExportFormat>,
+    out_dir: Option<PathBuf>,
+    output: Option<PathBuf>,
+    check: bool,
+    strict: bool,
+    /// The colors of `ls-colors`.
+    depth: Depth,
+    /// Map extensions rather than names in `rust`, `python`, and `json-map`.
+    extensions: bool,
+    /// Write `rust` as a `phf::Map`.
+    phf: bool,
+    numbers: Rounding,
+}
+
+fn export(
+    out: &mut impl Write,
+    format: ExportFormat,
+    flags: ExportFlags,
+    data: &Dataset<'_>,
+    color_space: Metric,
+) -> anyhow::Result<()> {
+    let ExportFlags {
+        preview_cmd,
+        langs,
+        formats,
+        out_dir,
+        output,
+        check,
+        strict,
+        depth,
+        extensions,
+        phf,
+        numbers,
+    } = flags;
+
+    if preview_cmd {
+        let ExportFormat::Fzf = format else {
+            Err(anyhow::anyhow!("--preview-cmd is only available for fzf"))?
+        };
+        let bin = env!("CARGO_BIN_NAME");
+        let preview = format!("{bin} xterm {{2}}");
+        writeln!(
+            out,
+            "{bin} --color always export fzf | fzf --ansi --delimiter '\\t' --preview '{preview}'"
+        )?;
+        return Ok(());
+    }
+
+    let formats = match format {
+        ExportFormat::All if formats.is_empty() || formats.contains(&ExportFormat::All) => {
+            ExportFormat::EVERY.to_vec()
+        }
+        ExportFormat::All => formats,
+        format => vec![format],
+    };
+    if extensions && !formats.iter().all(|format| format.is_table()) {
+        anyhow::bail!("--extensions is only available for rust, python, and json-map")
+    }
+    if phf && !formats.iter().all(|&format| format == ExportFormat::Rust) {
+        anyhow::bail!("--phf is only available for rust")
+    }
+
+    let (linguist, names) = data.load()?;
+    let selected = match langs.is_empty() {
+        true => linguist.languages().collect::<Vec<_>>(),
+        false => langs
+            .iter()
+            .map(|name| {
+                linguist
+                    .get(name)
+                    .ok_or_else(|| NotFound(format!("unknown language {name:?}")))
+            })
+            .collect::<Result<_, _>>()?,
+    };
+    // without a selection, languages without a color are simply not exported
+    let colorless = selected
+        .iter()
+        .filter(|lang| !langs.is_empty() && lang.color().is_none())
+        .map(|lang| lang.name())
+        .collect::<Vec<_>>();
+    if !colorless.is_empty() {
+        let message = format!(
+            "{} language(s) without a color: {}",
+            colorless.len(),
+            colorless.join(", ")
+        );
+        match strict {
+            true => anyhow::bail!(message),
+            false => eprintln!("{} skipped {message}", "warning:".yellow()),
+        }
+    }
+    let selected = selected
+        .into_iter()
+        .filter_map(|lang| Some((lang, TermColor::from(lang.color()?))))
+        .collect::<Vec<_>>();
+    let colors = linguist.colors()?;
+    let table = ExportTable {
+        entries: match extensions {
+            true => extension_colors(&selected, |ext, lang| {
+                colors.primary_language(ext) == Some(lang)
+            }),
+            false => {
+                let mut entries = (selected.iter())
+                    .map(|(lang, color)| (lang.name(), color))
+                    .collect::<Vec<_>>();
+                entries.sort_by_cached_key(|(name, _)| name.to_lowercase());
+                entries
+            }
+        },
+        extensions,
+        phf,
+    };
+    let render = |format: ExportFormat| -> anyhow::Result<Vec<u8>> {
+        let mut buf = vec![];
+        write_export(
+            &mut buf,
+            format,
+            (&selected, &table),
+            &names,
+            color_space,
+            depth,
+            numbers,
+        )?;
+        Ok(buf)
+    };
+
+    let Some(dir) = out_dir else {
+        let [format] = formats[..] else {
+            Err(anyhow::anyhow!(
+                "exporting more than one format requires --out"
+            ))?
+        };
+        match output {
+            Some(path) => write_atomic(&path, &render(format)?)?,
+            None => out.write_all(&render(format)?)?,
+        }
+        return Ok(());
+    };
+
+    if !check {
+        std::fs::create_dir_all(&dir)?;
+    }
+    let mut stale = vec![];
+    for format in formats {
+        let contents = render(format)?;
+        let path = dir.join(format.file_name());
+        if check {
+            if std::fs::read(&path).ok().as_deref() != Some(&contents[..]) {
+                stale.push(path.display().to_string());
+            }
+            continue;
+        }
+        write_atomic(&path, &contents)?;
+        writeln!(out, "wrote {} ({} bytes)", path.display(), contents.len())?;
     }
+    if !stale.is_empty() {
+        Err(anyhow::anyhow!(
+            "{} file(s) would change: {}",
+            stale.len(),
+            stale.join(", ")
+        ))?
+    }
+    Ok(())
 }
 
-fn xterm(colors: Vec<String>, color_space: ColorSpace) -> anyhow::Result<()> {
-    for color in colors {
-        let color = Color::from_hex(&color)?;
-        let color = TermColor::from(color);
-        println!("{}", color.print(color_space));
+/// Write `contents` to a temporary file next to `path`, then rename it into
+/// place, so that readers never see a partially written file.
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{name}.{}.tmp", std::process::id()));
+    std::fs::write(&temp, contents)?;
+    std::fs::rename(&temp, path).inspect_err(|_| {
+        std::fs::remove_file(&temp).ok();
+    })
+}
+
+/// What the `rust`, `python`, and `json-map` exports list: languages by their
+/// names as Linguist writes them, sorted as Linguist sorts them, or with
+/// --extensions, extensions without the leading dot, sorted.
+struct ExportTable<'a> {
+    entries: Vec<(&'a str, &'a TermColor)>,
+    extensions: bool,
+    phf: bool,
+}
+
+impl ExportTable<'_> {
+    /// The name of the Rust static or Python variable.
+    fn name(&self) -> &'static str {
+        match self.extensions {
+            true => "EXTENSION_COLORS",
+            false => "LANGUAGE_COLORS",
+        }
+    }
+
+    fn write_rust(&self, out: &mut impl Write) -> io::Result<()> {
+        let bin = env!("CARGO_BIN_NAME");
+        let flags = match (self.extensions, self.phf) {
+            (true, true) => " --extensions --phf",
+            (true, false) => " --extensions",
+            (false, true) => " --phf",
+            (false, false) => "",
+        };
+        writeln!(
+            out,
+            "// Generated by `{bin} export rust{flags}`. Do not edit.\n"
+        )?;
+        let name = self.name();
+        match self.phf {
+            true => writeln!(
+                out,
+                "pub static {name}: phf::Map<&'static str, u32> = phf::phf_map! {{"
+            )?,
+            false => writeln!(out, "pub static {name}: &[(&str, u32)] = &[")?,
+        }
+        for (key, color) in &self.entries {
+            let (r, g, b) = color.rgb();
+            let rgb = format!("0x{r:02x}{g:02x}{b:02x}");
+            match self.phf {
+                true => writeln!(out, "    {key:?} => {rgb},")?,
+                false => writeln!(out, "    ({key:?}, {rgb}),")?,
+            }
+        }
+        match self.phf {
+            true => writeln!(out, "}};"),
+            false => writeln!(out, "];"),
+        }
+    }
+
+    fn write_python(&self, out: &mut impl Write) -> anyhow::Result<()> {
+        let bin = env!("CARGO_BIN_NAME");
+        let flags = if self.extensions { " --extensions" } else { "" };
+        writeln!(
+            out,
+            "# Generated by `{bin} export python{flags}`. Do not edit.\n"
+        )?;
+        writeln!(out, "{} = {{", self.name())?;
+        for (key, color) in &self.entries {
+            // JSON strings are also Python strings
+            let key = serde_json::to_string(key)?;
+            writeln!(out, "    {key}: \"{}\",", color.hex())?;
+        }
+        writeln!(out, "}}")?;
+        Ok(())
+    }
+
+    fn write_json_map(&self, out: &mut impl Write) -> anyhow::Result<()> {
+        let map = (self.entries.iter())
+            .map(|(key, color)| (key.to_string(), serde_json::Value::from(color.hex())))
+            .collect::<serde_json::Map<_, _>>();
+        serde_json::to_writer_pretty(&mut *out, &map)?;
+        writeln!(out)?;
+        Ok(())
+    }
+}
+
+fn write_export(
+    out: &mut impl Write,
+    format: ExportFormat,
+    (langs, table): (&[(Language<'_>, TermColor)], &ExportTable<'_>),
+    names: &DisplayNames,
+    color_space: Metric,
+    depth: Depth,
+    numbers: Rounding,
+) -> anyhow::Result<()> {
+    match format {
+        ExportFormat::All => unreachable!("expanded by the caller"),
+        ExportFormat::Fzf => {
+            for (lang, color) in langs {
+                let (r, g, b) = color.rgb();
+                let hex = color.hex();
+                let xterm = color.xterm_index(color_space);
+                let name = names.get(lang.name());
+                let painted = name.truecolor(r, g, b).bold();
+                writeln!(out, "{painted}\t{hex}\t{xterm}")?;
+                for alias in lang.aliases() {
+                    if alias.eq_ignore_ascii_case(lang.name()) {
+                        continue;
+                    }
+                    let painted = alias.truecolor(r, g, b).bold();
+                    writeln!(out, "{painted} → {name}\t{hex}\t{xterm}")?;
+                }
+            }
+        }
+        ExportFormat::Css => {
+            writeln!(out, ":root {{")?;
+            for (ident, color) in identifiers(langs, css_ident)? {
+                writeln!(out, "  --color-{ident}: {};", color.hex())?;
+            }
+            writeln!(out, "}}")?;
+        }
+        ExportFormat::Scss => {
+            for (ident, color) in identifiers(langs, css_ident)? {
+                writeln!(out, "$lang-{ident}: {};", color.hex())?;
+            }
+        }
+        ExportFormat::Json => {
+            let colors = langs
+                .iter()
+                .map(|(lang, color)| JsonColor {
+                    about: Some(JsonAbout::of(*lang)),
+                    ..JsonColor::new(
+                        Some(lang.name()),
+                        color,
+                        &RenderOptions::new().color_space(color_space),
+                        numbers,
+                    )
+                })
+                .collect::<Vec<_>>();
+            write_json(out, &colors)?;
+        }
+        ExportFormat::Html => write_html(out, langs, names, color_space)?,
+        ExportFormat::Vim => {
+            for (ident, color) in identifiers(langs, vim_group)? {
+                let hex = color.hex();
+                let xterm = color.xterm_index(color_space);
+                writeln!(out, "highlight {ident} guifg={hex} ctermfg={xterm}")?;
+            }
+        }
+        ExportFormat::Lua => {
+            writeln!(out, "return {{")?;
+            for (ident, color) in identifiers(langs, lua_key)? {
+                let hex = color.hex();
+                let xterm = color.xterm_index(color_space);
+                writeln!(out, "  {ident} = {{ fg = \"{hex}\", ctermfg = {xterm} }},")?;
+            }
+            writeln!(out, "}}")?;
+        }
+        ExportFormat::LsColors => {
+            let entries = ls_colors(langs).into_iter().map(|(ext, color)| {
+                let sgr = match depth {
+                    Depth::TrueColor => {
+                        let (r, g, b) = color.rgb();
+                        format!("38;2;{r};{g};{b}")
+                    }
+                    Depth::Xterm256 => format!("38;5;{}", color.xterm_index(color_space)),
+                };
+                format!("*.{ext}={sgr}")
+            });
+            writeln!(out, "{}", entries.collect::<Vec<_>>().join(":"))?;
+        }
+        ExportFormat::Rust => table.write_rust(out)?,
+        ExportFormat::Python => table.write_python(out)?,
+        ExportFormat::JsonMap => table.write_json_map(out)?,
     }
     Ok(())
 }
 
-fn linguist(query: Vec<String>, color_space: ColorSpace) -> anyhow::Result<()> {
-    let linguist = Linguist::new()?;
-    let colors = linguist.colors()?;
-    let found = colors.query(&query.join(" "));
-    if found.is_empty() {
-        Err(anyhow::anyhow!("no colors found for this language"))?
+/// Each extension of `langs` once, with the color of the language named by
+/// it, as Markdown by its alias `md`, or else the language it is the first
+/// extension of, or else the first of its languages by name, as C for `.h`.
+/// Extensions that cannot be written in LS_COLORS, such as with a `:`, are
+/// left out.
+fn ls_colors<'a>(langs: &'a [(Language<'_>, TermColor)]) -> Vec<(&'a str, &'a TermColor)> {
+    (extension_colors(langs, |_, _| false).into_iter())
+        .filter(|(ext, _)| !ext.contains([':', '=', '*', '?', '[', '\\']))
+        .collect()
+}
+
+/// Each extension of `langs` once, without the leading dot, with the color of
+/// the language for which `is_primary` holds, if any, or else as in
+/// [`ls_colors`], sorted by extension.
+fn extension_colors<'a>(
+    langs: &'a [(Language<'_>, TermColor)],
+    is_primary: impl Fn(&str, &str) -> bool,
+) -> Vec<(&'a str, &'a TermColor)> {
+    let mut langs = langs.iter().collect::<Vec<_>>();
+    langs.sort_by_cached_key(|(lang, _)| lang.name().to_lowercase());
+    let mut found = BTreeMap::<&str, ((bool, bool, bool), &TermColor)>::new();
+    for (lang, color) in langs {
+        for (idx, ext) in lang.extensions().iter().enumerate() {
+            let ext = ext.trim_start_matches('.');
+            if ext.is_empty() {
+                continue;
+            }
+            let named = std::iter::once(lang.name())
+                .chain(lang.aliases().iter().map(String::as_str))
+                .any(|name| name.eq_ignore_ascii_case(ext));
+            let rank = (is_primary(ext, lang.name()), named, idx == 0);
+            match found.get(ext) {
+                Some((other, _)) if *other >= rank => {}
+                _ => {
+                    found.insert(ext, (rank, color));
+                }
+            }
+        }
     }
-    for (lang, color) in found {
-        println!("{} {}", color.print(color_space), lang);
+    (found.into_iter())
+        .map(|(ext, (_, color))| (ext, color))
+        .collect()
+}
+
+/// The `html` export: a table sorted by name, styled inline so that the
+/// page needs nothing else.
+fn write_html(
+    out: &mut impl Write,
+    langs: &[(Language<'_>, TermColor)],
+    names: &DisplayNames,
+    color_space: Metric,
+) -> io::Result<()> {
+    let mut langs = langs.iter().collect::<Vec<_>>();
+    langs.sort_by_cached_key(|(lang, _)| lang.name().to_lowercase());
+    let space = color_space.name();
+    write!(
+        out,
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Linguist language colors</title>
+<style>
+body {{ font-family: system-ui, sans-serif; margin: 2rem; }}
+table {{ border-collapse: collapse; }}
+th, td {{ padding: 0.25rem 0.75rem; text-align: left; }}
+tr + tr td {{ border-top: 1px solid #8884; }}
+code {{ font-family: ui-monospace, monospace; }}
+.swatch {{ display: inline-block; width: 2.5rem; height: 1.25rem; border-radius: 0.25rem; vertical-align: middle; }}
+.chip {{ width: 1.25rem; }}
+</style>
+</head>
+<body>
+<h1>Linguist language colors</h1>
+<p>{count} languages. Nearest xterm colors are found in {space}.</p>
+<table>
+<thead><tr><th></th><th>Language</th><th>Hex</th><th>xterm</th></tr></thead>
+<tbody>
+"#,
+        count = langs.len(),
+    )?;
+    for (lang, color) in langs {
+        let hex = color.hex();
+        let xterm = color.xterm_color(color_space).hex();
+        let index = color.xterm_index(color_space);
+        let name = escape_html(names.get(lang.name()));
+        writeln!(
+            out,
+            r#"<tr><td><span class="swatch" style="background: {hex}"></span></td><td>{name}</td><td><code>{hex}</code></td><td><span class="swatch chip" style="background: {xterm}"></span> {index}</td></tr>"#
+        )?;
+    }
+    writeln!(out, "</tbody>\n</table>\n</body>\n</html>")
+}
+
+/// Write `--format markdown`: a table of languages with their colors, a
+/// square of about the same color, since GitHub drops inline styles, and the
+/// nearest xterm colors, with a column of extensions if `extensions` is set.
+fn write_markdown<'a>(
+    out: &mut impl Write,
+    rows: impl IntoIterator<Item = (&'a str, TermColor, Option<&'a [String]>)>,
+    extensions: bool,
+    options: &RenderOptions,
+) -> io::Result<()> {
+    let (header, align) = match extensions {
+        true => (" Extensions |", " :--------- |"),
+        false => ("", ""),
+    };
+    writeln!(out, "| Language | Hex | Color | xterm |{header}")?;
+    writeln!(out, "| :------- | :-- | :---: | ----: |{align}")?;
+    for (name, color, lang_extensions) in rows {
+        let resolved = color.resolve(options);
+        let shown = TermColor::from(resolved.shown);
+        write!(
+            out,
+            "| {} | `{}` | {} | {} |",
+            escape_markdown(name),
+            shown.hex(),
+            color_square(&shown),
+            resolved.nearest.index
+        )?;
+        if extensions {
+            let lang_extensions = lang_extensions.unwrap_or_default().iter();
+            let lang_extensions = lang_extensions.map(|ext| format!("`{ext}`"));
+            write!(out, " {} |", lang_extensions.collect::<Vec<_>>().join(" "))?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// The colored square emoji nearest `color` by its [`Family`], e.g. 🟧 for
+/// Rust, with 🟫 for dark oranges, 🟥 for pinks, and ⬛ or ⬜ for grays.
+fn color_square(color: &TermColor) -> &'static str {
+    let lightness = oklab(&color.color())[0];
+    match color_family(&color.color()) {
+        Family::Red | Family::Pink => "🟥",
+        Family::Orange if lightness < 0.55 => "🟫",
+        Family::Orange => "🟧",
+        Family::Yellow => "🟨",
+        Family::Green => "🟩",
+        Family::Cyan | Family::Blue => "🟦",
+        Family::Purple => "🟪",
+        Family::Gray if lightness < 0.6 => "⬛",
+        Family::Gray => "⬜",
+    }
+}
+
+/// Escape `text` for a cell of a Markdown table, where `|` ends the cell and
+/// the rest would be read as emphasis, links, code, or HTML.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '~'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `langs` named by `ident`, failing if two languages share a name.
+fn identifiers<'a>(
+    langs: &'a [(Language<'_>, TermColor)],
+    ident: fn(&str) -> String,
+) -> anyhow::Result<Vec<(String, &'a TermColor)>> {
+    let mut seen = HashMap::<String, &str>::new();
+    langs
+        .iter()
+        .map(|(lang, color)| {
+            let ident = ident(lang.name());
+            if let Some(other) = seen.insert(ident.clone(), lang.name()) {
+                anyhow::bail!("{other} and {} are both exported as {ident}", lang.name())
+            }
+            Ok((ident, color))
+        })
+        .collect()
+}
+
+/// A language name as a CSS or SCSS identifier: lowercase ASCII letters,
+/// digits, and `-`, with `+` as `p` and `#` as `sharp`, e.g. `cpp` for `C++`
+/// and `csharp` for `C#`.
+fn css_ident(name: &str) -> String {
+    let mut ident = String::new();
+    for c in name.chars() {
+        match c {
+            c if c.is_ascii_alphanumeric() => ident.push(c.to_ascii_lowercase()),
+            '+' => ident.push('p'),
+            '#' => ident.push_str("sharp"),
+            '*' => ident.push_str("star"),
+            _ if ident.is_empty() || ident.ends_with('-') => {}
+            _ => ident.push('-'),
+        }
+    }
+    ident.trim_end_matches('-').to_string()
+}
+
+/// A language name as a Vim highlight group: `Linguist` and each word of its
+/// [`css_ident`] capitalized, e.g. `LinguistCpp` for `C++` and
+/// `LinguistObjectiveC` for `Objective-C`.
+fn vim_group(name: &str) -> String {
+    let mut group = String::from("Linguist");
+    for word in css_ident(name).split('-') {
+        let mut chars = word.chars();
+        group.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+        group.extend(chars);
+    }
+    group
+}
+
+/// A language name as a key of a Lua table: its [`css_ident`] with `_` for
+/// `-`, e.g. `objective_c`, or `["1c_enterprise"]` if that is no Lua name.
+fn lua_key(name: &str) -> String {
+    const KEYWORDS: [&str; 22] = [
+        "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if",
+        "in", "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+    ];
+    let key = css_ident(name).replace('-', "_");
+    match key.starts_with(|c: char| c.is_ascii_digit()) || KEYWORDS.contains(&key.as_str()) {
+        true => format!("[\"{key}\"]"),
+        false => key,
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+    Elvish,
+}
+
+/// Parses with [`parse_metric`], and lists the canonical name of each
+/// supported metric for completions.
+#[derive(Debug, Clone, Copy)]
+struct ColorSpaceParser;
+
+impl TypedValueParser for ColorSpaceParser {
+    type Value = Metric;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Metric, clap::Error> {
+        parse_metric.parse_ref(cmd, arg, value)
+    }
+
+    fn possible_values(
+        &self,
+    ) -> Option<Box<dyn Iterator<Item = clap::builder::PossibleValue> + '_>> {
+        let names = supported_metrics().into_iter().map(Metric::name);
+        Some(Box::new(names.map(clap::builder::PossibleValue::new)))
+    }
+}
+
+/// `--palette`, see [`parse_palette`].
+#[derive(Clone)]
+struct PaletteParser;
+
+impl TypedValueParser for PaletteParser {
+    type Value = Palette;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Palette, clap::Error> {
+        parse_palette.parse_ref(cmd, arg, value)
+    }
+
+    fn possible_values(
+        &self,
+    ) -> Option<Box<dyn Iterator<Item = clap::builder::PossibleValue> + '_>> {
+        let names = ["xterm256", "ansi16", "solarized-dark", "solarized-light"];
+        // clap only takes names that live as long as the process
+        let base16 = BASE16_SCHEMES
+            .iter()
+            .map(|(name, _)| &*Box::leak(format!("base16:{name}").into_boxed_str()));
+        let names = names.into_iter().chain(base16);
+        Some(Box::new(names.map(clap::builder::PossibleValue::new)))
+    }
+}
+
+/// A palette by name, or `base16:` and the path of a base16 scheme's YAML file,
+/// which is read right away.
+fn parse_palette(name: &str) -> Result<Palette, String> {
+    let named = |palette| Ok(Palette::Named(palette));
+    match name {
+        "xterm256" => return Ok(Palette::Xterm256),
+        "ansi16" => return Ok(Palette::Ansi16),
+        "solarized-dark" => return named(NamedPalette::solarized_dark()),
+        "solarized-light" => return named(NamedPalette::solarized_light()),
+        _ => {}
+    }
+    let Some(scheme) = name.strip_prefix("base16:") else {
+        return Err(format!(
+            "unknown palette {name:?}, expected xterm256, ansi16, solarized-dark, \
+            solarized-light, or base16:NAME"
+        ));
+    };
+    if let Some(palette) = NamedPalette::base16(scheme) {
+        return named(palette);
+    }
+    if !Path::new(scheme).is_file() {
+        let schemes = BASE16_SCHEMES.iter().map(|(name, _)| *name);
+        return Err(format!(
+            "{scheme:?} is neither a built-in base16 scheme ({}) nor a file",
+            schemes.collect::<Vec<_>>().join(", ")
+        ));
+    }
+    let yaml = std::fs::read_to_string(scheme).map_err(|err| format!("{scheme}: {err}"))?;
+    let palette =
+        NamedPalette::from_base16_yaml(&yaml).map_err(|err| format!("{scheme}: {err}"))?;
+    // for the rest of the process, as the built-in palettes are
+    named(Box::leak(Box::new(palette)))
+}
+
+/// `--palette-file`, read right away, in JSON if the path ends in `.json` and
+/// in YAML otherwise.
+fn parse_palette_file(path: &str) -> Result<Palette, String> {
+    let file = std::fs::File::open(path).map_err(|err| format!("{path}: {err}"))?;
+    let format = PaletteFormat::of_path(Path::new(path));
+    Palette::from_reader(io::BufReader::new(file), format).map_err(|err| format!("{path}: {err}"))
+}
+
+/// What a shell can complete for an option or a positional argument.
+enum Values {
+    /// A flag, taking no value.
+    None,
+    /// Anything, such as a number.
+    Any,
+    Paths,
+    Choices(Vec<String>),
+    /// Language names, from `list --names-only`.
+    Languages,
+}
+
+struct CompletionArg {
+    long: Option<String>,
+    short: Option<char>,
+    help: String,
+    repeated: bool,
+    values: Values,
+}
+
+struct CompletionCommand {
+    name: String,
+    about: String,
+    options: Vec<CompletionArg>,
+    positional: Option<CompletionArg>,
+}
+
+/// The options of the command line itself, and its subcommands.
+fn completion_spec() -> (Vec<CompletionArg>, Vec<CompletionCommand>) {
+    let mut main = Main::command();
+    main.build();
+    let options = |cmd: &clap::Command| {
+        cmd.get_arguments()
+            .filter(|arg| !arg.is_positional() && !arg.is_hide_set())
+            .map(|arg| completion_arg(cmd, arg))
+            .collect::<Vec<_>>()
+    };
+    let commands = main
+        .get_subcommands()
+        .filter(|cmd| !cmd.is_hide_set())
+        .map(|cmd| {
+            let subcommands = cmd.get_subcommands().map(|cmd| cmd.get_name().to_string());
+            let subcommands = subcommands.collect::<Vec<_>>();
+            let positional = match subcommands.is_empty() {
+                true => cmd
+                    .get_positionals()
+                    .next()
+                    .map(|arg| completion_arg(cmd, arg)),
+                false => Some(CompletionArg {
+                    long: None,
+                    short: None,
+                    help: String::new(),
+                    repeated: false,
+                    values: Values::Choices(subcommands),
+                }),
+            };
+            CompletionCommand {
+                name: cmd.get_name().to_string(),
+                about: first_sentence(cmd.get_about().map(|about| about.to_string())),
+                options: options(cmd),
+                positional,
+            }
+        });
+    let commands = commands.collect();
+    (options(&main), commands)
+}
+
+fn completion_arg(cmd: &clap::Command, arg: &clap::Arg) -> CompletionArg {
+    let choices = arg.get_possible_values();
+    let values = if !arg.get_action().takes_values() {
+        Values::None
+    } else if arg.is_positional() && cmd.get_name() == "for" {
+        Values::Languages
+    } else if !choices.is_empty() {
+        Values::Choices(choices.iter().map(|v| v.get_name().to_string()).collect())
+    } else if arg.get_value_hint() == clap::ValueHint::AnyPath {
+        Values::Paths
+    } else {
+        Values::Any
+    };
+    CompletionArg {
+        long: arg.get_long().map(str::to_string),
+        short: arg.get_short(),
+        help: first_sentence(arg.get_help().map(|help| help.to_string())),
+        repeated: matches!(
+            arg.get_action(),
+            clap::ArgAction::Append | clap::ArgAction::Count
+        ),
+        values,
+    }
+}
+
+/// The first sentence of a help text, on one line.
+fn first_sentence(help: Option<String>) -> String {
+    let help = help.unwrap_or_default();
+    let help = help.split_whitespace().collect::<Vec<_>>().join(" ");
+    // not at `e.g. `, which is followed by lowercase
+    let end = help.match_indices(". ").find(|&(i, _)| {
+        let next = help[i + 2..].chars().next();
+        next.is_some_and(|c| c.is_uppercase())
+    });
+    let first = match end {
+        Some((i, _)) => &help[..i],
+        None => &help,
+    };
+    first.trim_end_matches('.').to_string()
+}
+
+/// A completion script for `shell`, completing subcommands, options, the
+/// values of options that have a fixed set of them, file names, and, in zsh
+/// and fish, language names for `for` from `list --names-only`.
+/// `capabilities` in `--format json`.
+#[derive(Serialize)]
+struct JsonCapabilities {
+    spaces: Vec<JsonSpace>,
+    unsupported: Vec<JsonUnsupported>,
+}
+
+#[derive(Serialize)]
+struct JsonSpace {
+    name: &'static str,
+    aliases: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+struct JsonUnsupported {
+    name: &'static str,
+    closest: &'static str,
+}
+
+fn capabilities(out: &mut impl Write, format: OutputFormat) -> anyhow::Result<()> {
+    let spaces = supported_metrics().into_iter().map(|metric| {
+        let mut aliases = metric_aliases(metric);
+        let name = aliases.remove(0);
+        JsonSpace { name, aliases }
+    });
+    let spaces = spaces.collect::<Vec<_>>();
+    let unsupported = UNSUPPORTED
+        .iter()
+        .map(|&(name, closest)| JsonUnsupported { name, closest });
+    let unsupported = unsupported.collect::<Vec<_>>();
+    if format == OutputFormat::Json {
+        return write_json(
+            out,
+            &JsonCapabilities {
+                spaces,
+                unsupported,
+            },
+        );
+    }
+
+    let width = (spaces.iter().map(|space| space.name.len()))
+        .chain(unsupported.iter().map(|space| space.name.len()))
+        .max()
+        .unwrap_or(0);
+    writeln!(out, "{}", "Color spaces, with other names for them".bold())?;
+    for JsonSpace { name, aliases } in &spaces {
+        let line = format!("{name:width$}  {}", aliases.join(", "));
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{}",
+        "Not supported, with the closest supported space".bold()
+    )?;
+    for JsonUnsupported { name, closest } in &unsupported {
+        writeln!(out, "{name:width$}  {closest}")?;
+    }
+    Ok(())
+}
+
+fn completions(out: &mut impl Write, shell: Shell) -> anyhow::Result<()> {
+    let bin = Main::command().get_name().to_string();
+    let (options, commands) = completion_spec();
+    match shell {
+        Shell::Bash => bash_completions(out, &bin, &options, &commands)?,
+        Shell::Zsh => zsh_completions(out, &bin, &options, &commands)?,
+        Shell::Fish => fish_completions(out, &bin, &options, &commands)?,
+        // without language names, which clap_complete cannot run a command for
+        Shell::Powershell => clap_complete::generate(
+            clap_complete::Shell::PowerShell,
+            &mut Main::command(),
+            bin,
+            out,
+        ),
+        Shell::Elvish => {
+            clap_complete::generate(clap_complete::Shell::Elvish, &mut Main::command(), bin, out)
+        }
+    }
+    Ok(())
+}
+
+fn bash_completions(
+    out: &mut impl Write,
+    bin: &str,
+    options: &[CompletionArg],
+    commands: &[CompletionCommand],
+) -> io::Result<()> {
+    let function = format!("_{}", bin.replace('-', "_"));
+    let names = |args: &[CompletionArg]| {
+        let longs = args
+            .iter()
+            .filter_map(|arg| Some(format!("--{}", arg.long.as_ref()?)));
+        let shorts = args
+            .iter()
+            .filter_map(|arg| Some(format!("-{}", arg.short?)));
+        longs.chain(shorts).collect::<Vec<_>>().join(" ")
+    };
+    let reply = |values: &Values| match values {
+        Values::Choices(choices) => Some(format!(
+            "COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+            choices.join(" ")
+        )),
+        Values::Paths => Some("COMPREPLY=($(compgen -f -- \"$cur\"))".to_string()),
+        Values::Any | Values::Languages => Some("COMPREPLY=()".to_string()),
+        Values::None => None,
+    };
+    let values = |out: &mut dyn Write, args: &[CompletionArg]| -> io::Result<()> {
+        writeln!(out, "            case \"$prev\" in")?;
+        for arg in args {
+            let Some(reply) = reply(&arg.values) else {
+                continue;
+            };
+            let long = arg.long.iter().map(|long| format!("--{long}"));
+            let short = arg.short.iter().map(|short| format!("-{short}"));
+            let patterns = long.chain(short).collect::<Vec<_>>().join("|");
+            writeln!(out, "                {patterns}) {reply}; return ;;")?;
+        }
+        writeln!(out, "            esac")
+    };
+
+    writeln!(
+        out,
+        "# bash completion for {bin}, from `{bin} completions bash`"
+    )?;
+    writeln!(out)?;
+    writeln!(out, "{function}() {{")?;
+    writeln!(out, "    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"")?;
+    writeln!(out, "    local prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"")?;
+    writeln!(out, "    local cmd=\"\" i")?;
+    writeln!(out, "    for ((i = 1; i < COMP_CWORD; i++)); do")?;
+    writeln!(out, "        case \"${{COMP_WORDS[i]}}\" in")?;
+    let subcommands = commands.iter().map(|cmd| cmd.name.as_str());
+    let subcommands = subcommands.collect::<Vec<_>>();
+    writeln!(
+        out,
+        "            {}) cmd=\"${{COMP_WORDS[i]}}\"; break ;;",
+        subcommands.join("|")
+    )?;
+    writeln!(out, "        esac")?;
+    writeln!(out, "    done")?;
+    writeln!(out, "    case \"$cmd\" in")?;
+    writeln!(out, "        \"\")")?;
+    values(out, options)?;
+    writeln!(
+        out,
+        "            COMPREPLY=($(compgen -W \"{} {}\" -- \"$cur\")) ;;",
+        names(options),
+        subcommands.join(" ")
+    )?;
+    for cmd in commands {
+        writeln!(out, "        {})", cmd.name)?;
+        values(out, &cmd.options)?;
+        let positional = cmd.positional.as_ref().and_then(|arg| reply(&arg.values));
+        if let Some(positional) = positional {
+            writeln!(out, "            if [[ \"$cur\" != -* ]]; then")?;
+            writeln!(out, "                {positional}; return")?;
+            writeln!(out, "            fi")?;
+        }
+        writeln!(
+            out,
+            "            COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")) ;;",
+            names(&cmd.options)
+        )?;
+    }
+    writeln!(out, "    esac")?;
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+    writeln!(out, "complete -F {function} {bin}")
+}
+
+fn zsh_completions(
+    out: &mut impl Write,
+    bin: &str,
+    options: &[CompletionArg],
+    commands: &[CompletionCommand],
+) -> io::Result<()> {
+    let function = format!("_{}", bin.replace('-', "_"));
+    let languages = format!("{function}_languages");
+    let quote = |text: &str| format!("'{}'", text.replace('\'', "'\\''"));
+    let action = |values: &Values| match values {
+        Values::None => None,
+        Values::Any => Some("_default".to_string()),
+        Values::Paths => Some("_files".to_string()),
+        Values::Choices(choices) => Some(format!("({})", choices.join(" "))),
+        Values::Languages => Some(languages.clone()),
+    };
+    let spec = |arg: &CompletionArg| {
+        let help = arg.help.replace('\\', "\\\\");
+        let help = help.replace('[', "\\[").replace(']', "\\]");
+        let value = match action(&arg.values) {
+            Some(action) => format!(":value:{action}"),
+            None => String::new(),
+        };
+        let repeated = if arg.repeated { "*" } else { "" };
+        let long = arg.long.as_ref().map(|long| match arg.values {
+            Values::None => format!("--{long}"),
+            _ => format!("--{long}="),
+        });
+        match (arg.short, long) {
+            (Some(short), Some(long)) => {
+                let exclusive = match arg.repeated {
+                    true => String::new(),
+                    false => format!("(-{short} {})", long.trim_end_matches('=')),
+                };
+                format!(
+                    "{}{repeated}{{-{short},{long}}}{}",
+                    quote(&exclusive),
+                    quote(&format!("[{help}]{value}"))
+                )
+            }
+            (Some(short), None) => quote(&format!("{repeated}-{short}[{help}]{value}")),
+            (None, Some(long)) => quote(&format!("{repeated}{long}[{help}]{value}")),
+            (None, None) => String::new(),
+        }
+    };
+    let specs = |out: &mut dyn Write, args: &[CompletionArg], indent: &str| -> io::Result<()> {
+        for arg in args {
+            writeln!(out, "{indent}{} \\", spec(arg))?;
+        }
+        Ok(())
+    };
+
+    writeln!(out, "#compdef {bin}")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "# zsh completion for {bin}, from `{bin} completions zsh`"
+    )?;
+    writeln!(out)?;
+    writeln!(out, "{languages}() {{")?;
+    writeln!(out, "    local -a languages")?;
+    writeln!(
+        out,
+        "    languages=(${{(f)\"$({bin} list --names-only 2>/dev/null)\"}})"
+    )?;
+    writeln!(out, "    compadd -a languages")?;
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+    writeln!(out, "{function}() {{")?;
+    writeln!(out, "    local curcontext=\"$curcontext\" state line")?;
+    writeln!(out, "    _arguments -C \\")?;
+    specs(out, options, "        ")?;
+    writeln!(out, "        ': :->command' \\")?;
+    writeln!(out, "        '*:: :->args'")?;
+    writeln!(out, "    case $state in")?;
+    writeln!(out, "        command)")?;
+    writeln!(out, "            local -a commands")?;
+    writeln!(out, "            commands=(")?;
+    for cmd in commands {
+        let about = cmd.about.replace(':', "\\:");
+        writeln!(
+            out,
+            "                {}",
+            quote(&format!("{}:{about}", cmd.name))
+        )?;
+    }
+    writeln!(out, "            )")?;
+    writeln!(out, "            _describe -t commands command commands ;;")?;
+    writeln!(out, "        args)")?;
+    writeln!(out, "            case $line[1] in")?;
+    for cmd in commands {
+        writeln!(out, "                {})", cmd.name)?;
+        writeln!(out, "                    _arguments \\")?;
+        specs(out, &cmd.options, "                        ")?;
+        let positional = cmd.positional.as_ref().and_then(|arg| {
+            let action = action(&arg.values)?;
+            let repeated = if arg.repeated { "*:" } else { "" };
+            Some(format!("{repeated}:value:{action}"))
+        });
+        // `_arguments` needs at least one spec, and `-` is a no-op one
+        let positional = positional.unwrap_or_else(|| "-".to_string());
+        writeln!(out, "                        {} ;;", quote(&positional))?;
+    }
+    writeln!(out, "            esac ;;")?;
+    writeln!(out, "    esac")?;
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+    writeln!(out, "if [ \"$funcstack[1]\" = \"{function}\" ]; then")?;
+    writeln!(out, "    {function} \"$@\"")?;
+    writeln!(out, "else")?;
+    writeln!(out, "    compdef {function} {bin}")?;
+    writeln!(out, "fi")
+}
+
+fn fish_completions(
+    out: &mut impl Write,
+    bin: &str,
+    options: &[CompletionArg],
+    commands: &[CompletionCommand],
+) -> io::Result<()> {
+    let quote = |text: &str| format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"));
+    let values = |values: &Values| match values {
+        Values::None => String::new(),
+        Values::Any => " -r".to_string(),
+        Values::Paths => " -r -F".to_string(),
+        Values::Choices(choices) => format!(" -r -a {}", quote(&choices.join(" "))),
+        Values::Languages => format!(
+            " -r -a {}",
+            quote(&format!("({bin} list --names-only 2>/dev/null)"))
+        ),
+    };
+    let complete = |out: &mut dyn Write, condition: &str, args: &[CompletionArg]| {
+        for arg in args {
+            write!(out, "complete -c {bin} -n {}", quote(condition))?;
+            if let Some(short) = arg.short {
+                write!(out, " -s {short}")?;
+            }
+            if let Some(long) = &arg.long {
+                write!(out, " -l {long}")?;
+            }
+            writeln!(out, "{} -d {}", values(&arg.values), quote(&arg.help))?;
+        }
+        io::Result::Ok(())
+    };
+
+    writeln!(
+        out,
+        "# fish completion for {bin}, from `{bin} completions fish`"
+    )?;
+    writeln!(out)?;
+    writeln!(out, "complete -c {bin} -f")?;
+    complete(out, "__fish_use_subcommand", options)?;
+    for cmd in commands {
+        writeln!(
+            out,
+            "complete -c {bin} -n '__fish_use_subcommand' -a {} -d {}",
+            cmd.name,
+            quote(&cmd.about)
+        )?;
+    }
+    for cmd in commands {
+        let condition = format!("__fish_seen_subcommand_from {}", cmd.name);
+        complete(out, &condition, &cmd.options)?;
+        let positional = cmd.positional.as_ref().map(|arg| values(&arg.values));
+        if let Some(positional) = positional.filter(|values| !values.is_empty()) {
+            let positional = positional.trim_start().trim_start_matches("-r ");
+            writeln!(
+                out,
+                "complete -c {bin} -n {} {positional}",
+                quote(&condition)
+            )?;
+        }
     }
     Ok(())
 }
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, after_help = EXIT_CODES)]
 struct Main {
     #[command(subcommand)]
     command: Commands,
     #[arg(
         short = 'c',
         long = "colors",
-        default_value = "RGB",
-        help = "The color model to be used for distance calculation. Default: RGB"
+        default_value = "rgb",
+        value_parser = ColorSpaceParser,
+        hide_possible_values = true,
+        help = "The color model to be used for distance calculation: \
+            rgb, rgba, hsl, hsla, hsv, hsi, hwb, cmyk, xyz, yiq, yuv, ycbcr, or lab, \
+            or de2000 for CIEDE2000 color differences, \
+            linear-rgb for RGB without gamma encoding, \
+            or hsl-weighted for HSL with the weights below. \
+            CSS and CIE names such as cielab and srgb also work."
+    )]
+    color_space: Metric,
+    #[arg(
+        long,
+        value_name = "WEIGHT",
+        value_parser = parse_weight,
+        help = "With -c hsl-weighted, how much differences in hue count. Default: 2"
+    )]
+    hue_weight: Option<f64>,
+    #[arg(
+        long,
+        value_name = "WEIGHT",
+        value_parser = parse_weight,
+        help = "With -c hsl-weighted, how much differences in saturation count. Default: 1"
+    )]
+    saturation_weight: Option<f64>,
+    #[arg(
+        long,
+        value_name = "WEIGHT",
+        value_parser = parse_weight,
+        help = "With -c hsl-weighted, how much differences in lightness count. Default: 1"
+    )]
+    lightness_weight: Option<f64>,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = ColorChoice::Auto,
+        help = "When to use colors. Honors CLICOLOR_FORCE, NO_COLOR, and CLICOLOR in auto mode"
+    )]
+    color: ColorChoice,
+    #[arg(long, conflicts_with = "color", help = "Same as --color never")]
+    no_color: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = ColorDepth::Auto,
+        help = "How many colors the terminal shows; with fewer than 24-bit, colors are shown as \
+            the nearest xterm or ANSI color. Auto is 24-bit with --color always or CLICOLOR_FORCE"
+    )]
+    color_depth: ColorDepth,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "A YAML file of `canonical: localized` pairs to show languages by"
     )]
-    color_space: ColorSpace,
+    names: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "Where to get languages.yml from, or `-` to read it from stdin. Default: GitHub"
+    )]
+    source: Option<String>,
+    #[arg(
+        long,
+        visible_alias = "warn-distance",
+        value_name = "THRESHOLD",
+        help = "Mark colors more than this CIEDE2000 difference away from their xterm color, \
+            e.g. 10 for those that look clearly different. Default: 10 with -vv and the xterm palette, \
+            otherwise none are marked"
+    )]
+    warn_delta: Option<f64>,
+    #[arg(
+        long,
+        conflicts_with = "warn_delta",
+        help = "Do not mark colors poorly approximated by their xterm color, even with -vv"
+    )]
+    no_warn: bool,
+    #[arg(
+        long = "as",
+        value_enum,
+        default_value_t = LayerChoice::Fg,
+        help = "Show colors as the text color or as the background"
+    )]
+    layer: LayerChoice,
+    #[arg(
+        long,
+        conflicts_with = "layer",
+        help = "Show colors as the background, with black or white text, like --as bg"
+    )]
+    bg: bool,
+    #[arg(
+        long,
+        value_name = "BYTES",
+        default_value_t = 10 * 1024 * 1024,
+        help = "The largest languages.yml to accept"
+    )]
+    max_size: u64,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "A PEM file of root certificates to trust in addition to the built-in ones"
+    )]
+    cacert: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "SECS",
+        help = "Give up on fetching languages.yml after this many seconds [default: 30]"
+    )]
+    timeout: Option<u64>,
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "Fetch through this HTTP, HTTPS, or SOCKS proxy instead of that of HTTPS_PROXY"
+    )]
+    proxy: Option<reqwest::Url>,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 0,
+        help = "Try fetching again up to N times after a timeout, a failed connection, or a \
+            5xx status, waiting 0.5s, 1s, 2s, and so on in between"
+    )]
+    retries: u32,
+    #[arg(
+        long,
+        conflicts_with = "source",
+        help = "Use the languages.yml snapshot built into this binary instead of fetching it"
+    )]
+    offline: bool,
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["source", "offline"],
+        help = "Read languages.yml from a local file instead of fetching it"
+    )]
+    languages_file: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Merge a YAML file of languages over the data: new languages are added, and for \
+            others, color, type, group, and metadata are replaced, while aliases, extensions, \
+            filenames, and interpreters are extended"
+    )]
+    overrides: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Never read files to tell apart languages sharing an extension, such as C and C++ for .h"
+    )]
+    no_heuristics: bool,
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "no_heuristics",
+        help = "Tell apart languages sharing an extension by the rules of a local copy of \
+            Linguist's heuristics.yml, instead of the built-in ones for common extensions"
+    )]
+    heuristics_file: Option<PathBuf>,
+    #[arg(
+        long,
+        conflicts_with_all = ["no_heuristics", "heuristics_file", "offline"],
+        help = "Fetch Linguist's heuristics.yml, as of --linguist-ref, and tell apart languages \
+            sharing an extension by all of its rules instead of the built-in ones"
+    )]
+    fetch_heuristics: bool,
+    #[arg(
+        long,
+        value_name = "REF",
+        env = "LINGUIST_TERMCOLOR_REF",
+        help = "The Linguist branch, tag, or commit to fetch languages.yml from, such as v7.30.0. \
+            Ignored with --source, --offline, or --languages-file [default: master]"
+    )]
+    linguist_ref: Option<String>,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help = "How to print results of `for`, `xterm`, and `info LANGUAGE`"
+    )]
+    format: OutputFormat,
+    #[arg(
+        long,
+        help = "In --format json and `export json`, print distances and differences in full \
+            rather than rounded as in the text, e.g. 2.8312 rather than 2.8 for `xterm_delta`"
+    )]
+    raw_numbers: bool,
+    #[arg(
+        long,
+        default_value = "xterm256",
+        value_parser = PaletteParser,
+        hide_possible_values = true,
+        help = "The colors to show the nearest of next to each color: xterm256, ansi16, \
+            solarized-dark, solarized-light, or base16:NAME with a built-in base16 scheme \
+            such as ocean, or with the path of a scheme's YAML file"
+    )]
+    palette: Palette,
+    #[arg(
+        long,
+        value_name = "PATH",
+        value_parser = parse_palette_file,
+        value_hint = clap::ValueHint::AnyPath,
+        conflicts_with = "palette",
+        help = "Like --palette, the colors of your terminal theme, from a JSON or YAML file \
+            with a map from names to hex codes, such as {\"red\": \"#dc322f\"}, or a list \
+            of hex codes or of {name, hex, index}"
+    )]
+    palette_file: Option<Palette>,
+    #[arg(
+        long,
+        help = "Leave out xterm colors 0 to 15 when finding the nearest, like --palette, \
+            since terminal themes redefine them"
+    )]
+    skip_system: bool,
+    #[arg(
+        long,
+        help = "Leave out the grayscale ramp, xterm colors 232 to 255, when finding the nearest, \
+            like --palette"
+    )]
+    skip_grayscale: bool,
+    #[arg(
+        long,
+        help = "Prefix each color and its xterm color with a block of the color, to compare them"
+    )]
+    swatch: bool,
+    #[arg(
+        long,
+        value_name = "COLOR",
+        help = "Lighten or darken text colors until they are readable on this background, \
+            e.g. #1e1e1e, showing the color before and after"
+    )]
+    contrast_bg: Option<TermColor>,
+    #[arg(
+        long,
+        value_name = "PCT",
+        value_parser = parse_percent,
+        help = "Raise the HSL lightness of colors by this many percentage points, up to white, \
+            before finding their nearest xterm colors. Shown with the original with -v"
+    )]
+    lighten: Option<f64>,
+    #[arg(
+        long,
+        value_name = "PCT",
+        value_parser = parse_percent,
+        help = "Lower the HSL lightness of colors by this many percentage points, down to black, \
+            like --lighten"
+    )]
+    darken: Option<f64>,
+    #[arg(
+        long,
+        value_name = "PCT",
+        value_parser = parse_percent,
+        help = "Raise the HSL saturation of colors by this many percentage points, like --lighten"
+    )]
+    saturate: Option<f64>,
+    #[arg(
+        long,
+        value_name = "KIND",
+        value_parser = parse_cvd,
+        help = "Also show each color as seen with protanopia, deuteranopia, or tritanopia, \
+            and its nearest xterm color"
+    )]
+    simulate: Option<Cvd>,
+    #[arg(
+        long,
+        value_name = "RATIO",
+        default_value_t = 4.5,
+        value_parser = parse_contrast,
+        help = "The WCAG contrast ratio that --contrast-bg ensures, from 1 to 21"
+    )]
+    min_contrast: f64,
+    #[arg(
+        short,
+        long,
+        conflicts_with = "verbose",
+        help = "Print nothing on stderr but errors and warnings, such as the URL being fetched"
+    )]
+    quiet: bool,
+    #[arg(
+        short,
+        long,
+        action = clap::ArgAction::Count,
+        help = "Print diagnostics on stderr, such as timings and index sizes; \
+            twice for how each query is matched"
+    )]
+    verbose: u8,
+    #[arg(
+        long,
+        help = "Do not read defaults from the config file, see `config path`"
+    )]
+    no_config: bool,
+}
+
+impl Main {
+    /// Parse the command line, then fill in flags that neither it nor the
+    /// environment gives from the config file, unless `--no-config` is given
+    /// or the command is `config`, which should work with a broken file.
+    fn with_config() -> anyhow::Result<Self> {
+        let matches = Main::command()
+            .try_get_matches()
+            .unwrap_or_else(|err| usage_error(err));
+        let mut main = Main::from_arg_matches(&matches).unwrap_or_else(|err| usage_error(err));
+        if main.no_config || matches!(main.command, Commands::Config { .. }) {
+            return Ok(main);
+        }
+        let Some(path) = config_path().filter(|path| path.is_file()) else {
+            return Ok(main);
+        };
+        Config::read(&path)?.apply(&mut main, &matches);
+        Ok(main)
+    }
+}
+
+/// Defaults for global flags, by their long names, from
+/// `linguist-termcolor/config.toml` in the config directory, e.g.
+///
+/// ```text
+/// colors = "lab"
+/// skip-system = true
+/// overrides = "overrides.yml"
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct Config {
+    #[serde(default, deserialize_with = "config_colors")]
+    colors: Option<Metric>,
+    #[serde(default, deserialize_with = "config_palette")]
+    palette: Option<Palette>,
+    skip_system: Option<bool>,
+    skip_grayscale: Option<bool>,
+    /// Relative to the directory of the config file.
+    overrides: Option<PathBuf>,
+    #[serde(default, deserialize_with = "config_format")]
+    format: Option<OutputFormat>,
+    quiet: Option<bool>,
+}
+
+impl Config {
+    fn read(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| anyhow::anyhow!("{}: {err}", path.display()))?;
+        let mut config: Config = toml::from_str(&text)
+            .map_err(|err| anyhow::anyhow!("invalid config in {}: {err}", path.display()))?;
+        if let (Some(overrides), Some(dir)) = (&mut config.overrides, path.parent()) {
+            *overrides = dir.join(&*overrides);
+        }
+        Ok(config)
+    }
+
+    /// Set the flags of `main` that `matches` has from their defaults.
+    fn apply(self, main: &mut Main, matches: &ArgMatches) {
+        let unset = |id: &str| {
+            !matches!(
+                matches.value_source(id),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            )
+        };
+        if let Some(colors) = self.colors.filter(|_| unset("color_space")) {
+            main.color_space = colors;
+        }
+        if let Some(palette) = self
+            .palette
+            .filter(|_| unset("palette") && unset("palette_file"))
+        {
+            main.palette = palette;
+        }
+        if let Some(skip_system) = self.skip_system.filter(|_| unset("skip_system")) {
+            main.skip_system = skip_system;
+        }
+        if let Some(skip_grayscale) = self.skip_grayscale.filter(|_| unset("skip_grayscale")) {
+            main.skip_grayscale = skip_grayscale;
+        }
+        if let Some(overrides) = self.overrides.filter(|_| unset("overrides")) {
+            main.overrides = Some(overrides);
+        }
+        if let Some(format) = self.format.filter(|_| unset("format")) {
+            main.format = format;
+        }
+        // -v on the command line wins over `quiet = true`
+        if let Some(quiet) = self.quiet.filter(|_| unset("quiet") && unset("verbose")) {
+            main.quiet = quiet;
+        }
+    }
+}
+
+/// Where the config file is looked up: `linguist-termcolor/config.toml` in
+/// `$XDG_CONFIG_HOME`, else in `%APPDATA%` on Windows and `~/.config`
+/// elsewhere. `None` without any of these.
+fn config_path() -> Option<PathBuf> {
+    let var = |key| std::env::var_os(key).filter(|value| !value.is_empty());
+    let dir = var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| match cfg!(windows) {
+            true => var("APPDATA").map(PathBuf::from),
+            false => var("HOME").map(|home| Path::new(&home).join(".config")),
+        })?;
+    Some(dir.join("linguist-termcolor").join("config.toml"))
+}
+
+/// A config value parsed as its flag would be, so that errors name its key and
+/// line.
+fn parse_config<'de, D, T, E>(
+    deserializer: D,
+    parse: impl Fn(&str) -> Result<T, E>,
+) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    E: std::fmt::Display,
+{
+    let value = String::deserialize(deserializer)?;
+    parse(&value).map(Some).map_err(serde::de::Error::custom)
+}
+
+fn config_colors<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Metric>, D::Error> {
+    parse_config(deserializer, parse_metric)
+}
+
+fn config_palette<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Palette>, D::Error> {
+    parse_config(deserializer, parse_palette)
+}
+
+fn config_format<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<OutputFormat>, D::Error> {
+    parse_config(deserializer, |value| {
+        OutputFormat::from_str(value, false).map_err(|_| {
+            let names = OutputFormat::value_variants()
+                .iter()
+                .map(|format| format.to_string());
+            format!("expected one of: {}", names.collect::<Vec<_>>().join(", "))
+        })
+    })
+}
+
+/// Prints the logs of the library on stderr, up to [`log::max_level`].
+struct Logger;
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+        // not those of reqwest and its dependencies
+        metadata.level() <= log::max_level() && metadata.target().starts_with("linguist_termcolor")
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        match record.level() {
+            log::Level::Error | log::Level::Warn => {
+                eprintln!("{} {}", "warning:".yellow(), record.args())
+            }
+            log::Level::Info => eprintln!("{}", record.args().to_string().dimmed()),
+            level => {
+                let message = format!("{}: {}", level.as_str().to_lowercase(), record.args());
+                eprintln!("{}", message.dimmed())
+            }
+        }
+    }
+
+    fn flush(&self) {}
 }
 
 #[derive(Subcommand, Debug)]
 enum Commands {
-    #[command(name = "for", about = "Query GitHub Linguist's language colors")]
+    #[command(
+        name = "for",
+        about = "Query GitHub Linguist's language colors",
+        long_about = "Query GitHub Linguist's language colors.\n\n\
+            Matches are printed best first, so the first line is always the best match: \
+            an exact name or alias, else the language sharing the most words with the query. \
+            Ties are broken by name, with group members right after their parent.\n\n\
+            Each argument, or each of several separated by commas, is its own query, so \
+            `for rust go,python` shows the best match of each as a row of a table, and \
+            `for \"jupyter notebook\"` queries both words at once. --each lists every match \
+            of each query instead, and --joined joins the arguments into one query.\n\n\
+            A joined query mixing names and extensions, such as `for --joined rust .rs`, is \
+            resolved argument by argument: if they all agree, the one language is shown with \
+            what matched it; otherwise each argument is shown on its own, as with --each.\n\n\
+            An argument with a path separator, such as `src/main.rs`, is looked up as a file: \
+            by its name and extension, and by its contents if it exists. Arguments that are \
+            not detected as a file are queried as words instead."
+    )]
     Linguist {
-        #[arg(required = true, trailing_var_arg = true)]
+        #[arg(required_unless_present_any = ["stdin", "dir"], trailing_var_arg = true)]
+        query: Vec<String>,
+        #[arg(
+            long,
+            value_name = "DIR",
+            conflicts_with_all = ["query", "stdin", "each", "explain", "set_tab_color", "only"],
+            help = "Show the languages of the files under DIR by bytes, like `scan DIR`"
+        )]
+        dir: Option<PathBuf>,
+        #[arg(
+            long,
+            value_name = "N",
+            requires = "dir",
+            help = "With --dir, only show the N languages with the most bytes, and the rest as other"
+        )]
+        top: Option<usize>,
+        #[arg(
+            long,
+            conflicts_with_all = ["query", "each", "explain", "set_tab_color", "only", "fail_on_ambiguous"],
+            help = "Read queries separated by whitespace from stdin, and print the best match of each \
+                as `query → result`"
+        )]
+        stdin: bool,
+        #[arg(
+            long,
+            requires = "stdin",
+            help = "With --stdin, leave out queries that match nothing instead of printing a placeholder"
+        )]
+        skip_missing: bool,
+        #[arg(
+            long,
+            help = "List every match of each query under it, instead of the best match of each as a row"
+        )]
+        each: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["each", "stdin", "dir"],
+            help = "Join the arguments into one query, as if quoted, instead of querying each"
+        )]
+        joined: bool,
+        #[arg(
+            long,
+            help = "Treat each argument as a file, detected by its name, shebang line, or contents"
+        )]
+        file: bool,
+        #[arg(
+            long,
+            value_name = "N",
+            help = "Show at most N languages for each query"
+        )]
+        limit: Option<usize>,
+        #[arg(long, value_enum, default_value_t = SortOrder::Score, help = "How to order results")]
+        sort: SortOrder,
+        #[arg(
+            long,
+            help = "Only match whole language names and aliases, such as `c` for exactly C"
+        )]
+        exact: bool,
+        #[arg(
+            long,
+            help = "For an extension shared by several languages, such as .h, only match the one \
+                Linguist defaults to, such as C"
+        )]
+        primary_only: bool,
+        #[arg(
+            long = "type",
+            value_enum,
+            value_delimiter = ',',
+            value_name = "TYPE",
+            help = "Only match languages of these Linguist types"
+        )]
+        types: Vec<TypeChoice>,
+        #[arg(
+            long,
+            help = "Exit with code 5 if more than one language ties for the best match"
+        )]
+        fail_on_ambiguous: bool,
+        #[arg(long, help = "Explain how the results were found")]
+        explain: bool,
+        #[arg(
+            long,
+            help = "Show how each result matched: by name, alias, extension, or words"
+        )]
+        why: bool,
+        #[arg(
+            long,
+            help = "List group members found alongside their parent language instead of collapsing them"
+        )]
+        all: bool,
+        #[arg(
+            long,
+            value_name = "N",
+            help = "Ignore words of the query that match more than N languages"
+        )]
+        max_results_per_keyword: Option<usize>,
+        #[arg(
+            long,
+            conflicts_with = "each",
+            help = "Also tint the terminal tab with the color of the best match"
+        )]
+        set_tab_color: bool,
+        #[arg(
+            long,
+            value_enum,
+            requires = "set_tab_color",
+            help = "The terminal to tint the tab of. Default: detected from the environment"
+        )]
+        terminal: Option<TerminalChoice>,
+        #[arg(
+            long,
+            requires = "set_tab_color",
+            help = "Print the tab color sequence escaped instead of sending it"
+        )]
+        dry_run: bool,
+        #[arg(
+            long,
+            help = "With --format json, ask a running `daemon` instead of loading the data, \
+                if there is one"
+        )]
+        via_daemon: bool,
+        #[arg(
+            long,
+            value_enum,
+            conflicts_with_all = ["explain", "why", "set_tab_color"],
+            help = "Print only this value of each result, one per line, e.g. for `$(...)`"
+        )]
+        only: Option<OnlyValue>,
+        #[arg(
+            long,
+            requires = "only",
+            help = "With --only, fail unless exactly one language is found for the query"
+        )]
+        single: bool,
+        #[arg(
+            long,
+            value_name = "N",
+            conflicts_with_all = ["stdin", "only"],
+            help = "Also show the N xterm colors nearest each result, with their distances"
+        )]
+        candidates: Option<usize>,
+    },
+    #[command(about = "Show languages grouped under their parent languages")]
+    Tree {
+        #[arg(help = "Only show the group containing this language")]
+        name: Option<String>,
+        #[arg(long, help = "Draw the tree with ASCII characters only")]
+        ascii: bool,
+    },
+    #[command(about = "Show the languages making up one or more directory trees, by bytes")]
+    Scan {
+        #[arg(help = "Directories to scan, merged into one total. Default: the current directory")]
+        paths: Vec<PathBuf>,
+        #[arg(long, help = "Also show the languages found under each directory")]
+        per_root: bool,
+        #[arg(
+            long,
+            value_name = "N",
+            help = "Only show the N languages with the most bytes, and the rest as other"
+        )]
+        top: Option<usize>,
+        #[arg(
+            long,
+            help = "Also count files that .gitignore, .ignore, and Git's excludes leave out"
+        )]
+        no_ignore: bool,
+    },
+    #[command(about = "Print text in the colors of languages")]
+    Paint {
+        #[arg(
+            required = true,
+            value_name = "LANG TEXT",
+            help = "Pairs of a language name or alias and the text to print in its color"
+        )]
+        pairs: Vec<String>,
+        #[arg(long, value_enum, default_value_t = PaintFormat::Styled)]
+        format: PaintFormat,
+    },
+    #[command(about = "Adjust the colors of languages so that they are told apart easily")]
+    Distinct {
+        #[arg(required = true)]
+        langs: Vec<String>,
+        #[arg(
+            long,
+            default_value_t = 10.0,
+            help = "The smallest CIEDE2000 difference allowed between any two colors"
+        )]
+        min_delta: f64,
+    },
+    #[command(about = "Check which file extensions have a language with a color")]
+    Coverage {
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "A file of extensions, one per line, or `-` for stdin. \
+                Exits with 6 if only some have colors and 7 if none do"
+        )]
+        ext_file: PathBuf,
+    },
+    #[command(about = "List every language with a color")]
+    List {
+        #[arg(
+            long,
+            value_parser = parse_family,
+            help = "Only list colors in this hue family, most saturated first: \
+                red, orange, yellow, green, cyan, blue, purple, pink, or gray"
+        )]
+        family: Option<Family>,
+        #[arg(
+            long,
+            help = "Only list languages whose name starts with this, case-insensitively"
+        )]
+        prefix: Option<String>,
+        #[arg(long, help = "Also show the extensions of each language")]
+        extensions: bool,
+        #[arg(
+            long,
+            conflicts_with = "family",
+            help = "List the languages without a color instead"
+        )]
+        no_color_only: bool,
+        #[arg(
+            long,
+            conflicts_with = "extensions",
+            help = "Print only the names, one per line and uncolored, e.g. for shell completions"
+        )]
+        names_only: bool,
+    },
+    #[command(
+        about = "Check whether a name is a known language",
+        long_about = "Check whether a name is a known language name or alias, case-insensitively. \
+            Prints nothing: exits with 0 if it is known, 2 if not, and 3 or 4 as other commands \
+            if the data cannot be fetched or parsed."
+    )]
+    Exists { name: String },
+    #[command(
+        about = "Find languages whose name, an alias, or a filename contains the text",
+        long_about = "Find languages whose name, an alias, or a filename contains the text, \
+            case-insensitively, as `noteb` finds Jupyter Notebook. Unlike `for`, this matches \
+            inside words too. Languages with a name or alias starting with it come first."
+    )]
+    Search { needle: String },
+    #[command(about = "Summarize the Linguist data, or show everything it has on one language")]
+    Info {
+        #[arg(help = "A language name or alias to show the color, aliases, and extensions of")]
+        language: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "language",
+            help = "Also show the size of the search index"
+        )]
+        index: bool,
+    },
+    #[command(about = "Export language colors for use in other tools")]
+    Export {
+        #[arg(value_enum)]
+        format: ExportFormat,
+        #[arg(
+            value_name = "LANG",
+            help = "Only export these languages, by name or alias. Default: every language with a color"
+        )]
+        langs: Vec<String>,
+        #[arg(
+            long,
+            value_name = "LANGS",
+            value_delimiter = ',',
+            conflicts_with = "langs",
+            help = "Like LANG, only export these languages, separated by commas, \
+                e.g. `ls-colors --only rust,python` for only their extensions"
+        )]
+        only: Vec<String>,
+        #[arg(long, help = "Print a command line using the export instead")]
+        preview_cmd: bool,
+        #[arg(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            help = "With `all`, only export these formats"
+        )]
+        formats: Vec<ExportFormat>,
+        #[arg(
+            long,
+            value_name = "DIR",
+            help = "Write each format to a file in this directory"
+        )]
+        out: Option<PathBuf>,
+        #[arg(
+            long,
+            requires = "out",
+            help = "Write nothing, but fail if any file in --out would change"
+        )]
+        check: bool,
+        #[arg(
+            short = 'o',
+            long,
+            value_name = "FILE",
+            conflicts_with = "out",
+            help = "Write the export to this file instead of stdout"
+        )]
+        output: Option<PathBuf>,
+        #[arg(
+            long,
+            help = "Fail instead of skipping languages given as LANG that have no color"
+        )]
+        strict: bool,
+        #[arg(
+            long,
+            help = "With `ls-colors`, use exact colors instead of the nearest xterm colors"
+        )]
+        truecolor: bool,
+        #[arg(
+            long,
+            help = "With `rust`, `python`, or `json-map`, map each extension to a color instead, \
+                that of its primary language if several share it"
+        )]
+        extensions: bool,
+        #[arg(long, help = "With `rust`, write a phf::Map instead of an array")]
+        phf: bool,
+    },
+    #[command(
+        about = "Compare the nearest xterm colors of colors or languages in every color space",
+        long_about = "Compare the nearest xterm colors of colors or languages in every color space, \
+            to choose one for -c. Each argument is a color, as for `xterm`, or else a query, \
+            of which the best match is shown.\n\n\
+            Distances are in the units of each space, so they only compare within one."
+    )]
+    Compare {
+        #[arg(required = true)]
+        args: Vec<String>,
+    },
+    #[command(
+        about = "Find the languages whose colors are nearest a color such as #dea584, rgb(222, 165, 132), or peru"
+    )]
+    #[command(
+        about = "Print a prompt snippet showing a language in its color",
+        long_about = "Print a prompt snippet showing a language in its color, using its nearest \
+            xterm color unless --truecolor is given. The snippets are:\n\n\
+            starship: a [custom.<language>] module for starship.toml, shown in directories \
+            with files of the language.\n\n\
+            ps1: the text between escapes for bash, e.g. \\[\\e[38;5;180m\\]rust\\[\\e[0m\\], \
+            to put in PS1 inside single quotes.\n\n\
+            tmux: the text between styles for a status line, e.g. #[fg=colour180]rust#[default], \
+            with any # in it doubled."
+    )]
+    Snippet {
+        #[arg(value_enum)]
+        format: SnippetFormat,
+        #[arg(
+            required = true,
+            help = "The language, of which the best match is used"
+        )]
         query: Vec<String>,
+        #[arg(long, help = "The text to show [default: the language name]")]
+        text: Option<String>,
+        #[arg(long, help = "Use the exact color, for terminals with 24-bit color")]
+        truecolor: bool,
+        #[arg(
+            long,
+            value_name = "COLOR",
+            value_parser = parse_xterm_arg,
+            help = "Also set the background to this color, such as #262626 or an xterm number"
+        )]
+        bg: Option<XtermArg>,
+    },
+    #[command(
+        about = "Find the languages whose colors are nearest a color",
+        long_about = "Find the languages whose colors are nearest a color, nearest first, with how far \
+            each is in the color space of `-c`.\n\n\
+            The color is given as hex, rgb(), hsl(), or a CSS color name, e.g. \
+            `nearest '#3572a5'` or `nearest -n 3 tomato`."
+    )]
+    Nearest {
+        #[arg(help = "The color, as hex, rgb(), hsl(), or a CSS color name")]
+        color: String,
+        #[arg(
+            short,
+            long = "count",
+            default_value_t = 5,
+            help = "How many languages to show"
+        )]
+        n: usize,
     },
-    #[command(about = "Find nearest xterm colors for the colors given in hex notation")]
+    #[command(
+        about = "Find nearest xterm colors for colors given as hex, rgb(), hsl(), or CSS color names",
+        long_about = "Find nearest xterm colors for colors given as hex, rgb(), hsl(), or CSS color names.\n\n\
+            A number from 0 to 255 is an xterm color itself, shown with its hex, e.g. `xterm 61`. \
+            Numbers and other colors can be mixed, as in `xterm 61 '#dea584'`."
+    )]
     Xterm {
-        #[arg(required = true, trailing_var_arg = true)]
+        #[arg(required_unless_present = "stdin", trailing_var_arg = true)]
         colors: Vec<String>,
+        #[arg(
+            long,
+            conflicts_with = "colors",
+            help = "Read colors from stdin, one per line, and print each as `color → result`"
+        )]
+        stdin: bool,
+        #[arg(
+            long,
+            requires = "stdin",
+            help = "With --stdin, leave out colors that cannot be parsed instead of printing a placeholder"
+        )]
+        skip_missing: bool,
+        #[arg(
+            long,
+            help = "Also show the language nearest each xterm color among those whose colors it stands for"
+        )]
+        languages: bool,
+        #[arg(
+            long,
+            value_name = "N",
+            conflicts_with = "stdin",
+            help = "Also show the N xterm colors nearest each color, with their distances"
+        )]
+        candidates: Option<usize>,
+    },
+    #[cfg(unix)]
+    #[command(
+        about = "Keep the data loaded and answer queries over a Unix socket",
+        long_about = "Keep the data loaded and answer queries over a Unix socket, \
+            $XDG_RUNTIME_DIR/linguist-termcolor.sock, until stopped or idle. \
+            `for --format json --via-daemon` uses it when it is running.\n\n\
+            Messages are a 4-byte big-endian length followed by JSON. \
+            {\"op\":\"query\",\"q\":\"rust\",\"space\":\"lab\"} is answered with \
+            what `for --format json -c lab rust` prints, and {\"op\":\"stop\"} stops the daemon."
+    )]
+    Daemon {
+        #[command(subcommand)]
+        action: Option<DaemonAction>,
+        #[arg(
+            long,
+            value_name = "SECS",
+            default_value_t = 600,
+            help = "Stop after this many seconds without a client, or never if 0"
+        )]
+        idle_timeout: u64,
+    },
+    #[command(
+        about = "Print a completion script for a shell",
+        long_about = "Print a completion script for a shell, e.g. \
+            `linguist-termcolor completions zsh > ~/.zfunc/_linguist-termcolor`. \
+            The zsh and fish scripts complete language names for `for` by running \
+            `linguist-termcolor list --names-only`; the PowerShell and Elvish ones complete \
+            commands and options."
+    )]
+    Completions { shell: Shell },
+    #[command(
+        about = "List the color spaces that -c accepts",
+        long_about = "List the color spaces and metrics that -c accepts, each by its name and \
+            the other names it is accepted by, then the names of unsupported spaces \
+            with the closest supported one. With --format json, for wrappers to offer them as \
+            {\"spaces\": [{\"name\": \"lab\", \"aliases\": [\"cielab\", ...]}, ...], \
+            \"unsupported\": [{\"name\": \"oklch\", \"closest\": \"lab\"}, ...]}."
+    )]
+    Capabilities,
+    #[command(
+        about = "Show the config file",
+        long_about = "Show the config file, config.toml, which sets defaults for global flags \
+            by their long names, such as `colors = \"lab\"`, `palette`, `skip-system`, \
+            `skip-grayscale`, `overrides`, `format`, and `quiet`. Flags on the command line \
+            and environment variables win over it, and --no-config skips it."
+    )]
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    #[command(
+        about = "Print a chart of the 256 xterm colors",
+        long_about = "Print a chart of the 256 xterm colors, as the 16 standard colors, \
+            the 6×6×6 cube, and the grayscale ramp, in as many columns as fit in $COLUMNS \
+            [default: 80]. Needs no data unless --used is given."
+    )]
+    Palette {
+        #[arg(long, help = "Label each color with its hex as well as its number")]
+        hex: bool,
+        #[arg(
+            long,
+            value_parser = parse_xterm_range,
+            default_value = "0..256",
+            help = "Only show these colors, such as 16..232 for the cube or 232..256 for the grays"
+        )]
+        range: Range<usize>,
+        #[arg(
+            long,
+            help = "Mark with * the colors that are the nearest of at least one language's color, \
+                in the color space of -c"
+        )]
+        used: bool,
     },
+    #[command(
+        about = "Pick an xterm color from the chart of `palette` with the keyboard",
+        long_about = "Pick an xterm color from the chart of `palette` with the keyboard: \
+            arrow keys move, Enter prints the number and hex of the highlighted color, \
+            / jumps to the color nearest a typed hex, l lists the languages whose colors \
+            it is the nearest of in the color space of -c, and q or Esc quits without \
+            printing. The chart is drawn on stderr, so that `$(linguist-termcolor pick-xterm)` \
+            works. Needs a terminal; otherwise, see `palette`."
+    )]
+    PickXterm,
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigAction {
+    #[command(about = "Print where the config file is looked up, whether or not it exists")]
+    Path,
+}
+
+#[cfg(unix)]
+#[derive(Subcommand, Debug)]
+enum DaemonAction {
+    #[command(about = "Stop the running daemon")]
+    Stop,
 }
@@ -0,0 +1,117 @@
+//! How the distance between two colors is measured when finding the nearest
+//! xterm color.
+//!
+//! [`ColorSpace`] only measures Euclidean distances, with
+//! [`color_art::distance_with`]. [`Metric`] adds [CIEDE2000], which weighs
+//! lightness, chroma, and hue differences as people perceive them, and does
+//! better than Euclidean CIELAB for saturated blues and dark colors, the
+//! Euclidean distance in linear-light RGB, which unlike sRGB does not
+//! exaggerate differences between dark colors, and a weighted HSL distance, which can keep hues apart at the cost of lightness
+//! (`-c hsl-weighted --hue-weight 4`).
+//!
+//! Everything taking a [`Metric`] takes a [`ColorSpace`] as well, so that
+//! `xterm_index(ColorSpace::Lab)` and `xterm_index(Metric::De2000)` both work.
+//!
+//! [CIEDE2000]: https://en.wikipedia.org/wiki/Color_difference#CIEDE2000
+
+use color_art::{distance_with, Color, ColorSpace};
+
+use crate::color_math;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    /// The Euclidean distance in a color space.
+    Space(ColorSpace),
+    /// ΔE2000 between the CIELAB coordinates of the colors.
+    De2000,
+    /// The Euclidean distance between the linear sRGB components of the
+    /// colors, scaled to `0.0..=255.0` like those of [`ColorSpace::RGB`].
+    LinearRgb,
+    /// [`color_math::weighted_hsl`] between the HSL coordinates of the colors.
+    WeightedHsl(HslWeights),
+}
+
+/// What [`Metric::WeightedHsl`] multiplies the differences in hue, saturation,
+/// and lightness by.
+///
+/// The default weighs hue twice as much as the others.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HslWeights {
+    pub hue: f64,
+    pub saturation: f64,
+    pub lightness: f64,
+}
+
+impl Default for HslWeights {
+    fn default() -> Self {
+        Self {
+            hue: 2.0,
+            saturation: 1.0,
+            lightness: 1.0,
+        }
+    }
+}
+
+impl Default for Metric {
+    fn default() -> Self {
+        Metric::Space(ColorSpace::default())
+    }
+}
+
+impl From<ColorSpace> for Metric {
+    fn from(space: ColorSpace) -> Self {
+        Metric::Space(space)
+    }
+}
+
+impl Metric {
+    /// The distance between `a` and `b`, in the units of this metric.
+    pub fn distance(self, a: &Color, b: &Color) -> f64 {
+        match self {
+            Metric::Space(space) => distance_with(a, b, space),
+            _ => self.distance_of(&self.coordinates(a), &self.coordinates(b)),
+        }
+    }
+
+    /// `color` as this metric measures it.
+    pub(crate) fn coordinates(self, color: &Color) -> Vec<f64> {
+        match self {
+            Metric::Space(space) => color.vec_of(space),
+            Metric::De2000 => color_math::lab(color).to_vec(),
+            Metric::LinearRgb => color_math::linear(color).map(|c| c * 255.0).to_vec(),
+            Metric::WeightedHsl(_) => color_math::hsl(color).to_vec(),
+        }
+    }
+
+    /// The distance between two results of [`Metric::coordinates`].
+    pub(crate) fn distance_of(self, a: &[f64], b: &[f64]) -> f64 {
+        let triple = |c: &[f64]| [c[0], c[1], c[2]];
+        match self {
+            // the same arithmetic as color_art::distance_with,
+            // so that ties come out the same
+            Metric::Space(_) => {
+                let mut distance = 0.0;
+                for (a, b) in a.iter().zip(b) {
+                    distance += (a - b).powf(2.0);
+                }
+                distance.sqrt()
+            }
+            Metric::De2000 => color_math::delta_e2000(triple(a), triple(b)),
+            Metric::LinearRgb => color_math::euclidean(triple(a), triple(b)),
+            Metric::WeightedHsl(w) => {
+                color_math::weighted_hsl(triple(a), triple(b), [w.hue, w.saturation, w.lightness])
+            }
+        }
+    }
+
+    /// The canonical name of this metric, as accepted by
+    /// [`parse_metric`](crate::spaces::parse_metric), e.g. `lab` or `de2000`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Metric::Space(space) => crate::spaces::color_space_name(space).unwrap_or("rgb"),
+            Metric::De2000 => crate::spaces::DE2000_ALIASES[0],
+            Metric::LinearRgb => crate::spaces::LINEAR_RGB_ALIASES[0],
+            Metric::WeightedHsl(_) => crate::spaces::WEIGHTED_HSL_ALIASES[0],
+        }
+    }
+}
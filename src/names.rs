@@ -0,0 +1,51 @@
+//! Localized display names for languages.
+//!
+//! A mapping file is a YAML document of `canonical: localized` pairs:
+//!
+//! ```text
+//! JavaScript: ジャバスクリプト
+//! Rust: ラスト
+//! ```
+//!
+//! Names only change in output; queries still match Linguist's names and aliases.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{Error, Linguist};
+
+/// Display names keyed by canonical language name, as in [`Language::name`].
+///
+/// [`Language::name`]: crate::Language::name
+#[derive(Debug, Clone, Default)]
+pub struct DisplayNames(HashMap<String, String>);
+
+impl DisplayNames {
+    /// Read a mapping file, resolving each key like [`Linguist::get`].
+    ///
+    /// Keys that do not resolve to a known language are skipped and returned
+    /// alongside the mapping so that the caller can warn about them.
+    pub fn load(path: &Path, linguist: &Linguist) -> Result<(Self, Vec<String>), Error> {
+        let text = fs::read_to_string(path)?;
+        let pairs = serde_yaml::from_str::<HashMap<String, String>>(&text)?;
+
+        let mut names = HashMap::with_capacity(pairs.len());
+        let mut unknown = vec![];
+
+        for (key, localized) in pairs {
+            match linguist.get(&key) {
+                Some(lang) => {
+                    names.insert(lang.name().to_string(), localized);
+                }
+                None => unknown.push(key),
+            }
+        }
+
+        unknown.sort();
+        Ok((Self(names), unknown))
+    }
+
+    /// The display name for the language `name`, falling back to `name` itself.
+    pub fn get<'a>(&'a self, name: &'a str) -> &'a str {
+        self.0.get(name).map(String::as_str).unwrap_or(name)
+    }
+}
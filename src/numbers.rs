@@ -0,0 +1,126 @@
+//! How many decimal places each kind of number is printed with, in one place
+//! so that every output rounds the same value the same way.
+
+use std::fmt;
+
+/// A kind of number shown in output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    /// Distances in the color space chosen with `-c`: 2 places.
+    Distance,
+    /// CIEDE2000 differences: 1 place.
+    DeltaE,
+    /// Percentages: 1 place.
+    Percent,
+    /// Relative luminance, `0.0..=1.0`: 4 places.
+    Luminance,
+    /// Other ratios and averages: 2 places.
+    Ratio,
+}
+
+impl Precision {
+    pub const fn places(self) -> usize {
+        match self {
+            Precision::Distance => 2,
+            Precision::DeltaE => 1,
+            Precision::Percent => 1,
+            Precision::Luminance => 4,
+            Precision::Ratio => 2,
+        }
+    }
+
+    /// `value` rounded to [`Precision::places`], for machine-readable output
+    /// that should agree with the text.
+    pub fn round(self, value: f64) -> f64 {
+        let scale = 10f64.powi(self.places() as i32);
+        (value * scale).round() / scale
+    }
+
+    /// `value` as machine-readable output prints it with `rounding`.
+    pub fn rounded(self, value: f64, rounding: Rounding) -> f64 {
+        match rounding {
+            Rounding::Places => self.round(value),
+            Rounding::Full => value,
+        }
+    }
+
+    /// `value` formatted with [`Precision::places`]. A width in the format
+    /// string, as in `{:>5}`, right-aligns it.
+    pub fn fixed(self, value: f64) -> Fixed {
+        Fixed(value, self.places())
+    }
+}
+
+/// Whether machine-readable output rounds numbers as the text does, or has
+/// them in full, as with `--raw-numbers`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rounding {
+    /// To [`Precision::places`], so that the numbers equal those of the text.
+    #[default]
+    Places,
+    /// With every digit of the `f64`.
+    Full,
+}
+
+/// See [`Precision::fixed`].
+#[derive(Debug, Clone, Copy)]
+pub struct Fixed(f64, usize);
+
+impl fmt::Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Fixed(value, places) = *self;
+        match f.width() {
+            Some(width) => write!(f, "{value:>width$.places$}"),
+            None => write!(f, "{value:.places$}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn places() {
+        for (precision, value, fixed, rounded) in [
+            (Precision::Distance, 47.169_905, "47.17", 47.17),
+            (Precision::DeltaE, 13.728_434, "13.7", 13.7),
+            (Precision::DeltaE, 2.95, "3.0", 3.0),
+            (Precision::Percent, 12.345, "12.3", 12.3),
+            (Precision::Luminance, 0.212_672_9, "0.2127", 0.2127),
+            (Precision::Ratio, 4.478_089, "4.48", 4.48),
+            (Precision::Distance, 0.0, "0.00", 0.0),
+        ] {
+            assert_eq!(
+                precision.fixed(value).to_string(),
+                fixed,
+                "{precision:?} {value}"
+            );
+            assert_eq!(precision.round(value), rounded, "{precision:?} {value}");
+            // what JSON has parses as what the text shows
+            assert_eq!(
+                fixed.parse::<f64>().unwrap(),
+                rounded,
+                "{precision:?} {value}"
+            );
+        }
+    }
+
+    #[test]
+    fn rounding() {
+        let value = 13.728_434_615;
+        assert_eq!(Precision::DeltaE.rounded(value, Rounding::Places), 13.7);
+        assert_eq!(Precision::DeltaE.rounded(value, Rounding::Full), value);
+        assert_eq!(Rounding::default(), Rounding::Places);
+    }
+
+    #[test]
+    fn width() {
+        assert_eq!(
+            format!("{:>7}", Precision::Distance.fixed(3.125_9)),
+            "   3.13"
+        );
+        assert_eq!(format!("{:>3}", Precision::Percent.fixed(100.0)), "100.0");
+        assert_eq!(format!("{}", Precision::Luminance.fixed(1.0)), "1.0000");
+    }
+}
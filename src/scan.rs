@@ -0,0 +1,302 @@
+//! Tally languages in directory trees by bytes, like GitHub's language bar.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{detect::Pipeline, Language};
+
+/// Bytes per language.
+#[derive(Debug, Clone, Default)]
+pub struct Tally<'a>(BTreeMap<&'a str, (Language<'a>, u64)>);
+
+impl<'a> Tally<'a> {
+    pub fn add(&mut self, language: Language<'a>, bytes: u64) {
+        self.0.entry(language.name()).or_insert((language, 0)).1 += bytes;
+    }
+
+    /// Add everything in `other` to this tally.
+    pub fn merge(&mut self, other: &Tally<'a>) {
+        for &(language, bytes) in other.0.values() {
+            self.add(language, bytes);
+        }
+    }
+
+    /// Languages with their byte counts, largest first, then by name.
+    pub fn languages(&self) -> Vec<(Language<'a>, u64)> {
+        let mut languages = self.0.values().copied().collect::<Vec<_>>();
+        languages.sort_by(|(a, x), (b, y)| y.cmp(x).then_with(|| a.name().cmp(b.name())));
+        languages
+    }
+
+    pub fn total(&self) -> u64 {
+        self.0.values().map(|(_, bytes)| bytes).sum()
+    }
+
+    /// [`Tally::languages`] with the percent of [`Tally::total`] of each.
+    pub fn shares(&self) -> Vec<(Language<'a>, u64, f64)> {
+        let total = self.total().max(1) as f64;
+        let languages = self.languages().into_iter();
+        languages
+            .map(|(language, bytes)| (language, bytes, bytes as f64 / total * 100.0))
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// The result of scanning one or more roots.
+#[derive(Debug, Clone)]
+pub struct Scan<'a> {
+    /// Each root as given, with the languages found under it.
+    pub roots: Vec<(PathBuf, Tally<'a>)>,
+    /// Every root combined.
+    pub total: Tally<'a>,
+}
+
+/// How [`scan_with_options`] walks directories.
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    respect_ignore: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            respect_ignore: true,
+        }
+    }
+}
+
+impl ScanOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Skip files that `.gitignore`, `.ignore`, `.git/info/exclude`, and the
+    /// global gitignore of Git leave out, also outside of Git repositories.
+    /// Default: true
+    pub fn respect_ignore(mut self, respect_ignore: bool) -> Self {
+        self.respect_ignore = respect_ignore;
+        self
+    }
+}
+
+/// Walk every root and tally the files `pipeline` can classify, with the
+/// default [`ScanOptions`].
+///
+/// Roots are canonicalized and each file is counted at most once, so
+/// overlapping roots (one inside another, or the same root twice) are not
+/// counted twice: files are attributed to the first root that reaches them.
+/// Symbolic links and `.git` directories are skipped. A file matching several
+/// languages, even after the pipeline's heuristics, is attributed to the first one.
+pub fn scan<'a, P>(pipeline: &Pipeline<'a>, roots: &[P]) -> io::Result<Scan<'a>>
+where
+    P: AsRef<Path>,
+{
+    scan_with_options(pipeline, roots, &ScanOptions::default())
+}
+
+/// Like [`scan`], walking directories as configured in `options`.
+pub fn scan_with_options<'a, P>(
+    pipeline: &Pipeline<'a>,
+    roots: &[P],
+    options: &ScanOptions,
+) -> io::Result<Scan<'a>>
+where
+    P: AsRef<Path>,
+{
+    let mut visited = HashSet::new();
+    let mut total = Tally::default();
+    let mut tallies = vec![];
+
+    for root in roots {
+        let canonical = fs::canonicalize(root)?;
+        let mut tally = Tally::default();
+        walk(pipeline, &canonical, options, &mut visited, &mut tally)?;
+        total.merge(&tally);
+        tallies.push((root.as_ref().to_path_buf(), tally));
+    }
+
+    Ok(Scan {
+        roots: tallies,
+        total,
+    })
+}
+
+/// The languages of the files under `root`, with their bytes and percent of
+/// all bytes classified, largest first, as [`scan`] tallies them.
+pub fn classify_dir<'a>(
+    pipeline: &Pipeline<'a>,
+    root: impl AsRef<Path>,
+) -> io::Result<Vec<(Language<'a>, u64, f64)>> {
+    Ok(scan(pipeline, &[root])?.total.shares())
+}
+
+fn walk<'a>(
+    pipeline: &Pipeline<'a>,
+    root: &Path,
+    options: &ScanOptions,
+    visited: &mut HashSet<PathBuf>,
+    tally: &mut Tally<'a>,
+) -> io::Result<()> {
+    let walker = ignore::WalkBuilder::new(root)
+        .standard_filters(options.respect_ignore)
+        // unlike the standard filters, hidden files count, as on GitHub
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) if err.io_error().is_some() => return Err(err.into_io_error().unwrap()),
+            // e.g. a malformed glob in a .gitignore, whose other globs apply
+            Err(err) => {
+                log::debug!("{err}");
+                continue;
+            }
+        };
+        // symbolic links are not followed, and are reported as themselves
+        if !entry.file_type().is_some_and(|kind| kind.is_file()) {
+            continue;
+        }
+        let path = entry.path();
+        if !visited.insert(path.to_path_buf()) {
+            continue;
+        }
+        if let Some(language) = pipeline
+            .detect(path)
+            .and_then(|d| d.languages.first().copied())
+        {
+            tally.add(language, entry.metadata().map_err(io::Error::other)?.len());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Linguist;
+
+    /// The snapshot of `languages.yml` that the `bundled` feature embeds.
+    fn linguist() -> Linguist {
+        include_str!("../data/languages.yml").parse().unwrap()
+    }
+
+    /// A directory of its own for `test`, with `files` and their contents.
+    fn tree(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("linguist-termcolor-scan-{}", std::process::id()))
+            .join(test);
+        let _ = fs::remove_dir_all(&dir);
+        for (name, contents) in files {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    fn bytes(tally: &Tally<'_>) -> Vec<(String, u64)> {
+        let languages = tally.languages().into_iter();
+        languages
+            .map(|(language, bytes)| (language.name().to_string(), bytes))
+            .collect()
+    }
+
+    /// Files under roots inside other roots, or under a root given twice, are
+    /// counted once, for the first root that reaches them.
+    #[test]
+    fn overlapping_roots() {
+        let root = tree(
+            "overlapping",
+            &[
+                ("main.rs", "fn main() {}\n"),
+                ("src/lib.rs", "pub fn f() {}\n"),
+                ("src/util.py", "pass\n"),
+            ],
+        );
+        let linguist = linguist();
+        let pipeline = Pipeline::new(&linguist);
+        let all = [("Rust".to_string(), 27), ("Python".to_string(), 5)];
+
+        let roots = [root.clone(), root.join("src"), root.clone()];
+        let found = scan(&pipeline, &roots).unwrap();
+        assert_eq!(bytes(&found.total), all);
+        assert_eq!(bytes(&found.roots[0].1), all);
+        assert!(found.roots[1].1.is_empty());
+        assert!(found.roots[2].1.is_empty());
+
+        // the inner root first, then the rest of the outer one
+        let roots = [root.join("src"), root.join("./src/.."), root.clone()];
+        let found = scan(&pipeline, &roots).unwrap();
+        assert_eq!(bytes(&found.total), all);
+        assert_eq!(
+            bytes(&found.roots[0].1),
+            [("Rust".to_string(), 14), ("Python".to_string(), 5)]
+        );
+        assert_eq!(bytes(&found.roots[1].1), [("Rust".to_string(), 13)]);
+        assert!(found.roots[2].1.is_empty());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    /// Hidden files count, such as `.gitignore` itself, ignored ones only
+    /// without [`ScanOptions::respect_ignore`], and `.git` never does.
+    #[test]
+    fn ignored_files() {
+        let root = tree(
+            "ignored",
+            &[
+                (".gitignore", "target/\n"),
+                (".hidden.rs", "fn f() {}\n"),
+                ("target/out.rs", "fn g() {}\n"),
+                (".git/hooks/pre-commit.py", "pass\n"),
+            ],
+        );
+        let linguist = linguist();
+        let pipeline = Pipeline::new(&linguist);
+        let names = |options: &ScanOptions| {
+            let scan = scan_with_options(&pipeline, &[&root], options).unwrap();
+            bytes(&scan.total)
+        };
+        let ignore_list = ("Ignore List".to_string(), 8);
+        assert_eq!(
+            names(&ScanOptions::new()),
+            [("Rust".to_string(), 10), ignore_list.clone()]
+        );
+        let all = ScanOptions::new().respect_ignore(false);
+        assert_eq!(names(&all), [("Rust".to_string(), 20), ignore_list]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn shares() {
+        let linguist = linguist();
+        let (rust, python) = (
+            linguist.get("Rust").unwrap(),
+            linguist.get("Python").unwrap(),
+        );
+        let mut tally = Tally::default();
+        tally.add(python, 1);
+        tally.add(rust, 2);
+        tally.add(python, 1);
+        let shares = tally.shares();
+        let shares = shares
+            .iter()
+            .map(|(lang, bytes, share)| (lang.name(), *bytes, *share));
+        assert_eq!(
+            shares.collect::<Vec<_>>(),
+            [("Python", 2, 50.0), ("Rust", 2, 50.0)]
+        );
+        assert!(Tally::default().shares().is_empty());
+    }
+}
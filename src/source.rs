@@ -0,0 +1,460 @@
+//! Where and how [`Linguist`] data is loaded, configured in one place.
+//!
+//! ```text
+//! let linguist = Linguist::builder()
+//!     .git_ref("v7.30.0")
+//!     .timeout(Duration::from_secs(10))
+//!     .retries(2)
+//!     .user_agent("my-site-generator")
+//!     .quiet(true)
+//!     .build()?;
+//! ```
+
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{is_not_found, languages_yml_at, limits::Limits, Error, Linguist, LANGUAGES_YML};
+
+/// Build a blocking or an async `reqwest` client with the timeout,
+/// `User-Agent`, proxy, and root certificates of a [`LinguistSource`], whose
+/// builders have the same methods but no trait in common.
+macro_rules! configure {
+    ($source:expr, $builder:expr) => {{
+        let mut client = $builder;
+        if let Some(timeout) = $source.timeout {
+            client = client.timeout(timeout);
+        }
+        if let Some(user_agent) = &$source.user_agent {
+            client = client.user_agent(user_agent);
+        }
+        if let Some(proxy) = &$source.proxy {
+            client = client.proxy(reqwest::Proxy::all(proxy.clone())?);
+        }
+        for certificate in &$source.root_certificates {
+            client = client.add_root_certificate(certificate.clone());
+        }
+        client
+    }};
+}
+/// A builder for [`Linguist`], see [`Linguist::builder`].
+///
+/// By default, `languages.yml` is fetched from [`LANGUAGES_YML`] with the
+/// default [`Limits`], logging the URL at the `info` level. The same options
+/// apply to [`LinguistSource::build`] and [`LinguistSource::build_async`].
+pub struct LinguistSource {
+    origin: Origin,
+    limits: Limits,
+    timeout: Option<Duration>,
+    user_agent: Option<String>,
+    proxy: Option<reqwest::Url>,
+    root_certificates: Vec<reqwest::Certificate>,
+    client: Option<reqwest::blocking::Client>,
+    #[cfg(feature = "async")]
+    async_client: Option<reqwest::Client>,
+    retries: u32,
+    quiet: bool,
+}
+
+enum Origin {
+    Url(String),
+    Ref(String),
+    Path(PathBuf),
+    Reader(Box<dyn Read>),
+}
+
+impl Default for LinguistSource {
+    fn default() -> Self {
+        Self {
+            origin: Origin::Url(LANGUAGES_YML.to_string()),
+            limits: Limits::default(),
+            timeout: None,
+            user_agent: None,
+            proxy: None,
+            root_certificates: vec![],
+            client: None,
+            #[cfg(feature = "async")]
+            async_client: None,
+            retries: 0,
+            quiet: false,
+        }
+    }
+}
+
+impl LinguistSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fetch `languages.yml` from `url`. Default: [`LANGUAGES_YML`]
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.origin = Origin::Url(url.into());
+        self
+    }
+
+    /// Fetch `languages.yml` as of a Linguist branch, tag, or commit, see
+    /// [`languages_yml_at`]. A ref upstream does not have fails with
+    /// [`Error::RefNotFound`].
+    pub fn git_ref(mut self, git_ref: impl Into<String>) -> Self {
+        self.origin = Origin::Ref(git_ref.into());
+        self
+    }
+
+    /// Read `languages.yml` from a local file instead of fetching it.
+    pub fn path(mut self, path: impl AsRef<Path>) -> Self {
+        self.origin = Origin::Path(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Read `languages.yml` from `reader`, e.g. stdin, instead of fetching it.
+    pub fn reader(mut self, reader: impl Read + 'static) -> Self {
+        self.origin = Origin::Reader(Box::new(reader));
+        self
+    }
+
+    /// Default: [`Limits::default`]
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Give up on fetching after `timeout`. Default: reqwest's, 30 seconds
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// The `User-Agent` to fetch with. Default: none
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Fetch through the HTTP, HTTPS, or SOCKS proxy at `proxy`, rather than
+    /// those of the `HTTPS_PROXY` and similar environment variables.
+    pub fn proxy(mut self, proxy: reqwest::Url) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Also trust `certificate`, e.g. that of a proxy that intercepts HTTPS.
+    pub fn root_certificate(mut self, certificate: reqwest::Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    /// Fetch with `client` in [`LinguistSource::build`].
+    /// [`LinguistSource::timeout`], [`LinguistSource::user_agent`],
+    /// [`LinguistSource::proxy`], and [`LinguistSource::root_certificate`] do
+    /// not apply to it.
+    pub fn client(mut self, client: reqwest::blocking::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Like [`LinguistSource::client`], for [`LinguistSource::build_async`].
+    /// Requires the `async` feature.
+    #[cfg(feature = "async")]
+    pub fn async_client(mut self, client: reqwest::Client) -> Self {
+        self.async_client = Some(client);
+        self
+    }
+
+    /// Try fetching again up to `retries` times after a timeout, a failed
+    /// connection, or a 5xx or 429 status from GitHub, waiting half a second
+    /// before the first retry and twice as long before each next one. If every
+    /// attempt fails, the error is [`Error::FetchAttempts`]. Default: 0
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Do not log `Fetching <url>`. Default: false
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Load and parse the data.
+    pub fn build(mut self) -> Result<Linguist, Error> {
+        let Some(url) = self.fetched_url() else {
+            return self.read();
+        };
+        let client = match self.client.take() {
+            Some(client) => client,
+            None => configure!(self, reqwest::blocking::Client::builder()).build()?,
+        };
+        let policy = self.policy();
+        let fetched = Linguist::fetch(&url, &client, &self.limits, &policy);
+        fetched.map_err(|err| self.not_found(err))
+    }
+
+    /// Like [`LinguistSource::build`], but without blocking the current
+    /// thread while fetching, for use inside an async runtime. Files and
+    /// readers are still read in place. Requires the `async` feature, and
+    /// with [`LinguistSource::retries`], a tokio runtime with time enabled.
+    #[cfg(feature = "async")]
+    pub async fn build_async(mut self) -> Result<Linguist, Error> {
+        let Some(url) = self.fetched_url() else {
+            return self.read();
+        };
+        let client = match self.async_client.take() {
+            Some(client) => client,
+            None => configure!(self, reqwest::Client::builder()).build()?,
+        };
+        let policy = self.policy();
+        let fetched = Linguist::fetch_async(&url, &client, &self.limits, &policy).await;
+        fetched.map_err(|err| self.not_found(err))
+    }
+
+    /// The URL to fetch, unless the data is read instead.
+    fn fetched_url(&self) -> Option<String> {
+        match &self.origin {
+            Origin::Url(url) => Some(url.clone()),
+            Origin::Ref(git_ref) => Some(languages_yml_at(git_ref)),
+            Origin::Path(_) | Origin::Reader(_) => None,
+        }
+    }
+
+    fn read(self) -> Result<Linguist, Error> {
+        match self.origin {
+            Origin::Path(path) => Linguist::from_path_with_limits(path, &self.limits),
+            Origin::Reader(reader) => Linguist::from_reader_with_limits(reader, &self.limits),
+            Origin::Url(_) | Origin::Ref(_) => unreachable!("URLs are fetched"),
+        }
+    }
+
+    fn policy(&self) -> Policy {
+        Policy {
+            retries: self.retries,
+            quiet: self.quiet,
+        }
+    }
+
+    /// A 404 for a ref as [`Error::RefNotFound`].
+    fn not_found(&self, err: Error) -> Error {
+        match (&self.origin, is_not_found(&err)) {
+            (Origin::Ref(git_ref), true) => Error::RefNotFound(git_ref.clone()),
+            _ => err,
+        }
+    }
+}
+
+/// How the blocking and the async client fetch: whether to log, and when to
+/// try again.
+pub(crate) struct Policy {
+    pub(crate) retries: u32,
+    pub(crate) quiet: bool,
+}
+
+/// The first retry waits this long, and each next one twice as long.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+impl Policy {
+    /// Just once, logging the URL.
+    pub(crate) const ONCE: Policy = Policy {
+        retries: 0,
+        quiet: false,
+    };
+
+    pub(crate) fn announce(&self, url: &str) {
+        if !self.quiet {
+            log::info!("Fetching {url}");
+        }
+    }
+
+    /// After the `attempts`th attempt failed with `err`, how long to wait
+    /// before trying again, or the error to give up with: on failures that
+    /// may be transient, up to `retries` times, see [`LinguistSource::retries`].
+    pub(crate) fn retry(&self, attempts: u32, err: reqwest::Error) -> Result<Duration, Error> {
+        let transient = err.is_timeout()
+            || err.is_connect()
+            || err.status().is_some_and(|status| {
+                status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            });
+        if !transient || attempts > self.retries {
+            return Err(match attempts {
+                1 => Error::Fetch(err),
+                _ => Error::FetchAttempts {
+                    attempts,
+                    source: err,
+                },
+            });
+        }
+        let delay = RETRY_DELAY * 2u32.saturating_pow(attempts - 1);
+        let timed_out = if err.is_timeout() { ": timed out" } else { "" };
+        log::info!("{err}{timed_out}, retrying in {delay:?}");
+        Ok(delay)
+    }
+}
+
+/// `GET url`, failing on an error status, and trying again as `policy` says.
+pub(crate) fn get(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    policy: &Policy,
+) -> Result<reqwest::blocking::Response, Error> {
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match client
+            .get(url)
+            .send()
+            .and_then(|res| res.error_for_status())
+        {
+            Ok(res) => return Ok(res),
+            Err(err) => std::thread::sleep(policy.retry(attempts, err)?),
+        }
+    }
+}
+
+/// Like [`get`], without blocking.
+#[cfg(feature = "async")]
+pub(crate) async fn get_async(
+    client: &reqwest::Client,
+    url: &str,
+    policy: &Policy,
+) -> Result<reqwest::Response, Error> {
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match client
+            .get(url)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+        {
+            Ok(res) => return Ok(res),
+            Err(err) => tokio::time::sleep(policy.retry(attempts, err)?).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use super::*;
+
+    const LANGUAGES_YML: &str = include_str!("../tests/fixtures/primary-extensions.yml");
+
+    /// Answer one connection after another on a local port with `responses`,
+    /// a status and a body, or for `None`, nothing until the client gives up.
+    fn serve(responses: Vec<Option<(u16, &'static str)>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/languages.yml", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while request.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                let Some((status, body)) = response else {
+                    thread::sleep(Duration::from_secs(2));
+                    continue;
+                };
+                let head = format!(
+                    "HTTP/1.1 {status} Status\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                let _ = stream.write_all(head.as_bytes());
+                let _ = stream.write_all(body.as_bytes());
+            }
+        });
+        url
+    }
+
+    /// What came of loading, to compare between the two clients.
+    fn outcome(result: Result<Linguist, Error>) -> String {
+        match result {
+            Ok(linguist) => {
+                let names = linguist.languages().map(|lang| lang.name().to_string());
+                format!("ok {}", names.collect::<Vec<_>>().join(", "))
+            }
+            Err(Error::Fetch(err)) if err.is_timeout() => "timed out".into(),
+            Err(Error::Fetch(err)) => format!("status {:?}", err.status().map(|s| s.as_u16())),
+            Err(Error::FetchAttempts { attempts, source }) => {
+                let status = source.status().map(|s| s.as_u16());
+                format!("gave up after {attempts} with status {status:?}")
+            }
+            Err(err) => format!("error {err}"),
+        }
+    }
+
+    /// Load from a fresh server with `responses`, blocking and without blocking,
+    /// and check that both come to `expected`.
+    fn scenario(
+        responses: Vec<Option<(u16, &'static str)>>,
+        source: impl Fn() -> LinguistSource,
+        expected: &str,
+    ) {
+        let blocking = source().url(serve(responses.clone())).build();
+        assert_eq!(outcome(blocking), expected, "blocking");
+        #[cfg(feature = "async")]
+        {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            let url = serve(responses);
+            let fetched = runtime.block_on(source().url(url).build_async());
+            assert_eq!(outcome(fetched), expected, "async");
+        }
+    }
+
+    #[test]
+    fn fetched() {
+        let source = || LinguistSource::new().quiet(true);
+        scenario(vec![Some((200, LANGUAGES_YML))], source, "ok Alpha, Zeta");
+    }
+
+    #[test]
+    fn retried_after_server_error() {
+        let responses = vec![Some((500, "")), Some((200, LANGUAGES_YML))];
+        let source = || LinguistSource::new().retries(1);
+        scenario(responses, source, "ok Alpha, Zeta");
+    }
+
+    #[test]
+    fn not_retried_by_default() {
+        let responses = vec![Some((503, "")), Some((200, LANGUAGES_YML))];
+        scenario(responses, LinguistSource::new, "status Some(503)");
+    }
+
+    #[test]
+    fn not_retried_when_not_found() {
+        let responses = vec![Some((404, "")), Some((200, LANGUAGES_YML))];
+        let source = || LinguistSource::new().retries(2);
+        scenario(responses, source, "status Some(404)");
+    }
+
+    #[test]
+    fn gave_up_after_retries() {
+        let responses = vec![Some((500, "")), Some((429, ""))];
+        let source = || LinguistSource::new().retries(1);
+        scenario(responses, source, "gave up after 2 with status Some(429)");
+    }
+
+    #[test]
+    fn timed_out() {
+        let source = || LinguistSource::new().timeout(Duration::from_millis(200));
+        scenario(vec![None], source, "timed out");
+    }
+
+    #[test]
+    fn too_large() {
+        let source = || LinguistSource::new().limits(Limits::default().max_bytes(16));
+        let len = LANGUAGES_YML.len();
+        let expected =
+            format!("error languages.yml is {len} bytes, exceeding the size limit of 16 bytes");
+        scenario(vec![Some((200, LANGUAGES_YML))], source, &expected);
+    }
+}
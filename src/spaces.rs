@@ -0,0 +1,311 @@
+//! Color space names as people type them.
+//!
+//! [`ColorSpace`] only knows its own spellings and maps anything else to
+//! [`ColorSpace::Unknown`], which cannot be used to measure distances. This
+//! module accepts the common CSS and colorimetry names for the same spaces,
+//! and explains the names of spaces that `color_art` does not implement.
+//!
+//! Names are compared case-insensitively, ignoring spaces, `-`, and `_`, so
+//! `CIE-Lab`, `cie_lab`, and `cielab` are the same.
+//!
+//! [`parse_metric`] also accepts the names of [`Metric::De2000`], which is not
+//! a space but is chosen in the same places.
+
+use color_art::ColorSpace;
+
+use crate::{metric::Metric, Error};
+
+/// Every accepted name, with the space it resolves to. The first name listed
+/// for each space is the canonical one.
+pub const ALIASES: &[(&str, ColorSpace)] = &[
+    ("rgb", ColorSpace::RGB),
+    ("srgb", ColorSpace::RGB),
+    ("hex", ColorSpace::RGB),
+    ("rgba", ColorSpace::RGBA),
+    ("hexa", ColorSpace::RGBA),
+    ("hsl", ColorSpace::HSL),
+    ("hsla", ColorSpace::HSLA),
+    ("hsv", ColorSpace::HSV),
+    ("hsb", ColorSpace::HSV),
+    ("hsi", ColorSpace::HSI),
+    ("hwb", ColorSpace::HWB),
+    ("cmyk", ColorSpace::CMYK),
+    ("xyz", ColorSpace::XYZ),
+    ("ciexyz", ColorSpace::XYZ),
+    ("yiq", ColorSpace::YIQ),
+    ("yuv", ColorSpace::YUV),
+    ("ycbcr", ColorSpace::YCbCr),
+    ("lab", ColorSpace::Lab),
+    ("cielab", ColorSpace::Lab),
+    ("l*a*b*", ColorSpace::Lab),
+    ("cie-l*a*b*", ColorSpace::Lab),
+];
+
+/// Every accepted name of [`Metric::De2000`], the first one canonical.
+pub const DE2000_ALIASES: &[&str] = &["de2000", "ciede2000", "deltae2000", "de00"];
+
+/// Every accepted name of [`Metric::LinearRgb`], the first one canonical.
+pub const LINEAR_RGB_ALIASES: &[&str] = &["linear-rgb", "srgb-linear", "linear-srgb", "linear"];
+
+/// Every accepted name of [`Metric::WeightedHsl`], the first one canonical.
+pub const WEIGHTED_HSL_ALIASES: &[&str] = &["hsl-weighted", "weighted-hsl", "hslw"];
+
+/// Names of spaces that are not implemented, with the closest accepted name.
+pub const UNSUPPORTED: &[(&str, &str)] = &[
+    ("lch", "lab"),
+    ("cielch", "lab"),
+    ("oklab", "lab"),
+    ("oklch", "lab"),
+    ("luv", "lab"),
+    ("cieluv", "lab"),
+    ("hsluv", "lab"),
+    ("hpluv", "lab"),
+    ("display-p3", "rgb"),
+    ("p3", "rgb"),
+    ("rec2020", "rgb"),
+    ("a98-rgb", "rgb"),
+    ("prophoto-rgb", "rgb"),
+];
+
+/// Resolve a color space name using [`ALIASES`].
+///
+/// Names in [`UNSUPPORTED`] fail with the closest supported space; other
+/// unknown names fail with the most similar accepted name.
+pub fn parse_color_space(name: &str) -> Result<ColorSpace, Error> {
+    let key = normalize(name);
+
+    if let Some(&(_, space)) = ALIASES.iter().find(|(alias, _)| normalize(alias) == key) {
+        return Ok(space);
+    }
+
+    if let Some(&(_, closest)) = UNSUPPORTED
+        .iter()
+        .find(|(alias, _)| normalize(alias) == key)
+    {
+        return Err(Error::UnsupportedColorSpace {
+            name: name.to_string(),
+            closest,
+        });
+    }
+
+    let suggestion = ALIASES
+        .iter()
+        .map(|(alias, _)| *alias)
+        .min_by_key(|alias| edit_distance(&normalize(alias), &key))
+        .unwrap();
+
+    Err(Error::UnknownColorSpace {
+        name: name.to_string(),
+        suggestion,
+    })
+}
+
+/// Resolve a color space name as [`parse_color_space`] does, a name in
+/// [`DE2000_ALIASES`] to [`Metric::De2000`], one in [`LINEAR_RGB_ALIASES`] to
+/// [`Metric::LinearRgb`], or one in [`WEIGHTED_HSL_ALIASES`] to [`Metric::WeightedHsl`] with the default
+/// weights.
+pub fn parse_metric(name: &str) -> Result<Metric, Error> {
+    let key = normalize(name);
+    let metrics = [
+        (DE2000_ALIASES, Metric::De2000),
+        (LINEAR_RGB_ALIASES, Metric::LinearRgb),
+        (
+            WEIGHTED_HSL_ALIASES,
+            Metric::WeightedHsl(Default::default()),
+        ),
+    ];
+    for (aliases, metric) in metrics {
+        if aliases.iter().any(|alias| normalize(alias) == key) {
+            return Ok(metric);
+        }
+    }
+    match parse_color_space(name) {
+        // a typo of a metric's name rather than of a space
+        Err(Error::UnknownColorSpace { name, suggestion }) => {
+            let nearest = metrics
+                .iter()
+                .flat_map(|(aliases, _)| aliases.iter().map(|alias| (alias, aliases[0])))
+                .min_by_key(|(alias, _)| edit_distance(alias, &key))
+                .filter(|(alias, _)| edit_distance(alias, &key) < edit_distance(suggestion, &key));
+            Err(Error::UnknownColorSpace {
+                name,
+                suggestion: nearest.map_or(suggestion, |(_, canonical)| canonical),
+            })
+        }
+        result => result.map(Metric::Space),
+    }
+}
+
+/// Every metric that [`parse_metric`] resolves to: those of
+/// [`supported_color_spaces`], then [`Metric::De2000`], [`Metric::LinearRgb`],
+/// and [`Metric::WeightedHsl`].
+pub fn supported_metrics() -> Vec<Metric> {
+    let spaces = supported_color_spaces().into_iter().map(Metric::Space);
+    let others = [
+        Metric::De2000,
+        Metric::LinearRgb,
+        Metric::WeightedHsl(Default::default()),
+    ];
+    spaces.chain(others).collect()
+}
+
+/// Every space that [`parse_color_space`] resolves to, each once and in the
+/// order of [`ALIASES`]. Distances with `color_art::distance_with` work in all
+/// of them.
+pub fn supported_color_spaces() -> Vec<ColorSpace> {
+    let mut spaces = Vec::new();
+    for &(_, space) in ALIASES {
+        if !spaces.contains(&space) {
+            spaces.push(space);
+        }
+    }
+    spaces
+}
+
+/// Every name that [`parse_metric`] resolves to `metric`, the canonical one,
+/// [`Metric::name`], first.
+pub fn metric_aliases(metric: Metric) -> Vec<&'static str> {
+    match metric {
+        Metric::Space(space) => (ALIASES.iter())
+            .filter(|(_, other)| *other == space)
+            .map(|(name, _)| *name)
+            .collect(),
+        Metric::De2000 => DE2000_ALIASES.to_vec(),
+        Metric::LinearRgb => LINEAR_RGB_ALIASES.to_vec(),
+        Metric::WeightedHsl(_) => WEIGHTED_HSL_ALIASES.to_vec(),
+    }
+}
+
+/// The canonical name of `space`, the first listed for it in [`ALIASES`].
+pub fn color_space_name(space: ColorSpace) -> Option<&'static str> {
+    ALIASES
+        .iter()
+        .find(|(_, other)| *other == space)
+        .map(|(name, _)| *name)
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Levenshtein distance, in chars.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, x) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(x != *y);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aliases() {
+        for (name, metric) in [
+            ("rgb", Metric::Space(ColorSpace::RGB)),
+            ("sRGB", Metric::Space(ColorSpace::RGB)),
+            ("hex", Metric::Space(ColorSpace::RGB)),
+            ("hsb", Metric::Space(ColorSpace::HSV)),
+            ("HSL", Metric::Space(ColorSpace::HSL)),
+            ("lab", Metric::Space(ColorSpace::Lab)),
+            ("cielab", Metric::Space(ColorSpace::Lab)),
+            ("CIE-Lab", Metric::Space(ColorSpace::Lab)),
+            ("cie_lab", Metric::Space(ColorSpace::Lab)),
+            ("l*a*b*", Metric::Space(ColorSpace::Lab)),
+            ("CIE L*a*b*", Metric::Space(ColorSpace::Lab)),
+            ("ciexyz", Metric::Space(ColorSpace::XYZ)),
+            ("YCbCr", Metric::Space(ColorSpace::YCbCr)),
+            ("de2000", Metric::De2000),
+            ("CIEDE2000", Metric::De2000),
+            ("de00", Metric::De2000),
+            ("linear", Metric::LinearRgb),
+            ("srgb-linear", Metric::LinearRgb),
+            ("weighted_hsl", Metric::WeightedHsl(Default::default())),
+            ("hslw", Metric::WeightedHsl(Default::default())),
+        ] {
+            assert_eq!(parse_metric(name).unwrap(), metric, "{name}");
+        }
+    }
+
+    #[test]
+    fn unsupported() {
+        for (name, expected) in [
+            ("hsluv", "lab"),
+            ("OKLCh", "lab"),
+            ("cie-luv", "lab"),
+            ("Display P3", "rgb"),
+            ("rec2020", "rgb"),
+        ] {
+            let Err(Error::UnsupportedColorSpace { closest, .. }) = parse_metric(name) else {
+                panic!("{name} was accepted or unknown");
+            };
+            assert_eq!(closest, expected, "{name}");
+        }
+    }
+
+    /// Typos suggest the nearest accepted name, of a space or of a metric.
+    #[test]
+    fn suggestions() {
+        for (name, expected) in [
+            ("lav", "lab"),
+            ("rbg", "rgb"),
+            ("hsv2", "hsv"),
+            ("cmk", "cmyk"),
+            ("de200", "de2000"),
+            ("ciede200", "de2000"),
+            ("linear-rbg", "linear-rgb"),
+            ("hsl-weigthed", "hsl-weighted"),
+        ] {
+            let Err(Error::UnknownColorSpace { suggestion, .. }) = parse_metric(name) else {
+                panic!("{name} was accepted or unsupported");
+            };
+            assert_eq!(suggestion, expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn edit_distances() {
+        for (a, b, distance) in [
+            ("", "", 0),
+            ("lab", "lab", 0),
+            ("", "lab", 3),
+            ("lab", "", 3),
+            ("lab", "lba", 2),
+            ("rgb", "rgba", 1),
+            ("kitten", "sitting", 3),
+            ("l*a*b*", "lab", 3),
+            ("é", "e", 1),
+        ] {
+            assert_eq!(edit_distance(a, b), distance, "{a:?} {b:?}");
+            assert_eq!(edit_distance(b, a), distance, "{b:?} {a:?}");
+        }
+    }
+
+    /// Each supported metric has its canonical name first among its aliases,
+    /// and the aliases resolve back to it.
+    #[test]
+    fn every_metric_has_its_aliases() {
+        for metric in supported_metrics() {
+            let aliases = metric_aliases(metric);
+            assert_eq!(aliases[0], metric.name());
+            for alias in aliases {
+                assert_eq!(parse_metric(alias).unwrap(), metric, "{alias}");
+            }
+        }
+        for (name, closest) in UNSUPPORTED {
+            assert!(parse_metric(closest).is_ok(), "{name}: {closest}");
+        }
+    }
+}
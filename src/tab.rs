@@ -0,0 +1,42 @@
+//! Escape sequences that tint the tab of the terminal running this program.
+//!
+//! There is no standard sequence for this, so it depends on the terminal; the
+//! sequences have no effect, or print garbage, elsewhere.
+
+use std::ffi::OsString;
+
+use color_art::Color;
+
+/// A terminal with a known tab color sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terminal {
+    /// <https://iterm2.com/documentation-escape-codes.html>
+    ITerm2,
+}
+
+impl Terminal {
+    /// Guess the terminal from environment variables, looked up with `env`.
+    pub fn detect<F>(env: F) -> Option<Terminal>
+    where
+        F: Fn(&str) -> Option<OsString>,
+    {
+        if env("TERM_PROGRAM").is_some_and(|v| v == "iTerm.app")
+            || env("ITERM_SESSION_ID").is_some_and(|v| !v.is_empty())
+        {
+            return Some(Terminal::ITerm2);
+        }
+        None
+    }
+
+    /// The sequence setting the tab color to `color`.
+    pub fn tab_color(self, color: &Color) -> String {
+        let (r, g, b) = (color.red(), color.green(), color.blue());
+        match self {
+            Terminal::ITerm2 => format!(
+                "\x1b]6;1;bg;red;brightness;{r}\x07\
+                 \x1b]6;1;bg;green;brightness;{g}\x07\
+                 \x1b]6;1;bg;blue;brightness;{b}\x07"
+            ),
+        }
+    }
+}
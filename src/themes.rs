@@ -0,0 +1,514 @@
+//! Palettes of terminal themes, for [`Palette::Named`].
+//!
+//! Themes such as Solarized redefine the first 16 xterm colors, and base16
+//! themes also the next 6, so what a terminal shows for `\x1b[38;5;3m` is not
+//! the xterm color 3. A [`NamedPalette`] holds the colors a theme shows
+//! instead, with the index that shows each one and its name in the theme,
+//! such as `yellow` or `base0A`.
+//!
+//! The color a theme uses as the background is left out, since text in it
+//! cannot be read.
+//!
+//! Base16 colors are placed as by [base16-shell]: `base08` to `base0E` at the
+//! 8 standard ANSI colors and their bright variants, and the others from 16 to
+//! 21.
+//!
+//! Palettes can also be read from a file, in JSON or YAML, see
+//! [`NamedPalette::from_reader`].
+//!
+//! [base16-shell]: https://github.com/chriskempson/base16-shell
+//! [`Palette::Named`]: crate::Palette::Named
+
+use std::{io::Read, path::Path};
+
+use color_art::Color;
+use once_cell::sync::Lazy;
+use serde_yaml::Value;
+
+use crate::{Error, TermColor};
+
+/// The colors of a terminal theme, see the [module docs](self).
+#[derive(Debug, PartialEq)]
+pub struct NamedPalette {
+    name: String,
+    /// the index in escape sequences of each color
+    indices: Vec<u8>,
+    labels: Vec<String>,
+    colors: Vec<Color>,
+}
+
+// no color has a NaN component
+impl Eq for NamedPalette {}
+
+impl NamedPalette {
+    /// A palette of `colors`, each with the index in escape sequences that
+    /// shows it and its name.
+    pub fn new(
+        name: impl Into<String>,
+        colors: impl IntoIterator<Item = (u8, String, Color)>,
+    ) -> Self {
+        let mut palette = NamedPalette {
+            name: name.into(),
+            indices: Vec::new(),
+            labels: Vec::new(),
+            colors: Vec::new(),
+        };
+        for (index, label, color) in colors {
+            palette.indices.push(index);
+            palette.labels.push(label);
+            palette.colors.push(color);
+        }
+        palette
+    }
+
+    /// Solarized, by Ethan Schoonover, without its dark background `base03`.
+    pub fn solarized_dark() -> &'static NamedPalette {
+        static PALETTE: Lazy<NamedPalette> = Lazy::new(|| solarized("solarized-dark", "base03"));
+        &PALETTE
+    }
+
+    /// Solarized, by Ethan Schoonover, without its light background `base3`.
+    pub fn solarized_light() -> &'static NamedPalette {
+        static PALETTE: Lazy<NamedPalette> = Lazy::new(|| solarized("solarized-light", "base3"));
+        &PALETTE
+    }
+
+    /// A built-in base16 scheme by its name in [`BASE16_SCHEMES`], such as
+    /// `ocean`.
+    pub fn base16(name: &str) -> Option<&'static NamedPalette> {
+        static PALETTES: Lazy<Vec<NamedPalette>> = Lazy::new(|| {
+            let palettes = BASE16_SCHEMES.iter().map(|(name, colors)| {
+                let colors = colors.map(|hex| {
+                    let [_, r, g, b] = hex.to_be_bytes();
+                    Color::new(r, g, b, 1.0)
+                });
+                base16(&format!("base16:{name}"), colors)
+            });
+            palettes.collect()
+        });
+        let index = BASE16_SCHEMES
+            .iter()
+            .position(|(other, _)| *other == name)?;
+        Some(&PALETTES[index])
+    }
+
+    /// A base16 scheme from its YAML file, which has `base00` to `base0F` as
+    /// hex codes, at the top level or under `palette`, and its name as
+    /// `scheme` or `name`.
+    pub fn from_base16_yaml(yaml: &str) -> Result<NamedPalette, Error> {
+        let scheme: serde_yaml::Value = serde_yaml::from_str(yaml)?;
+        let colors = match scheme.get("palette") {
+            Some(palette) if palette.is_mapping() => palette,
+            _ => &scheme,
+        };
+        let mut parsed = [Color::new(0, 0, 0, 1.0); 16];
+        for (color, key) in parsed.iter_mut().zip(BASE16_KEYS) {
+            let Some(hex) = colors.get(key).and_then(|hex| hex.as_str()) else {
+                let missing = <serde_yaml::Error as serde::de::Error>::missing_field(key);
+                return Err(Error::Parse(missing));
+            };
+            *color = hex.parse::<TermColor>()?.color();
+        }
+        let name = ["scheme", "name"]
+            .iter()
+            .find_map(|key| scheme.get(key).and_then(|name| name.as_str()))
+            .unwrap_or("base16");
+        Ok(base16(&format!("base16:{name}"), parsed))
+    }
+
+    /// A palette from a file, named `custom`, in one of two forms. A map from
+    /// names to hex codes, such as
+    ///
+    /// ```text
+    /// {"red": "#dc322f", "green": "#859900"}
+    /// ```
+    ///
+    /// or a list of hex codes, or of entries with a `hex` code, and a `name`
+    /// and an `index` if wanted:
+    ///
+    /// ```text
+    /// - "#dc322f"
+    /// - { name: green, hex: "#859900", index: 2 }
+    /// ```
+    ///
+    /// Each color is shown with its `index` in escape sequences, or its name
+    /// if that is a number up to 255, such as `"1"`, or else the lowest index
+    /// that no other color is shown with. Colors without a name are named by
+    /// that index. There must be from 2 to 256 colors, each shown with an
+    /// index of its own.
+    pub fn from_reader(reader: impl Read, format: PaletteFormat) -> Result<NamedPalette, Error> {
+        let value: Value = match format {
+            PaletteFormat::Json => serde_json::from_reader(reader)?,
+            PaletteFormat::Yaml => serde_yaml::from_reader(reader)?,
+        };
+        let entries = match value {
+            Value::Mapping(map) => map
+                .into_iter()
+                .enumerate()
+                .map(|(position, (name, hex))| {
+                    let Some(name) = scalar(&name) else {
+                        return Err(invalid(format!(
+                            "entry {} has a name that is not text",
+                            position + 1
+                        )));
+                    };
+                    let entry = format!("entry {name:?}");
+                    let index = name.parse::<u8>().ok();
+                    palette_entry(entry, index, Some(name), &hex)
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Value::Sequence(list) => list
+                .into_iter()
+                .enumerate()
+                .map(|(position, item)| {
+                    let entry = format!("entry {}", position + 1);
+                    let Value::Mapping(_) = item else {
+                        return palette_entry(entry, None, None, &item);
+                    };
+                    let name = item.get("name").and_then(scalar);
+                    let index = match item.get("index") {
+                        None => name.as_deref().and_then(|name| name.parse::<u8>().ok()),
+                        Some(index) => match index.as_u64().and_then(|i| u8::try_from(i).ok()) {
+                            Some(index) => Some(index),
+                            None => {
+                                return Err(invalid(format!(
+                                    "{entry} has index {}, expected a number from 0 to 255",
+                                    scalar(index).unwrap_or_default()
+                                )))
+                            }
+                        },
+                    };
+                    let hex = item.get("hex").unwrap_or(&Value::Null);
+                    palette_entry(entry, index, name, hex)
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => {
+                return Err(invalid(
+                    "expected a map from names to hex codes, or a list of hex codes".into(),
+                ))
+            }
+        };
+        if entries.len() > 256 {
+            return Err(invalid(format!(
+                "has {} colors, expected at most 256",
+                entries.len()
+            )));
+        }
+        if entries.len() < 2 {
+            let count = match entries.len() {
+                1 => "1 color".to_string(),
+                count => format!("{count} colors"),
+            };
+            return Err(invalid(format!("has {count}, expected at least 2")));
+        }
+        let mut shown_by: [Option<&str>; 256] = [None; 256];
+        for (entry, index, ..) in &entries {
+            let Some(index) = *index else { continue };
+            if let Some(other) = shown_by[usize::from(index)].replace(entry) {
+                return Err(invalid(format!(
+                    "{other} and {entry} are both shown with index {index}"
+                )));
+            }
+        }
+        let mut free = (0..=u8::MAX).filter(|&i| shown_by[usize::from(i)].is_none());
+        let colors = entries.iter().map(|(_, index, name, color)| {
+            // there are as many free indices as colors without one
+            let index = index.unwrap_or_else(|| free.next().unwrap_or_default());
+            let label = name.clone().unwrap_or_else(|| index.to_string());
+            (index, label, *color)
+        });
+        Ok(NamedPalette::new("custom", colors))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The colors, in the same order as [`NamedPalette::indices`].
+    pub fn colors(&self) -> &[Color] {
+        &self.colors
+    }
+
+    /// The index in escape sequences of each color.
+    pub fn indices(&self) -> &[u8] {
+        &self.indices
+    }
+
+    /// The name of the color shown at `index`, such as `base0A`.
+    pub fn label(&self, index: u8) -> Option<&str> {
+        let position = self.indices.iter().position(|&other| other == index)?;
+        Some(&self.labels[position])
+    }
+
+    /// The length of the longest name, for aligning them.
+    pub fn label_width(&self) -> usize {
+        self.labels
+            .iter()
+            .map(|label| label.len())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Solarized, with the index of each color in its terminal themes.
+const SOLARIZED: [(u8, &str, u32); 16] = [
+    (8, "base03", 0x002b36),
+    (0, "base02", 0x073642),
+    (10, "base01", 0x586e75),
+    (11, "base00", 0x657b83),
+    (12, "base0", 0x839496),
+    (14, "base1", 0x93a1a1),
+    (7, "base2", 0xeee8d5),
+    (15, "base3", 0xfdf6e3),
+    (3, "yellow", 0xb58900),
+    (9, "orange", 0xcb4b16),
+    (1, "red", 0xdc322f),
+    (5, "magenta", 0xd33682),
+    (13, "violet", 0x6c71c4),
+    (4, "blue", 0x268bd2),
+    (6, "cyan", 0x2aa198),
+    (2, "green", 0x859900),
+];
+
+fn solarized(name: &str, background: &str) -> NamedPalette {
+    let colors = SOLARIZED
+        .iter()
+        .filter(|(_, label, _)| *label != background)
+        .map(|&(index, label, hex)| {
+            let [_, r, g, b] = hex.to_be_bytes();
+            (index, label.to_string(), Color::new(r, g, b, 1.0))
+        });
+    NamedPalette::new(name, colors)
+}
+
+/// The format of a palette file, see [`NamedPalette::from_reader`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteFormat {
+    Json,
+    Yaml,
+}
+
+impl PaletteFormat {
+    /// JSON for paths ending in `.json`, YAML for the others.
+    pub fn of_path(path: &Path) -> PaletteFormat {
+        match path.extension() {
+            Some(extension) if extension.eq_ignore_ascii_case("json") => PaletteFormat::Json,
+            _ => PaletteFormat::Yaml,
+        }
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::Palette(message)
+}
+
+/// A string or a number as text, as names and hex codes may be written in
+/// YAML.
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+/// `entry`, with its index in escape sequences and its name if given, and
+/// its color.
+type PaletteEntry = (String, Option<u8>, Option<String>, Color);
+
+fn palette_entry(
+    entry: String,
+    index: Option<u8>,
+    name: Option<String>,
+    hex: &Value,
+) -> Result<PaletteEntry, Error> {
+    let color = match hex {
+        Value::Null => {
+            return Err(invalid(format!(
+                "{entry} has no hex code; in YAML, quote it, as in \"#dc322f\""
+            )))
+        }
+        hex => scalar(hex).and_then(|hex| crate::parse_hex_color(&hex).ok()),
+    };
+    let Some(color) = color else {
+        let value = scalar(hex).unwrap_or_else(|| "a list or map".into());
+        return Err(invalid(format!(
+            "{entry} has color {value:?}, expected #rrggbb or #rgb"
+        )));
+    };
+    Ok((entry, index, name, color))
+}
+
+const BASE16_KEYS: [&str; 16] = [
+    "base00", "base01", "base02", "base03", "base04", "base05", "base06", "base07", "base08",
+    "base09", "base0A", "base0B", "base0C", "base0D", "base0E", "base0F",
+];
+
+/// Where base16-shell puts `base00` to `base0F`.
+const BASE16_INDICES: [u8; 16] = [0, 18, 19, 8, 20, 7, 21, 15, 1, 16, 3, 2, 6, 4, 5, 17];
+
+/// Built-in base16 schemes, `base00` to `base0F`.
+pub const BASE16_SCHEMES: &[(&str, [u32; 16])] = &[
+    (
+        "default-dark",
+        [
+            0x181818, 0x282828, 0x383838, 0x585858, 0xb8b8b8, 0xd8d8d8, 0xe8e8e8, 0xf8f8f8,
+            0xab4642, 0xdc9656, 0xf7ca88, 0xa1b56c, 0x86c1b9, 0x7cafc2, 0xba8baf, 0xa16946,
+        ],
+    ),
+    (
+        "default-light",
+        [
+            0xf8f8f8, 0xe8e8e8, 0xd8d8d8, 0xb8b8b8, 0x585858, 0x383838, 0x282828, 0x181818,
+            0xab4642, 0xdc9656, 0xf7ca88, 0xa1b56c, 0x86c1b9, 0x7cafc2, 0xba8baf, 0xa16946,
+        ],
+    ),
+    (
+        "eighties",
+        [
+            0x2d2d2d, 0x393939, 0x515151, 0x747369, 0xa09f93, 0xd3d0c8, 0xe8e6df, 0xf2f0ec,
+            0xf2777a, 0xf99157, 0xffcc66, 0x99cc99, 0x66cccc, 0x6699cc, 0xcc99cc, 0xd27b53,
+        ],
+    ),
+    (
+        "monokai",
+        [
+            0x272822, 0x383830, 0x49483e, 0x75715e, 0xa59f85, 0xf8f8f2, 0xf5f4f1, 0xf9f8f5,
+            0xf92672, 0xfd971f, 0xf4bf75, 0xa6e22e, 0xa1efe4, 0x66d9ef, 0xae81ff, 0xcc6633,
+        ],
+    ),
+    (
+        "ocean",
+        [
+            0x2b303b, 0x343d46, 0x4f5b66, 0x65737e, 0xa7adba, 0xc0c5ce, 0xdfe1e8, 0xeff1f5,
+            0xbf616a, 0xd08770, 0xebcb8b, 0xa3be8c, 0x96b5b4, 0x8fa1b3, 0xb48ead, 0xab7967,
+        ],
+    ),
+    (
+        "tomorrow-night",
+        [
+            0x1d1f21, 0x282a2e, 0x373b41, 0x969896, 0xb4b7b4, 0xc5c8c6, 0xe0e0e0, 0xffffff,
+            0xcc6666, 0xde935f, 0xf0c674, 0xb5bd68, 0x8abeb7, 0x81a2be, 0xb294bb, 0xa3685a,
+        ],
+    ),
+];
+
+/// A base16 scheme without its background, `base00`.
+fn base16(name: &str, colors: [Color; 16]) -> NamedPalette {
+    let colors = (1..16).map(|i| (BASE16_INDICES[i], BASE16_KEYS[i].to_string(), colors[i]));
+    NamedPalette::new(name, colors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(file: &str, format: PaletteFormat) -> Result<NamedPalette, Error> {
+        NamedPalette::from_reader(file.as_bytes(), format)
+    }
+
+    /// `(index, name, hex)` of each color, in the order of the file.
+    fn entries(palette: &NamedPalette) -> Vec<(u8, &str, String)> {
+        (palette.indices().iter().zip(palette.colors()))
+            .map(|(&index, color)| {
+                (
+                    index,
+                    palette.label(index).unwrap(),
+                    TermColor::from(*color).hex(),
+                )
+            })
+            .collect()
+    }
+
+    /// Names that are numbers are the indices of their colors, and the others
+    /// are shown with the lowest indices left.
+    #[test]
+    fn map() {
+        let file = include_str!("../tests/fixtures/palette-map.json");
+        let palette = read(file, PaletteFormat::Json).unwrap();
+        assert_eq!(palette.name(), "custom");
+        assert_eq!(
+            entries(&palette),
+            [
+                (0, "red", "#dc322f".into()),
+                (1, "green", "#859900".into()),
+                (3, "3", "#b58900".into()),
+                (2, "blue", "#268bd2".into()),
+            ]
+        );
+        // the same map in YAML
+        assert_eq!(read(file, PaletteFormat::Yaml).unwrap(), palette);
+    }
+
+    /// Entries of a list are hex codes, or have one with a name and an index
+    /// if wanted, and those without a name are named by their index.
+    #[test]
+    fn list() {
+        let file = include_str!("../tests/fixtures/palette-list.yml");
+        let palette = read(file, PaletteFormat::Yaml).unwrap();
+        assert_eq!(
+            entries(&palette),
+            [
+                (0, "0", "#dc322f".into()),
+                (1, "green", "#859900".into()),
+                (11, "yellow", "#b58900".into()),
+                (4, "4", "#268bd2".into()),
+            ]
+        );
+        assert_eq!(palette.label_width(), 6);
+        let json = r##"["#dc322f", {"name": "green", "hex": "#859900", "index": 9}]"##;
+        let palette = read(json, PaletteFormat::Json).unwrap();
+        assert_eq!(
+            entries(&palette),
+            [(0, "0", "#dc322f".into()), (9, "green", "#859900".into())]
+        );
+    }
+
+    /// Malformed palettes fail with [`Error::Palette`] naming the entry that
+    /// is wrong, and files that are not JSON or YAML with the parser's error.
+    #[test]
+    fn malformed() {
+        let message = |file: &str, format| match read(file, format) {
+            Err(Error::Palette(message)) => message,
+            other => panic!("{file}: {other:?}"),
+        };
+        let file = include_str!("../tests/fixtures/palette-malformed.yml");
+        assert_eq!(
+            message(file, PaletteFormat::Yaml),
+            "entry 2 has color \"#85990\", expected #rrggbb or #rgb"
+        );
+        for (file, expected) in [
+            (
+                r##"{"red": "#dc322f"}"##,
+                "has 1 color, expected at least 2",
+            ),
+            ("[]", "has 0 colors, expected at least 2"),
+            (
+                r##"{"red": "#dc322f", "green": null}"##,
+                "entry \"green\" has no hex code; in YAML, quote it, as in \"#dc322f\"",
+            ),
+            (
+                r##"[{"hex": "#dc322f", "index": 256}, "#859900"]"##,
+                "entry 1 has index 256, expected a number from 0 to 255",
+            ),
+            (
+                r##"{"1": "#dc322f", "x": "#859900", "y": {"name": "1", "hex": "#b58900"}}"##,
+                "entry \"y\" has color \"a list or map\", expected #rrggbb or #rgb",
+            ),
+            (
+                r##"[{"hex": "#dc322f", "index": 1}, {"name": "1", "hex": "#859900"}]"##,
+                "entry 1 and entry 2 are both shown with index 1",
+            ),
+            (
+                r##""#dc322f""##,
+                "expected a map from names to hex codes, or a list of hex codes",
+            ),
+        ] {
+            assert_eq!(message(file, PaletteFormat::Json), expected, "{file}");
+        }
+        let json = read("[\"#dc322f\",", PaletteFormat::Json);
+        assert!(matches!(json, Err(Error::Json(_))), "{json:?}");
+        let yaml = read("- \"#dc322f\n", PaletteFormat::Yaml);
+        assert!(matches!(yaml, Err(Error::Parse(_))), "{yaml:?}");
+    }
+}
@@ -0,0 +1,69 @@
+mod support;
+
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+
+use support::fixture;
+
+/// `linguist-termcolor -q` on the bundled `languages.yml`, as in
+/// [`support::cli`], but spawned with its stdout piped to this test.
+fn spawn(args: &[&str]) -> std::process::Child {
+    Command::new(assert_cmd::cargo::cargo_bin("linguist-termcolor"))
+        .env("XDG_CONFIG_HOME", fixture("no-config"))
+        .env("TERM", "xterm-256color")
+        .env_remove("COLORTERM")
+        .env_remove("NO_COLOR")
+        .env_remove("CLICOLOR_FORCE")
+        .env_remove("LINGUIST_TERMCOLOR_REF")
+        .arg("-q")
+        .arg("--languages-file")
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/data/languages.yml"))
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap()
+}
+
+/// Waits for `child` and checks that it exited 0 without a panic, after its
+/// reader went away.
+fn ends_cleanly(child: std::process::Child, args: &[&str]) {
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("panicked"), "{args:?} panicked: {stderr}");
+    assert!(
+        output.status.success(),
+        "{args:?}: {:?} {stderr}",
+        output.status
+    );
+}
+
+/// A reader that is gone before anything is written, as in
+/// `linguist-termcolor list | true`.
+#[test]
+fn closed_reader() {
+    for args in [&["list"][..], &["export", "json"], &["for", "rust"]] {
+        let mut child = spawn(args);
+        drop(child.stdout.take());
+        ends_cleanly(child, args);
+    }
+}
+
+/// A reader that stops after the first lines, as in `... | head -n 2`. The
+/// HTML export is larger than a pipe buffer, so its writes do fail.
+#[test]
+fn reader_closes_early() {
+    for args in [
+        &["list"][..],
+        &["--color", "always", "list"],
+        &["export", "html"],
+    ] {
+        let mut child = spawn(args);
+        let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+        for _ in 0..2 {
+            assert!(lines.next().unwrap().is_ok(), "{args:?} printed nothing");
+        }
+        drop(lines);
+        ends_cleanly(child, args);
+    }
+}
@@ -0,0 +1,33 @@
+mod support;
+
+use serde_json::Value;
+use support::{cli, stdout};
+
+/// Every space `capabilities` lists is accepted by `-c` by each of its names,
+/// and every unsupported one fails, pointing to the closest.
+#[test]
+fn lists_what_c_accepts() {
+    let json = stdout(cli().args(["--format", "json", "capabilities"]));
+    let json = serde_json::from_str::<Value>(&json).unwrap();
+    let text = stdout(cli().arg("capabilities"));
+
+    let spaces = json["spaces"].as_array().unwrap();
+    assert!(spaces.len() >= 16, "{spaces:?}");
+    for space in spaces {
+        let name = space["name"].as_str().unwrap();
+        assert!(text.lines().any(|line| line.starts_with(name)), "{name}");
+        let aliases = space["aliases"].as_array().unwrap().iter();
+        for alias in std::iter::once(name).chain(aliases.map(|alias| alias.as_str().unwrap())) {
+            let output = cli().args(["-c", alias, "for", "--exact", "rust"]).output();
+            assert!(output.unwrap().status.success(), "-c {alias}");
+        }
+    }
+
+    for space in json["unsupported"].as_array().unwrap() {
+        let (name, closest) = (space["name"].as_str().unwrap(), space["closest"].as_str());
+        let output = cli().args(["-c", name, "for", "rust"]).output().unwrap();
+        assert!(!output.status.success(), "-c {name}");
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains(closest.unwrap()), "-c {name}: {stderr}");
+    }
+}
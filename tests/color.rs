@@ -0,0 +1,81 @@
+mod support;
+
+use support::{cli, sgr_runs, stdout, truecolor, xterm256};
+
+/// `--no-color` prints what `--color never` does, even where colors are
+/// forced otherwise.
+#[test]
+fn no_color() {
+    let never = stdout(cli().args(["--color", "never", "for", "rust"]));
+    assert!(!never.contains('\x1b'), "{never:?}");
+    let forced = |args: &[&str]| stdout(cli().env("CLICOLOR_FORCE", "1").args(args));
+    assert_eq!(forced(&["--no-color", "for", "rust"]), never);
+    assert!(forced(&["for", "rust"]).contains('\x1b'));
+    cli()
+        .args(["--no-color", "--color", "always", "for", "rust"])
+        .assert()
+        .code(1);
+}
+
+/// `for --exact python` with `args` where `TERM` is `term`.
+fn python(term: &str, args: &[&str]) -> String {
+    stdout(
+        cli()
+            .env("TERM", term)
+            .args(args)
+            .args(["for", "--exact", "python"]),
+    )
+}
+
+/// Colors asked for with `--color always` or `CLICOLOR_FORCE` are shown in
+/// 24-bit, whatever `TERM` says the terminal shows.
+#[test]
+fn forced_colors_ignore_term() {
+    let always = python("xterm-256color", &["--color", "always"]);
+    let runs = sgr_runs(&always);
+    assert_eq!(truecolor(&runs[0].params, false), Some([0x35, 0x72, 0xa5]));
+    for term in ["dumb", "xterm", "vt100"] {
+        assert_eq!(python(term, &["--color", "always"]), always, "TERM={term}");
+        let forced = stdout(
+            cli()
+                .env("TERM", term)
+                .env("CLICOLOR_FORCE", "1")
+                .args(["for", "--exact", "python"]),
+        );
+        assert_eq!(forced, always, "TERM={term} CLICOLOR_FORCE=1");
+    }
+}
+
+/// `--color-depth` sets how colors are shown, and never which xterm color is
+/// printed.
+#[test]
+fn depth_keeps_the_palette() {
+    let depth = |depth: &str| python("dumb", &["--color", "always", "--color-depth", depth]);
+
+    let xterm = depth("8bit");
+    assert!(
+        xterm.contains("rgb #3572a5") && xterm.contains("xterm 61"),
+        "{xterm:?}"
+    );
+    let runs = sgr_runs(&xterm);
+    assert_eq!(xterm256(&runs[0].params, false), Some(61));
+
+    let ansi = depth("4bit");
+    assert!(
+        ansi.contains("rgb #3572a5") && ansi.contains("xterm 61"),
+        "{ansi:?}"
+    );
+    for run in sgr_runs(&ansi) {
+        assert!(
+            run.params[1..]
+                .iter()
+                .all(|&p| matches!(p, 30..=37 | 90..=97)),
+            "{run:?}"
+        );
+    }
+
+    let none = depth("none");
+    assert!(!none.contains('\x1b'), "{none:?}");
+    let never = python("xterm-256color", &["--color", "never"]);
+    assert_eq!(none, never);
+}
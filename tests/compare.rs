@@ -0,0 +1,18 @@
+mod support;
+
+use support::{cli, golden, stdout};
+
+/// The nearest xterm colors of a color and of a language in every space, in
+/// columns as wide as the longest name of a space.
+#[test]
+fn every_space() {
+    let text = stdout(cli().args(["compare", "#555555", "rust"]));
+    golden("compare.txt", &text);
+    let columns = text
+        .lines()
+        .filter(|line| line.starts_with("  "))
+        .map(|line| line.find('█').unwrap())
+        .collect::<Vec<_>>();
+    assert!(columns.len() > 30);
+    assert!(columns.iter().all(|&column| column == columns[0]), "{text}");
+}
@@ -0,0 +1,33 @@
+mod support;
+
+use support::{cli, stdout};
+
+/// Every shell gets a script registering its completions for every command,
+/// and zsh and fish complete language names by listing them.
+#[test]
+fn every_shell() {
+    for (shell, registers) in [
+        ("bash", "complete -F _linguist_termcolor"),
+        ("zsh", "#compdef linguist-termcolor"),
+        ("fish", "complete -c linguist-termcolor"),
+        (
+            "powershell",
+            "Register-ArgumentCompleter -Native -CommandName 'linguist-termcolor'",
+        ),
+        (
+            "elvish",
+            "set edit:completion:arg-completer[linguist-termcolor]",
+        ),
+    ] {
+        let script = stdout(cli().args(["completions", shell]));
+        assert!(script.contains(registers), "{shell}");
+        for command in ["for", "xterm", "export", "completions", "capabilities"] {
+            assert!(script.contains(command), "{shell}: {command}");
+        }
+        for option in ["colors", "palette", "format"] {
+            assert!(script.contains(option), "{shell}: {option}");
+        }
+        let names = script.contains("list --names-only");
+        assert_eq!(names, matches!(shell, "zsh" | "fish"), "{shell}");
+    }
+}
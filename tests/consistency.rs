@@ -0,0 +1,227 @@
+mod support;
+
+use support::{cli, stdout};
+
+/// Options that change which color is shown or which xterm color is nearest.
+const OPTIONS: &[&[&str]] = &[
+    &[],
+    &["--palette", "ansi16"],
+    &["--skip-grayscale"],
+    &["--skip-system"],
+    &["--lighten", "20"],
+    &["--darken", "20"],
+    &["--saturate", "30"],
+    &["--min-contrast", "7", "--contrast-bg", "#000000"],
+    &["-c", "lab"],
+    &["-c", "de2000"],
+    &["-c", "hsl-weighted"],
+    &["--simulate", "deuteranopia"],
+    &["--palette", "ansi16", "--lighten", "20", "-c", "lab"],
+];
+
+/// The hex and xterm index shown in one format for one query.
+type Shown = (String, u8);
+
+fn run(options: &[&str], format: &str, command: &[&str]) -> String {
+    stdout(cli().args(options).args(["--format", format]).args(command))
+}
+
+/// `rgb #rrggbb xterm N` or `rgb #rrggbb ansi N`, after any arrow from the
+/// original color, and before any simulated color.
+fn from_text(text: &str) -> Shown {
+    let (before, after) = [" xterm ", " ansi "]
+        .iter()
+        .filter_map(|label| text.split_once(label))
+        .min_by_key(|(before, _)| before.len())
+        .unwrap_or_else(|| panic!("no xterm color in {text:?}"));
+    let hex = before.rsplit("rgb ").next().unwrap().trim();
+    let index = after.split_whitespace().next().unwrap();
+    (hex.to_string(), index.parse().unwrap())
+}
+
+fn from_json(json: &str) -> Shown {
+    let value = |key: &str| {
+        let (_, rest) = json.split_once(&format!("\"{key}\":"))?;
+        let end = rest.find([',', '}']).unwrap();
+        Some(rest[..end].trim_matches('"').to_string())
+    };
+    let hex = value("shown_hex").or_else(|| value("hex")).unwrap();
+    (hex, value("index").unwrap().parse().unwrap())
+}
+
+/// The last row of a table: `| Language | Hex | Color | xterm |`.
+fn from_markdown(markdown: &str) -> Shown {
+    let row = markdown.lines().last().unwrap();
+    let cells = row.split('|').map(str::trim).collect::<Vec<_>>();
+    (
+        cells[2].trim_matches('`').to_string(),
+        cells[4].parse().unwrap(),
+    )
+}
+
+/// The hex of `\x1b[38;2;R;G;Bm`.
+fn from_escape(escape: &str) -> String {
+    let sgr = escape.trim().strip_prefix("\x1b[38;2;").unwrap();
+    let rgb = sgr.strip_suffix('m').unwrap().split(';');
+    let rgb = rgb.map(|c| c.parse::<u8>().unwrap()).collect::<Vec<_>>();
+    format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+}
+
+/// The index of `\x1b[38;5;Nm`.
+fn from_escape_256(escape: &str) -> u8 {
+    let sgr = escape.trim().strip_prefix("\x1b[38;5;").unwrap();
+    sgr.strip_suffix('m').unwrap().parse().unwrap()
+}
+
+/// `for` shows the same color and xterm index in every format, whatever
+/// the options.
+#[test]
+fn for_agrees_across_formats() {
+    for options in OPTIONS {
+        for language in ["python", "nix", "c++"] {
+            let command = ["for", "--exact", language];
+            let text = from_text(&run(options, "text", &command));
+            let context = format!("{options:?} for {language}");
+            assert_eq!(
+                from_json(&run(options, "json", &command)),
+                text,
+                "{context} json"
+            );
+            assert_eq!(
+                from_markdown(&run(options, "markdown", &command)),
+                text,
+                "{context} markdown"
+            );
+            assert_eq!(
+                from_escape(&run(options, "escape", &command)),
+                text.0,
+                "{context} escape"
+            );
+            assert_eq!(
+                from_escape_256(&run(options, "escape-256", &command)),
+                text.1,
+                "{context} escape-256"
+            );
+            let only = |value: &str| {
+                let only = ["for", "--exact", "--only", value, language];
+                run(options, "text", &only).trim().to_string()
+            };
+            assert_eq!(only("hex"), text.0, "{context} --only hex");
+            assert_eq!(only("xterm"), text.1.to_string(), "{context} --only xterm");
+        }
+    }
+}
+
+/// `xterm` with a color shows the same color and xterm index in every
+/// format, whatever the options.
+#[test]
+fn xterm_agrees_across_formats() {
+    for options in OPTIONS {
+        for color in ["#555555", "#3572a5", "#dea584"] {
+            let command = ["xterm", color];
+            let text = from_text(&run(options, "text", &command));
+            let context = format!("{options:?} xterm {color}");
+            assert_eq!(
+                from_json(&run(options, "json", &command)),
+                text,
+                "{context} json"
+            );
+            assert_eq!(
+                from_escape(&run(options, "escape", &command)),
+                text.0,
+                "{context} escape"
+            );
+            assert_eq!(
+                from_escape_256(&run(options, "escape-256", &command)),
+                text.1,
+                "{context} escape-256"
+            );
+        }
+    }
+}
+
+/// Languages and their colors in the bundled `languages.yml`.
+const LANGUAGES: &[(&str, &str)] = &[
+    ("rust", "#dea584"),
+    ("python", "#3572a5"),
+    ("c", "#555555"),
+    ("javascript", "#f1e05a"),
+    ("go", "#00add8"),
+    ("ruby", "#701516"),
+];
+
+/// `xterm` with the color of a language finds the same xterm color as `for`
+/// does for the language, and `--languages` names a language shown in that
+/// color, for every palette and combination of `--skip-*`.
+#[test]
+fn xterm_agrees_with_for() {
+    let palettes = [
+        &[][..],
+        &["--palette", "ansi16"],
+        &["--palette", "solarized-dark"],
+    ];
+    let skips = [
+        &[][..],
+        &["--skip-system"],
+        &["--skip-grayscale"],
+        &["--skip-system", "--skip-grayscale"],
+    ];
+    for palette in palettes {
+        for skip in skips {
+            let options = [palette, skip].concat();
+            let run = |command: &[&str]| {
+                let mut cmd = cli();
+                cmd.args(&options).args(["--format", "json"]).args(command);
+                let output = cmd.output().unwrap();
+                output
+                    .status
+                    .success()
+                    .then(|| String::from_utf8(output.stdout).unwrap())
+            };
+            for (language, color) in LANGUAGES {
+                let context = format!("{options:?} {language}");
+                let Some(found) = run(&["for", "--exact", language]) else {
+                    // e.g. --skip-system leaves no ANSI colors
+                    assert!(run(&["xterm", color]).is_none(), "{context}");
+                    continue;
+                };
+                let xterm = run(&["xterm", "--languages", color]).unwrap();
+                let index = from_json(&found).1;
+                assert_eq!(from_json(&xterm).1, index, "{context}");
+
+                let (_, named) = xterm.split_once("\"language\":\"").unwrap();
+                let named = named.split('"').next().unwrap();
+                let named = run(&["for", "--exact", named]).unwrap();
+                assert_eq!(from_json(&named).1, index, "{context} --languages");
+            }
+        }
+    }
+}
+
+/// `--format json` names the palette that `index` and the candidates are in,
+/// which are ANSI indices with `--palette ansi16`.
+#[test]
+fn json_names_the_palette() {
+    let palettes = [
+        (&[][..], "xterm256"),
+        (&["--skip-grayscale"][..], "xterm256"),
+        (&["--palette", "ansi16"][..], "ansi16"),
+        (&["--palette", "solarized-dark"][..], "solarized-dark"),
+    ];
+    for (options, name) in palettes {
+        let command = ["for", "--exact", "--candidates", "3", "python"];
+        let json = run(options, "json", &command);
+        let json = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+        let color = &json[0];
+        assert_eq!(color["palette"], name, "{options:?}");
+        assert!(color.get("xterm").is_none(), "{options:?}");
+        if name != "solarized-dark" {
+            let text = from_text(&run(options, "text", &["for", "--exact", "python"]));
+            assert_eq!(color["index"], u64::from(text.1), "{options:?}");
+        }
+        if name == "ansi16" {
+            let candidates = color["candidates"].as_array().unwrap();
+            assert!(candidates.iter().all(|c| c["index"].as_u64().unwrap() < 16));
+        }
+    }
+}
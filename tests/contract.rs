@@ -0,0 +1,88 @@
+//! The first result of `for` is the best match, and scripts rely on it by
+//! reading the first line or the result of `"rank": 0`. These are the best
+//! matches of common queries in the bundled `languages.yml`; a change to the
+//! ranking that moves one of them should be deliberate.
+
+mod support;
+
+use serde_json::Value;
+use support::{cli, stdout};
+
+const BEST: &[(&str, &str)] = &[
+    ("rust", "Rust"),
+    ("rs", "Rust"),
+    ("py", "Python"),
+    ("python", "Python"),
+    ("js", "JavaScript"),
+    ("javascript", "JavaScript"),
+    ("ts", "TypeScript"),
+    ("typescript", "TypeScript"),
+    ("c", "C"),
+    ("h", "C"),
+    ("cpp", "C++"),
+    ("c++", "C++"),
+    ("cc", "C++"),
+    ("hpp", "C++"),
+    ("cs", "C#"),
+    ("c#", "C#"),
+    ("golang", "Go"),
+    ("go", "Go"),
+    ("shell", "Shell"),
+    ("sh", "Shell"),
+    ("bash", "Shell"),
+    ("zsh", "Shell"),
+    ("yml", "YAML"),
+    ("yaml", "YAML"),
+    ("md", "Markdown"),
+    ("markdown", "Markdown"),
+    ("dockerfile", "Dockerfile"),
+    ("Makefile", "Makefile"),
+    ("json", "JSON"),
+    ("toml", "TOML"),
+    ("html", "HTML"),
+    ("css", "CSS"),
+    ("scss", "SCSS"),
+    ("java", "Java"),
+    ("kt", "Kotlin"),
+    ("kotlin", "Kotlin"),
+    ("rb", "Ruby"),
+    ("php", "PHP"),
+    ("swift", "Swift"),
+    ("lua", "Lua"),
+    ("sql", "SQL"),
+    ("vim", "Vim script"),
+    ("hs", "Haskell"),
+    ("ex", "Elixir"),
+    ("erl", "Erlang"),
+];
+
+#[test]
+fn first_result_is_the_best_match() {
+    for &(query, language) in BEST {
+        let json = stdout(cli().args(["--format", "json", "for", query]));
+        let json = serde_json::from_str::<Value>(&json).unwrap();
+        let first = &json[0];
+        assert_eq!(first["language"], language, "{query}");
+        assert_eq!(first["rank"], 0, "{query}");
+
+        let text = stdout(cli().args(["for", query]));
+        // `rgb #e34c26 xterm 166 HTML (+1 in group)`
+        let line = text.lines().next().unwrap();
+        let (_, after) = line.split_once(" xterm ").unwrap();
+        let (_, name) = after.split_once(' ').unwrap();
+        let name = name.trim_start().split(" (").next().unwrap();
+        assert_eq!(name, language, "{query}: {line}");
+    }
+}
+
+/// `rank` counts up from 0 in the order the results are printed.
+#[test]
+fn ranks_are_in_order() {
+    let json = stdout(cli().args(["--format", "json", "for", "ts"]));
+    let json = serde_json::from_str::<Value>(&json).unwrap();
+    let results = json.as_array().unwrap();
+    assert!(results.len() > 1);
+    for (i, result) in results.iter().enumerate() {
+        assert_eq!(result["rank"], i);
+    }
+}
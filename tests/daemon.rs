@@ -0,0 +1,114 @@
+#![cfg(unix)]
+
+mod support;
+
+use std::{
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use support::{bare, cli, cli_on, fixture, stdout};
+
+/// A `daemon` on the bundled `languages.yml`, listening in a runtime
+/// directory of its own, stopped when dropped.
+struct Daemon {
+    child: Child,
+    runtime: PathBuf,
+}
+
+impl Daemon {
+    fn start(name: &str) -> Self {
+        let runtime = std::env::temp_dir().join(format!(
+            "linguist-termcolor-daemon-{}-{name}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&runtime).unwrap();
+        let languages = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/languages.yml");
+        let child = Command::new(assert_cmd::cargo::cargo_bin("linguist-termcolor"))
+            .env("XDG_CONFIG_HOME", fixture("no-config"))
+            .env("XDG_RUNTIME_DIR", &runtime)
+            .arg("-q")
+            .arg("--languages-file")
+            .arg(languages)
+            .arg("daemon")
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let socket = runtime.join("linguist-termcolor.sock");
+        let started = Instant::now();
+        while !socket.exists() {
+            assert!(started.elapsed() < Duration::from_secs(10), "no daemon");
+            thread::sleep(Duration::from_millis(20));
+        }
+        Self { child, runtime }
+    }
+
+    /// `for --format json --via-daemon` with no data flags, which only the
+    /// daemon can answer without the network.
+    fn query(&self, global: &[&std::ffi::OsStr], args: &[&str]) -> assert_cmd::Command {
+        let mut cmd = bare();
+        cmd.env("XDG_RUNTIME_DIR", &self.runtime)
+            .args(["-q", "--format", "json"])
+            .args(global)
+            .args(["for", "--via-daemon"])
+            .args(args);
+        cmd
+    }
+}
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = std::fs::remove_dir_all(&self.runtime);
+    }
+}
+
+/// The daemon answers as `for` would on its own, including `--sort` and
+/// `--limit`.
+#[test]
+fn answers_like_for() {
+    let daemon = Daemon::start("answers");
+    for args in [
+        &["python"][..],
+        &["--each", "rust", "go"],
+        &["--sort", "name", "java"],
+        &["--sort", "hue", "--limit", "2", "script"],
+        &["--each", "--sort", "luminance", "--limit", "1", "java", "c"],
+    ] {
+        let local = stdout(cli().args(["--format", "json", "for"]).args(args));
+        assert_eq!(stdout(&mut daemon.query(&[], args)), local, "{args:?}");
+    }
+    let output = daemon
+        .query(&[], &["--limit", "1", "script"])
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("more"), "{stderr}");
+}
+
+/// With data flags, the query is answered from that data and not the
+/// daemon's.
+#[test]
+fn data_flags_are_local() {
+    let daemon = Daemon::start("local");
+    let languages = fixture("primary-extensions.yml");
+    let local = stdout(cli_on(&languages).args(["--format", "json", "for", "alpha"]));
+    let global = ["--languages-file".as_ref(), languages.as_os_str()];
+    assert_eq!(stdout(&mut daemon.query(&global, &["alpha"])), local);
+}
+
+/// `daemon stop` stops it.
+#[test]
+fn stopped() {
+    let mut daemon = Daemon::start("stop");
+    bare()
+        .env("XDG_RUNTIME_DIR", &daemon.runtime)
+        .args(["daemon", "stop"])
+        .assert()
+        .success();
+    assert!(daemon.child.wait().unwrap().success());
+    assert!(!daemon.runtime.join("linguist-termcolor.sock").exists());
+}
@@ -0,0 +1,40 @@
+mod support;
+
+use support::{cli, stdout};
+
+/// Exports and listings come out byte for byte the same from two processes,
+/// whose hash maps are seeded differently.
+#[test]
+fn same_output_across_processes() {
+    for args in [
+        &["export", "json"][..],
+        &["export", "ls-colors"],
+        &["export", "css"],
+        &["list"],
+        &["info"],
+    ] {
+        let first = stdout(cli().args(args));
+        let second = stdout(cli().args(args));
+        assert!(!first.is_empty(), "{args:?} printed nothing");
+        assert!(first == second, "{args:?} differs between runs");
+    }
+}
+
+/// `export json` lists languages by lowercased name, then by name.
+#[test]
+fn canonical_order() {
+    let json = stdout(cli().args(["export", "json"]));
+    let names = json
+        .split("\"language\":\"")
+        .skip(1)
+        .map(|rest| rest.split('"').next().unwrap().to_string())
+        .collect::<Vec<_>>();
+    let mut sorted = names.clone();
+    sorted.sort_by(|a, b| {
+        a.to_lowercase()
+            .cmp(&b.to_lowercase())
+            .then_with(|| a.cmp(b))
+    });
+    assert_eq!(names, sorted);
+    assert!(names.len() > 500, "only {} languages", names.len());
+}
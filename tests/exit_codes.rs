@@ -0,0 +1,157 @@
+mod support;
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    thread,
+};
+
+use support::{bare, cli, cli_on, fixture};
+
+/// A local server that answers one request with `status` and `body`, and
+/// `linguist-termcolor -q --source` on its URL.
+fn served(status: u16, body: &str) -> assert_cmd::Command {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/languages.yml", listener.local_addr().unwrap());
+    let body = body.to_string();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = BufReader::new(stream.try_clone().unwrap());
+        let mut line = String::new();
+        while request.read_line(&mut line).unwrap() > 2 {
+            line.clear();
+        }
+        let head = format!(
+            "HTTP/1.1 {status} Status\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        );
+        let _ = stream.write_all(head.as_bytes());
+        let _ = stream.write_all(body.as_bytes());
+    });
+    let mut cmd = bare();
+    cmd.args(["-q", "--source", &url]);
+    cmd
+}
+
+fn read(name: &str) -> String {
+    std::fs::read_to_string(fixture(name)).unwrap()
+}
+
+/// The codes in `--help`: 1 for usage errors, 2 for no match, 3 for failing to
+/// fetch, 4 for data that cannot be read or parsed, and after them those of
+/// `--fail-on-ambiguous` and `coverage`.
+#[test]
+fn codes() {
+    let languages = read("primary-extensions.yml");
+    served(200, &languages)
+        .args(["for", "alpha"])
+        .assert()
+        .code(0);
+    served(200, &languages)
+        .args(["for", "zzqx"])
+        .assert()
+        .code(2);
+    served(404, "").args(["for", "alpha"]).assert().code(3);
+    served(500, "").args(["for", "alpha"]).assert().code(3);
+    served(200, &read("malformed.yml"))
+        .args(["for", "alpha"])
+        .assert()
+        .code(4);
+
+    cli().args(["for", "zzqx"]).assert().code(2);
+    cli()
+        .args(["for", "--no-such-flag", "rust"])
+        .assert()
+        .code(1);
+    cli_on(&fixture("no-such-file.yml"))
+        .args(["for", "rust"])
+        .assert()
+        .code(4);
+    cli()
+        .arg("--overrides")
+        .arg(fixture("no-such-file.yml"))
+        .args(["for", "rust"])
+        .assert()
+        .code(4);
+
+    cli().assert().code(1);
+    cli().arg("--help").assert().code(0);
+    cli().arg("--version").assert().code(0);
+    cli()
+        .args(["for", "--fail-on-ambiguous", "h"])
+        .assert()
+        .code(5);
+    let coverage = |extensions: &str| {
+        let mut cmd = cli();
+        cmd.args(["coverage", "--ext-file", "-"])
+            .write_stdin(extensions.to_string());
+        cmd
+    };
+    coverage("rs\nzzqx\n").assert().code(6);
+    coverage("zzqx\n").assert().code(7);
+}
+
+/// `exists` answers with 0 or 2, and fails to load the data with the exit
+/// codes of other commands rather than 2.
+#[test]
+fn exists() {
+    cli().args(["exists", "rust"]).assert().code(0).stdout("");
+    cli().args(["exists", "c++"]).assert().code(0);
+    cli()
+        .args(["exists", "no such language"])
+        .assert()
+        .code(2)
+        .stdout("");
+    cli_on(&fixture("malformed.yml"))
+        .args(["exists", "rust"])
+        .assert()
+        .code(4);
+    bare()
+        .args(["-q", "--source", "http://127.0.0.1:1/languages.yml"])
+        .args(["exists", "rust"])
+        .assert()
+        .code(3);
+}
+
+/// A query that finds nothing fails with languages spelled like it, and one
+/// that finds something does not suggest anything.
+#[test]
+fn suggestions() {
+    let output = cli().args(["for", "pyton"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("did you mean: Python,"), "{stderr:?}");
+
+    let output = cli().args(["for", "python"]).output().unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!stderr.contains("did you mean"), "{stderr:?}");
+}
+
+/// Data cut off or with a field of the wrong type fails with 4 and where in
+/// the file it went wrong, whichever command reads it.
+#[test]
+fn broken_yaml() {
+    for (name, message) in [
+        (
+            "truncated.yml",
+            "found unexpected end of stream at line 10 column 1, \
+            while scanning a quoted scalar at line 9 column 10",
+        ),
+        (
+            "wrong-type.yml",
+            "Rust.extensions: invalid type: string \".rs\", expected a sequence \
+            at line 5 column 15",
+        ),
+    ] {
+        let path = fixture(name);
+        for command in [&["for", "rust"][..], &["list"], &["exists", "rust"]] {
+            let output = cli_on(&path).args(command).output().unwrap();
+            assert_eq!(output.status.code(), Some(4), "{name} {command:?}");
+            assert!(output.stdout.is_empty(), "{name} {command:?}");
+            let stderr = String::from_utf8(output.stderr).unwrap();
+            let expected = format!("Error: failed to parse {}: {message}\n", path.display());
+            assert_eq!(stderr, expected, "{name} {command:?}");
+        }
+    }
+}
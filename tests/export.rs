@@ -0,0 +1,106 @@
+mod support;
+
+use support::{cli, cli_on, fixture, golden, stdout};
+
+/// `.aa` is colored as Zeta's, whose primary extension it is, not as Alpha's,
+/// which lists it second.
+#[test]
+fn ls_colors_uses_primary_extensions() {
+    let fixture = fixture("primary-extensions.yml");
+    let out = stdout(cli_on(&fixture).args(["export", "ls-colors"]));
+    assert_eq!(out.trim_end(), "*.aa=38;5;235:*.zz=38;5;233");
+}
+
+/// `--only` limits the entries to the extensions of those languages, so that
+/// `.aa` is Alpha's without Zeta, and every entry splits as `dircolors` does.
+#[test]
+fn ls_colors_only() {
+    let fixture = fixture("primary-extensions.yml");
+    let only = |langs: &str| {
+        let out = stdout(cli_on(&fixture).args(["export", "ls-colors", "--only", langs]));
+        out.trim_end().to_string()
+    };
+    assert_eq!(only("alpha"), "*.aa=38;5;233:*.zz=38;5;233");
+    assert_eq!(only("zeta"), "*.aa=38;5;235");
+    assert_eq!(only("alpha,zeta"), "*.aa=38;5;235:*.zz=38;5;233");
+
+    let out = stdout(cli().args(["export", "ls-colors", "--only", "rust,python"]));
+    let entries = out
+        .trim_end()
+        .split(':')
+        .map(|entry| entry.split_once('=').unwrap());
+    let entries = entries.collect::<Vec<_>>();
+    assert!(entries.contains(&("*.rs", "38;5;180")), "{out}");
+    assert!(entries.contains(&("*.py", "38;5;61")), "{out}");
+    assert!(
+        (entries.iter()).all(|(_, sgr)| ["38;5;180", "38;5;61"].contains(sgr)),
+        "{out}"
+    );
+}
+
+/// Each of `runs` of `linguist-termcolor ARGS` on the fixture `file`, after a
+/// `$` line with its arguments.
+fn exports(file: &str, runs: &[&[&str]]) -> String {
+    let fixture = fixture(file);
+    let mut text = String::new();
+    for args in runs {
+        text += &format!("$ {}\n", args.join(" "));
+        text += &stdout(cli_on(&fixture).args(*args));
+    }
+    text
+}
+
+/// Group names made Vim identifiers, with `ctermfg` the nearest xterm color in
+/// the space of `-c`, of every language with a color or only those given.
+#[test]
+fn vim() {
+    let text = exports(
+        "vim.yml",
+        &[
+            &["export", "vim"],
+            &["-c", "hsl", "export", "vim"],
+            &["export", "vim", "c#", "c++"],
+        ],
+    );
+    golden("export-vim.txt", &text);
+}
+
+/// Keys made Lua names, or quoted where they cannot be, as in the table of
+/// `vim`.
+#[test]
+fn lua() {
+    let text = exports(
+        "vim.yml",
+        &[
+            &["export", "lua"],
+            &["-c", "hsl", "export", "lua"],
+            &["export", "lua", "--only", "1c enterprise,ren'py"],
+        ],
+    );
+    golden("export-lua.txt", &text);
+}
+
+/// Tables from names as Linguist writes them, sorted as it sorts them, and
+/// with `--extensions`, from extensions in the color of their primary
+/// languages, `.aa` in Zeta's.
+#[test]
+fn tables() {
+    for format in ["rust", "python", "json-map"] {
+        let mut text = exports(
+            "vim.yml",
+            &[&["export", format], &["export", format, "c#", "c++"]],
+        );
+        text += &exports(
+            "primary-extensions.yml",
+            &[&["export", format, "--extensions"]],
+        );
+        if format == "rust" {
+            text += &exports("vim.yml", &[&["export", "rust", "--phf"]]);
+            text += &exports(
+                "primary-extensions.yml",
+                &[&["export", "rust", "--extensions", "--phf"]],
+            );
+        }
+        golden(&format!("export-{format}.txt"), &text);
+    }
+}
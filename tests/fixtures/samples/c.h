@@ -0,0 +1,6 @@
+#ifndef ADD_H
+#define ADD_H
+
+int add(int a, int b);
+
+#endif
@@ -0,0 +1,12 @@
+#pragma once
+
+#include <vector>
+
+template <typename T>
+class Stack {
+  public:
+    void push(T value) { items.push_back(value); }
+
+  private:
+    std::vector<T> items;
+};
@@ -0,0 +1,4 @@
+% Square every element of x.
+function y = square(x)
+    y = x .^ 2;
+end
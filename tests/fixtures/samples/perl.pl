@@ -0,0 +1,6 @@
+#!/usr/bin/perl
+use strict;
+use warnings;
+
+my $name = shift // 'world';
+print "Hello, $name\n";
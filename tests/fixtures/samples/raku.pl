@@ -0,0 +1,4 @@
+use v6;
+
+my $name = @*ARGS[0] // 'world';
+say "Hello, $name";
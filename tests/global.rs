@@ -0,0 +1,84 @@
+//! `global` and `global_with` from many threads at once, in a process of their
+//! own so that nothing else has loaded the shared map first.
+
+#![cfg(feature = "fetch")]
+
+use std::{
+    io::{self, Read},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Barrier,
+    },
+    thread,
+    time::Duration,
+};
+
+use linguist_termcolor::{global, global_with, Linguist};
+
+/// How many sources have started being read.
+static LOADS: AtomicUsize = AtomicUsize::new(0);
+
+/// `languages.yml`, counted in [`LOADS`] when first read, and slow to read
+/// so that other threads call in the meantime.
+struct Counted {
+    yaml: io::Cursor<&'static [u8]>,
+    started: bool,
+}
+
+impl Counted {
+    fn new(yaml: &'static str) -> Self {
+        Self {
+            yaml: io::Cursor::new(yaml.as_bytes()),
+            started: false,
+        }
+    }
+}
+
+impl Read for Counted {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.started {
+            self.started = true;
+            LOADS.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(50));
+        }
+        self.yaml.read(buf)
+    }
+}
+
+/// Threads calling at once load the data once, the others waiting for the
+/// one loading it, and they and every later caller get the same map. A
+/// failed load is not kept.
+#[test]
+fn concurrent_threads() {
+    let broken = Linguist::builder().reader(Counted::new("Rust: [\n"));
+    assert!(global_with(broken).is_err());
+    assert_eq!(LOADS.load(Ordering::SeqCst), 1);
+
+    let yaml = include_str!("../data/languages.yml");
+    let threads = 8;
+    let barrier = Arc::new(Barrier::new(threads));
+    let handles = (0..threads)
+        .map(|_| {
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                let source = Linguist::builder().reader(Counted::new(yaml));
+                barrier.wait();
+                global_with(source).unwrap()
+            })
+        })
+        .collect::<Vec<_>>();
+    let maps = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(LOADS.load(Ordering::SeqCst), 2, "loaded more than once");
+    assert!(maps.iter().all(|map| Arc::ptr_eq(map, &maps[0])));
+    assert_eq!(maps[0].ranked("rust")[0].color.hex(), "#dea584");
+
+    // without fetching, as the map is loaded
+    let later = global().unwrap();
+    assert!(Arc::ptr_eq(&later, &maps[0]));
+    assert_eq!(LOADS.load(Ordering::SeqCst), 2);
+    // the map and every clone handed out
+    assert_eq!(Arc::strong_count(&later), threads + 2);
+}
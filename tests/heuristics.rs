@@ -0,0 +1,61 @@
+//! Files with extensions that several languages share, under
+//! `tests/fixtures/samples`, found by their contents.
+
+mod support;
+
+use support::{cli, fixture, stdout};
+
+/// Each sample with the language its contents pick, and the one its
+/// extension alone does.
+const SAMPLES: &[(&str, &str, &str)] = &[
+    ("objc.h", "Objective-C", "C"),
+    ("cpp.h", "C++", "C"),
+    ("c.h", "C", "C"),
+    ("objc.m", "Objective-C", "MATLAB"),
+    ("mercury.m", "Mercury", "MATLAB"),
+    ("matlab.m", "MATLAB", "MATLAB"),
+    ("perl.pl", "Perl", "Perl"),
+    ("prolog.pl", "Prolog", "Perl"),
+    ("raku.pl", "Raku", "Perl"),
+];
+
+/// The language of the first line of `for`.
+fn first(args: &[&str], sample: &str) -> String {
+    let path = fixture("samples").join(sample);
+    let text = stdout(cli().args(args).arg("for").arg(path));
+    let line = text.lines().next().unwrap();
+    let (_, after) = line.split_once(" xterm ").unwrap();
+    let (_, name) = after.split_once(' ').unwrap();
+    name.trim_start().split(" (").next().unwrap().to_string()
+}
+
+#[test]
+fn by_contents() {
+    for &(sample, language, _) in SAMPLES {
+        assert_eq!(first(&[], sample), language, "{sample}");
+    }
+}
+
+/// `--no-heuristics` does not read the files, so they are found by their
+/// extensions.
+#[test]
+fn without_heuristics() {
+    for &(sample, _, by_extension) in SAMPLES {
+        assert_eq!(
+            first(&["--no-heuristics"], sample),
+            by_extension,
+            "{sample}"
+        );
+    }
+}
+
+/// The rules of Linguist's `heuristics.yml` for the same extensions, from
+/// `--heuristics-file`, pick the same languages.
+#[test]
+fn from_heuristics_file() {
+    let file = fixture("heuristics.yml");
+    let args = ["--heuristics-file", file.to_str().unwrap()];
+    for &(sample, language, _) in SAMPLES {
+        assert_eq!(first(&args, sample), language, "{sample}");
+    }
+}
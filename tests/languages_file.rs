@@ -0,0 +1,196 @@
+//! Data from a `languages.yml` of one's own, as for a fork of Linguist, with
+//! `Linguist::from_path` and `--languages-file`.
+
+mod support;
+
+use linguist_termcolor::{Error, Linguist};
+use support::{bare, cli, cli_on, fixture, stdout};
+
+/// The names of the languages `for` prints for `query`, in order.
+fn found(query: &str) -> Vec<String> {
+    let text = stdout(cli_on(&fixture("fork.yml")).args(["for", query]));
+    let lines = text.lines().map(|line| {
+        let (_, after) = line.split_once(" xterm ").unwrap();
+        let (_, name) = after.split_once(' ').unwrap();
+        name.trim_start().split(" (").next().unwrap().to_string()
+    });
+    lines.collect()
+}
+
+#[test]
+fn queries() {
+    for (query, languages) in [
+        ("rust", &["Rust"][..]),
+        ("rs", &["Rust", "Ferrite"]),
+        (".rs", &["Rust", "Ferrite"]),
+        ("ferrite", &["Ferrite"]),
+        ("rust-ish", &["Ferrite"]),
+        ("fe", &["Ferrite"]),
+        // by its group's color, as it has none
+        ("Ferritefile", &["Ferrite Config"]),
+        ("fecfg", &["Ferrite Config"]),
+    ] {
+        assert_eq!(found(query), languages, "{query}");
+    }
+    let map = stdout(cli_on(&fixture("fork.yml")).args(["export", "json-map"]));
+    let map = serde_json::from_str::<serde_json::Value>(&map).unwrap();
+    assert_eq!(
+        map,
+        serde_json::json!({"Ferrite": "#b7410e", "Rust": "#dea584"})
+    );
+}
+
+#[test]
+fn from_path() {
+    let linguist = Linguist::from_path(fixture("fork.yml")).unwrap();
+    assert_eq!(linguist.languages().count(), 3);
+    let ferrite = linguist.get("RUST-ISH").unwrap();
+    assert_eq!(ferrite.name(), "Ferrite");
+    assert_eq!(ferrite.extensions(), [".fe", ".rs"]);
+    assert_eq!(ferrite.interpreters(), ["ferrite"]);
+    let config = linguist.get("ferrite config").unwrap();
+    assert_eq!(config.group(), Some("Ferrite"));
+    assert_eq!(config.color(), None);
+
+    let colors = linguist.colors().unwrap();
+    let ranked = colors.ranked("*.rs").into_iter();
+    let ranked = ranked.map(|ranked| (ranked.name.to_string(), ranked.color.hex()));
+    assert_eq!(
+        ranked.collect::<Vec<_>>(),
+        [
+            ("Rust".to_string(), "#dea584".to_string()),
+            ("Ferrite".to_string(), "#b7410e".to_string())
+        ]
+    );
+}
+
+/// A missing file and one that is not YAML fail differently, both naming
+/// the path.
+#[test]
+fn errors() {
+    let missing = fixture("missing.yml");
+    let Err(Error::File { path, source }) = Linguist::from_path(&missing) else {
+        panic!("a missing file was read");
+    };
+    assert_eq!(path, missing);
+    assert!(matches!(*source, Error::Io(_)), "{source:?}");
+
+    let malformed = fixture("malformed.yml");
+    let Err(err @ Error::File { .. }) = Linguist::from_path(&malformed) else {
+        panic!("malformed YAML was parsed");
+    };
+    let Error::File { source, .. } = &err else {
+        unreachable!()
+    };
+    assert!(matches!(**source, Error::Parse(_)), "{source:?}");
+    assert!(err
+        .to_string()
+        .starts_with(&format!("failed to parse {}: ", malformed.display())));
+
+    for (file, message) in [
+        ("missing.yml", "failed to read"),
+        ("malformed.yml", "failed to parse"),
+    ] {
+        let output = cli_on(&fixture(file))
+            .args(["for", "rust"])
+            .output()
+            .unwrap();
+        assert!(!output.status.success(), "{file}");
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        let expected = format!("{message} {}", fixture(file).display());
+        assert!(stderr.contains(&expected), "{file}: {stderr}");
+    }
+}
+
+/// A language two groups down from a color is shown in it, marked with where
+/// it comes from, and one whose groups loop finds nothing instead of hanging.
+#[test]
+fn group_chains() {
+    let groups = fixture("groups.yml");
+    let text = stdout(cli_on(&groups).args(["for", "jsx template"]));
+    assert_eq!(
+        text,
+        "rgb #f1e05a xterm 221 JSX Template (via JavaScript)\n"
+    );
+    for query in ["loop a", "self loop"] {
+        cli_on(&groups).args(["for", query]).assert().code(2);
+    }
+}
+
+/// `type`, `group`, `language_id`, and `tm_scope` come out of `info` and of
+/// `for` in JSON as the library reads them, `null` when missing.
+#[test]
+fn metadata_in_json() {
+    let path = fixture("fork.yml");
+    let linguist = Linguist::from_path(&path).unwrap();
+    let json = |args: &[&str]| {
+        let text = stdout(cli_on(&path).args(["--format", "json"]).args(args));
+        serde_json::from_str::<serde_json::Value>(&text).unwrap()
+    };
+    for name in ["Rust", "Ferrite", "Ferrite Config"] {
+        let lang = linguist.get(name).unwrap();
+        let expected = serde_json::json!({
+            "type": lang.language_type().map(|t| t.name()),
+            "group": lang.group(),
+            "language_id": lang.language_id(),
+            "tm_scope": lang.tm_scope(),
+        });
+        let info = json(&["info", name]);
+        let found = json(&["for", "--exact", name]);
+        for (output, json) in [("info", &info), ("for", &found[0])] {
+            for (key, value) in expected.as_object().unwrap() {
+                assert_eq!(json.get(key), Some(value), "{output} {name} {key}");
+            }
+        }
+    }
+    let config = json(&["info", "Ferrite Config"]);
+    assert_eq!(config["language_id"], 900002);
+    assert!(config["tm_scope"].is_null());
+}
+
+/// `--overrides` adds a language, recolors one, and extends the aliases of
+/// another, as `Linguist::with_overrides` does.
+#[test]
+fn overrides() {
+    let overrides = fixture("overrides.yml");
+    let found = |query: &str| {
+        let mut cmd = cli();
+        cmd.arg("--overrides").arg(&overrides);
+        stdout(cmd.args(["for", "--exact", query]))
+    };
+    assert_eq!(found("acme"), "rgb #ff6600 xterm 202 Acme Config\n");
+    assert_eq!(found("typescript"), "rgb #0057b8 xterm 25  TypeScript\n");
+    assert_eq!(found("rustlang"), "rgb #dea584 xterm 180 Rust\n");
+    assert_eq!(found("rs"), "rgb #dea584 xterm 180 Rust\n");
+}
+
+/// A color that is not a hex color is skipped with a warning that counts it,
+/// and names it with `-v`, rather than failing every query.
+#[test]
+fn skipped_colors() {
+    let path = fixture("bad-color.yml");
+    let output = cli_on(&path).args(["for", "rust"]).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "rgb #dea584 xterm 180 Rust\n"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(
+        stderr,
+        "warning: skipped the colors of 1 language(s), which are not hex colors, see -v\n"
+    );
+
+    let mut verbose = bare();
+    verbose.arg("-v").arg("--languages-file").arg(&path);
+    let output = verbose.args(["for", "rust"]).output().unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(
+            "warning: skipped the colors of 1 language(s), which are not hex colors: \
+            Bad Lang has color \"#zzzzzz\", expected #rrggbb or #rgb\n"
+        ),
+        "{stderr}"
+    );
+    cli_on(&path).args(["for", "bad lang"]).assert().code(2);
+}
@@ -0,0 +1,30 @@
+//! `--format markdown` of `list` and `for`, against a golden file under
+//! `tests/fixtures/golden`.
+
+mod support;
+
+use support::{cli_on, fixture, golden, stdout};
+
+/// Tables with alignment markers, and the characters of Markdown in names
+/// escaped, a pipe among them, whose languages have colors.
+#[test]
+fn tables() {
+    let fixture = fixture("markdown.yml");
+    let mut text = String::new();
+    for args in [
+        &["list"][..],
+        &["for", "rust", "c#", "pipe|lang"],
+        &["for", ".brk"],
+        &["-c", "de2000", "for", "f*"],
+    ] {
+        text += &format!("$ {}\n", args.join(" "));
+        text += &stdout(cli_on(&fixture).args(["--format", "markdown"]).args(args));
+    }
+    golden("markdown.md", &text);
+
+    // every row has as many cells as the header, so no pipe went unescaped
+    for line in text.lines().filter(|line| line.starts_with('|')) {
+        let cells = line.replace("\\|", "").matches('|').count();
+        assert_eq!(cells, 5, "{line}");
+    }
+}
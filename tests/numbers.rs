@@ -0,0 +1,161 @@
+mod support;
+
+use std::path::Path;
+
+use serde_json::Value;
+use support::{bare, cli, stdout};
+
+/// Queries whose colors are all off their xterm colors, so that with
+/// `--warn-delta 0.01` every one shows its difference.
+const QUERIES: &[&[&str]] = &[
+    &[
+        "for",
+        "--exact",
+        "--candidates",
+        "3",
+        "python",
+        "rust",
+        "nix",
+        "prolog",
+    ],
+    &[
+        "xterm",
+        "--candidates",
+        "3",
+        "#3572a5",
+        "#dea584",
+        "#123456",
+    ],
+];
+
+/// `⚠ Δ13.7` and the `Δ47.17` of each candidate, in order.
+fn from_text(text: &str) -> Vec<f64> {
+    let numbers = text
+        .split_whitespace()
+        .filter_map(|word| word.strip_prefix('Δ'));
+    numbers.map(|number| number.parse().unwrap()).collect()
+}
+
+/// `xterm_delta` and the `distance` of each candidate, in order, of results
+/// or of results per query, as of `for` with several.
+fn from_json(json: &str) -> Vec<f64> {
+    let json = serde_json::from_str::<Value>(json).unwrap();
+    let mut numbers = vec![];
+    let colors =
+        json.as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|color| match color["results"].as_array() {
+                Some(results) => results.iter().collect(),
+                None => vec![color],
+            });
+    for color in colors {
+        numbers.push(color["xterm_delta"].as_f64().unwrap());
+        for candidate in color["candidates"].as_array().unwrap() {
+            numbers.push(candidate["distance"].as_f64().unwrap());
+        }
+    }
+    numbers
+}
+
+fn run(query: &[&str], args: &[&str]) -> String {
+    stdout(
+        cli()
+            .args(["--color", "never", "--warn-delta", "0.01"])
+            .args(args)
+            .args(query),
+    )
+}
+
+/// JSON has the numbers of the text, rounded the same way.
+#[test]
+fn json_agrees_with_text() {
+    for query in QUERIES {
+        let text = from_text(&run(query, &[]));
+        assert!(text.len() > 3, "{query:?}: {text:?}");
+        assert_eq!(
+            from_json(&run(query, &["--format", "json"])),
+            text,
+            "{query:?}"
+        );
+    }
+}
+
+/// `--raw-numbers` has them in full, which round to those of the text.
+#[test]
+fn raw_numbers_round_to_text() {
+    for query in QUERIES {
+        let text = from_text(&run(query, &[]));
+        let raw = from_json(&run(query, &["--format", "json", "--raw-numbers"]));
+        assert_eq!(raw.len(), text.len(), "{query:?}");
+        // the first of each color is a CIEDE2000 difference, the rest distances
+        let per_color = 1 + 3;
+        for (i, (raw, text)) in raw.iter().zip(&text).enumerate() {
+            let places = if i % per_color == 0 { 1 } else { 2 };
+            let scale = 10f64.powi(places);
+            assert_eq!((raw * scale).round() / scale, *text, "{query:?} #{i}");
+        }
+        assert!(raw != text, "{query:?}: nothing was rounded");
+    }
+}
+
+/// Colors are marked as poorly approximated only with `--warn-delta`, and
+/// then only those more than it off, with a count on stderr.
+#[test]
+fn marked_only_with_warn_delta() {
+    let query = ["for", "--exact", "rust", "python", "prolog"];
+    let output = cli().args(query).output().unwrap();
+    let (plain, stderr) = (String::from_utf8(output.stdout).unwrap(), output.stderr);
+    assert!(!plain.contains('⚠'), "{plain}");
+    assert!(stderr.is_empty(), "{}", String::from_utf8_lossy(&stderr));
+    assert_eq!(stdout(cli().args(["--no-warn"]).args(query)), plain);
+
+    let output = cli()
+        .args(["--warn-delta", "10"])
+        .args(query)
+        .output()
+        .unwrap();
+    let text = String::from_utf8(output.stdout).unwrap();
+    let marked = text.lines().filter(|line| line.contains('⚠'));
+    let marked = marked.map(|line| line.split_whitespace().next().unwrap());
+    assert_eq!(marked.collect::<Vec<_>>(), ["python", "prolog"], "{text}");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("2 colors poorly approximated"), "{stderr}");
+}
+
+/// With `-vv`, colors more than 10 off are marked unless `--warn-delta` or
+/// `--no-warn` says otherwise, and `--format json` says which were with
+/// `approximation_poor`.
+#[test]
+fn marked_with_vv() {
+    let query = ["for", "--exact", "rust", "python", "prolog"];
+    // -q cannot be combined with -v
+    let verbose = || {
+        let mut cmd = bare();
+        let languages = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/languages.yml");
+        cmd.arg("--languages-file").arg(languages);
+        cmd
+    };
+    let marked = |args: &[&str]| {
+        let text = stdout(verbose().args(args).args(query));
+        let marked = text.lines().filter(|line| line.contains('⚠'));
+        let marked = marked.map(|line| line.split_whitespace().next().unwrap().to_string());
+        marked.collect::<Vec<_>>()
+    };
+    assert_eq!(marked(&["-vv"]), ["python", "prolog"]);
+    assert_eq!(marked(&["-vv", "--warn-delta", "20"]), ["prolog"]);
+    assert!(marked(&["-v"]).is_empty());
+    assert!(marked(&["-vv", "--no-warn"]).is_empty());
+    assert!(marked(&["-vv", "--palette", "ansi16"]).is_empty());
+
+    let poor = |args: &[&str]| {
+        let json = stdout(verbose().args(["--format", "json"]).args(args).args(query));
+        let json = serde_json::from_str::<Value>(&json).unwrap();
+        let poor = json.as_array().unwrap().iter();
+        let poor = poor.map(|query| query["results"][0]["approximation_poor"].as_bool().unwrap());
+        poor.collect::<Vec<_>>()
+    };
+    assert_eq!(poor(&[]), [false, false, false]);
+    assert_eq!(poor(&["-vv"]), [false, true, true]);
+    assert_eq!(poor(&["--warn-delta", "20"]), [false, false, true]);
+}
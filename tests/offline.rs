@@ -0,0 +1,26 @@
+mod support;
+
+use support::bare;
+
+/// `--offline` answers from the data embedded with the `bundled` feature,
+/// without `--languages-file` or a network.
+#[cfg(feature = "bundled")]
+#[test]
+fn offline() {
+    let rust = support::stdout(bare().args(["-q", "--offline", "for", "--only", "hex", "rust"]));
+    assert_eq!(rust.trim(), "#dea584");
+}
+
+/// Without the `bundled` feature, `--offline` fails saying how to get it,
+/// rather than fetching.
+#[cfg(not(feature = "bundled"))]
+#[test]
+fn offline() {
+    let output = bare()
+        .args(["-q", "--offline", "for", "rust"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("--features bundled"), "{stderr}");
+}
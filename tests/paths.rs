@@ -0,0 +1,79 @@
+//! Arguments of `for` that look like paths are told by the file's name, also
+//! when the file does not exist, before being queried as words.
+
+mod support;
+
+use serde_json::Value;
+use support::{cli, stdout};
+
+/// The languages of `for` with `query`, best first, run away from any file
+/// that the query could name.
+fn found(query: &str) -> Option<Vec<String>> {
+    let mut cmd = cli();
+    cmd.current_dir(std::env::temp_dir())
+        .args(["--format", "json", "for", query]);
+    let output = cmd.output().unwrap();
+    if !output.status.success() {
+        assert_eq!(output.status.code(), Some(2), "{query}");
+        return None;
+    }
+    let json = serde_json::from_slice::<Value>(&output.stdout).unwrap();
+    let languages = json.as_array().unwrap().iter();
+    let languages = languages.map(|color| color["language"].as_str().unwrap().to_string());
+    Some(languages.collect())
+}
+
+#[test]
+fn paths() {
+    for (query, language) in [
+        ("src/main.rs", "Rust"),
+        ("./Cargo.toml", "TOML"),
+        ("../lib/app.py", "Python"),
+        ("/usr/share/types/index.d.ts", "TypeScript"),
+        ("project/Makefile", "Makefile"),
+        ("project/Dockerfile", "Dockerfile"),
+    ] {
+        let found = found(query).unwrap_or_default();
+        assert_eq!(found.first().map(String::as_str), Some(language), "{query}");
+    }
+}
+
+/// Hidden files are told by their whole name, with or without a directory.
+#[test]
+fn hidden_files() {
+    for (query, language) in [
+        (".gitignore", "Ignore List"),
+        ("dir/.gitignore", "Ignore List"),
+        ("home/.bashrc", "Shell"),
+        ("home/.vimrc", "Vim script"),
+    ] {
+        let found = found(query).unwrap_or_default();
+        assert_eq!(found.first().map(String::as_str), Some(language), "{query}");
+    }
+}
+
+/// A name without an extension is told by the filenames of languages, and
+/// one that no language has finds nothing.
+#[test]
+fn extensionless_names() {
+    let found_in = |query| found(query).map(|found| found[0].clone());
+    assert_eq!(found_in("ruby/Gemfile").as_deref(), Some("Ruby"));
+    assert_eq!(found_in("Makefile").as_deref(), Some("Makefile"));
+    assert_eq!(found_in("some/zzqx"), None);
+}
+
+/// Backslashes separate directories as slashes do, wherever this runs.
+#[test]
+fn backslashes() {
+    for (query, slashed) in [
+        (r"C:\src\main.rs", "C:/src/main.rs"),
+        (r"C:\proj\Cargo.lock", "C:/proj/Cargo.lock"),
+        (r"a\b\c.py", "a/b/c.py"),
+        (r"project\Makefile", "project/Makefile"),
+    ] {
+        assert!(found(query).is_some(), "{query}");
+        assert_eq!(found(query), found(slashed), "{query}");
+    }
+    let text = stdout(cli().args(["for", r"C:\src\main.rs"]));
+    assert!(text.contains("Rust"), "{text:?}");
+}
@@ -0,0 +1,19 @@
+mod support;
+
+use support::cli;
+
+/// Without a terminal to read keys from, `pick-xterm` fails and points to
+/// the chart that needs none.
+#[test]
+fn needs_a_terminal() {
+    let output = cli()
+        .arg("pick-xterm")
+        .assert()
+        .failure()
+        .get_output()
+        .clone();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("needs a terminal"), "{stderr}");
+    assert!(stderr.contains("linguist-termcolor palette"), "{stderr}");
+    assert!(output.stdout.is_empty());
+}
@@ -0,0 +1,66 @@
+mod support;
+
+use support::{cli, hex_rgb, sgr_runs, stdout, truecolor, xterm256, xterm_rgb};
+
+/// Options that change which colors are shown, or how.
+const OPTIONS: &[&[&str]] = &[
+    &[],
+    &["--bg"],
+    &["--swatch"],
+    &["--lighten", "20"],
+    &["--darken", "20", "--saturate", "30"],
+    &["--min-contrast", "7", "--contrast-bg", "#000000"],
+    &["--contrast-bg", "#ffffff"],
+    &["--simulate", "tritanopia"],
+    &["--skip-grayscale", "--skip-system"],
+];
+
+/// Check every colored run of `output`: `rgb #rrggbb` shows that color, and
+/// `xterm N` that xterm color, in truecolor or by its index.
+fn assert_runs_match(output: &str, background: bool, context: &str) -> usize {
+    let mut checked = 0;
+    for line in output.lines() {
+        for run in sgr_runs(line) {
+            let text = run.text.trim_start_matches(['█', ' ']);
+            let shown = truecolor(&run.params, background);
+            if let Some(hex) = text.strip_prefix("rgb ") {
+                assert_eq!(shown, Some(hex_rgb(&hex[..7])), "{context}: {line:?}");
+            } else if let Some(index) = text.strip_prefix("xterm ") {
+                let index = index.split_whitespace().next().unwrap().parse().unwrap();
+                match xterm256(&run.params, background) {
+                    Some(shown) => assert_eq!(shown, index, "{context}: {line:?}"),
+                    None => assert_eq!(shown, Some(xterm_rgb(index)), "{context}: {line:?}"),
+                }
+            } else {
+                continue;
+            }
+            checked += 1;
+        }
+    }
+    checked
+}
+
+/// In truecolor, the escape sequence before every printed color shows that
+/// color exactly, before and after adjustments.
+#[test]
+fn truecolor_shows_printed_hex() {
+    for options in OPTIONS {
+        let background = options.contains(&"--bg");
+        for command in [
+            &["list"][..],
+            &["for", "rust", "python", "c", "nix", "prolog"],
+            &["xterm", "#555555", "#3572a5", "98", "240"],
+            &["list", "--family", "blue"],
+        ] {
+            let output = stdout(
+                cli()
+                    .args(["--color", "always", "--color-depth", "24bit"])
+                    .args(*options)
+                    .args(command),
+            );
+            let context = format!("{options:?} {command:?}");
+            let checked = assert_runs_match(&output, background, &context);
+            assert!(checked > 0, "{context}: no colors to check in {output:?}");
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! Slugs are keys that other systems store, so once a language has one, it
+//! keeps it. `tests/fixtures/slugs.tsv` has the slug of every language as of
+//! the bundled `languages.yml`; languages added since go at its end.
+
+use std::collections::{BTreeMap, HashMap};
+
+use linguist_termcolor::{slugify, Linguist};
+
+/// `name<TAB>slug` lines of the snapshot.
+fn snapshot() -> Vec<(&'static str, &'static str)> {
+    let snapshot = include_str!("fixtures/slugs.tsv").lines();
+    let snapshot = snapshot.map(|line| line.split_once('\t').unwrap());
+    snapshot.collect()
+}
+
+#[test]
+fn slugs_are_stable() {
+    let linguist: Linguist = include_str!("../data/languages.yml").parse().unwrap();
+    let snapshot = snapshot().into_iter().collect::<HashMap<_, _>>();
+
+    let mut changed = vec![];
+    let mut added = vec![];
+    for language in linguist.languages() {
+        let slug = language.slug();
+        match snapshot.get(language.name()) {
+            Some(&known) if known != slug => {
+                changed.push(format!("{}: {known} is now {slug}", language.name()))
+            }
+            Some(_) => {}
+            None => added.push(format!("{}\t{slug}", language.name())),
+        }
+    }
+    assert!(changed.is_empty(), "slugs changed:\n{}", changed.join("\n"));
+    assert!(
+        added.is_empty(),
+        "languages without a slug in tests/fixtures/slugs.tsv, to add at its end:\n{}",
+        added.join("\n")
+    );
+}
+
+/// No two languages of the snapshot share a slug, and each is what
+/// `slugify` makes of the name, whether or not the language still exists.
+#[test]
+fn snapshot_is_consistent() {
+    let mut seen = BTreeMap::new();
+    for (name, slug) in snapshot() {
+        assert_eq!(slugify(name), slug, "{name}");
+        if let Some(other) = seen.insert(slug, name) {
+            panic!("{other} and {name} are both {slug}");
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! The snippets of `snippet`, against golden files under
+//! `tests/fixtures/golden`.
+
+mod support;
+
+use support::{cli, golden, stdout};
+
+/// Each of `runs` of `snippet FORMAT ...` after a `$` line with its arguments,
+/// those with spaces quoted.
+fn snippets(format: &str, runs: &[&[&str]]) -> String {
+    let mut text = String::new();
+    for args in runs {
+        let quoted = args.iter().map(|arg| match arg.contains(' ') {
+            true => format!("'{arg}'"),
+            false => arg.to_string(),
+        });
+        text += &format!(
+            "$ snippet {format} {}\n",
+            quoted.collect::<Vec<_>>().join(" ")
+        );
+        text += &stdout(cli().args(["snippet", format]).args(*args));
+    }
+    text
+}
+
+/// Modules with the xterm or exact color, for a language with extensions and
+/// one known by its filenames, and with the characters of format strings in
+/// the text escaped.
+#[test]
+fn starship() {
+    let text = snippets(
+        "starship",
+        &[
+            &["rust"],
+            &["--truecolor", "rust"],
+            &["--bg", "#262626", "rust"],
+            &["dockerfile"],
+            &["--text", "[$(x)] \\", "c++"],
+        ],
+    );
+    golden("snippet-starship.txt", &text);
+}
+
+/// Escapes inside `\[` and `\]`, with the characters bash expands in a prompt
+/// escaped in the text.
+#[test]
+fn ps1() {
+    let text = snippets(
+        "ps1",
+        &[
+            &["rust"],
+            &["--truecolor", "rust"],
+            &["--bg", "235", "rust"],
+            &["--text", "$HOME `pwd` \\", "python"],
+        ],
+    );
+    golden("snippet-ps1.txt", &text);
+}
+
+/// Styles with `colour` numbers or exact colors, on their own or on a
+/// background, with each `#` of the text doubled, as in C# and F#.
+#[test]
+fn tmux() {
+    let text = snippets(
+        "tmux",
+        &[
+            &["rust"],
+            &["--truecolor", "rust"],
+            &["c#"],
+            &["--truecolor", "f#"],
+            &["--text", "#[bold]#S", "c#"],
+            &["--bg", "#262626", "c#"],
+            &["--truecolor", "--bg", "235", "rust"],
+        ],
+    );
+    let args = ["--as", "bg", "snippet", "tmux", "c#"];
+    let text = format!("{text}$ {}\n{}", args.join(" "), stdout(cli().args(args)));
+    golden("snippet-tmux.txt", &text);
+}
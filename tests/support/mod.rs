@@ -0,0 +1,137 @@
+//! Running the binary in the CLI tests, on the bundled `languages.yml` or a
+//! fixture under `tests/fixtures`, away from the user's config and terminal.
+
+// not every test uses every helper
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+
+use assert_cmd::Command;
+
+/// A file under `tests/fixtures`.
+pub fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+/// Check `output` against `tests/fixtures/golden/name`, or with
+/// `UPDATE_GOLDEN=1`, write it there, to review with `git diff`.
+pub fn golden(name: &str, output: &str) {
+    let path = fixture("golden").join(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, output).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("{}: {err}; run with UPDATE_GOLDEN=1", path.display()));
+    assert!(
+        output == expected,
+        "{name} differs from {}, run with UPDATE_GOLDEN=1 to update it:\n{output}",
+        path.display()
+    );
+}
+
+/// `linguist-termcolor -q` on the bundled `languages.yml`.
+pub fn cli() -> Command {
+    cli_on(&Path::new(env!("CARGO_MANIFEST_DIR")).join("data/languages.yml"))
+}
+
+/// `linguist-termcolor -q` on `languages`, with no config file, colors
+/// turned off, and no terminal to detect.
+pub fn cli_on(languages: &Path) -> Command {
+    let mut cmd = bare();
+    cmd.arg("-q").arg("--languages-file").arg(languages);
+    cmd
+}
+
+/// `linguist-termcolor` with no arguments, config file, or terminal.
+pub fn bare() -> Command {
+    let mut cmd = Command::cargo_bin("linguist-termcolor").unwrap();
+    cmd.env("XDG_CONFIG_HOME", fixture("no-config"))
+        .env("TERM", "xterm-256color")
+        .env_remove("COLORTERM")
+        .env_remove("NO_COLOR")
+        .env_remove("CLICOLOR_FORCE")
+        .env_remove("LINGUIST_TERMCOLOR_REF");
+    cmd
+}
+
+/// The stdout of a command that succeeded.
+pub fn stdout(cmd: &mut Command) -> String {
+    let output = cmd.assert().success().get_output().stdout.clone();
+    String::from_utf8(output).unwrap()
+}
+
+/// Text and the SGR parameters of the escape sequence just before it, from
+/// [`sgr_runs`].
+#[derive(Debug)]
+pub struct Run {
+    pub params: Vec<u16>,
+    pub text: String,
+}
+
+/// The runs of `line` that follow an SGR escape sequence, `ESC [ ... m`,
+/// other than a reset.
+pub fn sgr_runs(line: &str) -> Vec<Run> {
+    line.split("\x1b[")
+        .skip(1)
+        .filter_map(|sequence| {
+            let (params, text) = sequence.split_once('m')?;
+            let params = params.split(';').map(|param| param.parse().unwrap());
+            let params = params.collect::<Vec<u16>>();
+            (params != [0]).then(|| Run {
+                params,
+                text: text.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The 24-bit color that SGR parameters set with `38;2;R;G;B`, or for the
+/// background, `48;2;R;G;B`.
+pub fn truecolor(params: &[u16], background: bool) -> Option<[u8; 3]> {
+    let layer = if background { 48 } else { 38 };
+    let sgr = params
+        .windows(5)
+        .find(|sgr| sgr[0] == layer && sgr[1] == 2)?;
+    Some([sgr[2], sgr[3], sgr[4]].map(|c| u8::try_from(c).unwrap()))
+}
+
+/// The xterm color that SGR parameters set with `38;5;N`, or for the
+/// background, `48;5;N`.
+pub fn xterm256(params: &[u16], background: bool) -> Option<u8> {
+    let layer = if background { 48 } else { 38 };
+    let sgr = params
+        .windows(3)
+        .find(|sgr| sgr[0] == layer && sgr[1] == 5)?;
+    Some(u8::try_from(sgr[2]).unwrap())
+}
+
+/// The RGB of `#rrggbb`.
+pub fn hex_rgb(hex: &str) -> [u8; 3] {
+    let hex = hex.strip_prefix('#').unwrap();
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+    [channel(0), channel(2), channel(4)]
+}
+
+/// The RGB of xterm color `index`, as the tool shows it in truecolor.
+pub fn xterm_rgb(index: u8) -> [u8; 3] {
+    const SYSTEM: [u32; 16] = [
+        0x000000, 0x800000, 0x008000, 0x808000, 0x000080, 0x800080, 0x008080, 0xc0c0c0, 0x808080,
+        0xff0000, 0x00ff00, 0xffff00, 0x0000ff, 0xff00ff, 0x00ffff, 0xffffff,
+    ];
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    match index {
+        0..=15 => {
+            let rgb = SYSTEM[usize::from(index)];
+            [(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8]
+        }
+        16..=231 => {
+            let cube = index - 16;
+            [cube / 36, cube / 6 % 6, cube % 6].map(|level| LEVELS[usize::from(level)])
+        }
+        232.. => [8 + 10 * (index - 232); 3],
+    }
+}
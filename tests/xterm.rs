@@ -0,0 +1,52 @@
+//! Arguments of `xterm`: each an index or a color in any notation that
+//! `TermColor` parses.
+
+mod support;
+
+use support::{cli, stdout};
+
+#[test]
+fn notations() {
+    for (color, line) in [
+        ("#3572a5", "rgb #3572a5 xterm 61"),
+        ("3572A5", "rgb #3572a5 xterm 61"),
+        ("rgb(53,114,165)", "rgb #3572a5 xterm 61"),
+        ("rgb(100 100 240)", "rgb #6464f0 xterm 63"),
+        ("rgb(100%, 50%, 0%)", "rgb #ff8000 xterm 208"),
+        ("hsl(21deg, 58%, 69%)", "rgb #dea282 xterm 180"),
+        ("rebeccapurple", "rgb #663399 xterm 60"),
+    ] {
+        let text = stdout(cli().args(["xterm", color]));
+        assert_eq!(text.trim_end(), line, "{color}");
+    }
+    let output = cli().args(["xterm", "rgb(53, 114, 165"]).output().unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.starts_with("Error: unrecognized color \"rgb(53, 114, 165\", expected hex"),
+        "{stderr:?}"
+    );
+}
+
+/// Indices and colors mix, each read by its own syntax, and indices out of
+/// range are not taken for hex.
+#[test]
+fn indices() {
+    let text = stdout(cli().args(["xterm", "61", "#dea584", "061"]));
+    let lines = text.lines().map(str::trim_end).collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        [
+            "██ xterm 61  #5f5faf",
+            "rgb #dea584 xterm 180",
+            "██ xterm 61  #5f5faf"
+        ]
+    );
+    for index in ["256", "-1"] {
+        let output = cli().args(["xterm", "--", index]).output().unwrap();
+        assert!(!output.status.success(), "{index}");
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        let expected = format!("Error: xterm colors are numbered 0 to 255, found {index}\n");
+        assert_eq!(stderr, expected);
+    }
+}